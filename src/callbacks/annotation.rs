@@ -1,11 +1,20 @@
 //! Annotation manipulation callbacks.
 //!
-//! Handles: delete, classify, undo, redo, copy, paste operations
+//! Handles: delete, classify, reclassify-region, eyedropper, reject-by-class, revert, clear-frame, purge-rejected, remap-class, mirror, duplicate, align, move-selected, rotate, merge-boxes, points-to-boxes, boxes-to-points, shift-frame, interpolate, track ids, undo, redo, copy, paste operations
 
-use crate::state::{replace_annotations, snapshot_annotations, UndoHistory};
+use crate::config::AppConfig;
+use crate::state::{
+    align_boxes, apply_class, boxes_to_points, can_edit, changed_ids, class_at, clear_frame,
+    dataset_max_track_id, duplicate_selected, interpolate_from_keyframe, max_group_id,
+    merge_selected_boxes, mirror_annotation, mirrored_class, move_selected, paste_annotations,
+    points_to_boxes, purge_rejected, reclassify_region, reject, reject_by_class, remap_class,
+    replace_annotations, rotate_selected, set_group_id, shift_annotations, snapshot_annotations,
+    topmost_hit, AlignMode, BulkConfirm, ClassHistory, DatasetState, Easing, MirrorAxis,
+    UndoHistory, DEFAULT_POINT_BOX_SIZE, DUPLICATE_OFFSET, ROTATE_FINE_STEP, ROTATE_STEP,
+};
 use crate::{Annotation, AppWindow};
 use slint::{ComponentHandle, Model};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 /// Sets up all annotation manipulation callbacks on the UI.
@@ -14,15 +23,73 @@ pub fn setup_annotation_callbacks(
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
     clipboard: Rc<RefCell<Vec<Annotation>>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
+    class_history: Rc<RefCell<ClassHistory>>,
 ) {
     setup_delete_annotation_at(ui, annotations.clone(), undo_history.clone());
     setup_delete_annotation(ui, annotations.clone(), undo_history.clone());
     setup_classify_at(ui, annotations.clone(), undo_history.clone());
     setup_classify_selected(ui, annotations.clone(), undo_history.clone());
+    setup_reclassify_region(ui, annotations.clone(), undo_history.clone());
+    setup_eyedropper_class(ui, annotations.clone());
+    setup_reject_class(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        config.clone(),
+        bulk_confirm.clone(),
+    );
+    setup_revert_frame(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        dataset_state.clone(),
+        config.clone(),
+        bulk_confirm.clone(),
+    );
+    setup_frame_bulk_edits(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        config.clone(),
+        bulk_confirm,
+        class_history,
+    );
+    setup_interpolate_selected(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        dataset_state.clone(),
+        config.clone(),
+    );
+    setup_track_ids(ui, annotations.clone(), undo_history.clone(), dataset_state);
+    setup_mirror_selected(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_duplicate_selected(
+        ui,
+        annotations.clone(),
+        undo_history.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_align_selected(ui, annotations.clone(), undo_history.clone());
+    setup_move_selected(ui, annotations.clone(), undo_history.clone(), image_dimensions.clone());
+    setup_rotate_annotation(ui, annotations.clone(), undo_history.clone());
+    setup_merge_selected_boxes(ui, annotations.clone(), undo_history.clone());
+    setup_convert_points_boxes(ui, annotations.clone(), undo_history.clone());
+    setup_shift_frame(ui, annotations.clone(), undo_history.clone(), image_dimensions.clone());
     setup_undo_action(ui, annotations.clone(), undo_history.clone());
     setup_redo_action(ui, annotations.clone(), undo_history.clone());
     setup_copy_annotation(ui, annotations.clone(), clipboard.clone());
-    setup_paste_annotation(ui, annotations, undo_history, clipboard);
+    setup_paste_annotation(ui, annotations, undo_history, clipboard, image_dimensions, config);
 }
 
 fn setup_delete_annotation_at(
@@ -34,30 +101,17 @@ fn setup_delete_annotation_at(
     ui.on_delete_annotation_at(move |x, y| {
         undo_history.borrow_mut().push(snapshot_annotations(&annotations));
 
-        let count = annotations.row_count();
-        for i in (0..count).rev() {
-            if let Some(ann) = annotations.row_data(i) {
-                if ann.state == "Rejected" {
-                    continue;
-                }
-                let inside = if ann.r#type.as_str() == "point" {
-                    let dx = x - ann.x;
-                    let dy = y - ann.y;
-                    (dx * dx + dy * dy).sqrt() < 10.0
-                } else {
-                    x >= ann.x && x <= ann.x + ann.width && y >= ann.y && y <= ann.y + ann.height
-                };
-
-                if inside {
-                    let mut rejected = ann;
-                    rejected.state = "Rejected".into();
-                    rejected.selected = false;
-                    annotations.set_row_data(i, rejected);
-                    if let Some(ui) = ui_weak.upgrade() {
-                        ui.set_status_text("Annotation deleted".into());
-                    }
-                    break;
-                }
+        let current = snapshot_annotations(&annotations);
+        if let Some(i) = topmost_hit(&current, x, y) {
+            let mut rejected = current[i].clone();
+            let status = if reject(&mut rejected) {
+                annotations.set_row_data(i, rejected);
+                "Annotation deleted"
+            } else {
+                "Annotation is locked"
+            };
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_status_text(status.into());
             }
         }
     });
@@ -73,11 +127,14 @@ fn setup_delete_annotation(
         undo_history.borrow_mut().push(snapshot_annotations(&annotations));
 
         if let Some(mut ann) = annotations.row_data(index as usize) {
-            ann.state = "Rejected".into();
-            ann.selected = false;
-            annotations.set_row_data(index as usize, ann);
+            let status = if reject(&mut ann) {
+                annotations.set_row_data(index as usize, ann);
+                "Annotation deleted (double-click)"
+            } else {
+                "Annotation is locked"
+            };
             if let Some(ui) = ui_weak.upgrade() {
-                ui.set_status_text("Annotation deleted (double-click)".into());
+                ui.set_status_text(status.into());
             }
         }
     });
@@ -92,36 +149,33 @@ fn setup_classify_at(
     ui.on_classify_at(move |x, y, new_class| {
         undo_history.borrow_mut().push(snapshot_annotations(&annotations));
 
-        let count = annotations.row_count();
-        for i in (0..count).rev() {
-            if let Some(mut ann) = annotations.row_data(i) {
-                if ann.state == "Rejected" {
-                    continue;
-                }
-                let inside = if ann.r#type.as_str() == "point" {
-                    let dx = x - ann.x;
-                    let dy = y - ann.y;
-                    (dx * dx + dy * dy).sqrt() < 10.0
-                } else {
-                    x >= ann.x && x <= ann.x + ann.width && y >= ann.y && y <= ann.y + ann.height
-                };
-
-                if inside {
-                    ann.class = new_class;
-                    if ann.state == "Pending" {
-                        ann.state = "Accepted".into();
-                    }
-                    annotations.set_row_data(i, ann);
-                    if let Some(ui) = ui_weak.upgrade() {
-                        ui.set_status_text(format!("Annotation reclassified to {}", new_class).into());
-                    }
-                    break;
-                }
+        let current = snapshot_annotations(&annotations);
+        if let Some(i) = topmost_hit(&current, x, y) {
+            let mut ann = current[i].clone();
+            apply_class(&mut ann, new_class);
+            annotations.set_row_data(i, ann);
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_status_text(format!("Annotation reclassified to {}", new_class).into());
             }
         }
     });
 }
 
+fn setup_eyedropper_class(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_eyedropper_class(move |x, y| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        match class_at(&snapshot_annotations(&annotations), x, y) {
+            Some(class) => {
+                ui.set_current_class(class);
+                let name = ui.invoke_get_class_name(class);
+                ui.set_status_text(format!("Picked class {} ({})", class, name).into());
+            }
+            None => ui.set_status_text("Eyedropper: no annotation under cursor".into()),
+        }
+    });
+}
+
 fn setup_classify_selected(
     ui: &AppWindow,
     annotations: Rc<slint::VecModel<Annotation>>,
@@ -136,10 +190,7 @@ fn setup_classify_selected(
         for i in 0..count {
             if let Some(mut ann) = annotations.row_data(i) {
                 if ann.selected && ann.state != "Rejected" {
-                    ann.class = new_class;
-                    if ann.state == "Pending" {
-                        ann.state = "Accepted".into();
-                    }
+                    apply_class(&mut ann, new_class);
                     annotations.set_row_data(i, ann);
                     updated = true;
                 }
@@ -154,6 +205,532 @@ fn setup_classify_selected(
     });
 }
 
+/// Reclassify everything fully inside a dragged region (Alt+drag in Neutral mode)
+fn setup_reclassify_region(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_reclassify_region(move |x0, y0, x1, y1, new_class| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let count = reclassify_region(&mut anns, (x0, y0), (x1, y1), new_class);
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            let name = ui.invoke_get_class_name(new_class);
+            ui.set_status_text(format!("Reclassified {count} annotation(s) to {name}").into());
+        }
+    });
+}
+
+fn setup_reject_class(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_reject_class(move |class_id| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let count = reject_by_class(&mut anns, class_id);
+
+        let limit = config.borrow().annotation_modes.confirm_bulk_above;
+        let action = format!("reject-class:{class_id}");
+        if !bulk_confirm.borrow_mut().check(&action, &changed_ids(&before, &anns), limit) {
+            if let Some(ui) = ui_weak.upgrade() {
+                let name = ui.invoke_get_class_name(class_id);
+                ui.set_status_text(
+                    format!("Reject {count} annotation(s) of class {name}? Press again to confirm")
+                        .into(),
+                );
+            }
+            return;
+        }
+
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            let name = ui.invoke_get_class_name(class_id);
+            ui.set_status_text(format!("Rejected {} annotation(s) of class {}", count, name).into());
+        }
+    });
+}
+
+fn setup_mirror_selected(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_mirror_selected(move |axis| {
+        let Some(axis) = MirrorAxis::parse(&axis) else { return };
+        let img_size = *image_dimensions.borrow();
+        let pairs = config.borrow().annotation_modes.mirror_class_pairs.clone();
+
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let mut count = 0;
+        for ann in anns.iter_mut().filter(|a| a.selected && can_edit(a)) {
+            mirror_annotation(ann, img_size, axis);
+            ann.class = mirrored_class(ann.class, &pairs);
+            count += 1;
+        }
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text(format!("Mirrored {} annotation(s)", count).into());
+        }
+    });
+}
+
+fn setup_duplicate_selected(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_duplicate_selected(move || {
+        let img_size = *image_dimensions.borrow();
+        let author = config.borrow().user.author.clone();
+
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let count = duplicate_selected(&mut anns, DUPLICATE_OFFSET, img_size, &author);
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            let status = if count > 0 {
+                format!("Duplicated {} annotation(s)", count)
+            } else {
+                "No annotation selected to duplicate".to_string()
+            };
+            ui.set_status_text(status.into());
+        }
+    });
+}
+
+/// Line up the selected boxes with the first selected one
+fn setup_align_selected(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_align_selected(move |mode| {
+        let Some(mode) = AlignMode::parse(&mode) else { return };
+        let before = snapshot_annotations(&annotations);
+        let selected: Vec<usize> =
+            before.iter().enumerate().filter(|(_, a)| a.selected).map(|(i, _)| i).collect();
+        let mut anns = before.clone();
+        let count = align_boxes(&mut anns, &selected, mode);
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            let status = if selected.len() < 2 {
+                "Select two or more boxes to align".to_string()
+            } else {
+                format!("Aligned {count} box(es) to the first selected")
+            };
+            ui.set_status_text(status.into());
+        }
+    });
+}
+
+/// Drag a multi-selection: `start-move-selected` decides whether a drag on
+/// annotation `index` moves the group (it must be part of a selection of two
+/// or more) and takes the undo snapshot; `move-selected` then applies each
+/// pointer delta. Rows are updated in place so the dragged item survives.
+fn setup_move_selected(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+) {
+    let model = annotations.clone();
+    ui.on_start_move_selected(move |index| {
+        let before = snapshot_annotations(&model);
+        let pressed_selected = before.get(index as usize).is_some_and(|a| a.selected);
+        let group = before.iter().filter(|a| a.selected && can_edit(a)).count();
+        if !pressed_selected || group < 2 {
+            return false;
+        }
+        undo_history.borrow_mut().push(before);
+        true
+    });
+
+    ui.on_move_selected(move |dx, dy| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        if move_selected(&mut anns, dx, dy, *image_dimensions.borrow()) == 0 {
+            return;
+        }
+        for (row, (old, new)) in before.iter().zip(anns).enumerate() {
+            if (old.x, old.y) != (new.x, new.y) {
+                annotations.set_row_data(row, new);
+            }
+        }
+    });
+}
+
+/// Turn the selected rotated boxes with `[`/`]`
+fn setup_rotate_annotation(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_rotate_annotation(move |steps, fine| {
+        let step = if fine { ROTATE_FINE_STEP } else { ROTATE_STEP };
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let turned = rotate_selected(&mut anns, steps as f32 * step);
+        let status = match anns.iter().find(|a| a.selected && a.r#type == "rbbox") {
+            Some(ann) if turned > 0 => format!("Rotated {turned} box(es) to {:.0}°", ann.rotation),
+            _ => "Select a rotated box (drawn with R) to rotate it".to_string(),
+        };
+        if turned > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text(status.into());
+        }
+    });
+}
+
+/// Join fragmented detections into one box
+fn setup_merge_selected_boxes(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_merge_selected_boxes(move || {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let merged = merge_selected_boxes(&mut anns);
+        if merged > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            let status = if merged == 0 {
+                "Select two or more boxes to merge".to_string()
+            } else {
+                format!("Merged {} boxes into one", merged + 1)
+            };
+            ui.set_status_text(status.into());
+        }
+    });
+}
+
+/// Bulk-convert the frame's points to boxes and back
+fn setup_convert_points_boxes(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let convert = move |ui: &AppWindow, to_boxes: Option<f32>| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let (count, status) = match to_boxes {
+            Some(size) => {
+                let count = points_to_boxes(&mut anns, size);
+                (count, format!("Converted {count} point(s) to {size} px boxes"))
+            }
+            None => {
+                let count = boxes_to_points(&mut anns);
+                (count, format!("Converted {count} box(es) to points"))
+            }
+        };
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+        ui.set_status_text(status.into());
+    };
+    let convert = Rc::new(convert);
+
+    let ui_weak = ui.as_weak();
+    let to_boxes = convert.clone();
+    ui.on_points_to_boxes(move |size| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        // Zero or less picks the default size
+        let size = if size > 0.0 { size } else { DEFAULT_POINT_BOX_SIZE };
+        to_boxes(&ui, Some(size));
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_boxes_to_points(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        convert(&ui, None);
+    });
+}
+
+/// Move the whole frame's annotations to correct a systematic offset
+fn setup_shift_frame(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_shift_frame(move |dx, dy| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let count = shift_annotations(&mut anns, dx, dy, *image_dimensions.borrow());
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text(format!("Shifted {count} annotation(s) by ({dx}, {dy}) px").into());
+        }
+    });
+}
+
+/// Fill the frames since the previous keyframe with boxes eased towards the
+/// selected boxes of this frame
+fn setup_interpolate_selected(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_interpolate_selected(move |easing| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Some(easing) = Easing::from_name(&easing) else {
+            ui.set_status_text(format!("Unknown easing '{easing}'").into());
+            return;
+        };
+        let selected: Vec<Annotation> =
+            annotations.iter().filter(|a| a.selected && a.r#type == "bbox").collect();
+        if selected.is_empty() {
+            ui.set_status_text("Select the boxes to interpolate towards".into());
+            return;
+        }
+        let mut ds_ref = dataset_state.borrow_mut();
+        let Some(ds) = ds_ref.as_mut() else { return };
+        let end = ds.current_index;
+        let author = config.borrow().user.author.clone();
+        let mut history = undo_history.borrow_mut();
+        let added = interpolate_from_keyframe(ds, &mut history, end, &selected, easing, &author);
+        ui.set_status_text(
+            format!("Interpolated {added} box(es) into earlier frames (Pending)").into(),
+        );
+    });
+}
+
+/// Link the selected annotations across frames with a track id (MOT export).
+/// "Last track" reuses the id assigned most recently, to follow one object
+/// from frame to frame.
+fn setup_track_ids(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+) {
+    let last_track = Cell::new(0);
+    let live = annotations.clone();
+    let assign = move |ui: &AppWindow, group_id: Option<i32>| {
+        let before = snapshot_annotations(&annotations);
+        let mut anns = before.clone();
+        let changed = set_group_id(&mut anns, group_id);
+        if changed > 0 {
+            undo_history.borrow_mut().push(before);
+            replace_annotations(&annotations, anns);
+        }
+        let status = match group_id {
+            Some(id) => format!("Assigned track {id} to {changed} annotation(s)"),
+            None => format!("Cleared the track of {changed} annotation(s)"),
+        };
+        ui.set_status_text(status.into());
+    };
+    let assign = Rc::new(assign);
+
+    let ui_weak = ui.as_weak();
+    let assign_track = assign.clone();
+    ui.on_assign_track_id(move |new_track| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        if !live.iter().any(|a| a.selected && can_edit(&a)) {
+            ui.set_status_text("Select the annotations to put on a track".into());
+            return;
+        }
+        let id = if new_track || last_track.get() == 0 {
+            let live_max = max_group_id(&snapshot_annotations(&live));
+            let mut ds_opt = dataset_state.borrow_mut();
+            let dataset_max = ds_opt.as_mut().map_or(0, dataset_max_track_id);
+            let id = dataset_max.max(live_max).max(last_track.get()) + 1;
+            if let Some(ds) = ds_opt.as_mut() {
+                ds.max_track_id = Some(id);
+            }
+            id
+        } else {
+            last_track.get()
+        };
+        last_track.set(id);
+        assign_track(&ui, Some(id));
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_clear_track_id(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        assign(&ui, None);
+    });
+}
+
+fn setup_revert_frame(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_revert_frame(move || {
+        let initial = dataset_state
+            .borrow()
+            .as_ref()
+            .and_then(|ds| ds.initial_for(ds.current_index));
+        let Some(mut initial) = initial else {
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_status_text("Nothing to revert to for this frame".into());
+            }
+            return;
+        };
+
+        // Every current annotation is replaced
+        let affected: Vec<i32> = annotations.iter().map(|a| a.id).collect();
+        let count = affected.len();
+        let limit = config.borrow().annotation_modes.confirm_bulk_above;
+        if !bulk_confirm.borrow_mut().check("revert-frame", &affected, limit) {
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_status_text(
+                    format!("Revert {count} annotation(s) on this frame? Press again to confirm")
+                        .into(),
+                );
+            }
+            return;
+        }
+
+        undo_history.borrow_mut().push(snapshot_annotations(&annotations));
+        for ann in initial.iter_mut() {
+            ann.selected = false;
+        }
+        replace_annotations(&annotations, initial);
+
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text("Frame reverted to loaded state (Ctrl+Z to undo)".into());
+        }
+    });
+}
+
+/// Clear frame, purge rejected and remap previous class → current class.
+/// Each edit is worked out on a copy first so the confirmation prompt can
+/// name how many annotations it touches.
+fn setup_frame_bulk_edits(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
+    class_history: Rc<RefCell<ClassHistory>>,
+) {
+    // Runs `edit` once confirmed; `verb` starts both the prompt and the status
+    let run_bulk_edit = Rc::new(
+        move |ui: &AppWindow,
+              action: &str,
+              verb: &str,
+              edit: &dyn Fn(&mut Vec<Annotation>) -> usize| {
+            let before = snapshot_annotations(&annotations);
+            let mut anns = before.clone();
+            let count = edit(&mut anns);
+
+            let limit = config.borrow().annotation_modes.confirm_bulk_above;
+            if !bulk_confirm.borrow_mut().check(action, &changed_ids(&before, &anns), limit) {
+                ui.set_status_text(
+                    format!("{verb} {count} annotation(s)? Choose again to confirm").into(),
+                );
+                return;
+            }
+
+            if count > 0 {
+                undo_history.borrow_mut().push(before);
+                replace_annotations(&annotations, anns);
+            }
+            ui.set_status_text(format!("{verb}: {count} annotation(s) (Ctrl+Z to undo)").into());
+        },
+    );
+
+    let ui_weak = ui.as_weak();
+    let run = run_bulk_edit.clone();
+    ui.on_clear_frame(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        run(&ui, "clear-frame", "Reject", &|anns| clear_frame(anns));
+    });
+
+    let ui_weak = ui.as_weak();
+    let run = run_bulk_edit.clone();
+    ui.on_purge_rejected(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        run(&ui, "purge-rejected", "Permanently remove rejected", &|anns| purge_rejected(anns));
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_remap_previous_class(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let to = ui.get_current_class();
+        let Some(from) = class_history.borrow().previous().filter(|&from| from != to) else {
+            ui.set_status_text("Pick the class to remap from, then the class to remap to".into());
+            return;
+        };
+        let verb = format!(
+            "Remap {} → {}",
+            ui.invoke_get_class_name(from),
+            ui.invoke_get_class_name(to)
+        );
+        let action = format!("remap-class:{from}:{to}");
+        run_bulk_edit(&ui, &action, &verb, &|anns| remap_class(anns, from, to));
+    });
+}
+
+/// Status after undo/redo; names rejected rows compact undo left out
+fn undo_status(action: &str, dropped: usize) -> String {
+    if dropped == 0 {
+        action.to_string()
+    } else {
+        format!("{action} (compact undo dropped {dropped} rejected annotation(s))")
+    }
+}
+
 fn setup_undo_action(
     ui: &AppWindow,
     annotations: Rc<slint::VecModel<Annotation>>,
@@ -162,10 +739,13 @@ fn setup_undo_action(
     let ui_weak = ui.as_weak();
     ui.on_undo_action(move || {
         let current = snapshot_annotations(&annotations);
-        if let Some(previous) = undo_history.borrow_mut().undo(current) {
+        let compact = undo_history.borrow().excludes_rejected();
+        if let Some(previous) = undo_history.borrow_mut().undo(current.clone()) {
+            let dropped =
+                if compact { UndoHistory::dropped_rejected(&current, &previous) } else { 0 };
             replace_annotations(&annotations, previous);
             if let Some(ui) = ui_weak.upgrade() {
-                ui.set_status_text("Undo".into());
+                ui.set_status_text(undo_status("Undo", dropped).into());
             }
         } else if let Some(ui) = ui_weak.upgrade() {
             ui.set_status_text("Nothing to undo".into());
@@ -181,10 +761,13 @@ fn setup_redo_action(
     let ui_weak = ui.as_weak();
     ui.on_redo_action(move || {
         let current = snapshot_annotations(&annotations);
-        if let Some(next) = undo_history.borrow_mut().redo(current) {
+        let compact = undo_history.borrow().excludes_rejected();
+        if let Some(next) = undo_history.borrow_mut().redo(current.clone()) {
+            let dropped =
+                if compact { UndoHistory::dropped_rejected(&current, &next) } else { 0 };
             replace_annotations(&annotations, next);
             if let Some(ui) = ui_weak.upgrade() {
-                ui.set_status_text("Redo".into());
+                ui.set_status_text(undo_status("Redo", dropped).into());
             }
         } else if let Some(ui) = ui_weak.upgrade() {
             ui.set_status_text("Nothing to redo".into());
@@ -228,6 +811,8 @@ fn setup_paste_annotation(
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
     clipboard: Rc<RefCell<Vec<Annotation>>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_paste_annotation(move || {
@@ -241,29 +826,15 @@ fn setup_paste_annotation(
         }
 
         let snapshot = snapshot_annotations(&annotations);
+        let mut anns = snapshot.clone();
+        let author = config.borrow().user.author.clone();
+        let img_size = *image_dimensions.borrow();
+        let count = paste_annotations(&mut anns, &copied_anns, img_size, &author);
         undo_history.borrow_mut().push(snapshot);
-
-        let existing: Vec<_> = (0..annotations.row_count())
-            .filter_map(|i| annotations.row_data(i))
-            .collect();
-        let mut next_id = crate::state::next_id_from_annotations(&existing, 1);
-
-        let offset_x = 0.05;
-        let offset_y = 0.05;
-
-        for copied_ann in copied_anns.iter() {
-            let mut new_ann = copied_ann.clone();
-            new_ann.id = next_id;
-            new_ann.x += offset_x;
-            new_ann.y += offset_y;
-            new_ann.selected = false;
-
-            annotations.push(new_ann);
-            next_id += 1;
-        }
+        replace_annotations(&annotations, anns);
 
         if let Some(ui) = ui_weak.upgrade() {
-            ui.set_status_text(format!("Pasted {} annotation(s)", copied_anns.len()).into());
+            ui.set_status_text(format!("Pasted {count} annotation(s)").into());
         }
     });
 }
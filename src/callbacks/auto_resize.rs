@@ -1,11 +1,17 @@
 //! Auto-resize callback using edge detection.
 //!
-//! Handles: auto_resize_annotation (smart bbox resizing)
+//! Handles: auto_resize_annotation (smart bbox resizing), auto_resize_all
+//! (every box on the frame, run in chunks with progress)
 
-use crate::state::DatasetState;
+use crate::callbacks::batch::run_batch;
+use crate::progress::format_duration;
+use crate::state::{
+    can_edit, hit_test, snapshot_annotations, stamp_modified, DatasetState, UndoHistory,
+};
 use crate::{auto_resize, Annotation, AppWindow};
 use slint::{ComponentHandle, Model};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
 use std::rc::Rc;
 
 /// Sets up the auto-resize annotation callback on the UI.
@@ -14,7 +20,15 @@ pub fn setup_auto_resize_callback(
     annotations: Rc<slint::VecModel<Annotation>>,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
 ) {
+    setup_auto_resize_all(
+        ui,
+        annotations.clone(),
+        dataset_state.clone(),
+        image_dimensions.clone(),
+        undo_history,
+    );
     let ui_weak = ui.as_weak();
     ui.on_auto_resize_annotation(move |img_x, img_y, _gesture_kind| {
         let count = annotations.row_count();
@@ -23,16 +37,11 @@ pub fn setup_auto_resize_callback(
         // Find topmost bbox containing the click
         for i in (0..count).rev() {
             if let Some(ann) = annotations.row_data(i) {
-                if ann.state == "Rejected" {
+                if !can_edit(&ann) {
                     continue;
                 }
                 let is_box = ann.r#type.as_str() == "bbox" || ann.r#type.as_str() == "rbbox";
-                let inside = img_x >= ann.x
-                    && img_x <= ann.x + ann.width
-                    && img_y >= ann.y
-                    && img_y <= ann.y + ann.height;
-
-                if is_box && inside {
+                if is_box && hit_test(&ann, img_x, img_y) {
                     target_index = Some(i);
                     break;
                 }
@@ -69,6 +78,7 @@ pub fn setup_auto_resize_callback(
                         if ann.state == "Pending" {
                             ann.state = "Accepted".into();
                         }
+                        stamp_modified(&mut ann);
                         annotations.set_row_data(idx, ann);
 
                         if let Some(ui) = ui_weak.upgrade() {
@@ -86,3 +96,85 @@ pub fn setup_auto_resize_callback(
         }
     });
 }
+
+/// Index and image path of the frame on screen
+fn current_frame(dataset_state: &RefCell<Option<DatasetState>>) -> Option<(usize, PathBuf)> {
+    let ds_opt = dataset_state.try_borrow().ok()?;
+    let ds = ds_opt.as_ref()?;
+    let entry = ds.entries.get(ds.current_index)?;
+    Some((ds.current_index, entry.image_path.clone()))
+}
+
+/// Snap every editable box on the frame to image edges, a few per event loop
+/// turn so the progress bar updates; all changes undo as one step
+fn setup_auto_resize_all(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_auto_resize_all(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Some((frame, path)) = current_frame(&dataset_state) else {
+            ui.set_status_text("Auto-resize: image path not available".into());
+            return;
+        };
+        let before = snapshot_annotations(&annotations);
+        let total = before.len();
+        let img_size = *image_dimensions.borrow();
+        let resized = Rc::new(Cell::new(0usize));
+
+        let step = {
+            let (annotations, dataset_state) = (annotations.clone(), dataset_state.clone());
+            let resized = resized.clone();
+            move |idx: usize| {
+                // Navigating away swaps the model for another frame's
+                let same_frame = current_frame(&dataset_state).is_some_and(|(i, _)| i == frame);
+                if !same_frame || annotations.row_count() != total {
+                    return Err("frame changed during auto-resize".to_string());
+                }
+                let Some(mut ann) = annotations.row_data(idx) else { return Ok(()) };
+                let is_box = ann.r#type.as_str() == "bbox" || ann.r#type.as_str() == "rbbox";
+                if !is_box || !can_edit(&ann) {
+                    return Ok(());
+                }
+                let bbox = (ann.x, ann.y, ann.width, ann.height);
+                if let Some((x, y, w, h)) = auto_resize::smart_auto_resize(&path, bbox, img_size) {
+                    (ann.x, ann.y, ann.width, ann.height) = (x, y, w, h);
+                    if ann.state == "Pending" {
+                        ann.state = "Accepted".into();
+                    }
+                    stamp_modified(&mut ann);
+                    annotations.set_row_data(idx, ann);
+                    resized.set(resized.get() + 1);
+                }
+                Ok(())
+            }
+        };
+
+        let ui_weak = ui.as_weak();
+        let undo_history = undo_history.clone();
+        let finish = move |outcome: Result<std::time::Duration, String>| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            match outcome {
+                Ok(elapsed) => {
+                    if resized.get() > 0 {
+                        undo_history.borrow_mut().push(before);
+                    }
+                    ui.set_status_text(
+                        format!(
+                            "Auto-resized {} box(es) in {}",
+                            resized.get(),
+                            format_duration(elapsed)
+                        )
+                        .into(),
+                    );
+                }
+                Err(e) => ui.set_status_text(format!("Auto-resize stopped: {e}").into()),
+            }
+        };
+        run_batch(&ui, total, step, finish);
+    });
+}
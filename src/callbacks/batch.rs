@@ -0,0 +1,91 @@
+//! Long batch operations (exports, auto-resize-all) run a chunk at a time
+//! from the event loop.
+//!
+//! Each chunk works through items for a short time slice and then yields, so
+//! the window repaints the progress bar and stays responsive in between.
+
+use crate::progress::{Progress, ProgressReport};
+use crate::AppWindow;
+use slint::ComponentHandle;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Time spent on items before yielding back to the event loop
+const CHUNK_BUDGET: Duration = Duration::from_millis(50);
+
+type Step = Box<dyn FnMut(usize) -> Result<(), String>>;
+type Finish = Box<dyn FnOnce(Result<Duration, String>)>;
+
+struct Batch {
+    ui_weak: slint::Weak<AppWindow>,
+    next: usize,
+    total: usize,
+    progress: Progress,
+    step: Step,
+    finish: Option<Finish>,
+}
+
+/// Run `step` on items `0..total` spread over event loop iterations,
+/// mirroring progress in the status bar. The first error stops the batch.
+/// `finish` gets the elapsed time or that error, after the bar is reset.
+pub fn run_batch(
+    ui: &AppWindow,
+    total: usize,
+    step: impl FnMut(usize) -> Result<(), String> + 'static,
+    finish: impl FnOnce(Result<Duration, String>) + 'static,
+) {
+    let batch = Rc::new(RefCell::new(Batch {
+        ui_weak: ui.as_weak(),
+        next: 0,
+        total,
+        progress: Progress::new(total),
+        step: Box::new(step),
+        finish: Some(Box::new(finish)),
+    }));
+    slint::Timer::single_shot(Duration::ZERO, move || run_chunk(batch));
+}
+
+fn run_chunk(batch: Rc<RefCell<Batch>>) {
+    let deadline = Instant::now() + CHUNK_BUDGET;
+    let outcome = {
+        let b = &mut *batch.borrow_mut();
+        loop {
+            if b.next >= b.total {
+                break Some(Ok(b.progress.elapsed()));
+            }
+            let index = b.next;
+            b.next += 1;
+            if let Err(e) = (b.step)(index) {
+                break Some(Err(e));
+            }
+            show_progress(&b.ui_weak, b.progress.advance());
+            if Instant::now() >= deadline {
+                break None;
+            }
+        }
+    };
+    let Some(result) = outcome else {
+        slint::Timer::single_shot(Duration::ZERO, move || run_chunk(batch));
+        return;
+    };
+    let (ui_weak, finish) = {
+        let mut b = batch.borrow_mut();
+        (b.ui_weak.clone(), b.finish.take())
+    };
+    if let Some(ui) = ui_weak.upgrade() {
+        ui.set_progress_fraction(0.0);
+        ui.set_progress_eta("".into());
+    }
+    if let Some(finish) = finish {
+        finish(result);
+    }
+}
+
+/// Mirror a batch progress report in the status bar; `None` keeps the last one
+fn show_progress(ui_weak: &slint::Weak<AppWindow>, report: Option<ProgressReport>) {
+    if let (Some(report), Some(ui)) = (report, ui_weak.upgrade()) {
+        ui.set_progress_fraction(report.fraction);
+        ui.set_progress_eta(report.eta.into());
+    }
+}
@@ -0,0 +1,78 @@
+//! Class selection callbacks.
+//!
+//! Handles: tracking class changes, swapping between the last two classes, and
+//! picking a class by searching its name
+
+use crate::classes::{search_classes, ClassConfig};
+use crate::state::ClassHistory;
+use crate::{Annotation, AppWindow, ClassSearchRow};
+use slint::{ComponentHandle, Model};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Sets up all class selection callbacks on the UI.
+pub fn setup_class_callbacks(
+    ui: &AppWindow,
+    class_history: Rc<RefCell<ClassHistory>>,
+    classes: Rc<RefCell<ClassConfig>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+) {
+    setup_class_changed(ui, class_history.clone());
+    setup_swap_last_class(ui, class_history);
+    setup_class_search(ui, classes, annotations);
+}
+
+fn setup_class_changed(ui: &AppWindow, class_history: Rc<RefCell<ClassHistory>>) {
+    ui.on_class_changed(move |class_id| {
+        class_history.borrow_mut().select(class_id);
+    });
+}
+
+fn setup_swap_last_class(ui: &AppWindow, class_history: Rc<RefCell<ClassHistory>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_swap_last_class(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let swapped = class_history.borrow_mut().swap();
+        match swapped {
+            Some(class_id) => {
+                ui.set_current_class(class_id);
+                let name = ui.invoke_get_class_name(class_id);
+                ui.set_status_text(format!("Swapped to class {} ({})", class_id, name).into());
+            }
+            None => ui.set_status_text("No previous class to swap to".into()),
+        }
+    });
+}
+
+/// Fill the search dialog with matching classes and apply the one picked
+fn setup_class_search(
+    ui: &AppWindow,
+    classes: Rc<RefCell<ClassConfig>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_class_search(move |query| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let rows: Vec<ClassSearchRow> = search_classes(&classes.borrow(), &query)
+            .into_iter()
+            .map(|class_id| ClassSearchRow {
+                class_id,
+                name: ui.invoke_get_class_name(class_id),
+            })
+            .collect();
+        ui.set_class_search_results(Rc::new(slint::VecModel::from(rows)).into());
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_pick_class(move |class_id| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        ui.set_current_class(class_id);
+        let name = ui.invoke_get_class_name(class_id);
+        if annotations.iter().any(|a| a.selected && a.state != "Rejected") {
+            // Same path as the digit keys, so it is undo-able
+            ui.invoke_classify_selected(class_id);
+        } else {
+            ui.set_status_text(format!("Class {class_id} ({name}) selected").into());
+        }
+    });
+}
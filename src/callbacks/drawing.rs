@@ -1,8 +1,14 @@
-//! Drawing callbacks for bbox/point creation.
+//! Drawing callbacks for bbox/rbbox/point creation.
 //!
 //! Handles: start_drawing, update_drawing, finish_drawing, cancel_drawing
 
-use crate::state::{snapshot_annotations, DrawState, UndoHistory};
+use crate::classes::ClassConfig;
+use crate::config::AppConfig;
+use crate::state::{
+    apply_lock_on_create, push_created, snapshot_annotations, stamp_created, DrawState,
+    UndoHistory,
+};
+use crate::transform::{box_from_corners, snap_point};
 use crate::{Annotation, AppWindow};
 use slint::{ComponentHandle, Model};
 use std::cell::RefCell;
@@ -14,10 +20,12 @@ pub fn setup_drawing_callbacks(
     draw_state: Rc<RefCell<DrawState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    classes: Rc<RefCell<ClassConfig>>,
 ) {
     setup_start_drawing(ui, draw_state.clone(), annotations.clone());
     setup_update_drawing(ui, draw_state.clone());
-    setup_finish_drawing(ui, draw_state, annotations, undo_history);
+    setup_finish_drawing(ui, draw_state, annotations, undo_history, config, classes);
     setup_cancel_drawing(ui);
 }
 
@@ -58,10 +66,8 @@ fn setup_update_drawing(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>) {
         let state = draw_state.borrow();
 
         if let Some(ui) = ui_weak.upgrade() {
-            let min_x = state.start_x.min(x);
-            let min_y = state.start_y.min(y);
-            let width = (x - state.start_x).abs();
-            let height = (y - state.start_y).abs();
+            let (min_x, min_y, width, height) =
+                box_from_corners((state.start_x, state.start_y), (x, y));
 
             ui.set_preview_x(min_x);
             ui.set_preview_y(min_y);
@@ -76,6 +82,8 @@ fn setup_finish_drawing(
     draw_state: Rc<RefCell<DrawState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    classes: Rc<RefCell<ClassConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_finish_drawing(move |x, y| {
@@ -87,20 +95,23 @@ fn setup_finish_drawing(
         if let Some(ui) = ui_weak.upgrade() {
             ui.set_show_preview(false);
 
-            let min_x = state.start_x.min(x);
-            let min_y = state.start_y.min(y);
-            let width = (x - state.start_x).abs();
-            let height = (y - state.start_y).abs();
+            let grid = config.borrow().annotation_modes.snap_grid;
+            let start = snap_point((state.start_x, state.start_y), grid);
+            let (x, y) = snap_point((x, y), grid);
+            let (min_x, min_y, width, height) = box_from_corners(start, (x, y));
 
             let tool = ui.get_current_tool();
             let class = ui.get_current_class();
+            let author = config.borrow().user.author.clone();
+            let select = config.borrow().annotation_modes.select_on_create;
 
-            if tool.as_str().starts_with("BBox") {
+            let rotated = tool.as_str().starts_with("RBBox");
+            if tool.as_str().starts_with("BBox") || rotated {
                 // Create bbox annotation only if size is reasonable (at least 5 pixels)
                 if width >= 5.0 && height >= 5.0 {
-                    annotations.push(Annotation {
+                    let mut ann = Annotation {
                         id: state.next_id,
-                        r#type: "bbox".into(),
+                        r#type: if rotated { "rbbox" } else { "bbox" }.into(),
                         x: min_x,
                         y: min_y,
                         width,
@@ -112,12 +123,16 @@ fn setup_finish_drawing(
                         vertices: "".into(),
                         polygon_vertices: Default::default(),
                         polygon_path_commands: "".into(),
-                    });
+                        ..Default::default()
+                    };
+                    stamp_created(&mut ann, &author);
+                    apply_lock_on_create(&mut ann, &classes.borrow());
+                    push_created(&annotations, ann, select);
                     state.next_id += 1;
                 }
             } else if tool.as_str().starts_with("Point") {
                 // Create point annotation at click location (no minimum size)
-                annotations.push(Annotation {
+                let mut ann = Annotation {
                     id: state.next_id,
                     r#type: "point".into(),
                     x,
@@ -131,7 +146,11 @@ fn setup_finish_drawing(
                     vertices: "".into(),
                     polygon_vertices: Default::default(),
                     polygon_path_commands: "".into(),
-                });
+                    ..Default::default()
+                };
+                stamp_created(&mut ann, &author);
+                apply_lock_on_create(&mut ann, &classes.borrow());
+                push_created(&annotations, ann, select);
                 state.next_id += 1;
             }
         }
@@ -1,16 +1,39 @@
 //! File operation callbacks.
 //!
-//! Handles: save, open, new dataset, export COCO/VOC, and auto-save timer
+//! Handles: save, frame completion/review, image tags, open, new dataset, open video, next/prev dataset, open/save project, export COCO/VOC (whole dataset or current image)/contact sheet/heatmap/chips/MOT tracks, import CSV, load review corrections, reference diff, rename images, and auto-save timer
 
 use crate::state::{
-    create_dataset_from_folder, load_dataset, load_image_from_entry, save_all, save_current_state,
-    DatasetState, ViewState,
+    accept_pending, apply_corrections, cached_frame_mut, canvas_size, clamp_view,
+    create_dataset_from_folder, create_dataset_from_video, load_corrections, load_dataset,
+    load_image_from_entry, load_yolo_annotations, next_id_from_annotations, polygon_points,
+    rename_dataset_images, replace_annotations, save_all, save_backup, save_current_state,
+    set_polygon_points, sibling_manifests, snapshot_annotations, zoom_limits, DatasetEntry,
+    DatasetState, UndoHistory, ViewState, VIDEO_EXTENSIONS,
 };
-use crate::{classes, export, Annotation, AppWindow};
-use slint::ComponentHandle;
+use crate::config::{export_start_dir, record_export_dir, save_config, AppConfig, ExportConfig};
+use crate::export::preview::{
+    build_density_heatmap, compose_contact_sheet, export_chips, render_heatmap, render_thumbnail,
+    CONTACT_SHEET_FILE_NAME, CONTACT_SHEET_INDEX_FILE_NAME, HEATMAP_BINS, HEATMAP_CELL_SIZE,
+    TILE_SIZE,
+};
+use crate::export::csv::parse_annotation_csv;
+use crate::export::mot::{build_mot_tracks, MOT_FILE_NAME};
+use crate::export::import::{
+    detect_format, import_annotations, load_coco_reference, ReferenceAnnotations,
+};
+use crate::export::summary::{build_export_summary, SUMMARY_FILE_NAME};
+use crate::export::{ExportFormat, ExportResult};
+use crate::callbacks::batch::run_batch;
+use crate::progress::format_duration;
+use crate::project::{load_project, save_project, Project, PROJECT_EXTENSION};
+use crate::validation::{greedy_match, validate_counts};
+use crate::{classes, export, logging, Annotation, AppWindow, DiffMark};
+use slint::{ComponentHandle, Model};
 use std::cell::RefCell;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::Duration;
 
 /// Type alias for the image loader closure
 pub type ImageLoader = Rc<dyn Fn(usize)>;
@@ -21,31 +44,137 @@ pub fn setup_file_callbacks(
     loader: ImageLoader,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
     classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
-    setup_save_dataset(ui, dataset_state.clone(), annotations.clone(), image_dimensions.clone());
-    setup_toggle_frame_completion(ui, dataset_state.clone());
-    setup_open_dataset(ui, loader.clone(), dataset_state.clone(), classes.clone());
-    setup_new_dataset(ui, loader, dataset_state.clone(), classes.clone());
-    setup_export_coco(ui, dataset_state.clone(), classes.clone());
-    setup_export_voc(ui, dataset_state.clone(), classes);
+    setup_save_dataset(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        undo_history.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_toggle_frame_completion(ui, dataset_state.clone(), annotations.clone(), config.clone());
+    setup_toggle_reviewed(ui, dataset_state.clone());
+    setup_image_tags(ui, dataset_state.clone());
+    setup_open_dataset(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_new_dataset(ui, loader.clone(), dataset_state.clone(), classes.clone(), config.clone());
+    setup_open_video(ui, loader.clone(), dataset_state.clone(), classes.clone(), config.clone());
+    setup_project(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        undo_history,
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_import_csv(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+    );
+    setup_load_corrections(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+    );
+    setup_reference_diff(ui, dataset_state.clone(), annotations.clone(), classes.clone());
+    setup_rename_images(ui, dataset_state.clone(), config.clone());
+    setup_switch_dataset(
+        ui,
+        loader,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_export_coco(ui, dataset_state.clone(), classes.clone(), config.clone());
+    setup_export_voc(ui, dataset_state.clone(), classes.clone(), config.clone());
+    setup_export_current(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_export_contact_sheet(ui, dataset_state.clone(), classes.clone(), config.clone());
+    setup_export_heatmap(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+    );
+    setup_export_mot(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_export_yolo(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes.clone(),
+        config.clone(),
+    );
+    setup_export_chips(
+        ui,
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        classes,
+        config.clone(),
+    );
     setup_view_changed(ui, dataset_state.clone(), image_dimensions.clone());
-    setup_auto_save_timer(ui, dataset_state, annotations, image_dimensions);
+    setup_auto_save_timer(ui, dataset_state, annotations, image_dimensions, config);
 }
 
 fn setup_save_dataset(
     ui: &AppWindow,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_save_dataset(move || {
         if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
             if let Some(ds) = ds_opt.as_mut() {
+                accept_pending_on_screen(&annotations, &undo_history, &config);
                 save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
-                match save_all(ds) {
+                let export_config = config.borrow().export.clone();
+                let result = save_all(ds, &export_config).and_then(|_| {
+                    if export_config.write_data_yaml {
+                        write_dataset_yaml(ds, &classes.borrow())
+                    } else {
+                        Ok(())
+                    }
+                });
+                match result {
                     Ok(_) => ui.set_status_text("Save successful".into()),
                     Err(e) => ui.set_status_text(format!("Save failed: {e}").into()),
                 }
@@ -54,19 +183,80 @@ fn setup_save_dataset(
     });
 }
 
-fn setup_toggle_frame_completion(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+/// With `export.accept_pending_on_save`, accept the Pending annotations on
+/// screen as one undo step before an explicit save; `save_all` accepts those of
+/// the other cached frames
+fn accept_pending_on_screen(
+    annotations: &slint::VecModel<Annotation>,
+    undo_history: &RefCell<UndoHistory>,
+    config: &RefCell<AppConfig>,
+) {
+    if !config.borrow().export.accept_pending_on_save {
+        return;
+    }
+    let before = snapshot_annotations(annotations);
+    let mut anns = before.clone();
+    if accept_pending(&mut anns) > 0 {
+        undo_history.borrow_mut().push(before);
+        replace_annotations(annotations, anns);
+    }
+}
+
+/// Write `data.yaml` next to the manifest, pointing at the folder of the first image
+fn write_dataset_yaml(ds: &DatasetState, classes: &classes::ClassConfig) -> Result<(), String> {
+    let Some(manifest) = ds.manifest_path.as_ref() else { return Ok(()) };
+    let dir = manifest.parent().unwrap_or(Path::new("."));
+    let images_dir = ds
+        .entries
+        .first()
+        .and_then(|e| e.image_path.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| dir.to_path_buf());
+    let classes = ds.class_config.as_ref().unwrap_or(classes);
+    export::yolo::write_data_yaml(dir, &images_dir, classes, None)
+}
+
+fn setup_toggle_frame_completion(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
     let ui_weak = ui.as_weak();
     ui.on_toggle_frame_completion(move || {
         if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
             if let Some(ds) = ds_opt.as_mut() {
                 let idx = ds.current_index;
                 if idx < ds.completed_frames.len() {
+                    // Check count rules only when marking complete
+                    let mut warning = None;
+                    if !ds.completed_frames[idx] {
+                        let cfg = config.borrow();
+                        let anns: Vec<Annotation> = annotations.iter().collect();
+                        let violations = validate_counts(&anns, &cfg.validation.count_rules);
+                        if !violations.is_empty() {
+                            let details = violations
+                                .iter()
+                                .map(|v| v.describe(&ui.invoke_get_class_name(v.class_id)))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            if cfg.validation.block_on_violation {
+                                ui.set_status_text(
+                                    format!("Cannot mark complete, class counts violated: {details}").into(),
+                                );
+                                return;
+                            }
+                            warning = Some(details);
+                        }
+                    }
+
                     ds.completed_frames[idx] = !ds.completed_frames[idx];
                     ui.set_frame_completed(ds.completed_frames[idx]);
-                    let status = if ds.completed_frames[idx] {
-                        "✓ Frame marked as complete"
-                    } else {
-                        "Frame marked as incomplete"
+                    let status = match (ds.completed_frames[idx], warning) {
+                        (true, Some(details)) => {
+                            format!("✓ Frame marked as complete (warning: {details})")
+                        }
+                        (true, None) => "✓ Frame marked as complete".to_string(),
+                        (false, _) => "Frame marked as incomplete".to_string(),
                     };
                     ui.set_status_text(status.into());
                 }
@@ -75,76 +265,206 @@ fn setup_toggle_frame_completion(ui: &AppWindow, dataset_state: Rc<RefCell<Optio
     });
 }
 
+fn setup_toggle_reviewed(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_toggle_reviewed(move || {
+        if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                ds.ensure_len();
+                let idx = ds.current_index;
+                let Some(reviewed) = ds.reviewed_frames.get_mut(idx) else { return };
+                *reviewed = !*reviewed;
+                let reviewed = *reviewed;
+                ui.set_frame_reviewed(reviewed);
+                let action = if reviewed { "Frame marked as reviewed" } else { "Frame review cleared" };
+                ui.set_status_text(
+                    format!("{action} ({:.0}% of frames reviewed)", ds.reviewed_percent()).into(),
+                );
+            }
+        }
+    });
+}
+
+/// Show the current frame's tags in the side panel
+pub fn show_image_tags(ui: &AppWindow, tags: &[String]) {
+    let items: Vec<slint::SharedString> = tags.iter().map(|t| t.as_str().into()).collect();
+    ui.set_image_tags(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+fn setup_image_tags(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+    let ui_weak = ui.as_weak();
+    let ds_add = dataset_state.clone();
+    ui.on_add_image_tag(move |tag| {
+        if let (Ok(mut ds_opt), Some(ui)) = (ds_add.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                let idx = ds.current_index;
+                if ds.add_image_tag(idx, &tag) {
+                    show_image_tags(&ui, ds.tags_for(idx));
+                    ui.set_status_text(format!("Tagged image '{}'", tag.trim()).into());
+                }
+            }
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_remove_image_tag(move |tag| {
+        if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                let idx = ds.current_index;
+                if ds.remove_image_tag(idx, &tag) {
+                    show_image_tags(&ui, ds.tags_for(idx));
+                    ui.set_status_text(format!("Removed tag '{tag}'").into());
+                }
+            }
+        }
+    });
+}
+
+/// Replace the shared class configuration and the UI class list with a dataset's classes
+fn apply_dataset_classes(
+    ui: &AppWindow,
+    classes: &RefCell<classes::ClassConfig>,
+    dataset_classes: &classes::ClassConfig,
+) {
+    *classes.borrow_mut() = dataset_classes.clone();
+
+    use crate::utils::parse_color;
+    use crate::ClassItem;
+    let class_items: Vec<ClassItem> = dataset_classes
+        .classes
+        .iter()
+        .map(|c| ClassItem {
+            id: c.id,
+            name: c.name.clone().into(),
+            color: c
+                .color
+                .as_ref()
+                .and_then(|hex| parse_color(hex))
+                .unwrap_or(slint::Color::from_rgb_u8(128, 128, 128))
+                .into(),
+            shortcut: c.shortcut.clone().unwrap_or_default().into(),
+        })
+        .collect();
+    ui.set_class_items(slint::ModelRc::new(slint::VecModel::from(class_items)));
+    crate::keybindings::show_key_conflicts(ui, dataset_classes);
+}
+
+/// Install a freshly loaded dataset: reset per-frame caches, switch to the
+/// manifest's classes if it defines any, and show the first frame.
+/// Returns the number of images.
+fn activate_dataset(
+    ui: &AppWindow,
+    mut state: DatasetState,
+    loader: &ImageLoader,
+    dataset_state: &RefCell<Option<DatasetState>>,
+    classes: &RefCell<classes::ClassConfig>,
+    label_extension: &str,
+) -> usize {
+    let len = state.entries.len();
+    state.set_label_extension(label_extension);
+    state.ensure_len();
+
+    if let Some(dataset_classes) = &state.class_config {
+        apply_dataset_classes(ui, classes, dataset_classes);
+    }
+
+    *dataset_state.borrow_mut() = Some(state);
+    loader(0);
+    len
+}
+
 fn setup_open_dataset(
     ui: &AppWindow,
     loader: ImageLoader,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_open_dataset(move || {
         let file = rfd::FileDialog::new()
+            .add_filter("Dataset or annotations", &["json", "gz", "xml", "txt"])
             .add_filter("Dataset JSON", &["json"])
             .set_title("Open Dataset")
             .pick_file();
 
-        if let Some(path) = file {
-            match load_dataset(&path) {
-                Ok(state) => {
-                    let len = state.entries.len();
-                    let mut state = state;
-                    state.stored_annotations = vec![None; len];
-                    state.view_states = vec![None; len];
-                    state.completed_frames = vec![false; len];
-
-                    // Use class configuration from dataset if available
-                    if let (Some(dataset_classes), Some(ui)) = (&state.class_config, ui_weak.upgrade()) {
-                        *classes.borrow_mut() = dataset_classes.clone();
-
-                        // Update class items in UI
-                        use crate::utils::parse_color;
-                        use crate::ClassItem;
-                        let class_items: Vec<ClassItem> = dataset_classes
-                            .classes
-                            .iter()
-                            .map(|c| ClassItem {
-                                id: c.id,
-                                name: c.name.clone().into(),
-                                color: c
-                                    .color
-                                    .as_ref()
-                                    .and_then(|hex| parse_color(hex))
-                                    .unwrap_or(slint::Color::from_rgb_u8(128, 128, 128))
-                                    .into(),
-                                shortcut: c.shortcut.clone().unwrap_or_default().into(),
-                            })
-                            .collect();
-                        ui.set_class_items(slint::ModelRc::new(slint::VecModel::from(class_items)));
-                    }
-
-                    *dataset_state.borrow_mut() = Some(state);
-
-                    loader(0);
-
-                    if let Some(ui) = ui_weak.upgrade() {
-                        ui.set_status_text(format!("Loaded dataset: {}", path.display()).into());
-                    }
+        if let (Some(path), Some(ui)) = (file, ui_weak.upgrade()) {
+            let label_extension = config.borrow().dataset.label_extension.clone();
+            let loaded = match detect_format(&path) {
+                Some(format) => {
+                    dataset_from_annotations(&path, format, &classes.borrow(), &label_extension)
                 }
-                Err(e) => {
-                    if let Some(ui) = ui_weak.upgrade() {
-                        ui.set_status_text(format!("Failed to load dataset: {e}").into());
-                    }
+                None => load_dataset(&path).map(|state| (state, String::new())).map_err(|e| {
+                    format!(
+                        "Unrecognized format (not a dataset manifest, COCO, VOC or YOLO file): {e}"
+                    )
+                }),
+            };
+            match loaded {
+                Ok((state, note)) => {
+                    activate_dataset(
+                        &ui,
+                        state,
+                        &loader,
+                        &dataset_state,
+                        &classes,
+                        &label_extension,
+                    );
+                    ui.set_status_text(format!("Loaded dataset: {}{note}", path.display()).into());
                 }
+                Err(e) => ui.set_status_text(format!("Failed to load dataset: {e}").into()),
             }
         }
     });
 }
 
+/// Build an in-memory dataset around an opened COCO, VOC or YOLO file.
+/// Returns the state and a note about skipped classes for the status bar.
+fn dataset_from_annotations(
+    path: &Path,
+    format: ExportFormat,
+    classes: &classes::ClassConfig,
+    label_extension: &str,
+) -> Result<(DatasetState, String), String> {
+    let imported = import_annotations(path, format, classes, label_extension)?;
+    let entries = imported
+        .images
+        .iter()
+        .map(|img| DatasetEntry {
+            image_path: img.image_path.clone(),
+            labels_path: img.labels_path.clone(),
+        })
+        .collect();
+    let mut state = DatasetState::new(entries, None);
+    state.ensure_len();
+    for (idx, img) in imported.images.into_iter().enumerate() {
+        state.stored_annotations[idx] = img.annotations.map(|anns| {
+            anns.into_iter()
+                .map(|mut ann| {
+                    if ann.r#type == "polygon" {
+                        let points = polygon_points(&ann);
+                        set_polygon_points(&mut ann, &points);
+                    }
+                    ann
+                })
+                .collect()
+        });
+    }
+
+    let mut note = format!(" ({})", format.name());
+    if !imported.unknown_classes.is_empty() {
+        let names: Vec<String> = imported.unknown_classes.into_iter().collect();
+        note.push_str(&format!("; skipped unknown classes: {}", names.join(", ")));
+    }
+    Ok((state, note))
+}
+
 fn setup_new_dataset(
     ui: &AppWindow,
     loader: ImageLoader,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_new_dataset(move || {
@@ -152,7 +472,8 @@ fn setup_new_dataset(
             .set_title("Select Folder with Images")
             .pick_folder();
 
-        if let Some(folder_path) = folder {
+        if let (Some(folder_path), Some(ui)) = (folder, ui_weak.upgrade()) {
+            let label_extension = config.borrow().dataset.label_extension.clone();
             // Check if manifest.json already exists in the folder
             let manifest_path = folder_path.join("manifest.json");
 
@@ -160,212 +481,435 @@ fn setup_new_dataset(
                 // Load existing manifest
                 match load_dataset(&manifest_path) {
                     Ok(state) => {
-                        let len = state.entries.len();
-                        let mut state = state;
-                        state.stored_annotations = vec![None; len];
-                        state.view_states = vec![None; len];
-                        state.completed_frames = vec![false; len];
-
-                        // Use class configuration from dataset if available
-                        if let (Some(dataset_classes), Some(ui)) = (&state.class_config, ui_weak.upgrade()) {
-                            *classes.borrow_mut() = dataset_classes.clone();
-
-                            // Update class items in UI
-                            use crate::utils::parse_color;
-                            use crate::ClassItem;
-                            let class_items: Vec<ClassItem> = dataset_classes
-                                .classes
-                                .iter()
-                                .map(|c| ClassItem {
-                                    id: c.id,
-                                    name: c.name.clone().into(),
-                                    color: c
-                                        .color
-                                        .as_ref()
-                                        .and_then(|hex| parse_color(hex))
-                                        .unwrap_or(slint::Color::from_rgb_u8(128, 128, 128))
-                                        .into(),
-                                    shortcut: c.shortcut.clone().unwrap_or_default().into(),
-                                })
-                                .collect();
-                            ui.set_class_items(slint::ModelRc::new(slint::VecModel::from(class_items)));
-                        }
-
-                        *dataset_state.borrow_mut() = Some(state);
-
-                        loader(0);
-
-                        if let Some(ui) = ui_weak.upgrade() {
-                            ui.set_status_text(
-                                format!("Loaded existing dataset with {} images", len).into(),
-                            );
-                        }
+                        let len = activate_dataset(
+                            &ui,
+                            state,
+                            &loader,
+                            &dataset_state,
+                            &classes,
+                            &label_extension,
+                        );
+                        ui.set_status_text(
+                            format!("Loaded existing dataset with {} images", len).into(),
+                        );
                     }
                     Err(e) => {
-                        if let Some(ui) = ui_weak.upgrade() {
-                            ui.set_status_text(format!("Failed to load existing dataset: {e}").into());
-                        }
+                        ui.set_status_text(format!("Failed to load existing dataset: {e}").into())
                     }
                 }
             } else {
                 // Create new manifest with current class configuration
-                match create_dataset_from_folder(&folder_path, Some(&classes.borrow())) {
+                let max_depth = config.borrow().dataset.scan_max_depth;
+                let created = create_dataset_from_folder(
+                    &folder_path,
+                    Some(&classes.borrow()),
+                    max_depth,
+                    &label_extension,
+                );
+                match created {
                     Ok(manifest_path) => match load_dataset(&manifest_path) {
                         Ok(state) => {
-                            let len = state.entries.len();
-                            let mut state = state;
-                            state.stored_annotations = vec![None; len];
-                            state.view_states = vec![None; len];
-                            state.completed_frames = vec![false; len];
-                            *dataset_state.borrow_mut() = Some(state);
-
-                            loader(0);
-
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.set_status_text(
-                                    format!("Created new dataset with {} images", len).into(),
-                                );
-                            }
+                            let len = activate_dataset(
+                                &ui,
+                                state,
+                                &loader,
+                                &dataset_state,
+                                &classes,
+                                &label_extension,
+                            );
+                            ui.set_status_text(
+                                format!("Created new dataset with {} images", len).into(),
+                            );
                         }
                         Err(e) => {
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.set_status_text(format!("Failed to load new dataset: {e}").into());
-                            }
+                            ui.set_status_text(format!("Failed to load new dataset: {e}").into())
                         }
                     },
-                    Err(e) => {
-                        if let Some(ui) = ui_weak.upgrade() {
-                            ui.set_status_text(format!("Failed to create dataset: {e}").into());
-                        }
+                    Err(e) => ui.set_status_text(format!("Failed to create dataset: {e}").into()),
+                }
+            }
+        }
+    });
+}
+
+fn setup_open_video(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_open_video(move || {
+        let file = rfd::FileDialog::new()
+            .add_filter("Video", &VIDEO_EXTENSIONS)
+            .set_title("Open Video")
+            .pick_file();
+
+        if let (Some(video), Some(ui)) = (file, ui_weak.upgrade()) {
+            let fps = config.borrow().dataset.video_fps;
+            let label_extension = config.borrow().dataset.label_extension.clone();
+            ui.set_status_text(format!("Extracting frames at {fps} fps...").into());
+            let created =
+                create_dataset_from_video(&video, fps, Some(&classes.borrow()), &label_extension);
+            match created.and_then(|manifest_path| load_dataset(&manifest_path)) {
+                Ok(state) => {
+                    let len = activate_dataset(
+                        &ui,
+                        state,
+                        &loader,
+                        &dataset_state,
+                        &classes,
+                        &label_extension,
+                    );
+                    ui.set_status_text(
+                        format!("Loaded {} frames from {}", len, video.display()).into(),
+                    );
+                }
+                Err(e) => ui.set_status_text(format!("Failed to open video: {e}").into()),
+            }
+        }
+    });
+}
+
+fn setup_switch_dataset(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    // Save the current dataset and load the sibling manifest `step` places away
+    let switch: Rc<dyn Fn(isize)> = {
+        let ui_weak = ui.as_weak();
+        Rc::new(move |step| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+
+            let current = {
+                let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+                let Some(ds) = ds_opt.as_mut() else { return };
+                let Some(current) = ds.manifest_path.clone() else {
+                    ui.set_status_text("Current dataset has no manifest on disk".into());
+                    return;
+                };
+                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                if let Err(e) = save_all(ds, &config.borrow().export) {
+                    ui.set_status_text(format!("Save failed, staying on dataset: {e}").into());
+                    return;
+                }
+                current
+            };
+
+            let manifests = sibling_manifests(&current);
+            let Some(pos) = manifests.iter().position(|p| *p == current) else { return };
+            let target = pos as isize + step;
+            if target < 0 || target as usize >= manifests.len() {
+                ui.set_status_text("No more datasets in this folder".into());
+                return;
+            }
+
+            let path = &manifests[target as usize];
+            let label_extension = config.borrow().dataset.label_extension.clone();
+            match load_dataset(path) {
+                Ok(state) => {
+                    activate_dataset(
+                        &ui,
+                        state,
+                        &loader,
+                        &dataset_state,
+                        &classes,
+                        &label_extension,
+                    );
+                    ui.set_status_text(
+                        format!(
+                            "Loaded dataset {}/{}: {}",
+                            target + 1,
+                            manifests.len(),
+                            path.display()
+                        )
+                        .into(),
+                    );
+                }
+                Err(e) => ui.set_status_text(
+                    format!("Failed to load dataset {}: {e}", path.display()).into(),
+                ),
+            }
+        })
+    };
+
+    let next = switch.clone();
+    ui.on_next_dataset(move || next(1));
+    ui.on_prev_dataset(move || switch(-1));
+}
+
+fn setup_project(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    // Members of the last opened/saved project, kept when saving again
+    let project = Rc::new(RefCell::new(Project::default()));
+
+    {
+        let ui_weak = ui.as_weak();
+        let project = project.clone();
+        let dataset_state = dataset_state.clone();
+        let classes = classes.clone();
+        let config = config.clone();
+        ui.on_open_project(move || {
+            let file = rfd::FileDialog::new()
+                .add_filter("Annotator Project", &[PROJECT_EXTENSION])
+                .set_title("Open Project")
+                .pick_file();
+            let (Some(path), Some(ui)) = (file, ui_weak.upgrade()) else { return };
+
+            let opened = load_project(&path).and_then(|proj| {
+                // Every member must load, not just the active one
+                let mut active = None;
+                for manifest in &proj.datasets {
+                    let state = load_dataset(manifest)
+                        .map_err(|e| format!("{}: {e}", manifest.display()))?;
+                    if proj.active_manifest() == Some(manifest.as_path()) {
+                        active = Some(state);
                     }
                 }
+                let active = active.ok_or("Project has no active dataset")?;
+                Ok((proj, active))
+            });
+            let (proj, state) = match opened {
+                Ok(opened) => opened,
+                Err(e) => {
+                    ui.set_status_text(format!("Failed to open project: {e}").into());
+                    return;
+                }
+            };
+
+            config.borrow_mut().export = proj.export.clone();
+            let label_extension = config.borrow().dataset.label_extension.clone();
+            let len = activate_dataset(
+                &ui,
+                state,
+                &loader,
+                &dataset_state,
+                &classes,
+                &label_extension,
+            );
+            // Project classes take precedence over the manifest's
+            if let Some(project_classes) = &proj.classes {
+                apply_dataset_classes(&ui, &classes, project_classes);
+            }
+            if proj.active_index > 0 && proj.active_index < len {
+                loader(proj.active_index);
             }
+
+            ui.set_status_text(
+                format!("Opened project with {} dataset(s): {}", proj.datasets.len(), path.display())
+                    .into(),
+            );
+            *project.borrow_mut() = proj;
+        });
+    }
+
+    let ui_weak = ui.as_weak();
+    ui.on_save_project(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+        let Some(ds) = ds_opt.as_mut() else {
+            ui.set_status_text("Open a dataset before saving a project".into());
+            return;
+        };
+        let Some(manifest) = ds.manifest_path.clone() else {
+            ui.set_status_text("Current dataset has no manifest on disk".into());
+            return;
+        };
+
+        let file = rfd::FileDialog::new()
+            .add_filter("Annotator Project", &[PROJECT_EXTENSION])
+            .set_file_name(format!("project.{PROJECT_EXTENSION}"))
+            .set_title("Save Project")
+            .save_file();
+        let Some(path) = file else { return };
+
+        accept_pending_on_screen(&annotations, &undo_history, &config);
+        save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+        if let Err(e) = save_all(ds, &config.borrow().export) {
+            ui.set_status_text(format!("Save failed: {e}").into());
+            return;
+        }
+
+        let mut proj = project.borrow_mut();
+        proj.set_active(&manifest, ds.current_index);
+        proj.classes = Some(classes.borrow().clone());
+        proj.export = config.borrow().export.clone();
+        match save_project(&path, &proj) {
+            Ok(()) => ui.set_status_text(
+                format!("Saved project with {} dataset(s)", proj.datasets.len()).into(),
+            ),
+            Err(e) => ui.set_status_text(format!("Failed to save project: {e}").into()),
         }
     });
 }
 
+/// Folder holding the dataset: the manifest's folder, else the first image's
+fn dataset_dir(ds: &DatasetState) -> Option<PathBuf> {
+    ds.manifest_path
+        .as_ref()
+        .or_else(|| ds.entries.first().map(|e| &e.image_path))
+        .and_then(|p| p.parent())
+        .map(Path::to_path_buf)
+}
+
+/// File dialog starting in the last used export folder, or the dataset
+/// folder when that is unset or no longer exists
+fn export_folder_dialog(
+    config: &RefCell<AppConfig>,
+    dataset_state: &RefCell<Option<DatasetState>>,
+) -> rfd::FileDialog {
+    let fallback = dataset_state
+        .try_borrow()
+        .ok()
+        .and_then(|ds| ds.as_ref().and_then(dataset_dir));
+    match export_start_dir(&config.borrow(), fallback.as_deref()) {
+        Some(dir) => rfd::FileDialog::new().set_directory(dir),
+        None => rfd::FileDialog::new(),
+    }
+}
+
+/// Number of images in the open dataset, if one is open
+fn dataset_len(dataset_state: &RefCell<Option<DatasetState>>) -> Option<usize> {
+    dataset_state.try_borrow().ok()?.as_ref().map(|ds| ds.entries.len())
+}
+
+/// Persist `folder` as the starting folder of the next export
+fn remember_export_dir(config: &RefCell<AppConfig>, folder: &Path) {
+    record_export_dir(&mut config.borrow_mut(), folder);
+    if let Err(e) = save_config(&config.borrow()) {
+        logging::warn(|| format!("Failed to save export folder to config: {e}"));
+    }
+}
+
 fn setup_export_coco(
     ui: &AppWindow,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_export_coco(move || {
-        let folder = rfd::FileDialog::new()
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Some(export_folder) = export_folder_dialog(&config, &dataset_state)
             .set_title("Select Export Folder")
-            .pick_folder();
+            .pick_folder()
+        else {
+            return;
+        };
+        let Some(total) = dataset_len(&dataset_state) else { return };
 
-        if let Some(export_folder) = folder {
-            if let Ok(ds_opt) = dataset_state.try_borrow() {
-                if let Some(ds) = ds_opt.as_ref() {
-                    let mut coco = export::coco::CocoDataset::new();
+        let export_cfg = config.borrow().export.clone();
+        let class_config = classes.borrow().clone();
+        let mut coco = export::coco::CocoDataset::new();
+        coco.add_mapped_categories(&class_config, &export_cfg.class_export_map);
+        let coco = Rc::new(RefCell::new(coco));
+        let result = Rc::new(RefCell::new(ExportResult::default()));
+        let mut ann_id = 1;
 
-                    for class_def in &classes.borrow().classes {
-                        coco.add_category(class_def.id, class_def.name.clone());
-                    }
+        let step = {
+            let (dataset_state, coco, result) = (dataset_state.clone(), coco.clone(), result.clone());
+            let export_cfg = export_cfg.clone();
+            let class_config = class_config.clone();
+            move |img_idx: usize| {
+                let ds_opt = dataset_state.try_borrow().map_err(|_| "dataset is busy".to_string())?;
+                let ds = ds_opt.as_ref().filter(|ds| ds.entries.len() == total);
+                let Some(ds) = ds else { return Err("dataset changed during export".into()) };
+                if export_cfg.reviewed_only && !ds.is_reviewed(img_idx) {
+                    return Ok(());
+                }
+                let entry = &ds.entries[img_idx];
+                let filename = entry
+                    .image_path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or("unknown.png")
+                    .to_string();
+                let mut result = result.borrow_mut();
+                let Ok(img) = load_image_from_entry(entry) else {
+                    result.skipped_images.push(filename);
+                    return Ok(());
+                };
+                let size = img.size();
+                result.record_image();
 
-                    let mut ann_id = 1;
-
-                    for (img_idx, entry) in ds.entries.iter().enumerate() {
-                        let filename = entry
-                            .image_path
-                            .file_name()
-                            .and_then(|f| f.to_str())
-                            .unwrap_or("unknown.png")
-                            .to_string();
-
-                        let (width, height) = if let Ok(img) = load_image_from_entry(entry) {
-                            let size = img.size();
-                            (size.width as i32, size.height as i32)
-                        } else {
-                            (640, 480)
-                        };
-
-                        coco.images.push(export::coco::CocoImage {
-                            id: (img_idx + 1) as i32,
-                            width,
-                            height,
-                            file_name: filename,
-                        });
-
-                        if let Some(Some(annotations)) = ds.stored_annotations.get(img_idx) {
-                            for ann in annotations {
-                                let (bbox_opt, segmentation_opt, area_opt) =
-                                    match ann.r#type.as_str() {
-                                        "bbox" | "rbbox" => {
-                                            let bbox = [
-                                                ann.x as f64,
-                                                ann.y as f64,
-                                                ann.width as f64,
-                                                ann.height as f64,
-                                            ];
-                                            let area = ann.width as f64 * ann.height as f64;
-                                            (Some(bbox), None, Some(area))
-                                        }
-                                        "point" => {
-                                            let bbox = [ann.x as f64, ann.y as f64, 1.0, 1.0];
-                                            (Some(bbox), None, Some(1.0))
-                                        }
-                                        "polygon" => {
-                                            let verts: Vec<f64> = ann
-                                                .vertices
-                                                .as_str()
-                                                .split(',')
-                                                .filter_map(|s| s.trim().parse().ok())
-                                                .collect();
-                                            let area = if verts.len() >= 6 {
-                                                let mut a = 0.0;
-                                                for i in 0..verts.len() / 2 {
-                                                    let j = (i + 1) % (verts.len() / 2);
-                                                    a += verts[i * 2] * verts[j * 2 + 1];
-                                                    a -= verts[j * 2] * verts[i * 2 + 1];
-                                                }
-                                                (a / 2.0).abs()
-                                            } else {
-                                                0.0
-                                            };
-                                            (None, Some(vec![verts]), Some(area))
-                                        }
-                                        _ => continue,
-                                    };
-
-                                coco.annotations.push(export::coco::CocoAnnotation {
-                                    id: ann_id,
-                                    image_id: (img_idx + 1) as i32,
-                                    category_id: ann.class,
-                                    bbox: bbox_opt,
-                                    segmentation: segmentation_opt,
-                                    area: area_opt,
-                                    iscrowd: 0,
-                                });
-                                ann_id += 1;
-                            }
-                        }
-                    }
+                let annotations = match ds.stored_annotations.get(img_idx) {
+                    Some(Some(annotations)) => annotations.as_slice(),
+                    _ => &[],
+                };
+                let options = export::coco::CocoFrameOptions {
+                    state_filter: &export_cfg.state_filter,
+                    class_map: &export_cfg.class_export_map,
+                    classes: &class_config,
+                    stable_ids: export_cfg.stable_ids,
+                    winding: export_cfg.polygon_winding,
+                };
+                let rel_path = ds.relative_image_path(img_idx).unwrap_or_else(|| filename.clone());
+                let frame = export::coco::CocoFrame {
+                    index: img_idx,
+                    file_name: &filename,
+                    rel_path: &rel_path,
+                    size: (size.width as i32, size.height as i32),
+                    tags: ds.tags_for(img_idx),
+                    annotations,
+                };
+                for category_id in coco.borrow_mut().add_frame(&frame, &options, &mut ann_id) {
+                    result.record_annotation(category_id);
+                }
+                Ok(())
+            }
+        };
 
-                    let coco_path = export_folder.join("annotations.json");
-                    match coco.save(&coco_path) {
-                        Ok(_) => {
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.set_status_text(
-                                    format!(
-                                        "Exported {} images with {} annotations to COCO JSON",
-                                        coco.images.len(),
-                                        coco.annotations.len()
-                                    )
-                                    .into(),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            if let Some(ui) = ui_weak.upgrade() {
-                                ui.set_status_text(format!("Export failed: {e}").into());
-                            }
-                        }
-                    }
+        let ui_weak = ui.as_weak();
+        let config = config.clone();
+        let finish = move |outcome: Result<Duration, String>| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let coco = coco.borrow();
+            let result = result.borrow();
+            let coco_file = if export_cfg.compress_coco {
+                "annotations.json.gz"
+            } else {
+                "annotations.json"
+            };
+            let summary =
+                build_export_summary(ExportFormat::CocoJson, &result, &class_config, &export_cfg);
+            let saved = outcome.and_then(|elapsed| {
+                coco.check_unique_ids()
+                    .and_then(|_| coco.save(&export_folder.join(coco_file)))
+                    .and_then(|_| summary.save(&export_folder.join(SUMMARY_FILE_NAME)))
+                    .map(|_| elapsed)
+            });
+            match saved {
+                Ok(elapsed) => {
+                    remember_export_dir(&config, &export_folder);
+                    ui.set_status_text(
+                        format!(
+                            "Exported {} images with {} annotations to COCO JSON ({} skipped) in {}",
+                            coco.images.len(),
+                            coco.annotations.len(),
+                            result.skipped_images.len(),
+                            format_duration(elapsed)
+                        )
+                        .into(),
+                    );
                 }
+                Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
             }
-        }
+        };
+        run_batch(&ui, total, step, finish);
     });
 }
 
@@ -373,78 +917,732 @@ fn setup_export_voc(
     ui: &AppWindow,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_export_voc(move || {
-        let folder = rfd::FileDialog::new()
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Some(export_folder) = export_folder_dialog(&config, &dataset_state)
+            .set_title("Select Export Folder")
+            .pick_folder()
+        else {
+            return;
+        };
+        let Some(total) = dataset_len(&dataset_state) else { return };
+
+        let export_cfg = config.borrow().export.clone();
+        let class_config = classes.borrow().clone();
+        let result = Rc::new(RefCell::new(ExportResult::default()));
+
+        let step = {
+            let (dataset_state, result) = (dataset_state.clone(), result.clone());
+            let export_cfg = export_cfg.clone();
+            let class_config = class_config.clone();
+            let export_folder = export_folder.clone();
+            move |img_idx: usize| {
+                let ds_opt = dataset_state.try_borrow().map_err(|_| "dataset is busy".to_string())?;
+                let ds = ds_opt.as_ref().filter(|ds| ds.entries.len() == total);
+                let Some(ds) = ds else { return Err("dataset changed during export".into()) };
+                if export_cfg.reviewed_only && !ds.is_reviewed(img_idx) {
+                    return Ok(());
+                }
+                let entry = &ds.entries[img_idx];
+                let filename = entry
+                    .image_path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or("unknown.png")
+                    .to_string();
+                let mut result = result.borrow_mut();
+                let Ok(img) = load_image_from_entry(entry) else {
+                    result.skipped_images.push(filename);
+                    return Ok(());
+                };
+                let size = img.size();
+                let mut voc_ann = export::voc::VocAnnotation::new(
+                    filename.clone(),
+                    size.width as i32,
+                    size.height as i32,
+                );
+
+                if let Some(Some(annotations)) = ds.stored_annotations.get(img_idx) {
+                    let added = voc_ann.add_annotations(
+                        annotations,
+                        &class_config,
+                        &export_cfg.class_export_map,
+                        &export_cfg.state_filter,
+                    );
+                    for class_id in added {
+                        result.record_annotation(class_id);
+                    }
+                }
+
+                let xml_path = export_folder.join(Path::new(&filename).with_extension("xml"));
+                if voc_ann.save_unless_empty(&xml_path, export_cfg.export_empty_voc)? {
+                    result.record_image();
+                }
+                Ok(())
+            }
+        };
+
+        let ui_weak = ui.as_weak();
+        let config = config.clone();
+        let finish = move |outcome: Result<Duration, String>| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let result = result.borrow();
+            let summary =
+                build_export_summary(ExportFormat::PascalVoc, &result, &class_config, &export_cfg);
+            let saved = outcome.and_then(|elapsed| {
+                summary.save(&export_folder.join(SUMMARY_FILE_NAME)).map(|_| elapsed)
+            });
+            match saved {
+                Ok(elapsed) => {
+                    remember_export_dir(&config, &export_folder);
+                    ui.set_status_text(
+                        format!(
+                            "Exported {} XML files with {} annotations to Pascal VOC ({} skipped) in {}",
+                            result.images_exported,
+                            result.annotations_exported,
+                            result.skipped_images.len(),
+                            format_duration(elapsed)
+                        )
+                        .into(),
+                    );
+                }
+                Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
+            }
+        };
+        run_batch(&ui, total, step, finish);
+    });
+}
+
+fn setup_export_current(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let run_export = Rc::new(move |ui: &AppWindow, format: ExportFormat| {
+        export_current_frame(
+            ui,
+            format,
+            &dataset_state,
+            &annotations,
+            &image_dimensions,
+            &classes,
+            &config,
+        )
+    });
+    let ui_weak = ui.as_weak();
+    let export_coco = run_export.clone();
+    ui.on_export_current_coco(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            export_coco(&ui, ExportFormat::CocoJson);
+        }
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_export_current_voc(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            run_export(&ui, ExportFormat::PascalVoc);
+        }
+    });
+}
+
+/// Export just the frame on screen, edits included, to a COCO or VOC file
+/// picked in a save dialog
+fn export_current_frame(
+    ui: &AppWindow,
+    format: ExportFormat,
+    dataset_state: &RefCell<Option<DatasetState>>,
+    annotations: &slint::VecModel<Annotation>,
+    image_dimensions: &RefCell<(f32, f32)>,
+    classes: &RefCell<classes::ClassConfig>,
+    config: &RefCell<AppConfig>,
+) {
+    let (index, filename, rel_path, tags) = {
+        let Ok(ds_opt) = dataset_state.try_borrow() else { return };
+        let Some(ds) = ds_opt.as_ref() else {
+            ui.set_status_text("Open a dataset before exporting".into());
+            return;
+        };
+        let Some(entry) = ds.entries.get(ds.current_index) else { return };
+        let filename = entry
+            .image_path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("unknown.png")
+            .to_string();
+        let rel_path = ds.relative_image_path(ds.current_index).unwrap_or_else(|| filename.clone());
+        (ds.current_index, filename, rel_path, ds.tags_for(ds.current_index).to_vec())
+    };
+    let default_name = Path::new(&filename).with_extension(format.extension());
+    let Some(path) = export_folder_dialog(config, dataset_state)
+        .add_filter(format.name(), &[format.extension()])
+        .set_file_name(default_name.to_string_lossy())
+        .set_title("Export Current Image")
+        .save_file()
+    else {
+        return;
+    };
+
+    let anns = snapshot_annotations(annotations);
+    let (width, height) = *image_dimensions.borrow();
+    let size = (width as i32, height as i32);
+    let export_cfg = config.borrow().export.clone();
+    let class_config = classes.borrow();
+    let saved = match format {
+        ExportFormat::CocoJson => {
+            let options = export::coco::CocoFrameOptions {
+                state_filter: &export_cfg.state_filter,
+                class_map: &export_cfg.class_export_map,
+                classes: &class_config,
+                stable_ids: export_cfg.stable_ids,
+                winding: export_cfg.polygon_winding,
+            };
+            let frame = export::coco::CocoFrame {
+                index,
+                file_name: &filename,
+                rel_path: &rel_path,
+                size,
+                tags: &tags,
+                annotations: &anns,
+            };
+            let coco = export::coco::CocoDataset::single_frame(&frame, &options);
+            coco.save(&path).map(|_| coco.annotations.len())
+        }
+        _ => {
+            let mut voc = export::voc::VocAnnotation::new(filename.clone(), size.0, size.1);
+            let added = voc.add_annotations(
+                &anns,
+                &class_config,
+                &export_cfg.class_export_map,
+                &export_cfg.state_filter,
+            );
+            voc.save(&path).map(|_| added.len())
+        }
+    };
+    match saved {
+        Ok(count) => ui.set_status_text(
+            format!("Exported {filename} with {count} annotation(s) to {}", path.display()).into(),
+        ),
+        Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
+    }
+}
+
+fn setup_export_contact_sheet(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_contact_sheet(move || {
+        let folder = export_folder_dialog(&config, &dataset_state)
             .set_title("Select Export Folder")
             .pick_folder();
 
         if let Some(export_folder) = folder {
-            if let Ok(ds_opt) = dataset_state.try_borrow() {
+            if let (Ok(ds_opt), Some(ui)) = (dataset_state.try_borrow(), ui_weak.upgrade()) {
                 if let Some(ds) = ds_opt.as_ref() {
-                    let mut total_files = 0;
-                    let mut total_annotations = 0;
-
-                    for (img_idx, entry) in ds.entries.iter().enumerate() {
-                        let filename = entry
-                            .image_path
-                            .file_name()
-                            .and_then(|f| f.to_str())
-                            .unwrap_or("unknown.png")
-                            .to_string();
-
-                        let (width, height) = if let Ok(img) = load_image_from_entry(entry) {
-                            let size = img.size();
-                            (size.width as i32, size.height as i32)
-                        } else {
-                            (640, 480)
-                        };
-
-                        let mut voc_ann =
-                            export::voc::VocAnnotation::new(filename.clone(), width, height);
-
-                        let mut has_annotations = false;
-                        if let Some(Some(annotations)) = ds.stored_annotations.get(img_idx) {
-                            for ann in annotations {
-                                if ann.r#type.as_str() == "bbox" || ann.r#type.as_str() == "rbbox" {
-                                    let class_name =
-                                        classes::get_class_name(&classes.borrow(), ann.class);
-                                    let xmin = ann.x as i32;
-                                    let ymin = ann.y as i32;
-                                    let xmax = (ann.x + ann.width) as i32;
-                                    let ymax = (ann.y + ann.height) as i32;
-                                    voc_ann.add_object(class_name, xmin, ymin, xmax, ymax);
-                                    has_annotations = true;
-                                    total_annotations += 1;
-                                }
-                            }
-                        }
-
-                        if has_annotations || true {
-                            let xml_filename = Path::new(&filename).with_extension("xml");
-                            let xml_path = export_folder.join(xml_filename);
-                            if let Err(e) = voc_ann.save(&xml_path) {
-                                if let Some(ui) = ui_weak.upgrade() {
-                                    ui.set_status_text(format!("Export failed: {e}").into());
-                                }
-                                return;
-                            }
-                            total_files += 1;
+                    let (columns, completed_only) = {
+                        let cfg = config.borrow();
+                        (cfg.export.contact_sheet_columns, cfg.export.contact_sheet_completed_only)
+                    };
+                    let class_config = classes.borrow();
+                    let sheet = export_contact_sheet(
+                        ds,
+                        &export_folder,
+                        columns,
+                        completed_only,
+                        &class_config,
+                    );
+                    match sheet {
+                        Ok(count) => {
+                            remember_export_dir(&config, &export_folder);
+                            ui.set_status_text(
+                                format!("Exported contact sheet with {count} frames").into(),
+                            )
                         }
+                        Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
                     }
+                }
+            }
+        }
+    });
+}
 
-                    if let Some(ui) = ui_weak.upgrade() {
-                        ui.set_status_text(
-                            format!(
-                                "Exported {} XML files with {} annotations to Pascal VOC",
-                                total_files, total_annotations
-                            )
+/// Render every (or every completed) frame as a thumbnail showing annotations of
+/// exported classes, and tile them. Cells are listed with their filenames in a
+/// text index next to the PNG.
+fn export_contact_sheet(
+    ds: &DatasetState,
+    folder: &Path,
+    columns: usize,
+    completed_only: bool,
+    classes: &classes::ClassConfig,
+) -> Result<usize, String> {
+    let mut tiles = Vec::new();
+    let mut index = String::new();
+
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        if completed_only && !ds.completed_frames.get(idx).copied().unwrap_or(false) {
+            continue;
+        }
+        let Ok(img) = image::open(&entry.image_path) else {
+            continue;
+        };
+        let mut anns = match ds.stored_annotations.get(idx) {
+            Some(Some(anns)) => anns.clone(),
+            _ => {
+                let img_size = (img.width() as f32, img.height() as f32);
+                load_yolo_annotations(entry, img_size, 0, &ds.label_extension)
+            }
+        };
+        anns.retain(|a| export::class_exported(classes, a.class));
+
+        let cell = tiles.len();
+        index.push_str(&format!(
+            "{}\t{}\t{}\n",
+            cell / columns.max(1),
+            cell % columns.max(1),
+            entry.image_path.display()
+        ));
+        tiles.push(render_thumbnail(&img, &anns, TILE_SIZE));
+    }
+
+    if tiles.is_empty() {
+        return Err("No frames to include".to_string());
+    }
+
+    let sheet = compose_contact_sheet(&tiles, columns);
+    sheet
+        .save(folder.join(CONTACT_SHEET_FILE_NAME))
+        .map_err(|e| format!("Failed to write contact sheet: {e}"))?;
+    std::fs::write(folder.join(CONTACT_SHEET_INDEX_FILE_NAME), index)
+        .map_err(|e| format!("Failed to write contact sheet index: {e}"))?;
+    Ok(tiles.len())
+}
+
+fn setup_export_heatmap(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_heatmap(move |path| {
+        // An empty path asks for the destination
+        let target = if path.is_empty() {
+            rfd::FileDialog::new()
+                .add_filter("PNG", &["png"])
+                .set_file_name("heatmap.png")
+                .set_title("Export Annotation Heatmap")
+                .save_file()
+        } else {
+            Some(PathBuf::from(path.as_str()))
+        };
+        let Some(target) = target else { return };
+
+        if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                let hist = build_density_heatmap(ds, HEATMAP_BINS, &classes.borrow());
+                let total: u32 = hist.iter().flatten().sum();
+                match render_heatmap(&hist, HEATMAP_CELL_SIZE).save(&target) {
+                    Ok(()) => ui.set_status_text(
+                        format!("Exported heatmap of {total} box centers to {}", target.display())
                             .into(),
+                    ),
+                    Err(e) => ui.set_status_text(format!("Failed to write heatmap: {e}").into()),
+                }
+            }
+        }
+    });
+}
+
+fn setup_export_mot(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_mot(move |path| {
+        // An empty path asks for the destination
+        let target = if path.is_empty() {
+            rfd::FileDialog::new()
+                .add_filter("MOT", &["txt"])
+                .set_file_name(MOT_FILE_NAME)
+                .set_title("Export MOT Tracks")
+                .save_file()
+        } else {
+            Some(PathBuf::from(path.as_str()))
+        };
+        let Some(target) = target else { return };
+
+        if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                let export_cfg = &config.borrow().export;
+                let tracks = build_mot_tracks(
+                    ds,
+                    &classes.borrow(),
+                    &export_cfg.class_export_map,
+                    &export_cfg.state_filter,
+                );
+                let status = match tracks.save(&target) {
+                    Ok(()) => format!(
+                        "Exported {} tracked box(es) to {} ({} untracked and {} rotated box(es) skipped)",
+                        tracks.lines.len(),
+                        target.display(),
+                        tracks.untracked,
+                        tracks.rotated
+                    ),
+                    Err(e) => e,
+                };
+                ui.set_status_text(status.into());
+            }
+        }
+    });
+}
+
+fn setup_export_yolo(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_yolo(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Some(folder) = export_folder_dialog(&config, &dataset_state)
+            .set_title("Select YOLO Export Folder")
+            .pick_folder()
+        else {
+            return;
+        };
+        let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+        let Some(ds) = ds_opt.as_mut() else { return };
+
+        save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+        let export_cfg = config.borrow().export.clone();
+        match export::yolo::export_yolo_dataset(ds, &folder, &classes.borrow(), &export_cfg) {
+            Ok((images, count)) => {
+                remember_export_dir(&config, &folder);
+                ui.set_status_text(
+                    format!(
+                        "Exported {images} images with {count} annotations to YOLO in {}",
+                        folder.display()
+                    )
+                    .into(),
+                );
+            }
+            Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
+        }
+    });
+}
+
+fn setup_export_chips(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_chips(move |folder| {
+        // An empty folder asks for the destination
+        let folder = if folder.is_empty() {
+            export_folder_dialog(&config, &dataset_state)
+                .set_title("Select Chip Export Folder")
+                .pick_folder()
+        } else {
+            Some(PathBuf::from(folder.as_str()))
+        };
+        let Some(folder) = folder else { return };
+
+        if let (Ok(mut ds_opt), Some(ui)) = (dataset_state.try_borrow_mut(), ui_weak.upgrade()) {
+            if let Some(ds) = ds_opt.as_mut() {
+                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                let state_filter = config.borrow().export.state_filter.clone();
+                match export_chips(ds, &folder, &classes.borrow(), &state_filter) {
+                    Ok(count) => {
+                        remember_export_dir(&config, &folder);
+                        ui.set_status_text(
+                            format!("Exported {count} object chips to {}", folder.display())
+                                .into(),
                         );
                     }
+                    Err(e) => ui.set_status_text(format!("Export failed: {e}").into()),
+                }
+            }
+        }
+    });
+}
+
+/// Append imported rows to the cached annotations of the frames they name,
+/// loading uncached frames from disk first. Returns the number imported and
+/// the filenames that match no entry.
+fn import_csv_rows(ds: &mut DatasetState, rows: Vec<(String, Annotation)>) -> (usize, Vec<String>) {
+    ds.ensure_len();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        if let Some(name) = entry.image_path.file_name().and_then(|f| f.to_str()) {
+            by_name.entry(name.to_string()).or_insert(idx);
+        }
+    }
+
+    let mut imported = 0;
+    let mut unknown: Vec<String> = Vec::new();
+    for (name, mut ann) in rows {
+        let Some(&idx) = by_name.get(&name) else {
+            if !unknown.contains(&name) {
+                unknown.push(name);
+            }
+            continue;
+        };
+        let anns = cached_frame_mut(ds, idx);
+        ann.id = next_id_from_annotations(anns, 1);
+        if ann.r#type == "polygon" {
+            let points = polygon_points(&ann);
+            set_polygon_points(&mut ann, &points);
+        }
+        anns.push(ann);
+        imported += 1;
+    }
+    (imported, unknown)
+}
+
+fn setup_import_csv(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_import_csv(move |path| {
+        // An empty path asks for the file
+        let source = if path.is_empty() {
+            rfd::FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_title("Import Annotations CSV")
+                .pick_file()
+        } else {
+            Some(PathBuf::from(path.as_str()))
+        };
+        let (Some(source), Some(ui)) = (source, ui_weak.upgrade()) else { return };
+
+        let parsed = std::fs::read_to_string(&source)
+            .map_err(|e| format!("Failed to read {}: {e}", source.display()))
+            .and_then(|text| parse_annotation_csv(&text));
+        let rows = match parsed {
+            Ok(rows) => rows,
+            Err(e) => {
+                ui.set_status_text(format!("CSV import failed: {e}").into());
+                return;
+            }
+        };
+
+        let (current, imported, unknown) = {
+            let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+            let Some(ds) = ds_opt.as_mut() else {
+                ui.set_status_text("Open a dataset before importing a CSV".into());
+                return;
+            };
+            save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+            let (imported, unknown) = import_csv_rows(ds, rows);
+            (ds.current_index, imported, unknown)
+        };
+        // Reload the frame on screen so imported rows for it appear
+        loader(current);
+
+        let mut status = format!("Imported {imported} annotation(s) from {}", source.display());
+        if !unknown.is_empty() {
+            status.push_str(&format!("; unknown image(s): {}", unknown.join(", ")));
+        }
+        ui.set_status_text(status.into());
+    });
+}
+
+/// Flag annotations listed in a review corrections file with `needs_review`
+fn setup_load_corrections(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_load_corrections(move |path| {
+        // An empty path asks for the file
+        let source = if path.is_empty() {
+            rfd::FileDialog::new()
+                .add_filter("JSON", &["json"])
+                .set_title("Load Review Corrections")
+                .pick_file()
+        } else {
+            Some(PathBuf::from(path.as_str()))
+        };
+        let (Some(source), Some(ui)) = (source, ui_weak.upgrade()) else { return };
+
+        let corrections = match load_corrections(&source) {
+            Ok(corrections) => corrections,
+            Err(e) => {
+                ui.set_status_text(format!("Loading corrections failed: {e}").into());
+                return;
+            }
+        };
+
+        let (current, outcome) = {
+            let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+            let Some(ds) = ds_opt.as_mut() else {
+                ui.set_status_text("Open a dataset before loading corrections".into());
+                return;
+            };
+            save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+            (ds.current_index, apply_corrections(ds, &corrections))
+        };
+        // Reload the frame on screen so its flags appear
+        loader(current);
+
+        let mut status = format!("Flagged {} annotation(s) for review", outcome.flagged);
+        if !outcome.unknown_images.is_empty() {
+            status.push_str(&format!("; unknown image(s): {}", outcome.unknown_images.join(", ")));
+        }
+        ui.set_status_text(status.into());
+    });
+}
+
+/// Minimum IoU for an annotation to count as finding a reference one
+const DIFF_IOU_THRESHOLD: f32 = 0.5;
+
+fn setup_reference_diff(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    classes: Rc<RefCell<classes::ClassConfig>>,
+) {
+    let reference: Rc<RefCell<Option<ReferenceAnnotations>>> = Rc::new(RefCell::new(None));
+
+    let ui_weak = ui.as_weak();
+    let loaded = reference.clone();
+    ui.on_load_reference(move |path| {
+        // An empty path asks for the file
+        let source = if path.is_empty() {
+            rfd::FileDialog::new()
+                .add_filter("COCO JSON", &["json", "gz"])
+                .set_title("Load Reference Annotations")
+                .pick_file()
+        } else {
+            Some(PathBuf::from(path.as_str()))
+        };
+        let (Some(source), Some(ui)) = (source, ui_weak.upgrade()) else { return };
+
+        match load_coco_reference(&source, &classes.borrow()) {
+            Ok(loaded_reference) => {
+                let mut status = format!(
+                    "Loaded reference with {} image(s); use View → Show Reference Diff",
+                    loaded_reference.by_file_name.len()
+                );
+                if !loaded_reference.unknown_classes.is_empty() {
+                    let unknown: Vec<&str> =
+                        loaded_reference.unknown_classes.iter().map(String::as_str).collect();
+                    status.push_str(&format!("; skipped class(es): {}", unknown.join(", ")));
                 }
+                *loaded.borrow_mut() = Some(loaded_reference);
+                ui.set_diff_marks(slint::ModelRc::default());
+                ui.set_status_text(status.into());
             }
+            Err(e) => ui.set_status_text(format!("Loading reference failed: {e}").into()),
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_show_diff(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let reference = reference.borrow();
+        let Some(reference) = reference.as_ref() else {
+            ui.set_status_text("Load a reference file before showing the diff".into());
+            return;
+        };
+        let Ok(ds_opt) = dataset_state.try_borrow() else { return };
+        let Some(entry) = ds_opt.as_ref().and_then(|ds| ds.entries.get(ds.current_index)) else {
+            return;
+        };
+
+        let gt = reference.for_image(&entry.image_path);
+        let pred: Vec<Annotation> = annotations.iter().collect();
+        let result = greedy_match(&pred, gt, DIFF_IOU_THRESHOLD);
+
+        let mark = |ann: &Annotation, kind: &str| DiffMark {
+            x: ann.x,
+            y: ann.y,
+            width: ann.width,
+            height: ann.height,
+            rotation: if ann.r#type == "rbbox" { ann.rotation } else { 0.0 },
+            kind: kind.into(),
+        };
+        let marks: Vec<DiffMark> = result
+            .matches
+            .iter()
+            .map(|&(p, _, _)| mark(&pred[p], "match"))
+            .chain(result.unmatched_pred.iter().map(|&p| mark(&pred[p], "false-positive")))
+            .chain(result.unmatched_gt.iter().map(|&g| mark(&gt[g], "false-negative")))
+            .collect();
+        ui.set_diff_marks(slint::ModelRc::new(slint::VecModel::from(marks)));
+        ui.set_status_text(
+            format!(
+                "Reference diff: {} matched, {} false positive(s), {} missed; \
+                 precision {:.0}%, recall {:.0}%",
+                result.matches.len(),
+                result.unmatched_pred.len(),
+                result.unmatched_gt.len(),
+                result.precision() * 100.0,
+                result.recall() * 100.0,
+            )
+            .into(),
+        );
+    });
+}
+
+fn setup_rename_images(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_rename_images(move |pattern| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+        let Some(ds) = ds_opt.as_mut() else { return };
+        // An empty pattern uses dataset.rename_pattern
+        let pattern = if pattern.is_empty() {
+            config.borrow().dataset.rename_pattern.clone()
+        } else {
+            pattern.to_string()
+        };
+
+        match rename_dataset_images(ds, &pattern) {
+            Ok(count) => {
+                if let Some(name) = ds
+                    .entries
+                    .get(ds.current_index)
+                    .and_then(|e| e.image_path.file_name())
+                {
+                    ui.set_current_image_name(name.to_string_lossy().to_string().into());
+                }
+                ui.set_status_text(format!("Renamed {count} image(s) with '{pattern}'").into());
+            }
+            Err(e) => ui.set_status_text(format!("Rename failed: {e}").into()),
         }
     });
 }
@@ -454,25 +1652,44 @@ fn setup_view_changed(
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
 ) {
+    let ui_weak = ui.as_weak();
     ui.on_view_changed(move |px, py, z| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let requested = ViewState { pan_x: px, pan_y: py, zoom: z };
+        let img_size = *image_dimensions.borrow();
+        let view = clamp_view(&requested, img_size, canvas_size(&ui), zoom_limits(&ui));
+        // Drag-panning sets the pan directly; pull it back when the image leaves the canvas
+        if view != requested {
+            ui.set_view_pan_x(view.pan_x);
+            ui.set_view_pan_y(view.pan_y);
+            ui.set_view_zoom(view.zoom);
+        }
         if let Ok(mut ds_opt) = dataset_state.try_borrow_mut() {
             if let Some(ds) = ds_opt.as_mut() {
-                ds.global_view = Some(ViewState {
-                    pan_x: px,
-                    pan_y: py,
-                    zoom: z,
-                });
-                ds.last_view_image_size = Some(*image_dimensions.borrow());
+                ds.global_view = Some(view);
+                ds.last_view_image_size = Some(img_size);
             }
         }
     });
 }
 
+/// Folder for autosave backups; relative paths are taken from the dataset folder
+fn backup_root(ds: &DatasetState, dir: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(dir).as_ref());
+    match dataset_dir(ds) {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    }
+}
+
+/// Autosave every 5 s: in place, or into `dataset.autosave_backup_dir` when
+/// set (a new backup folder only once something changed)
 fn setup_auto_save_timer(
     ui: &AppWindow,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     slint::Timer::default().start(
@@ -483,7 +1700,23 @@ fn setup_auto_save_timer(
             {
                 if let Some(ds) = ds_opt.as_mut() {
                     save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
-                    if let Err(e) = save_all(ds) {
+                    let cfg = config.borrow();
+                    let result = match cfg.dataset.autosave_backup_dir.as_deref() {
+                        Some(dir) => {
+                            let root = backup_root(ds, dir);
+                            save_backup(ds, &cfg.export, &root, cfg.dataset.autosave_backup_keep)
+                                .map(|_| ())
+                        }
+                        None => {
+                            // Accepting pending annotations is for deliberate saves only
+                            let export = ExportConfig {
+                                accept_pending_on_save: false,
+                                ..cfg.export.clone()
+                            };
+                            save_all(ds, &export)
+                        }
+                    };
+                    if let Err(e) = result {
                         ui.set_status_text(format!("Autosave failed: {e}").into());
                     }
                 }
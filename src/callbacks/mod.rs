@@ -9,6 +9,9 @@
 //! - `resize` - Annotation resizing
 //! - `file_ops` - File operations (save, open, new, export)
 //! - `auto_resize` - Smart bbox auto-resize using edge detection
+//! - `batch` - Chunked long-running jobs with progress reporting
+//! - `view` - Pan/zoom handling
+//! - `class_selection` - Current class tracking and quick swap
 
 pub mod navigation;
 pub mod selection;
@@ -18,3 +21,6 @@ pub mod polygon;
 pub mod resize;
 pub mod file_ops;
 pub mod auto_resize;
+pub mod batch;
+pub mod view;
+pub mod class_selection;
@@ -1,9 +1,14 @@
 //! Navigation callbacks for image traversal.
 //!
-//! Handles: next, prev, first, last, and randomize image navigation.
+//! Handles: next, prev, first, last, go-to, and randomize image navigation,
+//! the rare-classes-first order, plus the class usage jump list.
 
-use crate::state::{save_current_state, DatasetState};
-use crate::{Annotation, AppWindow};
+use crate::config::AppConfig;
+use crate::state::{
+    class_usage, get_view_state, leave_frame, rarity_order, save_current_state,
+    snapshot_annotations, DatasetState,
+};
+use crate::{logging, Annotation, AppWindow, ClassUsageRow};
 use slint::ComponentHandle;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -19,18 +24,149 @@ pub type ImageLoader = Rc<dyn Fn(usize)>;
 /// * `dataset_state` - Shared dataset state
 /// * `annotations` - Shared annotations model
 /// * `image_dimensions` - Current image dimensions
+/// * `config` - App configuration (`dataset.save_on_navigate`)
 pub fn setup_navigation_callbacks(
     ui: &AppWindow,
     loader: ImageLoader,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
-    setup_next_image(ui, loader.clone(), dataset_state.clone(), annotations.clone(), image_dimensions.clone());
-    setup_prev_image(ui, loader.clone(), dataset_state.clone(), annotations.clone(), image_dimensions.clone());
-    setup_first_image(ui, loader.clone(), dataset_state.clone(), annotations.clone(), image_dimensions.clone());
-    setup_last_image(ui, loader.clone(), dataset_state.clone(), annotations.clone(), image_dimensions.clone());
-    setup_randomize(ui, loader, dataset_state, annotations, image_dimensions);
+    setup_next_image(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_prev_image(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_first_image(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_last_image(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_go_to_image(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_class_usage_report(ui, dataset_state.clone(), annotations.clone(), image_dimensions.clone());
+    setup_order_by_rarity(
+        ui,
+        loader.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+    );
+    setup_randomize(ui, loader, dataset_state, annotations, image_dimensions, config);
+}
+
+/// Cache the frame being left; with `dataset.save_on_navigate` also write its
+/// files. A failed write is logged and navigation goes ahead.
+fn leave_current_frame(
+    ds: &mut DatasetState,
+    annotations: &slint::VecModel<Annotation>,
+    ui: &AppWindow,
+    image_dimensions: &RefCell<(f32, f32)>,
+    config: &RefCell<AppConfig>,
+) {
+    let config = config.borrow();
+    let flush = config.dataset.save_on_navigate.then_some(&config.export);
+    let anns = snapshot_annotations(annotations);
+    let img_size = *image_dimensions.borrow();
+    if let Err(e) = leave_frame(ds, anns, get_view_state(ui), img_size, flush) {
+        logging::warn(|| format!("Saving the frame on navigation failed: {e}"));
+    }
+}
+
+fn setup_go_to_image(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_go_to_image(move |index| {
+        let target = {
+            let mut ds_ref = dataset_state.borrow_mut();
+            let Some(ds) = ds_ref.as_mut() else { return };
+            let Some(target) = usize::try_from(index).ok().filter(|&i| i < ds.entries.len())
+            else {
+                return;
+            };
+
+            if let Some(ui) = ui_weak.upgrade() {
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
+            }
+
+            target
+        };
+
+        loader(target);
+    });
+}
+
+/// Fill the class usage dialog with per-class totals and the frames using each class
+fn setup_class_usage_report(
+    ui: &AppWindow,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_class_usage_report(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let mut ds_ref = dataset_state.borrow_mut();
+        let Some(ds) = ds_ref.as_mut() else {
+            ui.set_status_text("Open a dataset to see class usage".into());
+            return;
+        };
+        // Include unsaved edits of the current frame
+        save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+
+        let mut usage: Vec<_> = class_usage(ds).into_iter().collect();
+        usage.sort_by_key(|(class, _)| *class);
+        let rows: Vec<ClassUsageRow> = usage
+            .into_iter()
+            .map(|(class, usage)| ClassUsageRow {
+                class_id: class,
+                name: ui.invoke_get_class_name(class),
+                count: usage.count as i32,
+                frames: Rc::new(slint::VecModel::from(
+                    usage.frames.iter().map(|&f| f as i32).collect::<Vec<_>>(),
+                ))
+                .into(),
+            })
+            .collect();
+        ui.set_class_usage_rows(Rc::new(slint::VecModel::from(rows)).into());
+        ui.set_class_usage_dialog_visible(true);
+    });
 }
 
 fn setup_next_image(
@@ -39,6 +175,7 @@ fn setup_next_image(
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_next_image(move || {
@@ -50,14 +187,10 @@ fn setup_next_image(
             }
 
             if let Some(ui) = ui_weak.upgrade() {
-                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
             }
 
-            let mut idx = ds.current_index;
-            if idx + 1 < ds.entries.len() {
-                idx += 1;
-            }
-            idx
+            ds.next_index(ds.current_index)
         };
 
         loader(next_idx);
@@ -70,6 +203,7 @@ fn setup_prev_image(
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_prev_image(move || {
@@ -81,14 +215,10 @@ fn setup_prev_image(
             }
 
             if let Some(ui) = ui_weak.upgrade() {
-                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
             }
 
-            if ds.current_index == 0 {
-                0
-            } else {
-                ds.current_index - 1
-            }
+            ds.prev_index(ds.current_index)
         };
 
         loader(prev_idx);
@@ -101,6 +231,7 @@ fn setup_first_image(
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_first_image(move || {
@@ -112,10 +243,10 @@ fn setup_first_image(
             }
 
             if let Some(ui) = ui_weak.upgrade() {
-                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
             }
 
-            0
+            ds.nav_index(0)
         };
 
         loader(first_idx);
@@ -128,6 +259,7 @@ fn setup_last_image(
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_last_image(move || {
@@ -139,22 +271,59 @@ fn setup_last_image(
             }
 
             if let Some(ui) = ui_weak.upgrade() {
-                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
             }
 
-            ds.entries.len() - 1
+            ds.nav_index(usize::MAX)
         };
 
         loader(last_idx);
     });
 }
 
+/// Toggle stepping through frames with the rarest classes first; the first
+/// frame of the new order is opened
+fn setup_order_by_rarity(
+    ui: &AppWindow,
+    loader: ImageLoader,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_order_by_rarity(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let (first, status) = {
+            let mut ds_ref = dataset_state.borrow_mut();
+            let Some(ds) = ds_ref.as_mut() else { return };
+            if ds.entries.is_empty() {
+                return;
+            }
+            // Count the current frame as edited
+            leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
+
+            let status = if ds.nav_order.take().is_some() {
+                "Navigation back in manifest order"
+            } else {
+                ds.nav_order = Some(rarity_order(ds));
+                "Navigation ordered by class rarity (rarest first)"
+            };
+            (ds.nav_index(0), status)
+        };
+
+        loader(first);
+        ui.set_status_text(status.into());
+    });
+}
+
 fn setup_randomize(
     ui: &AppWindow,
     loader: ImageLoader,
     dataset_state: Rc<RefCell<Option<DatasetState>>>,
     annotations: Rc<slint::VecModel<Annotation>>,
     image_dimensions: Rc<RefCell<(f32, f32)>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_randomize(move || {
@@ -167,7 +336,7 @@ fn setup_randomize(
             }
 
             if let Some(ui) = ui_weak.upgrade() {
-                save_current_state(ds, &annotations, &ui, *image_dimensions.borrow());
+                leave_current_frame(ds, &annotations, &ui, &image_dimensions, &config);
             }
 
             let mut rng = rand::thread_rng();
@@ -1,10 +1,21 @@
 //! Polygon annotation callbacks.
 //!
-//! Handles: add_vertex, finish, cancel polygon creation
+//! Handles: add_vertex, finish (key or right-click), cancel polygon creation, hole drawing,
+//! resampling, and moving, inserting or deleting vertices of committed polygons
 
-use crate::state::{generate_path_commands, parse_vertices, DrawState};
+use crate::classes::ClassConfig;
+use crate::config::{AppConfig, PolygonFinish};
+use crate::geometry::resample_polygon;
+use crate::logging;
+use crate::state::{
+    add_polygon_hole, apply_lock_on_create, can_edit, delete_polygon_vertex,
+    generate_path_commands, insert_polygon_vertex, move_polygon_vertex, polygon_points,
+    push_created, set_polygon_points, snapshot_annotations, stamp_created, stamp_modified,
+    DrawState, UndoHistory, VertexPush, MIN_RING_VERTICES,
+};
+use crate::transform::snap_point;
 use crate::{Annotation, AppWindow};
-use slint::ComponentHandle;
+use slint::{ComponentHandle, Model};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -13,17 +24,72 @@ pub fn setup_polygon_callbacks(
     ui: &AppWindow,
     draw_state: Rc<RefCell<DrawState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    classes: Rc<RefCell<ClassConfig>>,
 ) {
-    setup_add_polygon_vertex(ui, draw_state.clone());
-    setup_finish_polygon(ui, draw_state.clone(), annotations);
-    setup_cancel_polygon(ui, draw_state);
+    setup_add_polygon_vertex(ui, draw_state.clone(), config.clone());
+    setup_finish_polygon(
+        ui,
+        draw_state.clone(),
+        annotations.clone(),
+        undo_history.clone(),
+        config.clone(),
+        classes,
+    );
+    setup_polygon_right_click(ui, draw_state.clone(), config.clone());
+    setup_cancel_polygon(ui, draw_state.clone());
+    setup_add_polygon_hole(ui, draw_state, annotations.clone());
+    setup_polygon_vertex_editing(ui, annotations.clone(), undo_history.clone(), config.clone());
+    setup_resample_polygon(ui, annotations, undo_history, config);
 }
 
-fn setup_add_polygon_vertex(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>) {
+/// Fewest vertices a committed polygon may have
+const MIN_POLYGON_VERTICES: usize = MIN_RING_VERTICES;
+
+/// Whether a right-click should commit the polygon in progress
+fn commits_on_right_click(mode: PolygonFinish, vertex_count: usize) -> bool {
+    mode.on_right_click() && vertex_count >= MIN_POLYGON_VERTICES
+}
+
+/// New manual polygon annotation from drawn vertices
+fn build_polygon(vertices: &[(f32, f32)], id: i32, class: i32) -> Annotation {
+    let mut ann = Annotation {
+        id,
+        r#type: "polygon".into(),
+        class,
+        state: "Manual".into(),
+        ..Default::default()
+    };
+    set_polygon_points(&mut ann, vertices);
+    ann
+}
+
+fn setup_add_polygon_vertex(
+    ui: &AppWindow,
+    draw_state: Rc<RefCell<DrawState>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
     let ui_weak = ui.as_weak();
     ui.on_add_polygon_vertex(move |x, y| {
+        let (x, y) = snap_point((x, y), config.borrow().annotation_modes.snap_grid);
         let mut state = draw_state.borrow_mut();
-        state.polygon_vertices.push((x, y));
+        match state.push_polygon_vertex((x, y)) {
+            VertexPush::Added => {}
+            // Freehand strokes fire far more often than needed
+            VertexPush::TooClose => return,
+            VertexPush::AtCap => {
+                let cap = state.max_polygon_vertices;
+                logging::warn(|| format!("Polygon vertex cap of {cap} reached"));
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_status_text(
+                        format!("Polygon: {cap} vertices is the maximum; finish it with Tab/Enter")
+                            .into(),
+                    );
+                }
+                return;
+            }
+        }
 
         if let Some(ui) = ui_weak.upgrade() {
             let vertices_str = state
@@ -34,23 +100,8 @@ fn setup_add_polygon_vertex(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>)
                 .join(";");
             ui.set_polygon_preview_vertices(vertices_str.into());
 
-            let preview_path = if state.polygon_vertices.len() >= 2 {
-                let mut commands = format!(
-                    "M {} {}",
-                    state.polygon_vertices[0].0, state.polygon_vertices[0].1
-                );
-                for vertex in state.polygon_vertices.iter().skip(1) {
-                    commands.push_str(&format!(" L {} {}", vertex.0, vertex.1));
-                }
-                commands
-            } else if state.polygon_vertices.len() == 1 {
-                format!(
-                    "M {} {}",
-                    state.polygon_vertices[0].0, state.polygon_vertices[0].1
-                )
-            } else {
-                String::new()
-            };
+            // Left open until the polygon is finished
+            let preview_path = generate_path_commands(&state.polygon_vertices, false);
             ui.set_polygon_preview_path(preview_path.into());
 
             ui.set_status_text(
@@ -61,12 +112,14 @@ fn setup_add_polygon_vertex(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>)
                 .into(),
             );
         }
-        println!(
-            "Vertex added at ({:.1}, {:.1}), total: {}",
-            x,
-            y,
-            state.polygon_vertices.len()
-        );
+        logging::trace(|| {
+            format!(
+                "Vertex added at ({:.1}, {:.1}), total: {}",
+                x,
+                y,
+                state.polygon_vertices.len()
+            )
+        });
     });
 }
 
@@ -74,53 +127,48 @@ fn setup_finish_polygon(
     ui: &AppWindow,
     draw_state: Rc<RefCell<DrawState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    classes: Rc<RefCell<ClassConfig>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_finish_polygon(move || {
         let mut state = draw_state.borrow_mut();
+        let hole_target = state.hole_target.take();
 
-        if state.polygon_vertices.len() >= 3 {
+        if let (Some(index), Some(ui)) = (hole_target, ui_weak.upgrade()) {
+            let before = snapshot_annotations(&annotations);
+            let edited = annotations.row_data(index).filter(can_edit).and_then(|mut ann| {
+                add_polygon_hole(&mut ann, &state.polygon_vertices).then_some(ann)
+            });
+            if let Some(mut ann) = edited {
+                stamp_modified(&mut ann);
+                undo_history.borrow_mut().push(before);
+                annotations.set_row_data(index, ann);
+                ui.set_status_text(
+                    format!("Hole with {} vertices added", state.polygon_vertices.len()).into(),
+                );
+            } else {
+                ui.set_status_text("Hole needs at least 3 vertices on an editable polygon".into());
+            }
+        } else if state.polygon_vertices.len() >= MIN_POLYGON_VERTICES {
             if let Some(ui) = ui_weak.upgrade() {
                 let class = ui.get_current_class();
 
-                let vertices_str = state
-                    .polygon_vertices
-                    .iter()
-                    .map(|(x, y)| format!("{},{}", x, y))
-                    .collect::<Vec<_>>()
-                    .join(";");
-
-                let xs: Vec<f32> = state.polygon_vertices.iter().map(|(x, _)| *x).collect();
-                let ys: Vec<f32> = state.polygon_vertices.iter().map(|(_, y)| *y).collect();
-                let min_x = xs.iter().cloned().fold(f32::INFINITY, f32::min);
-                let max_x = xs.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-                let min_y = ys.iter().cloned().fold(f32::INFINITY, f32::min);
-                let max_y = ys.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-
-                let polygon_verts = parse_vertices(&vertices_str);
-                let path_commands = generate_path_commands(&state.polygon_vertices);
-
-                annotations.push(Annotation {
-                    id: state.next_id,
-                    r#type: "polygon".into(),
-                    x: min_x,
-                    y: min_y,
-                    width: max_x - min_x,
-                    height: max_y - min_y,
-                    rotation: 0.0,
-                    selected: false,
-                    class,
-                    state: "Manual".into(),
-                    vertices: vertices_str.clone().into(),
-                    polygon_vertices: std::rc::Rc::new(slint::VecModel::from(polygon_verts)).into(),
-                    polygon_path_commands: path_commands.into(),
-                });
+                let mut ann = build_polygon(&state.polygon_vertices, state.next_id, class);
+                stamp_created(&mut ann, &config.borrow().user.author);
+                apply_lock_on_create(&mut ann, &classes.borrow());
+                let ann_vertices = ann.vertices.clone();
+                let select = config.borrow().annotation_modes.select_on_create;
+                push_created(&annotations, ann, select);
                 state.next_id += 1;
-                println!(
-                    "Polygon created with {} vertices: {}",
-                    state.polygon_vertices.len(),
-                    vertices_str
-                );
+                logging::debug(|| {
+                    format!(
+                        "Polygon created with {} vertices: {}",
+                        state.polygon_vertices.len(),
+                        ann_vertices
+                    )
+                });
                 ui.set_status_text(
                     format!(
                         "Polygon created with {} vertices",
@@ -141,11 +189,31 @@ fn setup_finish_polygon(
     });
 }
 
+fn setup_polygon_right_click(
+    ui: &AppWindow,
+    draw_state: Rc<RefCell<DrawState>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_polygon_right_click(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let mode = config.borrow().annotation_modes.polygon_finish;
+        let vertex_count = draw_state.borrow().polygon_vertices.len();
+        if commits_on_right_click(mode, vertex_count) {
+            // Same path as S-release/Tab/Enter
+            ui.invoke_finish_polygon();
+        } else if mode.on_right_click() {
+            ui.set_status_text("Polygon needs at least 3 vertices".into());
+        }
+    });
+}
+
 fn setup_cancel_polygon(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>) {
     let ui_weak = ui.as_weak();
     ui.on_cancel_polygon(move || {
         let mut state = draw_state.borrow_mut();
         state.polygon_vertices.clear();
+        state.hole_target = None;
 
         if let Some(ui) = ui_weak.upgrade() {
             ui.set_polygon_preview_vertices("".into());
@@ -156,3 +224,206 @@ fn setup_cancel_polygon(ui: &AppWindow, draw_state: Rc<RefCell<DrawState>>) {
         }
     });
 }
+
+/// Enter hole-drawing mode for the polygon at `index` (-1 = the selected polygon).
+/// The next finished ring is cut out of that polygon instead of becoming a new one.
+fn setup_add_polygon_hole(
+    ui: &AppWindow,
+    draw_state: Rc<RefCell<DrawState>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+) {
+    let ui_weak = ui.as_weak();
+    ui.on_add_polygon_hole(move |index| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let target = if index >= 0 {
+            Some(index as usize)
+        } else {
+            let selected: Vec<usize> = (0..annotations.row_count())
+                .filter(|&i| {
+                    annotations
+                        .row_data(i)
+                        .is_some_and(|a| a.selected && a.r#type == "polygon")
+                })
+                .collect();
+            match selected.as_slice() {
+                [only] => Some(*only),
+                _ => None,
+            }
+        };
+        let Some(target) = target.filter(|&i| {
+            annotations
+                .row_data(i)
+                .is_some_and(|a| a.r#type == "polygon" && can_edit(&a))
+        }) else {
+            ui.set_status_text("Select one editable polygon to add a hole".into());
+            return;
+        };
+
+        let mut state = draw_state.borrow_mut();
+        state.polygon_vertices.clear();
+        state.hole_target = Some(target);
+        ui.set_polygon_preview_vertices("".into());
+        ui.set_polygon_preview_path("".into());
+        ui.set_polygon_mode_active(true);
+        ui.set_current_tool("Polygon Hole (O)".into());
+        ui.set_status_text("Draw the hole, then Tab/Enter to cut it out (Esc cancels)".into());
+    });
+}
+
+/// Apply `edit` to the polygon at `index` as one undo step, reporting `done`
+/// or the reason it was refused in the status bar
+fn edit_polygon_at(
+    ui: &AppWindow,
+    annotations: &slint::VecModel<Annotation>,
+    undo_history: &RefCell<UndoHistory>,
+    index: i32,
+    done: &str,
+    edit: impl FnOnce(&mut Annotation) -> Result<(), String>,
+) {
+    let Some(mut ann) = usize::try_from(index).ok().and_then(|i| annotations.row_data(i)) else {
+        return;
+    };
+    let before = snapshot_annotations(annotations);
+    match edit(&mut ann) {
+        Ok(()) => {
+            undo_history.borrow_mut().push(before);
+            annotations.set_row_data(index as usize, ann);
+            ui.set_status_text(done.into());
+        }
+        Err(e) => ui.set_status_text(e.into()),
+    }
+}
+
+/// Reshape committed polygons vertex by vertex; each edit is one undo step
+fn setup_polygon_vertex_editing(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    let ui_weak = ui.as_weak();
+    let (model, undo, cfg) = (annotations.clone(), undo_history.clone(), config.clone());
+    ui.on_move_polygon_vertex(move |index, vertex, x, y| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let to = snap_point((x, y), cfg.borrow().annotation_modes.snap_grid);
+        edit_polygon_at(&ui, &model, &undo, index, "Vertex moved", |ann| {
+            move_polygon_vertex(ann, vertex.max(0) as usize, to)
+        });
+    });
+
+    let ui_weak = ui.as_weak();
+    let (model, undo) = (annotations.clone(), undo_history.clone());
+    ui.on_insert_polygon_vertex(move |index, edge, x, y| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let at = snap_point((x, y), config.borrow().annotation_modes.snap_grid);
+        edit_polygon_at(&ui, &model, &undo, index, "Vertex added", |ann| {
+            insert_polygon_vertex(ann, edge.max(0) as usize, at)
+        });
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_delete_polygon_vertex(move |index, vertex| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        edit_polygon_at(&ui, &annotations, &undo_history, index, "Vertex deleted", |ann| {
+            delete_polygon_vertex(ann, vertex.max(0) as usize)
+        });
+    });
+}
+
+/// Resample one polygon annotation in place; false if it isn't an editable polygon
+fn resample_at(annotations: &slint::VecModel<Annotation>, index: usize, target_count: usize) -> bool {
+    let Some(mut ann) = annotations.row_data(index) else { return false };
+    if ann.r#type.as_str() != "polygon" || !can_edit(&ann) {
+        return false;
+    }
+    let points = resample_polygon(&polygon_points(&ann), target_count);
+    set_polygon_points(&mut ann, &points);
+    stamp_modified(&mut ann);
+    annotations.set_row_data(index, ann);
+    true
+}
+
+fn setup_resample_polygon(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
+    // One polygon to an explicit count, at least 3 vertices
+    let ui_weak = ui.as_weak();
+    let (model, undo) = (annotations.clone(), undo_history.clone());
+    ui.on_resample_polygon(move |index, target_count| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Ok(index) = usize::try_from(index) else { return };
+        let target_count = target_count.max(3) as usize;
+        let before = snapshot_annotations(&model);
+        if resample_at(&model, index, target_count) {
+            undo.borrow_mut().push(before);
+            ui.set_status_text(format!("Resampled polygon to {} vertices", target_count).into());
+        }
+    });
+
+    // Tools menu: resample every selected polygon to the configured count
+    let ui_weak = ui.as_weak();
+    ui.on_resample_selected_polygons(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let target_count = config.borrow().annotation_modes.polygon_resample_count.max(3);
+        let before = snapshot_annotations(&annotations);
+        let mut count = 0;
+        for i in 0..annotations.row_count() {
+            let selected = annotations.row_data(i).is_some_and(|a| a.selected);
+            if selected && resample_at(&annotations, i, target_count) {
+                count += 1;
+            }
+        }
+        if count > 0 {
+            undo_history.borrow_mut().push(before);
+        }
+        ui.set_status_text(
+            format!("Resampled {} polygon(s) to {} vertices", count, target_count).into(),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_right_click_commits_polygon_when_mode_allows() {
+        let vertices = [(0.0, 0.0), (10.0, 0.0), (10.0, 8.0)];
+        assert!(commits_on_right_click(PolygonFinish::RightClick, vertices.len()));
+        assert!(commits_on_right_click(PolygonFinish::Both, vertices.len()));
+        assert!(!commits_on_right_click(PolygonFinish::SReleased, vertices.len()));
+        assert!(!commits_on_right_click(PolygonFinish::Both, 2));
+
+        let ann = build_polygon(&vertices, 7, 2);
+        assert_eq!(ann.r#type, "polygon");
+        assert_eq!(ann.state, "Manual");
+        assert_eq!((ann.id, ann.class), (7, 2));
+        assert_eq!((ann.width, ann.height), (10.0, 8.0));
+        assert_eq!(ann.vertices, "0,0;10,0;10,8");
+    }
+
+    #[test]
+    fn test_resample_at_skips_uneditable_and_rewrites_vertices() {
+        let square = build_polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)], 1, 1);
+        let locked = Annotation { locked: true, ..square.clone() };
+        let rejected = Annotation { state: "Rejected".into(), ..square.clone() };
+        let bbox = crate::state::test_bbox(1, 0.0, 0.0, 10.0, 10.0);
+        let model = slint::VecModel::from(vec![square.clone(), locked, rejected, bbox]);
+
+        assert!(resample_at(&model, 0, 8));
+        let resampled = model.row_data(0).unwrap();
+        assert_eq!(polygon_points(&resampled).len(), 8);
+        assert_eq!(resampled.vertices, "0,0;5,0;10,0;10,5;10,10;5,10;0,10;0,5");
+        assert_ne!(resampled.polygon_path_commands, square.polygon_path_commands);
+
+        for index in 1..4 {
+            let before = model.row_data(index).unwrap();
+            assert!(!resample_at(&model, index, 8));
+            assert_eq!(model.row_data(index).unwrap(), before);
+        }
+        assert!(!resample_at(&model, 9, 8));
+    }
+}
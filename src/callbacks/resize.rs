@@ -2,7 +2,10 @@
 //!
 //! Handles: start_resize, update_resize, finish_resize
 
-use crate::state::ResizeState;
+use crate::config::AppConfig;
+use crate::logging;
+use crate::state::{can_edit, stamp_modified, ResizeState};
+use crate::transform::{resize_box, resize_rotated_box, snap_point};
 use crate::{Annotation, AppWindow};
 use slint::{ComponentHandle, Model};
 use std::cell::RefCell;
@@ -13,9 +16,10 @@ pub fn setup_resize_callbacks(
     ui: &AppWindow,
     resize_state: Rc<RefCell<ResizeState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     setup_start_resize(ui, resize_state.clone(), annotations.clone());
-    setup_update_resize(ui, resize_state, annotations);
+    setup_update_resize(ui, resize_state, annotations, config);
     setup_finish_resize(ui);
 }
 
@@ -26,7 +30,7 @@ fn setup_start_resize(
 ) {
     ui.on_start_resize(move |index, handle_type| {
         if let Some(ann) = annotations.row_data(index as usize) {
-            if ann.state == "Rejected" {
+            if !can_edit(&ann) {
                 return;
             }
             let mut state = resize_state.borrow_mut();
@@ -36,10 +40,12 @@ fn setup_start_resize(
             state.original_y = ann.y;
             state.original_width = ann.width;
             state.original_height = ann.height;
-            println!(
-                "Start resize: index={}, handle={}, bounds=({:.1}, {:.1}, {:.1}, {:.1})",
-                index, handle_type, ann.x, ann.y, ann.width, ann.height
-            );
+            logging::trace(|| {
+                format!(
+                    "Start resize: index={}, handle={}, bounds=({:.1}, {:.1}, {:.1}, {:.1})",
+                    index, handle_type, ann.x, ann.y, ann.width, ann.height
+                )
+            });
         }
     });
 }
@@ -48,74 +54,34 @@ fn setup_update_resize(
     ui: &AppWindow,
     resize_state: Rc<RefCell<ResizeState>>,
     annotations: Rc<slint::VecModel<Annotation>>,
+    config: Rc<RefCell<AppConfig>>,
 ) {
     ui.on_update_resize(move |mouse_x, mouse_y| {
+        let (mouse_x, mouse_y) =
+            snap_point((mouse_x, mouse_y), config.borrow().annotation_modes.snap_grid);
         let state = resize_state.borrow();
         let index = state.annotation_index;
 
         if let Some(mut ann) = annotations.row_data(index) {
-            if ann.state == "Rejected" {
+            if !can_edit(&ann) {
                 return;
             }
             let handle = state.handle_type.as_str();
-
-            match handle {
-                "corner-tl" => {
-                    let fixed_x = state.original_x + state.original_width;
-                    let fixed_y = state.original_y + state.original_height;
-                    ann.x = mouse_x.min(fixed_x);
-                    ann.y = mouse_y.min(fixed_y);
-                    ann.width = (fixed_x - ann.x).abs();
-                    ann.height = (fixed_y - ann.y).abs();
-                }
-                "corner-tr" => {
-                    let fixed_x = state.original_x;
-                    let fixed_y = state.original_y + state.original_height;
-                    ann.x = mouse_x.min(fixed_x);
-                    ann.y = mouse_y.min(fixed_y);
-                    ann.width = (mouse_x - fixed_x).abs();
-                    ann.height = (fixed_y - ann.y).abs();
-                }
-                "corner-bl" => {
-                    let fixed_x = state.original_x + state.original_width;
-                    let fixed_y = state.original_y;
-                    ann.x = mouse_x.min(fixed_x);
-                    ann.y = mouse_y.min(fixed_y);
-                    ann.width = (fixed_x - ann.x).abs();
-                    ann.height = (mouse_y - fixed_y).abs();
-                }
-                "corner-br" => {
-                    let fixed_x = state.original_x;
-                    let fixed_y = state.original_y;
-                    ann.x = mouse_x.min(fixed_x);
-                    ann.y = mouse_y.min(fixed_y);
-                    ann.width = (mouse_x - fixed_x).abs();
-                    ann.height = (mouse_y - fixed_y).abs();
-                }
-                "edge-t" => {
-                    let fixed_y = state.original_y + state.original_height;
-                    ann.y = mouse_y.min(fixed_y);
-                    ann.height = (fixed_y - ann.y).abs();
-                }
-                "edge-r" => {
-                    let fixed_x = state.original_x;
-                    ann.width = (mouse_x - fixed_x).max(1.0);
-                }
-                "edge-b" => {
-                    let fixed_y = state.original_y;
-                    ann.height = (mouse_y - fixed_y).max(1.0);
-                }
-                "edge-l" => {
-                    let fixed_x = state.original_x + state.original_width;
-                    ann.x = mouse_x.min(fixed_x);
-                    ann.width = (fixed_x - ann.x).abs();
-                }
-                _ => {}
-            }
+            let original =
+                (state.original_x, state.original_y, state.original_width, state.original_height);
+            // A rotated box resizes along its own sides
+            let resized = if ann.r#type == "rbbox" {
+                resize_rotated_box(original, ann.rotation, handle, (mouse_x, mouse_y))
+            } else {
+                resize_box(original, handle, (mouse_x, mouse_y))
+            };
+            let Some(bounds) = resized else { return };
+            (ann.x, ann.y, ann.width, ann.height) = bounds;
 
             if ann.state == "Pending" {
                 ann.state = "Accepted".into();
             }
+            stamp_modified(&mut ann);
             annotations.set_row_data(index, ann);
         }
     });
@@ -127,6 +93,6 @@ fn setup_finish_resize(ui: &AppWindow) {
         if let Some(ui) = ui_weak.upgrade() {
             ui.set_status_text("Resize complete".into());
         }
-        println!("Resize finished");
+        logging::trace(|| "Resize finished".to_string());
     });
 }
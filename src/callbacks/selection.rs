@@ -1,11 +1,17 @@
 //! Selection callbacks for annotation management.
 //!
-//! Handles: select, deselect_all, select_all, delete_selected
+//! Handles: select, deselect_all, select_all, select_overlapping, hover readout, delete_selected,
+//! toggle_isolate
 
-use crate::state::{snapshot_annotations, UndoHistory};
+use crate::config::AppConfig;
+use crate::state::{
+    can_edit, clear_isolation, isolate_selected, nearest_annotation, overlapping_indices,
+    plain_click_selection, reject, replace_annotations, snapshot_annotations, BulkConfirm,
+    UndoHistory,
+};
 use crate::{Annotation, AppWindow};
 use slint::{ComponentHandle, Model};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 /// Sets up all selection-related callbacks on the UI.
@@ -13,22 +19,124 @@ pub fn setup_selection_callbacks(
     ui: &AppWindow,
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
 ) {
-    setup_select_annotation(ui, annotations.clone());
+    setup_select_annotation(ui, annotations.clone(), config.clone());
     setup_deselect_all(ui, annotations.clone());
     setup_select_all(ui, annotations.clone());
-    setup_delete_selected(ui, annotations, undo_history);
+    setup_select_overlapping(ui, annotations.clone());
+    setup_hover(ui, annotations.clone());
+    setup_toggle_isolate(ui, annotations.clone());
+    setup_delete_selected(ui, annotations, undo_history, config, bulk_confirm);
 }
 
-fn setup_select_annotation(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+/// Ids of the selected annotations, to tell whether the selection changed
+fn selected_ids(annotations: &slint::VecModel<Annotation>) -> Vec<i32> {
+    annotations.iter().filter(|a| a.selected).map(|a| a.id).collect()
+}
+
+/// Leave the isolate view once the selection it was made for changes
+fn end_isolation_if_changed(annotations: &slint::VecModel<Annotation>, before: &[i32]) {
+    if selected_ids(annotations) == before {
+        return;
+    }
+    for i in 0..annotations.row_count() {
+        if let Some(mut data) = annotations.row_data(i).filter(|a| a.hidden) {
+            data.hidden = false;
+            annotations.set_row_data(i, data);
+        }
+    }
+}
+
+/// Show only the selected annotations, or everything again if already isolated.
+/// Hiding is display-only and not recorded in undo history.
+fn setup_toggle_isolate(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_toggle_isolate(move || {
+        let mut anns = snapshot_annotations(&annotations);
+        let status = if clear_isolation(&mut anns) > 0 {
+            "Showing all annotations".to_string()
+        } else {
+            match isolate_selected(&mut anns) {
+                0 => "Select annotations to isolate".to_string(),
+                hidden => format!("Isolated selection ({hidden} hidden; I to show all)"),
+            }
+        };
+        replace_annotations(&annotations, anns);
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text(status.into());
+        }
+    });
+}
+
+/// Select every box overlapping another one so duplicates can be reviewed by hand
+fn setup_select_overlapping(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_select_overlapping(move |threshold| {
+        let before = selected_ids(&annotations);
+        let anns = snapshot_annotations(&annotations);
+        let overlapping = overlapping_indices(&anns, threshold);
+        for (i, mut ann) in anns.into_iter().enumerate() {
+            let selected = overlapping.contains(&i);
+            if ann.selected != selected {
+                ann.selected = selected;
+                annotations.set_row_data(i, ann);
+            }
+        }
+        end_isolation_if_changed(&annotations, &before);
+
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_status_text(
+                format!(
+                    "Selected {} overlapping annotation(s) (IoU ≥ {:.2})",
+                    overlapping.len(),
+                    threshold
+                )
+                .into(),
+            );
+        }
+    });
+}
+
+/// Show the annotation nearest to the cursor in the status bar (without selecting it)
+fn setup_hover(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+    let ui_weak = ui.as_weak();
+    // Only rewrite the status when the nearest annotation changes
+    let last: Cell<Option<(i32, usize)>> = Cell::new(None);
+    ui.on_hover(move |x, y| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let anns = snapshot_annotations(&annotations);
+        let Some(index) = nearest_annotation(&anns, x, y) else {
+            last.set(None);
+            return;
+        };
+        let ann = &anns[index];
+        if last.replace(Some((ann.id, index))) == Some((ann.id, index)) {
+            return;
+        }
+        let name = ui.invoke_get_class_name(ann.class);
+        ui.set_status_text(
+            format!("Nearest: #{} {} ({}, {})", ann.id, name, ann.r#type, ann.state).into(),
+        );
+    });
+}
+
+fn setup_select_annotation(
+    ui: &AppWindow,
+    annotations: Rc<slint::VecModel<Annotation>>,
+    config: Rc<RefCell<AppConfig>>,
+) {
     let ui_weak = ui.as_weak();
     // Multi-selection support: Ctrl toggles, Shift extends range, normal click selects only one
-    ui.on_select_annotation(move |index| {
+    // (or, with annotation_modes.keep_group_on_click, keeps a dragged group)
+    ui.on_select_annotation(move |index, dragged| {
         let ui = ui_weak.upgrade().unwrap();
         let shift_held = ui.get_shift_key_held();
         let ctrl_held = ui.get_ctrl_key_held();
         let count = annotations.row_count();
         let target_index = index as usize;
+        let before = selected_ids(&annotations);
 
         if ctrl_held {
             // Ctrl+Click: Toggle selection of clicked annotation
@@ -71,18 +179,25 @@ fn setup_select_annotation(ui: &AppWindow, annotations: Rc<slint::VecModel<Annot
             }
         } else {
             // Normal click: Select only this annotation
-            for i in 0..count {
-                if let Some(mut data) = annotations.row_data(i) {
-                    data.selected = i == target_index;
-                    annotations.set_row_data(i, data);
+            let keep_group = config.borrow().annotation_modes.keep_group_on_click;
+            let selected: Vec<bool> = annotations.iter().map(|a| a.selected).collect();
+            let after = plain_click_selection(&selected, target_index, keep_group, dragged);
+            for (i, (&was, &now)) in selected.iter().zip(&after).enumerate() {
+                if was != now {
+                    if let Some(mut data) = annotations.row_data(i) {
+                        data.selected = now;
+                        annotations.set_row_data(i, data);
+                    }
                 }
             }
         }
+        end_isolation_if_changed(&annotations, &before);
     });
 }
 
 fn setup_deselect_all(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
     ui.on_deselect_all(move || {
+        let before = selected_ids(&annotations);
         let count = annotations.row_count();
         for i in 0..count {
             if let Some(mut data) = annotations.row_data(i) {
@@ -92,11 +207,13 @@ fn setup_deselect_all(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation
                 }
             }
         }
+        end_isolation_if_changed(&annotations, &before);
     });
 }
 
 fn setup_select_all(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
     ui.on_select_all(move || {
+        let before = selected_ids(&annotations);
         let count = annotations.row_count();
         for i in 0..count {
             if let Some(mut data) = annotations.row_data(i) {
@@ -104,6 +221,7 @@ fn setup_select_all(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>
                 annotations.set_row_data(i, data);
             }
         }
+        end_isolation_if_changed(&annotations, &before);
     });
 }
 
@@ -111,9 +229,28 @@ fn setup_delete_selected(
     ui: &AppWindow,
     annotations: Rc<slint::VecModel<Annotation>>,
     undo_history: Rc<RefCell<UndoHistory>>,
+    config: Rc<RefCell<AppConfig>>,
+    bulk_confirm: Rc<RefCell<BulkConfirm>>,
 ) {
     let ui_weak = ui.as_weak();
     ui.on_delete_selected(move || {
+        let affected: Vec<i32> = annotations
+            .iter()
+            .filter(|a| a.selected && can_edit(a))
+            .map(|a| a.id)
+            .collect();
+        let limit = config.borrow().annotation_modes.confirm_bulk_above;
+        if !bulk_confirm.borrow_mut().check("delete-selected", &affected, limit) {
+            if let Some(ui) = ui_weak.upgrade() {
+                let count = affected.len();
+                ui.set_status_text(
+                    format!("Delete {count} selected annotation(s)? Press again to confirm")
+                        .into(),
+                );
+            }
+            return;
+        }
+
         // Push current state to undo history before deletion
         undo_history.borrow_mut().push(snapshot_annotations(&annotations));
 
@@ -121,9 +258,7 @@ fn setup_delete_selected(
         let count = annotations.row_count();
         for i in 0..count {
             if let Some(mut ann) = annotations.row_data(i) {
-                if ann.selected {
-                    ann.state = "Rejected".into();
-                    ann.selected = false;
+                if ann.selected && reject(&mut ann) {
                     annotations.set_row_data(i, ann);
                     deleted_count += 1;
                 }
@@ -0,0 +1,197 @@
+//! View callbacks for pan/zoom handling.
+//!
+//! Handles: zoom_at (cursor-anchored wheel zoom), point_display_radius, enhance_image (equalization toggle),
+//! save/recall_view_bookmark, fit_annotations, set_onion_skin
+
+use crate::config::AppConfig;
+use crate::enhance::{equalize_image, to_slint_image};
+use crate::state::{
+    annotations_extent, apply_view_state, canvas_size, frame_view, get_view_state,
+    onion_skin_annotations, point_display_radius, zoom_at, zoom_limits, DatasetState, ZoomLimits,
+};
+use crate::{Annotation, AppWindow};
+use slint::{ComponentHandle, Model};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Zoom step applied per wheel notch
+const ZOOM_STEP: f32 = 1.1;
+
+/// Screen px left around the annotations by fit_annotations
+const FIT_MARGIN_PX: f32 = 40.0;
+
+/// Sets up all view-related callbacks on the UI.
+pub fn setup_view_callbacks(
+    ui: &AppWindow,
+    config: Rc<RefCell<AppConfig>>,
+    dataset_state: Rc<RefCell<Option<DatasetState>>>,
+    annotations: Rc<slint::VecModel<Annotation>>,
+) {
+    setup_zoom_at(ui, config.clone());
+    setup_point_display_radius(ui, config);
+    setup_enhance_image(ui, dataset_state.clone());
+    setup_view_bookmarks(ui, dataset_state.clone());
+    setup_fit_annotations(ui, annotations);
+    setup_onion_skin(ui, dataset_state);
+}
+
+/// Zoom factor for a scroll delta (>1 zooms in); `invert` flips the direction
+fn zoom_factor(delta: f32, invert: bool) -> f32 {
+    let zoom_in = (delta > 0.0) != invert;
+    if zoom_in { ZOOM_STEP } else { 1.0 / ZOOM_STEP }
+}
+
+fn setup_zoom_at(ui: &AppWindow, config: Rc<RefCell<AppConfig>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_zoom_at(move |cursor_x, cursor_y, delta| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        if delta == 0.0 {
+            return;
+        }
+        let (factor, limits) = {
+            let cfg = config.borrow();
+            let appearance = &cfg.appearance;
+            (zoom_factor(delta, appearance.invert_zoom_scroll), ZoomLimits::from_config(appearance))
+        };
+        let view = zoom_at(&get_view_state(&ui), (cursor_x, cursor_y), factor, limits);
+        // apply_view_state pushes the result through view_changed
+        apply_view_state(&ui, &view);
+    });
+}
+
+/// Frame every live annotation of the current frame; fit-to-window without any
+fn setup_fit_annotations(ui: &AppWindow, annotations: Rc<slint::VecModel<Annotation>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_fit_annotations(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let anns: Vec<Annotation> = annotations.iter().collect();
+        let Some(region) = annotations_extent(&anns) else {
+            ui.invoke_reset_view();
+            return;
+        };
+        let view = frame_view(region, canvas_size(&ui), FIT_MARGIN_PX, zoom_limits(&ui));
+        apply_view_state(&ui, &view);
+    });
+}
+
+/// Fill the faded overlay with the neighbor frames picked by the onion-skin
+/// toggles; called on every frame load
+pub fn show_onion_skin(ui: &AppWindow, ds: &DatasetState) {
+    let ghosts = onion_skin_annotations(
+        ds,
+        ds.current_index,
+        ui.get_onion_skin_prev(),
+        ui.get_onion_skin_next(),
+    );
+    ui.set_onion_annotations(slint::ModelRc::new(slint::VecModel::from(ghosts)));
+}
+
+fn setup_onion_skin(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+    let ui_weak = ui.as_weak();
+    ui.on_set_onion_skin(move |prev, next| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        ui.set_onion_skin_prev(prev);
+        ui.set_onion_skin_next(next);
+        let Ok(ds_opt) = dataset_state.try_borrow() else { return };
+        if let Some(ds) = ds_opt.as_ref() {
+            show_onion_skin(&ui, ds);
+        }
+    });
+}
+
+fn setup_point_display_radius(ui: &AppWindow, config: Rc<RefCell<AppConfig>>) {
+    ui.on_point_display_radius(move |zoom| {
+        point_display_radius(config.borrow().appearance.point_radius, zoom)
+    });
+}
+
+/// Original and equalized image of the last enhanced frame
+struct EnhanceCache {
+    index: usize,
+    original: slint::Image,
+    enhanced: slint::Image,
+}
+
+fn setup_enhance_image(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+    let ui_weak = ui.as_weak();
+    let cache: RefCell<Option<EnhanceCache>> = RefCell::new(None);
+    ui.on_enhance_image(move || {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let Ok(ds_opt) = dataset_state.try_borrow() else { return };
+        let Some(ds) = ds_opt.as_ref() else { return };
+        let index = ds.current_index;
+        let Some(entry) = ds.entries.get(index) else { return };
+
+        let mut cache = cache.borrow_mut();
+        if let Some(c) = cache.as_ref().filter(|c| c.index == index) {
+            // Navigation reloads the original, so compare with what is shown
+            if ui.get_image_source() == c.enhanced {
+                ui.set_image_source(c.original.clone());
+                ui.set_status_text("Enhancement off".into());
+            } else {
+                ui.set_image_source(c.enhanced.clone());
+                ui.set_status_text("Enhanced (histogram equalized)".into());
+            }
+            return;
+        }
+
+        match image::open(&entry.image_path) {
+            Ok(img) => {
+                let enhanced = to_slint_image(&equalize_image(&img));
+                let original = ui.get_image_source();
+                ui.set_image_source(enhanced.clone());
+                ui.set_status_text("Enhanced (histogram equalized)".into());
+                *cache = Some(EnhanceCache { index, original, enhanced });
+            }
+            Err(e) => ui.set_status_text(format!("Enhance failed: {e}").into()),
+        }
+    });
+}
+
+/// Bookmark slots are numbered from 1 in the UI
+fn setup_view_bookmarks(ui: &AppWindow, dataset_state: Rc<RefCell<Option<DatasetState>>>) {
+    {
+        let ui_weak = ui.as_weak();
+        let dataset_state = dataset_state.clone();
+        ui.on_save_view_bookmark(move |slot| {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let Ok(mut ds_opt) = dataset_state.try_borrow_mut() else { return };
+            let Some(ds) = ds_opt.as_mut() else { return };
+            if slot >= 1 && ds.set_bookmark(slot as usize - 1, get_view_state(&ui)) {
+                ui.set_status_text(format!("Saved view bookmark {slot}").into());
+            }
+        });
+    }
+
+    let ui_weak = ui.as_weak();
+    ui.on_recall_view_bookmark(move |slot| {
+        let Some(ui) = ui_weak.upgrade() else { return };
+        let view = {
+            let Ok(ds_opt) = dataset_state.try_borrow() else { return };
+            let Some(ds) = ds_opt.as_ref() else { return };
+            if slot < 1 {
+                return;
+            }
+            ds.bookmark(slot as usize - 1).cloned()
+        };
+        // Empty slots leave the view alone; the borrow is released because
+        // apply_view_state re-enters the dataset through view_changed
+        if let Some(view) = view {
+            apply_view_state(&ui, &view);
+            ui.set_status_text(format!("Recalled view bookmark {slot}").into());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invert_zoom_scroll_flips_direction() {
+        assert!(zoom_factor(120.0, false) > 1.0);
+        assert!(zoom_factor(-120.0, false) < 1.0);
+        assert!(zoom_factor(120.0, true) < 1.0);
+        assert!(zoom_factor(-120.0, true) > 1.0);
+    }
+}
@@ -9,6 +9,23 @@ pub struct ClassDefinition {
     pub color: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub shortcut: Option<String>,
+    /// Lock annotations of this class as soon as they are created
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lock_on_create: bool,
+    /// Other names mapped to this class on import (e.g. "motorbike" for "motorcycle")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Include this class in exports (`export: false` leaves it out)
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub export: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,30 +59,45 @@ impl Default for ClassConfig {
                     name: "Class 1".to_string(),
                     color: Some("#ff0000".to_string()),
                     shortcut: Some("1".to_string()),
+                    lock_on_create: false,
+                    aliases: Vec::new(),
+                    export: true,
                 },
                 ClassDefinition {
                     id: 2,
                     name: "Class 2".to_string(),
                     color: Some("#00ff00".to_string()),
                     shortcut: Some("2".to_string()),
+                    lock_on_create: false,
+                    aliases: Vec::new(),
+                    export: true,
                 },
                 ClassDefinition {
                     id: 3,
                     name: "Class 3".to_string(),
                     color: Some("#0000ff".to_string()),
                     shortcut: Some("3".to_string()),
+                    lock_on_create: false,
+                    aliases: Vec::new(),
+                    export: true,
                 },
                 ClassDefinition {
                     id: 4,
                     name: "Class 4".to_string(),
                     color: Some("#ffff00".to_string()),
                     shortcut: Some("4".to_string()),
+                    lock_on_create: false,
+                    aliases: Vec::new(),
+                    export: true,
                 },
                 ClassDefinition {
                     id: 5,
                     name: "Class 5".to_string(),
                     color: Some("#ff00ff".to_string()),
                     shortcut: Some("5".to_string()),
+                    lock_on_create: false,
+                    aliases: Vec::new(),
+                    export: true,
                 },
             ],
             hierarchy: Vec::new(),
@@ -83,6 +115,9 @@ pub fn flatten_hierarchy(nodes: &[HierarchicalClassNode]) -> Vec<ClassDefinition
                 name: name.clone(),
                 color: node.color.clone(),
                 shortcut: None, // Shortcuts are handled by the hierarchy navigation
+                lock_on_create: false,
+                aliases: Vec::new(),
+                export: true,
             });
         }
         classes.extend(flatten_hierarchy(&node.children));
@@ -90,8 +125,40 @@ pub fn flatten_hierarchy(nodes: &[HierarchicalClassNode]) -> Vec<ClassDefinition
     classes
 }
 
-/// Load class configuration from YAML file
-pub fn load_classes(path: Option<&str>) -> ClassConfig {
+/// Which class source `load_classes` ended up using
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassSource {
+    /// An external file, by its expanded path
+    File(String),
+    /// The `classes.yaml` compiled into the binary
+    Bundled,
+    /// `ClassConfig::default()`
+    Defaults,
+}
+
+/// The paths `load_classes` looked at, in order, and where the classes came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassLoadTrace {
+    /// Candidate paths (tilde-expanded), whether or not they existed
+    pub tried: Vec<String>,
+    pub source: ClassSource,
+}
+
+impl ClassLoadTrace {
+    /// Status message when no external file was used, naming the paths tried
+    pub fn fallback_message(&self) -> Option<String> {
+        let used = match self.source {
+            ClassSource::File(_) => return None,
+            ClassSource::Bundled => "the bundled default classes",
+            ClassSource::Defaults => "built-in default classes",
+        };
+        Some(format!("No class file found (tried {}); using {used}", self.tried.join(", ")))
+    }
+}
+
+/// Load class configuration from YAML file, trying `path` first; the trace
+/// records the paths tried and where the classes came from
+pub fn load_classes(path: Option<&str>) -> (ClassConfig, ClassLoadTrace) {
     // Preferred search order:
     //   1) explicit path (if provided)
     //   2) ./classes.yaml in the repo (requested default)
@@ -104,28 +171,44 @@ pub fn load_classes(path: Option<&str>) -> ClassConfig {
     search_paths.push("./classes.yaml".to_string());
     search_paths.push("./coco_hierarchy.yaml".to_string());
     search_paths.push("~/.config/annotator/classes.yaml".to_string());
+    load_classes_from(&search_paths)
+}
 
-    for candidate in search_paths {
-        let expanded = shellexpand::tilde(&candidate);
-        let path_obj = Path::new(expanded.as_ref());
+/// Load the first parsable class file among `candidates`, falling back to the
+/// bundled `classes.yaml` and then the built-in defaults. The fallbacks are
+/// logged with the paths tried.
+fn load_classes_from(candidates: &[String]) -> (ClassConfig, ClassLoadTrace) {
+    let mut tried = Vec::new();
+    for candidate in candidates {
+        let expanded = shellexpand::tilde(candidate).into_owned();
+        tried.push(expanded.clone());
+        let path_obj = Path::new(&expanded);
         if !path_obj.exists() {
             continue;
         }
 
         match try_load_class_file(path_obj) {
-            Ok(cfg) => return cfg,
-            Err(e) => eprintln!("Failed to parse class config '{}': {}", path_obj.display(), e),
+            Ok(cfg) => {
+                let trace = ClassLoadTrace { tried, source: ClassSource::File(expanded) };
+                return (cfg, trace);
+            }
+            Err(e) => crate::logging::warn(|| {
+                format!("Failed to parse class config '{}': {}", path_obj.display(), e)
+            }),
         }
     }
 
     // As a final fallback, try to use the bundled default at compile time
-    if let Ok(cfg) = parse_class_content(include_str!("../classes.yaml")) {
-        return cfg;
+    let (cfg, source) = match parse_class_content(include_str!("../classes.yaml")) {
+        Ok(cfg) => (cfg, ClassSource::Bundled),
+        // Nothing found/parsable; fall back to built-in defaults
+        Err(_) => (ClassConfig::default(), ClassSource::Defaults),
+    };
+    let trace = ClassLoadTrace { tried, source };
+    if let Some(msg) = trace.fallback_message() {
+        crate::logging::warn(|| msg);
     }
-
-    // Nothing found/parsable; fall back to built-in defaults
-    eprintln!("No class config found; using defaults.");
-    ClassConfig::default()
+    (cfg, trace)
 }
 
 /// Attempt to load a class file; returns an error string on failure so caller
@@ -167,6 +250,63 @@ pub fn get_class_name(config: &ClassConfig, class_id: i32) -> String {
         .unwrap_or_else(|| format!("Class {}", class_id))
 }
 
+/// Whether annotations of a class start out locked
+pub fn is_lock_on_create(config: &ClassConfig, class_id: i32) -> bool {
+    config
+        .classes
+        .iter()
+        .any(|c| c.id == class_id && c.lock_on_create)
+}
+
+/// Resolve an imported class name to a configured class ID.
+/// Names win over aliases; matching ignores case and surrounding whitespace.
+pub fn resolve_class_by_name_or_alias(config: &ClassConfig, name: &str) -> Option<i32> {
+    let name = name.trim();
+    config
+        .classes
+        .iter()
+        .find(|c| c.name.trim().eq_ignore_ascii_case(name))
+        .or_else(|| {
+            config
+                .classes
+                .iter()
+                .find(|c| c.aliases.iter().any(|a| a.trim().eq_ignore_ascii_case(name)))
+        })
+        .map(|c| c.id)
+}
+
+/// Ids of the classes whose name contains `query` (ignoring case), best match
+/// first: exact name, then name prefix, then word prefix, then any other
+/// substring. Ties go to the earlier match, then the shorter name, then the
+/// lower id. An empty query lists every class by id.
+pub fn search_classes(config: &ClassConfig, query: &str) -> Vec<i32> {
+    let query = query.trim().to_lowercase();
+    let mut hits: Vec<(u8, usize, usize, i32)> = config
+        .classes
+        .iter()
+        .filter_map(|c| {
+            let name = c.name.trim().to_lowercase();
+            let pos = name.find(&query)?;
+            let rank = if name == query {
+                0
+            } else if pos == 0 {
+                1
+            } else if !name[..pos].ends_with(|ch: char| ch.is_alphanumeric()) {
+                2
+            } else {
+                3
+            };
+            Some((rank, pos, name.len(), c.id))
+        })
+        .collect();
+    if query.is_empty() {
+        hits.sort_by_key(|&(.., id)| id);
+    } else {
+        hits.sort();
+    }
+    hits.into_iter().map(|(.., id)| id).collect()
+}
+
 /// Get class color by ID, or return None if not found
 #[allow(dead_code)]
 pub fn get_class_color(config: &ClassConfig, class_id: i32) -> Option<String> {
@@ -219,3 +359,97 @@ pub fn create_default_classes_file() -> Result<String, String> {
 
     Ok(classes_path.to_str().unwrap().to_string())
 }
+
+/// Exported class with just an id and name, for tests; set other fields with
+/// struct update syntax
+#[cfg(test)]
+pub fn test_class(id: i32, name: &str) -> ClassDefinition {
+    ClassDefinition {
+        id,
+        name: name.to_string(),
+        color: None,
+        shortcut: None,
+        lock_on_create: false,
+        aliases: Vec::new(),
+        export: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_load_classes_trace_lists_paths_when_falling_back() {
+        let dir = scratch_dir("no_classes");
+        let candidates: Vec<String> = ["explicit.yaml", "classes.yaml"]
+            .iter()
+            .map(|name| dir.join(name).to_string_lossy().into_owned())
+            .collect();
+
+        let (cfg, trace) = load_classes_from(&candidates);
+        assert_eq!(trace.tried, candidates);
+        assert_eq!(trace.source, ClassSource::Bundled);
+        assert!(!cfg.classes.is_empty());
+        let msg = trace.fallback_message().unwrap();
+        assert!(msg.contains(&candidates[0]) && msg.contains(&candidates[1]));
+        assert!(msg.contains("bundled"));
+
+        // An external file ends the search and needs no message
+        std::fs::write(&candidates[1], "classes:\n  - id: 1\n    name: car\n").unwrap();
+        let (cfg, trace) = load_classes_from(&candidates);
+        assert_eq!(cfg.classes[0].name, "car");
+        assert_eq!(trace.source, ClassSource::File(candidates[1].clone()));
+        assert_eq!(trace.fallback_message(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_search_classes_ranks_best_matches_first() {
+        let yaml = r#"
+classes:
+  - id: 1
+    name: Car Door
+  - id: 2
+    name: police car
+  - id: 3
+    name: Car
+  - id: 4
+    name: sidecar
+  - id: 5
+    name: carton
+  - id: 6
+    name: person
+"#;
+        let cfg: ClassConfig = serde_yaml::from_str(yaml).unwrap();
+        // Exact, then prefixes (shorter first), then word start, then inside a word
+        assert_eq!(search_classes(&cfg, "CAR"), vec![3, 5, 1, 2, 4]);
+        assert_eq!(search_classes(&cfg, " pers "), vec![6]);
+        assert!(search_classes(&cfg, "truck").is_empty());
+        assert_eq!(search_classes(&cfg, ""), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_resolve_class_by_name_or_alias() {
+        let yaml = r#"
+classes:
+  - id: 1
+    name: motorcycle
+    aliases: [motorbike, moped]
+  - id: 2
+    name: person
+    aliases: [pedestrian]
+  - id: 3
+    name: Moped
+"#;
+        let config: ClassConfig = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(resolve_class_by_name_or_alias(&config, "person"), Some(2));
+        assert_eq!(resolve_class_by_name_or_alias(&config, "Motorbike "), Some(1));
+        assert_eq!(resolve_class_by_name_or_alias(&config, "pedestrian"), Some(2));
+        // An exact name beats another class's alias
+        assert_eq!(resolve_class_by_name_or_alias(&config, "moped"), Some(3));
+        assert_eq!(resolve_class_by_name_or_alias(&config, "truck"), None);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::logging::LogLevel;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -13,6 +15,12 @@ pub struct AppConfig {
     pub classes: ClassesConfig,
     #[serde(default)]
     pub export: ExportConfig,
+    #[serde(default)]
+    pub user: UserConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub debug: DebugConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +33,34 @@ pub struct AppearanceConfig {
     pub show_left_sidebar: bool,
     #[serde(default = "default_false")]
     pub show_right_sidebar: bool,
+    /// Hex outline color for selected annotations (empty = theme default)
+    #[serde(default)]
+    pub selection_color: String,
+    #[serde(default = "default_selection_thickness")]
+    pub selection_thickness: f32,
+    /// Flip the scroll direction used for zooming (trackpads)
+    #[serde(default = "default_false")]
+    pub invert_zoom_scroll: bool,
+    /// On-screen radius of point markers in px, kept roughly constant across zoom
+    #[serde(default = "default_point_radius")]
+    pub point_radius: f32,
+    /// Zoom range for wheel zoom, fit and restored views
+    #[serde(default = "default_min_zoom")]
+    pub min_zoom: f32,
+    #[serde(default = "default_max_zoom")]
+    pub max_zoom: f32,
+    /// Window position and size at the last exit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowRect>,
+}
+
+/// A screen rectangle in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +71,63 @@ pub struct AnnotationModesConfig {
     pub enable_bboxes: bool,
     #[serde(default = "default_true")]
     pub enable_polygons: bool,
+    /// Snap drawn/edited coordinates to this pixel grid (off when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snap_grid: Option<f32>,
+    /// Which gestures finish a polygon in progress
+    #[serde(default)]
+    pub polygon_finish: PolygonFinish,
+    /// Class pairs swapped when mirroring (e.g. `[[3, 4]]` for left/right hand)
+    #[serde(default)]
+    pub mirror_class_pairs: Vec<[i32; 2]>,
+    /// Vertex count used by Tools → Resample Selected Polygons
+    #[serde(default = "default_polygon_resample_count")]
+    pub polygon_resample_count: usize,
+    /// Leave rejected rows out of undo snapshots to save memory. Undo then
+    /// drops rows that were already rejected, so their audit trail is lost.
+    #[serde(default)]
+    pub compact_undo: bool,
+    /// Bulk edits affecting more annotations than this need a second press (0 = never ask)
+    #[serde(default = "default_confirm_bulk_above")]
+    pub confirm_bulk_above: usize,
+    /// Select a box, point or polygon as soon as it is drawn (deselecting the rest)
+    #[serde(default = "default_false")]
+    pub select_on_create: bool,
+    /// Dragging a member of a multi-selection keeps the whole group selected;
+    /// a plain click without a drag still selects just that annotation
+    #[serde(default = "default_false")]
+    pub keep_group_on_click: bool,
+}
+
+/// Gesture that commits a polygon (Tab/Enter always work)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolygonFinish {
+    #[default]
+    SReleased,
+    RightClick,
+    Both,
+}
+
+impl PolygonFinish {
+    pub fn on_key_release(self) -> bool {
+        matches!(self, PolygonFinish::SReleased | PolygonFinish::Both)
+    }
+
+    pub fn on_right_click(self) -> bool {
+        matches!(self, PolygonFinish::RightClick | PolygonFinish::Both)
+    }
+}
+
+/// Vertex order for exported polygons, as seen on screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolygonWinding {
+    /// As drawn
+    #[default]
+    Keep,
+    Clockwise,
+    CounterClockwise,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +138,30 @@ pub struct DatasetConfig {
     pub auto_save_interval_seconds: u64,
     #[serde(default)]
     pub recent_datasets: Vec<String>,
+    /// How many subfolder levels "New Dataset" scans for images
+    #[serde(default = "default_scan_max_depth")]
+    pub scan_max_depth: usize,
+    /// Frames per second sampled when opening a video
+    #[serde(default = "default_video_fps")]
+    pub video_fps: f32,
+    /// Extension for YOLO label files (e.g. `txt`, `label`); the state
+    /// sidecar is derived from the label path
+    #[serde(default = "default_label_extension")]
+    pub label_extension: String,
+    /// File name pattern for Tools → Rename Images (`{index}`, `{index:05}`, `{stem}`)
+    #[serde(default = "default_rename_pattern")]
+    pub rename_pattern: String,
+    /// When set, autosave writes labels and state into a new timestamped
+    /// folder here instead of overwriting the working files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autosave_backup_dir: Option<String>,
+    /// Autosave backup folders to keep; older ones are deleted
+    #[serde(default = "default_autosave_backup_keep")]
+    pub autosave_backup_keep: usize,
+    /// Write the labels and state of the frame being left on every
+    /// navigation, instead of waiting for the autosave timer
+    #[serde(default = "default_false")]
+    pub save_on_navigate: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +175,120 @@ pub struct ExportConfig {
     pub default_format: String,
     #[serde(default = "default_coco_category_start_id")]
     pub coco_category_start_id: i32,
+    /// Write VOC XML files for images without any box objects
+    #[serde(default = "default_true")]
+    pub export_empty_voc: bool,
+    /// Write COCO as gzip-compressed `annotations.json.gz`
+    #[serde(default = "default_false")]
+    pub compress_coco: bool,
+    /// Annotation states to export (empty = all but Rejected)
+    #[serde(default)]
+    pub state_filter: Vec<String>,
+    /// Number of thumbnails per row in the contact sheet
+    #[serde(default = "default_contact_sheet_columns")]
+    pub contact_sheet_columns: usize,
+    /// Only include frames marked complete in the contact sheet
+    #[serde(default = "default_false")]
+    pub contact_sheet_completed_only: bool,
+    /// Working class id → exported class id, applied only when writing
+    /// COCO/VOC exports (e.g. `"3" = 2` merges class 3 into 2)
+    #[serde(default, with = "int_key_map", skip_serializing_if = "HashMap::is_empty")]
+    pub class_export_map: HashMap<i32, i32>,
+    /// Write a YOLO `data.yaml` next to the manifest when saving labels
+    #[serde(default = "default_false")]
+    pub write_data_yaml: bool,
+    /// Only export frames approved in review (COCO/VOC)
+    #[serde(default = "default_false")]
+    pub reviewed_only: bool,
+    /// Derive COCO image/annotation ids from a filename hash instead of
+    /// numbering them, so exports of separate subsets can be merged
+    #[serde(default = "default_false")]
+    pub stable_ids: bool,
+    /// Vertex order of exported COCO polygon outlines (holes get the opposite)
+    #[serde(default)]
+    pub polygon_winding: PolygonWinding,
+    /// Create missing label/state folders on save; off makes save fail instead,
+    /// catching manifest path mistakes early
+    #[serde(default = "default_true")]
+    pub create_missing_dirs: bool,
+    /// Decimal places of the numbers in saved YOLO labels; unset writes the
+    /// shortest exact form, which can change digits for tiny moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yolo_precision: Option<usize>,
+    /// Sort saved YOLO lines by class, then center x, then center y instead of
+    /// drawing order, so reordering annotations doesn't change the label file
+    #[serde(default = "default_false")]
+    pub sort_yolo_lines: bool,
+    /// Treat an explicit save as a review: `Pending` annotations are saved as
+    /// `Accepted` (autosave leaves them alone)
+    #[serde(default = "default_false")]
+    pub accept_pending_on_save: bool,
+    /// Folder export dialogs open in; updated to the last used export folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_export_dir: Option<String>,
+}
+
+/// TOML table keys are strings, so integer-keyed maps are stored as `"3" = 2`
+mod int_key_map {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::{BTreeMap, HashMap};
+
+    pub fn serialize<S: Serializer>(map: &HashMap<i32, i32>, s: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<String, i32> = map.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        sorted.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<HashMap<i32, i32>, D::Error> {
+        let raw = HashMap::<String, i32>::deserialize(d)?;
+        raw.into_iter()
+            .map(|(k, v)| {
+                let key = k
+                    .trim()
+                    .parse::<i32>()
+                    .map_err(|_| D::Error::custom(format!("bad class id {k:?}")))?;
+                Ok((key, v))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserConfig {
+    /// Name stamped on newly created annotations (empty = anonymous)
+    #[serde(default)]
+    pub author: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationConfig {
+    /// Per-class count limits checked when a frame is marked complete
+    #[serde(default)]
+    pub count_rules: Vec<CountRule>,
+    /// Refuse to mark a frame complete while a rule is violated (otherwise warn)
+    #[serde(default = "default_false")]
+    pub block_on_violation: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugConfig {
+    /// Diagnostics verbosity: off, error, warn, info, debug or trace.
+    /// Debug and trace messages go to `debug_output.log`.
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// Read sidebar `width/hide/show` commands from stdin (on by default only in debug builds)
+    #[serde(default = "default_stdin_console")]
+    pub stdin_console: bool,
+}
+
+/// Allowed number of annotations of one class per image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountRule {
+    pub class_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
 }
 
 // Default value functions
@@ -69,6 +300,26 @@ fn default_sidebar_width() -> i32 {
     250
 }
 
+fn default_selection_thickness() -> f32 {
+    2.0
+}
+
+fn default_point_radius() -> f32 {
+    5.0
+}
+
+fn default_min_zoom() -> f32 {
+    0.1
+}
+
+fn default_max_zoom() -> f32 {
+    10.0
+}
+
+fn default_stdin_console() -> bool {
+    cfg!(debug_assertions)
+}
+
 fn default_true() -> bool {
     true
 }
@@ -81,6 +332,10 @@ fn default_autosave_interval() -> u64 {
     5
 }
 
+fn default_scan_max_depth() -> usize {
+    8
+}
+
 fn default_export_format() -> String {
     "yolo".to_string()
 }
@@ -89,6 +344,36 @@ fn default_coco_category_start_id() -> i32 {
     1
 }
 
+fn default_video_fps() -> f32 {
+    1.0
+}
+
+pub const DEFAULT_LABEL_EXTENSION: &str = "txt";
+
+fn default_rename_pattern() -> String {
+    "frame_{index:05}".to_string()
+}
+
+fn default_autosave_backup_keep() -> usize {
+    10
+}
+
+fn default_label_extension() -> String {
+    DEFAULT_LABEL_EXTENSION.to_string()
+}
+
+fn default_confirm_bulk_above() -> usize {
+    20
+}
+
+fn default_polygon_resample_count() -> usize {
+    32
+}
+
+fn default_contact_sheet_columns() -> usize {
+    6
+}
+
 impl Default for AppearanceConfig {
     fn default() -> Self {
         Self {
@@ -96,6 +381,13 @@ impl Default for AppearanceConfig {
             sidebar_width: default_sidebar_width(),
             show_left_sidebar: true,
             show_right_sidebar: false,
+            selection_color: String::new(),
+            selection_thickness: default_selection_thickness(),
+            invert_zoom_scroll: false,
+            point_radius: default_point_radius(),
+            min_zoom: default_min_zoom(),
+            max_zoom: default_max_zoom(),
+            window: None,
         }
     }
 }
@@ -106,6 +398,14 @@ impl Default for AnnotationModesConfig {
             enable_points: true,
             enable_bboxes: true,
             enable_polygons: true,
+            snap_grid: None,
+            polygon_finish: PolygonFinish::default(),
+            mirror_class_pairs: Vec::new(),
+            polygon_resample_count: default_polygon_resample_count(),
+            compact_undo: false,
+            confirm_bulk_above: default_confirm_bulk_above(),
+            select_on_create: false,
+            keep_group_on_click: false,
         }
     }
 }
@@ -116,6 +416,13 @@ impl Default for DatasetConfig {
             randomize_order: false,
             auto_save_interval_seconds: 5,
             recent_datasets: Vec::new(),
+            scan_max_depth: default_scan_max_depth(),
+            video_fps: default_video_fps(),
+            label_extension: default_label_extension(),
+            rename_pattern: default_rename_pattern(),
+            autosave_backup_dir: None,
+            autosave_backup_keep: default_autosave_backup_keep(),
+            save_on_navigate: false,
         }
     }
 }
@@ -131,6 +438,38 @@ impl Default for ExportConfig {
         Self {
             default_format: default_export_format(),
             coco_category_start_id: 1,
+            export_empty_voc: true,
+            compress_coco: false,
+            state_filter: Vec::new(),
+            contact_sheet_columns: default_contact_sheet_columns(),
+            contact_sheet_completed_only: false,
+            class_export_map: HashMap::new(),
+            write_data_yaml: false,
+            reviewed_only: false,
+            stable_ids: false,
+            polygon_winding: PolygonWinding::Keep,
+            create_missing_dirs: true,
+            yolo_precision: None,
+            sort_yolo_lines: false,
+            accept_pending_on_save: false,
+            default_export_dir: None,
+        }
+    }
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            author: String::new(),
+        }
+    }
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            log_level: LogLevel::default(),
+            stdin_console: default_stdin_console(),
         }
     }
 }
@@ -143,6 +482,9 @@ impl Default for AppConfig {
             dataset: DatasetConfig::default(),
             classes: ClassesConfig::default(),
             export: ExportConfig::default(),
+            user: UserConfig::default(),
+            validation: ValidationConfig::default(),
+            debug: DebugConfig::default(),
         }
     }
 }
@@ -156,32 +498,57 @@ pub fn config_path() -> PathBuf {
     config_dir.join("config.toml")
 }
 
+/// Copy of the previous config kept next to it (`config.toml.bak`)
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Read and parse one config file
+fn read_config(path: &Path) -> Result<AppConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
 /// Load configuration from file, or return default if file doesn't exist
 pub fn load_config() -> AppConfig {
-    let path = config_path();
-    if path.exists() {
-        match std::fs::read_to_string(&path) {
-            Ok(content) => match toml::from_str(&content) {
-                Ok(config) => config,
-                Err(e) => {
-                    eprintln!("Failed to parse config file: {}. Using defaults.", e);
-                    AppConfig::default()
-                }
-            },
-            Err(e) => {
-                eprintln!("Failed to read config file: {}. Using defaults.", e);
-                AppConfig::default()
-            }
+    load_config_from(&config_path())
+}
+
+/// Load the config at `path`. If it can't be read or parsed, its `.bak` is
+/// tried before falling back to defaults; a fallback is logged.
+pub fn load_config_from(path: &Path) -> AppConfig {
+    let backup = backup_path(path);
+    if !path.exists() && !backup.exists() {
+        return AppConfig::default();
+    }
+    let err = match read_config(path) {
+        Ok(config) => return config,
+        Err(e) => e,
+    };
+    match read_config(&backup) {
+        Ok(config) => {
+            crate::logging::warn(|| format!("{err}. Using backup {}.", backup.display()));
+            config
+        }
+        Err(backup_err) => {
+            crate::logging::warn(|| format!("{err}; {backup_err}. Using defaults."));
+            AppConfig::default()
         }
-    } else {
-        AppConfig::default()
     }
 }
 
 /// Save configuration to file
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
-    let path = config_path();
+    save_config_to(config, &config_path())
+}
 
+/// Write `config` to `path` atomically: the TOML goes to a temp file that is
+/// renamed over the target, so a failed write never leaves a half-written
+/// config. A previous version that still parses is kept as `<path>.bak`.
+pub fn save_config_to(config: &AppConfig, path: &Path) -> Result<(), String> {
     // Create config directory if it doesn't exist
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
@@ -192,11 +559,32 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
     let toml = toml::to_string_pretty(config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-    // Write to file
-    std::fs::write(&path, toml)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp = PathBuf::from(tmp_name);
+    let write = || -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(toml.as_bytes())?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to write config file: {}", e));
+    }
 
-    Ok(())
+    // Keep a copy of the previous version, unless it is itself broken. The
+    // primary stays in place until the rename replaces it, so there is no
+    // moment without a config file.
+    let backed_up = match read_config(path) {
+        Ok(_) => std::fs::copy(path, backup_path(path)).map(|_| ()),
+        Err(_) => Ok(()),
+    };
+    if let Err(e) = backed_up {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to back up config file: {}", e));
+    }
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to write config file: {}", e))
 }
 
 /// Add a dataset path to recent datasets list
@@ -211,3 +599,110 @@ pub fn add_recent_dataset(config: &mut AppConfig, path: String) {
     // Keep only last 10
     config.dataset.recent_datasets.truncate(10);
 }
+
+/// Remember `folder` as the starting folder of the next export dialog
+pub fn record_export_dir(config: &mut AppConfig, folder: &Path) {
+    config.export.default_export_dir = Some(folder.to_string_lossy().to_string());
+}
+
+/// Folder an export dialog opens in: the configured (last used) export folder
+/// if it still exists, else `fallback` (the dataset folder)
+pub fn export_start_dir(config: &AppConfig, fallback: Option<&Path>) -> Option<PathBuf> {
+    config
+        .export
+        .default_export_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .or_else(|| fallback.map(Path::to_path_buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_save_keeps_valid_backup_and_load_falls_back_to_it() {
+        let dir = scratch_dir("config_backup");
+        let path = dir.join("config.toml");
+
+        let mut first = AppConfig::default();
+        first.appearance.point_radius = 7.0;
+        save_config_to(&first, &path).unwrap();
+        // Nothing to back up on the first save
+        assert!(!backup_path(&path).exists());
+
+        let mut second = first.clone();
+        second.appearance.point_radius = 9.0;
+        save_config_to(&second, &path).unwrap();
+        assert_eq!(read_config(&path).unwrap().appearance.point_radius, 9.0);
+        assert_eq!(read_config(&backup_path(&path)).unwrap().appearance.point_radius, 7.0);
+        assert!(!dir.join("config.toml.tmp").exists());
+
+        // A corrupt primary loads the backup instead of the defaults
+        std::fs::write(&path, "[appearance\npoint_radius = ").unwrap();
+        assert_eq!(load_config_from(&path).appearance.point_radius, 7.0);
+
+        // Saving over the corrupt file keeps the good backup
+        save_config_to(&second, &path).unwrap();
+        assert_eq!(read_config(&backup_path(&path)).unwrap().appearance.point_radius, 7.0);
+
+        // With both unreadable, defaults are used
+        std::fs::write(&path, "not toml [").unwrap();
+        std::fs::write(backup_path(&path), "also [ not toml").unwrap();
+        let default_radius = AppConfig::default().appearance.point_radius;
+        assert_eq!(load_config_from(&path).appearance.point_radius, default_radius);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_dir_is_recorded_and_stale_dir_falls_back() {
+        let dir = scratch_dir("export_dir");
+        let dataset_dir = Path::new("/data/cams");
+
+        let mut config = AppConfig::default();
+        assert_eq!(export_start_dir(&config, Some(dataset_dir)), Some(dataset_dir.to_path_buf()));
+
+        record_export_dir(&mut config, &dir);
+        let text = toml::to_string_pretty(&config).unwrap();
+        let parsed: AppConfig = toml::from_str(&text).unwrap();
+        assert_eq!(export_start_dir(&parsed, Some(dataset_dir)), Some(dir.clone()));
+
+        // A folder that no longer exists falls back to the dataset folder
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(export_start_dir(&parsed, Some(dataset_dir)), Some(dataset_dir.to_path_buf()));
+        assert_eq!(export_start_dir(&parsed, None), None);
+    }
+
+    #[test]
+    fn test_class_export_map_round_trips_through_toml() {
+        let mut config = AppConfig::default();
+        config.export.class_export_map.insert(3, 2);
+
+        let text = toml::to_string_pretty(&config).unwrap();
+        let parsed: AppConfig = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.export.class_export_map.get(&3), Some(&2));
+    }
+
+    #[test]
+    fn test_selection_style_round_trip() {
+        let mut config = AppConfig::default();
+        config.appearance.selection_color = "#ffcc00".to_string();
+        config.appearance.selection_thickness = 4.5;
+
+        let text = toml::to_string_pretty(&config).unwrap();
+        let parsed: AppConfig = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.appearance.selection_color, "#ffcc00");
+        assert_eq!(parsed.appearance.selection_thickness, 4.5);
+        assert!(crate::utils::parse_color(&parsed.appearance.selection_color).is_some());
+    }
+
+    #[test]
+    fn test_selection_style_defaults_when_missing() {
+        let parsed: AppConfig = toml::from_str("[appearance]\ntheme = \"light\"\n").unwrap();
+        assert_eq!(parsed.appearance.selection_color, "");
+        assert_eq!(parsed.appearance.selection_thickness, 2.0);
+    }
+}
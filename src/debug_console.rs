@@ -0,0 +1,167 @@
+//! Optional stdin console for adjusting the sidebar while debugging layouts.
+//!
+//! Only started when `debug.stdin_console` is set, so launching the app from
+//! scripts or pipes never has its stdin consumed.
+
+use crate::AppWindow;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+const HELP: &str = "\n=== SIDEBAR DEBUG COMMANDS ===\n  \
+    width <number>  - Set sidebar width in pixels (e.g., 'width 300')\n  \
+    hide            - Hide sidebar\n  \
+    show            - Show sidebar\n  \
+    help            - Show this help\n\
+    ==============================\n";
+
+/// A parsed console line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConsoleCommand {
+    Width(f32),
+    Hide,
+    Show,
+    Help,
+}
+
+/// Parse one console line; blank lines give `Ok(None)`
+pub fn parse_command(line: &str) -> Result<Option<ConsoleCommand>, String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let Some(&command) = parts.first() else {
+        return Ok(None);
+    };
+    let parsed = match command {
+        "width" | "w" => {
+            let width = parts.get(1).ok_or("Usage: width <number>")?;
+            let width = width
+                .parse::<f32>()
+                .map_err(|_| "Invalid number. Usage: width <number>".to_string())?;
+            ConsoleCommand::Width(width)
+        }
+        "hide" | "h" => ConsoleCommand::Hide,
+        "show" | "s" => ConsoleCommand::Show,
+        "help" | "?" => ConsoleCommand::Help,
+        other => return Err(format!("Unknown command '{other}'. Type 'help' for commands.")),
+    };
+    Ok(Some(parsed))
+}
+
+/// Read commands from `input` until it ends, `running` is cleared, or `apply`
+/// reports that the UI is gone
+pub fn run_console<R: BufRead>(
+    input: R,
+    running: &AtomicBool,
+    apply: impl Fn(ConsoleCommand) -> bool,
+) {
+    for line in input.lines() {
+        if !running.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(line) = line else { break };
+        match parse_command(&line) {
+            Ok(Some(ConsoleCommand::Help)) => println!("{HELP}"),
+            Ok(Some(command)) => {
+                if !apply(command) {
+                    break; // UI closed
+                }
+            }
+            Ok(None) => {}
+            Err(e) => println!("✗ {e}"),
+        }
+    }
+}
+
+/// Start the console thread when `enabled`. `open_input` is only called on
+/// the console thread, so a disabled console never touches stdin.
+pub fn start_console<R, F>(
+    enabled: bool,
+    open_input: F,
+    running: Arc<AtomicBool>,
+    apply: impl Fn(ConsoleCommand) -> bool + Send + 'static,
+) -> Option<JoinHandle<()>>
+where
+    R: BufRead,
+    F: FnOnce() -> R + Send + 'static,
+{
+    if !enabled {
+        return None;
+    }
+    Some(std::thread::spawn(move || {
+        println!("{HELP}");
+        run_console(open_input(), &running, apply);
+    }))
+}
+
+/// Start the stdin console for `ui` if enabled; clear `running` when the UI closes
+pub fn spawn_stdin_console(
+    enabled: bool,
+    ui: slint::Weak<AppWindow>,
+    running: Arc<AtomicBool>,
+) -> Option<JoinHandle<()>> {
+    start_console(enabled, || std::io::stdin().lock(), running, move |command| {
+        // The weak handle may only be upgraded on the UI thread
+        ui.upgrade_in_event_loop(move |ui| match command {
+            ConsoleCommand::Width(width) => {
+                ui.set_sidebar_width(width);
+                println!("✓ Sidebar width set to {}px", width);
+            }
+            ConsoleCommand::Hide => {
+                ui.set_sidebar_visible(false);
+                println!("✓ Sidebar hidden");
+            }
+            ConsoleCommand::Show => {
+                ui.set_sidebar_visible(true);
+                println!("✓ Sidebar shown");
+            }
+            ConsoleCommand::Help => {}
+        })
+        .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("width 300"), Ok(Some(ConsoleCommand::Width(300.0))));
+        assert_eq!(parse_command("  h "), Ok(Some(ConsoleCommand::Hide)));
+        assert_eq!(parse_command(""), Ok(None));
+        assert!(parse_command("width abc").is_err());
+        assert!(parse_command("jump").is_err());
+    }
+
+    #[test]
+    fn test_disabled_console_does_not_open_stdin() {
+        let opened = Arc::new(AtomicBool::new(false));
+        let flag = opened.clone();
+        let handle = start_console(
+            false,
+            move || {
+                flag.store(true, Ordering::Relaxed);
+                Cursor::new("hide\n")
+            },
+            Arc::new(AtomicBool::new(true)),
+            |_| true,
+        );
+        assert!(handle.is_none());
+        assert!(!opened.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_console_stops_when_ui_closes() {
+        let applied = std::cell::Cell::new(0);
+        let running = AtomicBool::new(true);
+        run_console(Cursor::new("hide\nshow\nshow\n"), &running, |_| {
+            applied.set(applied.get() + 1);
+            false
+        });
+        assert_eq!(applied.get(), 1);
+
+        running.store(false, Ordering::Relaxed);
+        run_console(Cursor::new("hide\n"), &running, |_| panic!("console ran after close"));
+    }
+}
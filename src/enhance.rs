@@ -0,0 +1,55 @@
+//! Display-only image enhancement (histogram equalization for dim frames).
+
+use image::{DynamicImage, GrayImage, RgbaImage};
+use imageproc::contrast::equalize_histogram;
+
+/// Equalize the histogram of each color channel; alpha is left unchanged
+pub fn equalize_image(img: &DynamicImage) -> RgbaImage {
+    let mut rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    for channel in 0..3 {
+        let plane = GrayImage::from_fn(width, height, |x, y| {
+            image::Luma([rgba.get_pixel(x, y)[channel]])
+        });
+        let equalized = equalize_histogram(&plane);
+        for (x, y, pixel) in rgba.enumerate_pixels_mut() {
+            pixel[channel] = equalized.get_pixel(x, y)[0];
+        }
+    }
+    rgba
+}
+
+/// Convert an RGBA buffer into an image the UI can display
+pub fn to_slint_image(img: &RgbaImage) -> slint::Image {
+    let buffer = slint::SharedPixelBuffer::<slint::Rgba8Pixel>::clone_from_slice(
+        img.as_raw(),
+        img.width(),
+        img.height(),
+    );
+    slint::Image::from_rgba8(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_equalize_widens_low_contrast_histogram() {
+        // Dim gradient spanning only 100..=120
+        let img = RgbaImage::from_fn(21, 4, |x, _| {
+            let v = 100 + x as u8;
+            Rgba([v, v, v, 255])
+        });
+        let spread = |img: &RgbaImage| {
+            let values: Vec<u8> = img.pixels().map(|p| p[0]).collect();
+            values.iter().max().unwrap() - values.iter().min().unwrap()
+        };
+        assert_eq!(spread(&img), 20);
+
+        let enhanced = equalize_image(&DynamicImage::ImageRgba8(img));
+        assert!(spread(&enhanced) > 200);
+        assert_eq!(enhanced.get_pixel(0, 0)[3], 255);
+    }
+}
@@ -1,12 +1,20 @@
 // COCO JSON format export
 // http://cocodataset.org/#format-data
 
+use super::compression::{read_maybe_gzip, write_maybe_gzip};
+use super::{class_exported, export_class_id, passes_state_filter};
+use crate::classes::{get_class_name, ClassConfig};
+use crate::config::PolygonWinding;
+use crate::geometry::{ensure_winding, polygon_area};
+use crate::state::{parse_millis, parse_rings};
+use crate::transform::{rbbox_bounds, rbbox_corners};
+use crate::Annotation;
 use chrono::Datelike;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CocoInfo {
     pub year: i32,
     pub version: String,
@@ -15,15 +23,18 @@ pub struct CocoInfo {
     pub date_created: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CocoImage {
     pub id: i32,
     pub width: i32,
     pub height: i32,
     pub file_name: String,
+    /// Whole-image tags (non-standard field, omitted when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CocoAnnotation {
     pub id: i32,
     pub image_id: i32,
@@ -35,16 +46,137 @@ pub struct CocoAnnotation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub area: Option<f64>,
     pub iscrowd: i32,
+    // Custom audit fields (not part of the COCO spec)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<CocoAttributes>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Custom per-annotation attributes (not part of the COCO spec)
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CocoAttributes {
+    /// Annotation provenance: Pending, Manual, Accepted, ...
+    pub state: String,
+    /// Angle in degrees of a rotated box, whose corners are the segmentation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f32>,
+}
+
+/// Rings reordered for `winding`: the outline runs that way, holes the other
+pub fn apply_winding(rings: &[Vec<(f32, f32)>], winding: PolygonWinding) -> Vec<Vec<(f32, f32)>> {
+    let clockwise = match winding {
+        PolygonWinding::Keep => return rings.to_vec(),
+        PolygonWinding::Clockwise => true,
+        PolygonWinding::CounterClockwise => false,
+    };
+    rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| ensure_winding(ring, if i == 0 { clockwise } else { !clockwise }))
+        .collect()
+}
+
+/// COCO `segmentation` and `area` for polygon rings (outline first, then holes).
+/// Every ring becomes one flat `[x1, y1, x2, y2, ...]` list; holes are subtracted
+/// from the area. Rings with fewer than three vertices are dropped.
+pub fn polygon_segmentation(rings: &[Vec<(f32, f32)>]) -> (Vec<Vec<f64>>, f64) {
+    let mut segmentation = Vec::new();
+    let mut area = 0.0;
+    for (i, ring) in rings.iter().filter(|r| r.len() >= 3).enumerate() {
+        segmentation.push(ring.iter().flat_map(|&(x, y)| [x as f64, y as f64]).collect());
+        let ring_area = polygon_area(ring) as f64;
+        area += if i == 0 { ring_area } else { -ring_area };
+    }
+    (segmentation, area.max(0.0))
+}
+
+/// Axis-aligned COCO `bbox` enclosing a rotated box, and its four corners as
+/// one flat segmentation ring (clockwise from the unrotated top-left corner)
+pub fn rotated_box_segmentation(ann: &Annotation) -> ([f64; 4], Vec<f64>) {
+    let rect = (ann.x, ann.y, ann.width, ann.height);
+    let (x, y, w, h) = rbbox_bounds(rect, ann.rotation);
+    let bbox = [x as f64, y as f64, w as f64, h as f64];
+    let corners = rbbox_corners(rect, ann.rotation);
+    (bbox, corners.iter().flat_map(|&(x, y)| [x as f64, y as f64]).collect())
+}
+
+/// 32-bit FNV-1a; unlike `DefaultHasher` it is fixed across Rust releases
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Positive id derived from a hash, so independently exported subsets merge
+/// without renumbering (0 is avoided as some tools treat it as "none")
+fn hashed_id(key: &str) -> i32 {
+    ((fnv1a(key.as_bytes()) & 0x7fff_ffff) as i32).max(1)
+}
+
+/// Stable COCO image id for the image at `rel_path` below the dataset root
+/// (`export.stable_ids`)
+pub fn stable_image_id(rel_path: &str) -> i32 {
+    hashed_id(rel_path)
+}
+
+/// Stable COCO annotation id for the `index`-th annotation of `rel_path`
+pub fn stable_annotation_id(rel_path: &str, index: usize) -> i32 {
+    hashed_id(&format!("{rel_path}#{index}"))
+}
+
+/// First id in `ids` seen twice
+fn first_duplicate(ids: impl IntoIterator<Item = i32>) -> Option<i32> {
+    let mut seen = HashSet::new();
+    ids.into_iter().find(|&id| !seen.insert(id))
+}
+
+/// Export settings applied to every image's annotations
+pub struct CocoFrameOptions<'a> {
+    pub state_filter: &'a [String],
+    pub class_map: &'a HashMap<i32, i32>,
+    pub classes: &'a ClassConfig,
+    pub stable_ids: bool,
+    pub winding: PolygonWinding,
+}
+
+/// Options exporting every state with numbered ids and polygons as drawn, for tests
+#[cfg(test)]
+pub fn test_frame_options<'a>(
+    classes: &'a ClassConfig,
+    class_map: &'a HashMap<i32, i32>,
+) -> CocoFrameOptions<'a> {
+    CocoFrameOptions {
+        state_filter: &[],
+        class_map,
+        classes,
+        stable_ids: false,
+        winding: PolygonWinding::Keep,
+    }
+}
+
+/// One dataset image as exported: its position in the dataset (for numbered
+/// ids), file name, path below the dataset root (for stable ids), pixel size,
+/// tags and stored annotations
+pub struct CocoFrame<'a> {
+    pub index: usize,
+    pub file_name: &'a str,
+    pub rel_path: &'a str,
+    pub size: (i32, i32),
+    pub tags: &'a [String],
+    pub annotations: &'a [Annotation],
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CocoCategory {
     pub id: i32,
     pub name: String,
     pub supercategory: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CocoDataset {
     pub info: CocoInfo,
     pub images: Vec<CocoImage>,
@@ -77,11 +209,418 @@ impl CocoDataset {
         });
     }
 
+    /// Add one category per distinct exported class id, named after the target
+    /// class; classes with `export: false` get none
+    pub fn add_mapped_categories(&mut self, classes: &ClassConfig, map: &HashMap<i32, i32>) {
+        let ids: BTreeSet<i32> = classes
+            .classes
+            .iter()
+            .filter(|c| c.export)
+            .map(|c| export_class_id(c.id, map))
+            .collect();
+        for id in ids {
+            self.add_category(id, get_class_name(classes, id));
+        }
+    }
+
+    /// Append the exported annotations of one image: those passing the state
+    /// filter whose class is exported. Ids come from `next_id` unless
+    /// `stable_ids` is on. Returns the category id of each annotation added.
+    pub fn add_image_annotations(
+        &mut self,
+        image_id: i32,
+        rel_path: &str,
+        anns: &[Annotation],
+        options: &CocoFrameOptions,
+        next_id: &mut i32,
+    ) -> Vec<i32> {
+        let mut added = Vec::new();
+        // Stable ids use the index among all stored annotations, so changing
+        // the state filter doesn't renumber the rest
+        for (ann_idx, ann) in anns.iter().enumerate().filter(|(_, a)| {
+            passes_state_filter(&a.state, options.state_filter)
+                && class_exported(options.classes, a.class)
+        }) {
+            let (bbox, segmentation, area) = match ann.r#type.as_str() {
+                "rbbox" => {
+                    let (bbox, corners) = rotated_box_segmentation(ann);
+                    (Some(bbox), Some(vec![corners]), ann.width as f64 * ann.height as f64)
+                }
+                "bbox" => {
+                    let bbox =
+                        [ann.x as f64, ann.y as f64, ann.width as f64, ann.height as f64];
+                    (Some(bbox), None, ann.width as f64 * ann.height as f64)
+                }
+                "point" => (Some([ann.x as f64, ann.y as f64, 1.0, 1.0]), None, 1.0),
+                "polygon" => {
+                    let rings = apply_winding(&parse_rings(&ann.vertices), options.winding);
+                    let (segmentation, area) = polygon_segmentation(&rings);
+                    (None, Some(segmentation), area)
+                }
+                _ => continue,
+            };
+
+            let category_id = export_class_id(ann.class, options.class_map);
+            let id = if options.stable_ids {
+                stable_annotation_id(rel_path, ann_idx)
+            } else {
+                *next_id
+            };
+            self.annotations.push(CocoAnnotation {
+                id,
+                image_id,
+                category_id,
+                bbox,
+                segmentation,
+                area: Some(area),
+                iscrowd: 0,
+                created_at: Some(parse_millis(&ann.created_at)).filter(|t| *t > 0),
+                modified_at: Some(parse_millis(&ann.modified_at)).filter(|t| *t > 0),
+                author: Some(ann.author.to_string()).filter(|a| !a.is_empty()),
+                attributes: Some(CocoAttributes {
+                    state: ann.state.to_string(),
+                    rotation: (ann.r#type == "rbbox").then_some(ann.rotation),
+                }),
+            });
+            added.push(category_id);
+            *next_id += 1;
+        }
+        added
+    }
+
+    /// Append one image and its exported annotations. The image id is the
+    /// 1-based dataset index, or a hash of the relative path with `stable_ids`. Returns the
+    /// category id of each annotation added.
+    pub fn add_frame(
+        &mut self,
+        frame: &CocoFrame,
+        options: &CocoFrameOptions,
+        next_id: &mut i32,
+    ) -> Vec<i32> {
+        let image_id = if options.stable_ids {
+            stable_image_id(frame.rel_path)
+        } else {
+            (frame.index + 1) as i32
+        };
+        self.images.push(CocoImage {
+            id: image_id,
+            width: frame.size.0,
+            height: frame.size.1,
+            file_name: frame.file_name.to_string(),
+            tags: frame.tags.to_vec(),
+        });
+        self.add_image_annotations(image_id, frame.rel_path, frame.annotations, options, next_id)
+    }
+
+    /// A COCO file holding just `frame`, with the usual categories, for
+    /// checking one image's output without exporting the whole dataset
+    pub fn single_frame(frame: &CocoFrame, options: &CocoFrameOptions) -> Self {
+        let mut coco = CocoDataset::new();
+        coco.add_mapped_categories(options.classes, options.class_map);
+        coco.add_frame(frame, options, &mut 1);
+        coco
+    }
+
+    /// Fail if two images or two annotations share an id (hashed stable ids
+    /// can collide; exporting without `stable_ids` avoids it)
+    pub fn check_unique_ids(&self) -> Result<(), String> {
+        if let Some(id) = first_duplicate(self.images.iter().map(|i| i.id)) {
+            let names: Vec<&str> = self
+                .images
+                .iter()
+                .filter(|i| i.id == id)
+                .map(|i| i.file_name.as_str())
+                .collect();
+            return Err(format!(
+                "Stable image id {id} is shared by {}; export without stable ids",
+                names.join(" and ")
+            ));
+        }
+        if let Some(id) = first_duplicate(self.annotations.iter().map(|a| a.id)) {
+            return Err(format!(
+                "Stable annotation id {id} is used twice; export without stable ids"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Save as JSON; paths ending in `.json.gz` are gzip-compressed
     pub fn save(&self, path: &Path) -> Result<(), String> {
         let json = serde_json::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize COCO JSON: {e}"))?;
-        fs::write(path, json)
+        write_maybe_gzip(path, json.as_bytes())
             .map_err(|e| format!("Failed to write COCO JSON: {e}"))?;
         Ok(())
     }
+
+    /// Load from JSON; paths ending in `.json.gz` are gunzipped first
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = read_maybe_gzip(path).map_err(|e| format!("Failed to read COCO JSON: {e}"))?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse COCO JSON: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{test_class, ClassDefinition};
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let dir = scratch_dir("coco_gz");
+        let path = dir.join("annotations.json.gz");
+
+        let mut coco = CocoDataset::new();
+        coco.add_category(1, "car".to_string());
+        coco.images.push(CocoImage {
+            id: 1,
+            width: 640,
+            height: 480,
+            file_name: "a.jpg".to_string(),
+            tags: Vec::new(),
+        });
+        coco.annotations.push(CocoAnnotation {
+            id: 1,
+            image_id: 1,
+            category_id: 1,
+            bbox: Some([10.0, 20.0, 30.0, 40.0]),
+            segmentation: None,
+            area: Some(1200.0),
+            iscrowd: 0,
+            created_at: Some(1000),
+            modified_at: None,
+            author: Some("alice".to_string()),
+            attributes: Some(CocoAttributes {
+                state: "Accepted".to_string(),
+                rotation: None,
+            }),
+        });
+
+        coco.save(&path).unwrap();
+        // Really compressed: gzip magic bytes, not plain JSON
+        let raw = std::fs::read(&path).unwrap();
+        assert_eq!(&raw[..2], &[0x1f, 0x8b]);
+
+        let loaded = CocoDataset::load(&path).unwrap();
+        assert_eq!(loaded, coco);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_polygon_segmentation_exports_holes() {
+        let outline = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let hole = vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)];
+
+        let (segmentation, area) = polygon_segmentation(&[outline.clone(), hole]);
+        assert_eq!(
+            segmentation,
+            vec![
+                vec![0.0, 0.0, 10.0, 0.0, 10.0, 10.0, 0.0, 10.0],
+                vec![4.0, 4.0, 6.0, 4.0, 6.0, 6.0, 4.0, 6.0],
+            ]
+        );
+        assert_eq!(area, 96.0);
+
+        // Single-ring polygons export as before
+        let (segmentation, area) = polygon_segmentation(&[outline]);
+        assert_eq!(segmentation.len(), 1);
+        assert_eq!(area, 100.0);
+    }
+
+    #[test]
+    fn test_triangle_vertices_export_as_flat_segmentation_with_area() {
+        let classes = ClassConfig::default();
+        let map = HashMap::new();
+        let options = test_frame_options(&classes, &map);
+        // Stored as "x,y;x,y;..." with `;` between points
+        let triangle = Annotation {
+            r#type: "polygon".into(),
+            class: 1,
+            state: "Manual".into(),
+            vertices: "0,0;4,0;0,3".into(),
+            ..Default::default()
+        };
+
+        let mut coco = CocoDataset::new();
+        let mut next_id = 1;
+        coco.add_image_annotations(1, "a.jpg", &[triangle], &options, &mut next_id);
+        let ann = &coco.annotations[0];
+        assert_eq!(ann.segmentation, Some(vec![vec![0.0, 0.0, 4.0, 0.0, 0.0, 3.0]]));
+        assert_eq!(ann.area, Some(6.0));
+    }
+
+    #[test]
+    fn test_class_export_map_merges_categories() {
+        let classes = ClassConfig {
+            classes: vec![test_class(2, "vehicle"), test_class(3, "truck")],
+            hierarchy: Vec::new(),
+        };
+        let map = HashMap::from([(3, 2)]);
+
+        let mut coco = CocoDataset::new();
+        coco.add_mapped_categories(&classes, &map);
+        assert_eq!(coco.categories.len(), 1);
+        assert_eq!(coco.categories[0].id, 2);
+        assert_eq!(coco.categories[0].name, "vehicle");
+
+        // Annotations of both working classes export under the merged id
+        assert_eq!(export_class_id(2, &map), 2);
+        assert_eq!(export_class_id(3, &map), 2);
+        assert_eq!(export_class_id(5, &map), 5);
+    }
+
+    #[test]
+    fn test_non_exported_class_is_left_out() {
+        let classes = ClassConfig {
+            classes: vec![
+                test_class(1, "car"),
+                ClassDefinition { export: false, ..test_class(2, "ignore") },
+            ],
+            hierarchy: Vec::new(),
+        };
+        let ann = |class: i32| Annotation {
+            r#type: "bbox".into(),
+            width: 10.0,
+            height: 10.0,
+            class,
+            state: "Manual".into(),
+            ..Default::default()
+        };
+        let map = HashMap::new();
+        let options = test_frame_options(&classes, &map);
+
+        let mut coco = CocoDataset::new();
+        coco.add_mapped_categories(&classes, &map);
+        let anns = [ann(2), ann(1), ann(2)];
+        let mut next_id = 1;
+        let added = coco.add_image_annotations(1, "a.jpg", &anns, &options, &mut next_id);
+        assert_eq!(added, vec![1]);
+        assert_eq!(coco.annotations.len(), 1);
+        assert_eq!((coco.annotations[0].id, coco.annotations[0].category_id), (1, 1));
+        let names: Vec<&str> = coco.categories.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["car"]);
+    }
+
+    #[test]
+    fn test_single_frame_has_one_image_and_its_annotations() {
+        let classes = ClassConfig::default();
+        let map = HashMap::new();
+        let options = test_frame_options(&classes, &map);
+        let ann = |class: i32, state: &str| Annotation {
+            r#type: "bbox".into(),
+            x: 5.0,
+            y: 6.0,
+            width: 10.0,
+            height: 20.0,
+            class,
+            state: state.into(),
+            ..Default::default()
+        };
+        let anns = [ann(1, "Manual"), ann(2, "Rejected"), ann(2, "Accepted")];
+        let tags = vec!["night".to_string()];
+        let frame = CocoFrame {
+            index: 6,
+            file_name: "frame_0007.jpg",
+            rel_path: "seq1/frame_0007.jpg",
+            size: (640, 480),
+            tags: &tags,
+            annotations: &anns,
+        };
+
+        let coco = CocoDataset::single_frame(&frame, &options);
+        assert_eq!(coco.images.len(), 1);
+        let image = &coco.images[0];
+        assert_eq!((image.id, image.width, image.height), (7, 640, 480));
+        assert_eq!((image.file_name.as_str(), image.tags.clone()), ("frame_0007.jpg", tags));
+        let exported: Vec<(i32, i32, i32)> =
+            coco.annotations.iter().map(|a| (a.id, a.image_id, a.category_id)).collect();
+        assert_eq!(exported, vec![(1, 7, 1), (2, 7, 2)]);
+        assert_eq!(coco.annotations[0].bbox, Some([5.0, 6.0, 10.0, 20.0]));
+        assert!(!coco.categories.is_empty());
+    }
+
+    #[test]
+    fn test_image_tags_export_on_their_image() {
+        let mut coco = CocoDataset::new();
+        for (id, tags) in [(1, vec![]), (2, vec!["rainy".to_string(), "night".to_string()])] {
+            coco.images.push(CocoImage {
+                id,
+                width: 10,
+                height: 10,
+                file_name: format!("{id}.jpg"),
+                tags,
+            });
+        }
+
+        let json: serde_json::Value = serde_json::to_value(&coco).unwrap();
+        let images = json["images"].as_array().unwrap();
+        assert!(images[0].get("tags").is_none());
+        assert_eq!(images[1]["id"], 2);
+        assert_eq!(images[1]["tags"], serde_json::json!(["rainy", "night"]));
+    }
+
+    #[test]
+    fn test_stable_ids_of_disjoint_subsets_do_not_overlap() {
+        // Two machines exporting disjoint halves of a dataset
+        let ids = |prefix: &str| -> (BTreeSet<i32>, BTreeSet<i32>) {
+            let names: Vec<String> = (0..200).map(|i| format!("{prefix}_{i:04}.jpg")).collect();
+            let images = names.iter().map(|n| stable_image_id(n)).collect();
+            let anns = names
+                .iter()
+                .flat_map(|n| (0..5).map(move |i| stable_annotation_id(n, i)))
+                .collect();
+            (images, anns)
+        };
+        let (images_a, anns_a) = ids("cam_a");
+        let (images_b, anns_b) = ids("cam_b");
+
+        assert_eq!((images_a.len(), anns_a.len()), (200, 1000));
+        assert!(images_a.is_disjoint(&images_b));
+        assert!(anns_a.is_disjoint(&anns_b));
+        assert!(images_a.iter().chain(&anns_a).all(|&id| id > 0));
+
+        // Same input, same id on every export
+        assert_eq!(stable_annotation_id("x.jpg", 3), stable_annotation_id("x.jpg", 3));
+    }
+
+    #[test]
+    fn test_stable_ids_key_on_relative_path_and_collisions_fail() {
+        let classes = ClassConfig::default();
+        let map = HashMap::new();
+        let options = CocoFrameOptions { stable_ids: true, ..test_frame_options(&classes, &map) };
+        let frame = |index: usize, rel_path: &'static str| CocoFrame {
+            index,
+            file_name: "0001.jpg",
+            rel_path,
+            size: (10, 10),
+            tags: &[],
+            annotations: &[],
+        };
+
+        // Same file name in two subfolders gets two ids
+        let mut coco = CocoDataset::new();
+        coco.add_frame(&frame(0, "cam_a/0001.jpg"), &options, &mut 1);
+        coco.add_frame(&frame(1, "cam_b/0001.jpg"), &options, &mut 1);
+        assert_ne!(coco.images[0].id, coco.images[1].id);
+        assert!(coco.check_unique_ids().is_ok());
+
+        // A clash is reported instead of written
+        coco.add_frame(&frame(2, "cam_a/0001.jpg"), &options, &mut 1);
+        assert!(coco.check_unique_ids().unwrap_err().contains("export without stable ids"));
+    }
+
+    #[test]
+    fn test_apply_winding_orders_outline_and_holes() {
+        let outline = vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)];
+        let hole = vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)];
+        let rings = vec![outline.clone(), hole.clone()];
+
+        assert_eq!(apply_winding(&rings, PolygonWinding::Keep), rings);
+        let wound = apply_winding(&rings, PolygonWinding::Clockwise);
+        assert_eq!(wound[0], vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert_eq!(wound[1], vec![(4.0, 4.0), (4.0, 6.0), (6.0, 6.0), (6.0, 4.0)]);
+        assert_eq!(polygon_segmentation(&wound).1, polygon_segmentation(&rings).1);
+    }
 }
@@ -0,0 +1,47 @@
+// Transparent gzip handling for export/import files
+//
+// Paths ending in `.gz` (e.g. `annotations.json.gz`) are gzipped on write
+// and gunzipped on read; everything else is passed through unchanged.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Whether a path should be read/written gzip-compressed
+pub fn is_gzip_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+}
+
+/// Write bytes to `path`, gzipping when the path ends in `.gz`
+pub fn write_maybe_gzip(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if !is_gzip_path(path) {
+        return fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {e}", path.display()));
+    }
+
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(bytes)
+        .and_then(|_| encoder.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to gzip {}: {e}", path.display()))
+}
+
+/// Read bytes from `path`, gunzipping when the path ends in `.gz`
+pub fn read_maybe_gzip(path: &Path) -> Result<Vec<u8>, String> {
+    let raw = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    if !is_gzip_path(path) {
+        return Ok(raw);
+    }
+
+    let mut bytes = Vec::new();
+    GzDecoder::new(raw.as_slice())
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to gunzip {}: {e}", path.display()))?;
+    Ok(bytes)
+}
@@ -0,0 +1,162 @@
+// Flat CSV annotation schema: one row per annotation
+
+use crate::Annotation;
+
+/// Column order of the annotation CSV; `rotation`, `state` and `vertices` are
+/// optional on import
+pub const CSV_COLUMNS: [&str; 10] = [
+    "image_filename",
+    "class_id",
+    "type",
+    "x",
+    "y",
+    "width",
+    "height",
+    "rotation",
+    "state",
+    "vertices",
+];
+
+/// Columns every imported file must have
+const REQUIRED_COLUMNS: usize = 7;
+
+const ANNOTATION_TYPES: [&str; 4] = ["bbox", "rbbox", "point", "polygon"];
+
+/// Split one CSV line; fields may be double-quoted with `""` as an escaped quote
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field".into());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Parse an annotation CSV into `(image_filename, annotation)` pairs.
+/// The header picks the columns, so optional ones may be left out or reordered.
+/// Imported annotations get id 0; the caller assigns ids per image.
+pub fn parse_annotation_csv(content: &str) -> Result<Vec<(String, Annotation)>, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+
+    let header = split_csv_line(header)?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let mut indices = Vec::with_capacity(CSV_COLUMNS.len());
+    for (i, name) in CSV_COLUMNS.iter().enumerate() {
+        let idx = column(name);
+        if idx.is_none() && i < REQUIRED_COLUMNS {
+            return Err(format!("Missing CSV column '{name}'"));
+        }
+        indices.push(idx);
+    }
+
+    let mut rows = Vec::new();
+    for (line_idx, line) in lines {
+        let line_no = line_idx + 1;
+        let fields = split_csv_line(line).map_err(|e| format!("Line {line_no}: {e}"))?;
+        let field = |col: usize| indices[col].and_then(|i| fields.get(i)).map(|f| f.trim());
+        let number = |col: usize| -> Result<f32, String> {
+            let value = field(col).unwrap_or("");
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("Line {line_no}: invalid {} '{value}'", CSV_COLUMNS[col]))
+        };
+
+        let filename = field(0).unwrap_or("");
+        if filename.is_empty() {
+            return Err(format!("Line {line_no}: missing image_filename"));
+        }
+        let class_text = field(1).unwrap_or("");
+        let class = class_text
+            .parse::<i32>()
+            .map_err(|_| format!("Line {line_no}: invalid class_id '{class_text}'"))?;
+        let kind = field(2).unwrap_or("");
+        if !ANNOTATION_TYPES.contains(&kind) {
+            return Err(format!("Line {line_no}: unknown type '{kind}'"));
+        }
+        let rotation = match field(7) {
+            Some(v) if !v.is_empty() => number(7)?,
+            _ => 0.0,
+        };
+        let state = match field(8) {
+            Some(v) if !v.is_empty() => v,
+            _ => "Pending",
+        };
+
+        let ann = Annotation {
+            r#type: kind.into(),
+            x: number(3)?,
+            y: number(4)?,
+            width: number(5)?,
+            height: number(6)?,
+            rotation,
+            class,
+            state: state.into(),
+            vertices: field(9).unwrap_or("").into(),
+            ..Default::default()
+        };
+        rows.push((filename.to_string(), ann));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotation_csv() {
+        let content = "image_filename,class_id,type,x,y,width,height,rotation,state,vertices\n\
+                       a.png,2,bbox,10,20,30,40,,Accepted,\n\
+                       \n\
+                       b.png,1,polygon,0,0,10,10,0,,\"0,0;10,0;10,10\"\n";
+        let rows = parse_annotation_csv(content).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let (name, ann) = &rows[0];
+        assert_eq!(name, "a.png");
+        assert_eq!((ann.class, ann.r#type.as_str()), (2, "bbox"));
+        assert_eq!((ann.x, ann.y, ann.width, ann.height), (10.0, 20.0, 30.0, 40.0));
+        assert_eq!(ann.state.as_str(), "Accepted");
+
+        let (name, ann) = &rows[1];
+        assert_eq!(name, "b.png");
+        assert_eq!(ann.state.as_str(), "Pending");
+        assert_eq!(ann.vertices.as_str(), "0,0;10,0;10,10");
+
+        // Optional columns may be omitted
+        let minimal = "image_filename,class_id,type,x,y,width,height\nc.png,1,point,5,5,0,0\n";
+        assert_eq!(parse_annotation_csv(minimal).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_annotation_csv_rejects_malformed_row() {
+        let header = "image_filename,class_id,type,x,y,width,height\n";
+        let err = parse_annotation_csv(&format!("{header}a.png,1,bbox,10,abc,5,5\n")).unwrap_err();
+        assert!(err.contains("Line 2") && err.contains('y'), "{err}");
+
+        assert!(parse_annotation_csv(&format!("{header}a.png,car,bbox,1,1,1,1\n")).is_err());
+        assert!(parse_annotation_csv(&format!("{header}a.png,1,circle,1,1,1,1\n")).is_err());
+        assert!(parse_annotation_csv("image_filename,class_id,type\n").is_err());
+    }
+}
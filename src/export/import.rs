@@ -0,0 +1,503 @@
+// Opening annotation files directly: format detection and COCO/VOC/YOLO importers
+
+use super::coco::CocoDataset;
+use super::compression::read_maybe_gzip;
+use super::ExportFormat;
+use crate::classes::{resolve_class_by_name_or_alias, ClassConfig};
+use crate::state::{format_rings, scan_image_files};
+use crate::transform::{normalize_rotation, obb_to_rbbox};
+use crate::Annotation;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An image found by an importer with the annotations read for it.
+/// `annotations` is `None` when the labels are read lazily from `labels_path` (YOLO).
+pub struct ImportedImage {
+    pub image_path: PathBuf,
+    pub labels_path: Option<PathBuf>,
+    pub annotations: Option<Vec<Annotation>>,
+}
+
+/// Result of importing an annotation file
+#[derive(Default)]
+pub struct ImportedDataset {
+    pub images: Vec<ImportedImage>,
+    /// Class names that matched no configured class (their annotations are skipped)
+    pub unknown_classes: BTreeSet<String>,
+}
+
+/// Guess the annotation format of `path` from its extension and structure.
+/// Dataset manifests and unrelated files give `None`.
+pub fn detect_format(path: &Path) -> Option<ExportFormat> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".xml") {
+        let text = fs::read_to_string(path).ok()?;
+        return (text.contains("<annotation") && text.contains("<filename>"))
+            .then_some(ExportFormat::PascalVoc);
+    }
+    if name.ends_with(".json") || name.ends_with(".json.gz") {
+        let bytes = read_maybe_gzip(path).ok()?;
+        let value: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+        let is_coco = ["images", "annotations", "categories"]
+            .iter()
+            .all(|key| value.get(key).is_some_and(|v| v.is_array()));
+        return is_coco.then_some(ExportFormat::CocoJson);
+    }
+    if name.ends_with(".txt") {
+        let text = fs::read_to_string(path).ok()?;
+        return is_yolo_text(&text).then_some(ExportFormat::Yolo);
+    }
+    None
+}
+
+/// Every non-blank, non-`#` line is `class x y w h` (optionally followed by a
+/// confidence, or a YOLO-seg line) with normalized numbers
+fn is_yolo_text(text: &str) -> bool {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .peekable();
+    lines.peek().is_some()
+        && lines.all(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            parts.len() >= 5
+                && (parts.len() % 2 == 1 || parts.len() == 6)
+                && parts[0].parse::<u32>().is_ok()
+                && parts[1..]
+                    .iter()
+                    .all(|p| p.parse::<f32>().is_ok_and(|v| (-0.01..=1.01).contains(&v)))
+        })
+}
+
+/// Import an annotation file of a detected `format`
+pub fn import_annotations(
+    path: &Path,
+    format: ExportFormat,
+    classes: &ClassConfig,
+    label_extension: &str,
+) -> Result<ImportedDataset, String> {
+    let imported = match format {
+        ExportFormat::CocoJson => import_coco(path, classes)?,
+        ExportFormat::PascalVoc => import_voc(path, classes)?,
+        ExportFormat::Yolo => import_yolo(path, label_extension)?,
+    };
+    if imported.images.is_empty() {
+        return Err(format!("No images found for {}", path.display()));
+    }
+    Ok(imported)
+}
+
+/// Resolve an image referenced by an annotation file: next to it, or in an
+/// `images` folder beside or above it
+fn resolve_image(base_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let candidates = [
+        base_dir.join(file_name),
+        base_dir.join("images").join(file_name),
+        base_dir.join("..").join("images").join(file_name),
+    ];
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+/// Class id for an imported class name; unknown names are recorded and give `None`
+fn class_for_name(
+    classes: &ClassConfig,
+    name: &str,
+    unknown: &mut BTreeSet<String>,
+) -> Option<i32> {
+    let id = resolve_class_by_name_or_alias(classes, name);
+    if id.is_none() {
+        unknown.insert(name.to_string());
+    }
+    id
+}
+
+fn import_coco(path: &Path, classes: &ClassConfig) -> Result<ImportedDataset, String> {
+    let coco = CocoDataset::load(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let mut imported = ImportedDataset::default();
+    let mut by_image = coco_annotations(&coco, classes, &mut imported.unknown_classes);
+
+    for image in &coco.images {
+        let Some(image_path) = resolve_image(base_dir, &image.file_name) else {
+            continue;
+        };
+        imported.images.push(ImportedImage {
+            image_path,
+            labels_path: None,
+            annotations: Some(by_image.remove(&image.id).unwrap_or_default()),
+        });
+    }
+    Ok(imported)
+}
+
+/// Annotations of a COCO file by image id. Categories map by name; unmatched
+/// names fall back to a class with the category id, else land in `unknown`.
+fn coco_annotations(
+    coco: &CocoDataset,
+    classes: &ClassConfig,
+    unknown: &mut BTreeSet<String>,
+) -> HashMap<i32, Vec<Annotation>> {
+    let mut category_class: HashMap<i32, Option<i32>> = HashMap::new();
+    for category in &coco.categories {
+        let class = resolve_class_by_name_or_alias(classes, &category.name).or_else(|| {
+            if classes.classes.iter().any(|c| c.id == category.id) {
+                Some(category.id)
+            } else {
+                unknown.insert(category.name.clone());
+                None
+            }
+        });
+        category_class.insert(category.id, class);
+    }
+
+    let mut by_image: HashMap<i32, Vec<Annotation>> = HashMap::new();
+    for ann in &coco.annotations {
+        let Some(class) = category_class.get(&ann.category_id).copied().flatten() else {
+            continue;
+        };
+        let anns = by_image.entry(ann.image_id).or_default();
+        let id = anns.len() as i32 + 1;
+        let state = ann
+            .attributes
+            .as_ref()
+            .map(|a| a.state.clone())
+            .unwrap_or_else(|| "Pending".to_string());
+        let [x, y, width, height] = ann.bbox.unwrap_or_default().map(|v| v as f32);
+
+        let rings: Vec<Vec<(f32, f32)>> = ann
+            .segmentation
+            .iter()
+            .flatten()
+            .map(|flat| flat.chunks_exact(2).map(|p| (p[0] as f32, p[1] as f32)).collect())
+            .filter(|ring: &Vec<(f32, f32)>| ring.len() >= 3)
+            .collect();
+        // A rotated box exported by this tool: its four corners are the only ring
+        let rotated = match (ann.attributes.as_ref().and_then(|a| a.rotation), rings.as_slice()) {
+            (Some(_), [ring]) => <[(f32, f32); 4]>::try_from(ring.as_slice()).ok(),
+            _ => None,
+        };
+        let imported_ann = if let Some(corners) = rotated {
+            let (x, y, width, height, rotation) = obb_to_rbbox(corners);
+            Annotation {
+                id,
+                r#type: "rbbox".into(),
+                x,
+                y,
+                width,
+                height,
+                rotation,
+                class,
+                state: state.into(),
+                ..Default::default()
+            }
+        } else if !rings.is_empty() {
+            Annotation {
+                id,
+                r#type: "polygon".into(),
+                x,
+                y,
+                width,
+                height,
+                class,
+                state: state.into(),
+                vertices: format_rings(&rings).into(),
+                ..Default::default()
+            }
+        } else if ann.bbox.is_some() {
+            Annotation {
+                id,
+                r#type: "bbox".into(),
+                x,
+                y,
+                width,
+                height,
+                class,
+                state: state.into(),
+                ..Default::default()
+            }
+        } else {
+            continue;
+        };
+        anns.push(imported_ann);
+    }
+    by_image
+}
+
+/// Annotations of a reference COCO file, for comparing against the dataset
+#[derive(Default)]
+pub struct ReferenceAnnotations {
+    /// Annotations by image file name (without folders)
+    pub by_file_name: HashMap<String, Vec<Annotation>>,
+    /// Category names that matched no configured class (their annotations are skipped)
+    pub unknown_classes: BTreeSet<String>,
+}
+
+impl ReferenceAnnotations {
+    /// Reference annotations of the image at `image_path` (matched by file name)
+    pub fn for_image(&self, image_path: &Path) -> &[Annotation] {
+        image_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| self.by_file_name.get(n))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Read a COCO file as reference annotations; unlike an import, its images
+/// need not exist on disk
+pub fn load_coco_reference(
+    path: &Path,
+    classes: &ClassConfig,
+) -> Result<ReferenceAnnotations, String> {
+    let coco = CocoDataset::load(path)?;
+    let mut reference = ReferenceAnnotations::default();
+    let mut by_image = coco_annotations(&coco, classes, &mut reference.unknown_classes);
+    for image in &coco.images {
+        let name = Path::new(&image.file_name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&image.file_name)
+            .to_string();
+        let anns = by_image.remove(&image.id).unwrap_or_default();
+        reference.by_file_name.entry(name).or_default().extend(anns);
+    }
+    Ok(reference)
+}
+
+/// Text of the first `<tag>...</tag>` in `xml`
+fn tag_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{tag}>"))? + start;
+    Some(xml[start..end].trim())
+}
+
+/// Every `<tag>...</tag>` block in `xml`
+fn tag_blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(&close) else { break };
+        blocks.push(&body[..end]);
+        rest = &body[end + close.len()..];
+    }
+    blocks
+}
+
+/// Parse one VOC file into the image file name and its boxes
+fn parse_voc(
+    xml: &str,
+    classes: &ClassConfig,
+    unknown: &mut BTreeSet<String>,
+) -> Result<(String, Vec<Annotation>), String> {
+    let filename = tag_text(xml, "filename").ok_or("VOC file has no <filename>")?;
+    let mut anns = Vec::new();
+    for object in tag_blocks(xml, "object") {
+        let Some(class) = tag_text(object, "name").and_then(|n| class_for_name(classes, n, unknown))
+        else {
+            continue;
+        };
+        let coord = |tag: &str| -> Result<f32, String> {
+            tag_text(object, tag)
+                .and_then(|v| v.parse::<f32>().ok())
+                .ok_or_else(|| format!("Invalid <{tag}> in {filename}"))
+        };
+        if let Some(rotated) = tag_blocks(object, "robndbox").first() {
+            // roLabelImg rotated box: center, size and angle in radians
+            let value = |tag: &str| -> Result<f32, String> {
+                tag_text(rotated, tag)
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .ok_or_else(|| format!("Invalid <{tag}> in {filename}"))
+            };
+            let (cx, cy, w, h) = (value("cx")?, value("cy")?, value("w")?, value("h")?);
+            let rotation = normalize_rotation(value("angle")?.to_degrees());
+            anns.push(Annotation {
+                id: anns.len() as i32 + 1,
+                r#type: "rbbox".into(),
+                x: cx - w / 2.0,
+                y: cy - h / 2.0,
+                width: w,
+                height: h,
+                rotation,
+                class,
+                state: "Pending".into(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let (xmin, ymin) = (coord("xmin")?, coord("ymin")?);
+        let (xmax, ymax) = (coord("xmax")?, coord("ymax")?);
+        anns.push(Annotation {
+            id: anns.len() as i32 + 1,
+            r#type: "bbox".into(),
+            x: xmin,
+            y: ymin,
+            width: (xmax - xmin).max(0.0),
+            height: (ymax - ymin).max(0.0),
+            class,
+            state: "Pending".into(),
+            ..Default::default()
+        });
+    }
+    Ok((filename.to_string(), anns))
+}
+
+/// Import every VOC file in the folder of `path`
+fn import_voc(path: &Path, classes: &ClassConfig) -> Result<ImportedDataset, String> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let mut xml_files: Vec<PathBuf> = fs::read_dir(base_dir)
+        .map_err(|e| format!("Failed to read folder: {e}"))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("xml"))
+        })
+        .collect();
+    xml_files.sort();
+
+    let mut imported = ImportedDataset::default();
+    for xml_path in xml_files {
+        let Ok(xml) = fs::read_to_string(&xml_path) else { continue };
+        if !xml.contains("<annotation") {
+            continue;
+        }
+        let (filename, anns) = parse_voc(&xml, classes, &mut imported.unknown_classes)?;
+        if let Some(image_path) = resolve_image(base_dir, &filename) {
+            imported.images.push(ImportedImage {
+                image_path,
+                labels_path: None,
+                annotations: Some(anns),
+            });
+        }
+    }
+    Ok(imported)
+}
+
+/// Images next to the YOLO label file (or in a sibling `images` folder), with
+/// labels read lazily from the label folder
+fn import_yolo(path: &Path, label_extension: &str) -> Result<ImportedDataset, String> {
+    let label_dir = path.parent().unwrap_or(Path::new("."));
+    let image_dir = [label_dir.to_path_buf(), label_dir.join("..").join("images")]
+        .into_iter()
+        .find(|dir| scan_image_files(dir, 0).is_ok_and(|files| !files.is_empty()))
+        .ok_or("No images next to the YOLO labels")?;
+
+    let mut files = scan_image_files(&image_dir, 0)?;
+    files.sort();
+    let ext = label_extension.trim_start_matches('.');
+    let images = files
+        .into_iter()
+        .map(|file| {
+            let image_path = image_dir.join(&file);
+            let labels_path = label_dir.join(Path::new(&file).with_extension(ext));
+            ImportedImage {
+                image_path,
+                labels_path: Some(labels_path),
+                annotations: None,
+            }
+        })
+        .collect();
+    Ok(ImportedDataset {
+        images,
+        unknown_classes: BTreeSet::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::test_class;
+    use crate::utils::scratch_dir;
+
+    const VOC: &str = "<annotation>\n  <filename>a.png</filename>\n  <object>\n    <name>car</name>\n    \
+                       <bndbox><xmin>10</xmin><ymin>20</ymin><xmax>40</xmax><ymax>60</ymax></bndbox>\n  \
+                       </object>\n</annotation>\n";
+
+    #[test]
+    fn test_detect_format_classifies_files() {
+        let dir = scratch_dir("detect_format");
+        let coco = dir.join("annotations.json");
+        fs::write(&coco, r#"{"images": [], "annotations": [], "categories": []}"#).unwrap();
+        let manifest = dir.join("manifest.json");
+        fs::write(&manifest, r#"{"images": [{"image": "a.png", "labels": "a.txt"}]}"#).unwrap();
+        let voc = dir.join("a.xml");
+        fs::write(&voc, VOC).unwrap();
+        let yolo = dir.join("a.txt");
+        fs::write(&yolo, "0 0.5 0.5 0.2 0.3\n\n1 0.1 0.1 0.05 0.05\n").unwrap();
+        let notes = dir.join("notes.txt");
+        fs::write(&notes, "remember to label the trucks\n").unwrap();
+
+        assert_eq!(detect_format(&coco), Some(ExportFormat::CocoJson));
+        assert_eq!(detect_format(&manifest), None);
+        assert_eq!(detect_format(&voc), Some(ExportFormat::PascalVoc));
+        assert_eq!(detect_format(&yolo), Some(ExportFormat::Yolo));
+        assert_eq!(detect_format(&notes), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_voc_maps_class_names() {
+        let classes = ClassConfig {
+            classes: vec![test_class(3, "Car")],
+            hierarchy: Vec::new(),
+        };
+        let mut unknown = BTreeSet::new();
+        let (filename, anns) = parse_voc(VOC, &classes, &mut unknown).unwrap();
+        assert_eq!(filename, "a.png");
+        assert_eq!(anns.len(), 1);
+        assert_eq!(anns[0].class, 3);
+        assert_eq!((anns[0].x, anns[0].y, anns[0].width, anns[0].height), (10.0, 20.0, 30.0, 40.0));
+
+        let (_, anns) = parse_voc(&VOC.replace("car", "tram"), &classes, &mut unknown).unwrap();
+        assert!(anns.is_empty());
+        assert!(unknown.contains("tram"));
+    }
+
+    #[test]
+    fn test_rotated_box_round_trips_through_voc_and_coco() {
+        use crate::export::coco::test_frame_options;
+        use crate::export::voc::VocAnnotation;
+
+        let classes = ClassConfig::default();
+        let map = HashMap::new();
+        let rotated = Annotation {
+            r#type: "rbbox".into(),
+            x: 40.0,
+            y: 30.0,
+            width: 20.0,
+            height: 10.0,
+            rotation: 30.0,
+            class: 1,
+            state: "Manual".into(),
+            ..Default::default()
+        };
+        let same_box = |a: &Annotation| {
+            assert_eq!(a.r#type, "rbbox");
+            assert!((a.rotation - 30.0).abs() < 1e-3, "rotation {}", a.rotation);
+            assert!((a.x - 40.0).abs() < 1e-3 && (a.y - 30.0).abs() < 1e-3);
+            assert!((a.width - 20.0).abs() < 1e-3 && (a.height - 10.0).abs() < 1e-3);
+        };
+
+        let mut voc = VocAnnotation::new("a.png".to_string(), 100, 100);
+        voc.add_annotations(std::slice::from_ref(&rotated), &classes, &map, &[]);
+        let mut unknown = BTreeSet::new();
+        let (_, anns) = parse_voc(&voc.to_xml(), &classes, &mut unknown).unwrap();
+        same_box(&anns[0]);
+
+        let options = test_frame_options(&classes, &map);
+        let mut coco = CocoDataset::new();
+        coco.add_mapped_categories(&classes, &map);
+        coco.add_image_annotations(1, "a.png", &[rotated], &options, &mut 1);
+        let by_image = coco_annotations(&coco, &classes, &mut unknown);
+        same_box(&by_image[&1][0]);
+    }
+}
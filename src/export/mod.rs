@@ -1,22 +1,62 @@
 // Export module for various annotation formats
 
 pub mod coco;
+pub mod compression;
+pub mod csv;
+pub mod import;
+pub mod mot;
+pub mod preview;
+pub mod summary;
 pub mod voc;
+pub mod yolo;
+
+use crate::classes::ClassConfig;
+use std::collections::{BTreeMap, HashMap};
 
 /// Export format types
-#[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExportFormat {
     CocoJson,
     PascalVoc,
+    Yolo,
 }
 
 /// Export result with statistics
-#[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ExportResult {
     pub images_exported: usize,
     pub annotations_exported: usize,
+    pub class_counts: BTreeMap<i32, usize>,
+    pub skipped_images: Vec<String>,
+}
+
+impl ExportResult {
+    pub fn record_image(&mut self) {
+        self.images_exported += 1;
+    }
+
+    pub fn record_annotation(&mut self, class_id: i32) {
+        self.annotations_exported += 1;
+        *self.class_counts.entry(class_id).or_insert(0) += 1;
+    }
+}
+
+/// Whether an annotation in `state` should be exported.
+/// Rejected rows are never exported; an empty filter allows every other state.
+pub fn passes_state_filter(state: &str, filter: &[String]) -> bool {
+    state != "Rejected" && (filter.is_empty() || filter.iter().any(|s| s == state))
+}
+
+/// Class id written to exported files; classes missing from `map` keep their id
+pub fn export_class_id(class_id: i32, map: &HashMap<i32, i32>) -> i32 {
+    map.get(&class_id).copied().unwrap_or(class_id)
+}
+
+/// Whether annotations of working class `class_id` go into exports (COCO,
+/// VOC, MOT, YOLO, chips, heatmap, contact sheet); classes missing from the config
+/// are exported
+pub fn class_exported(classes: &ClassConfig, class_id: i32) -> bool {
+    classes.classes.iter().find(|c| c.id == class_id).is_none_or(|c| c.export)
 }
 
 #[allow(dead_code)]
@@ -25,6 +65,7 @@ impl ExportFormat {
         match self {
             ExportFormat::CocoJson => "COCO JSON",
             ExportFormat::PascalVoc => "Pascal VOC XML",
+            ExportFormat::Yolo => "YOLO TXT",
         }
     }
 
@@ -32,6 +73,25 @@ impl ExportFormat {
         match self {
             ExportFormat::CocoJson => "json",
             ExportFormat::PascalVoc => "xml",
+            ExportFormat::Yolo => "txt",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_filter_excludes_unlisted_states() {
+        let filter = vec!["Accepted".to_string()];
+        assert!(passes_state_filter("Accepted", &filter));
+        assert!(!passes_state_filter("Pending", &filter));
+        assert!(!passes_state_filter("Manual", &filter));
+
+        // Empty filter keeps everything except rejected rows
+        assert!(passes_state_filter("Pending", &[]));
+        assert!(!passes_state_filter("Rejected", &[]));
+        assert!(!passes_state_filter("Rejected", &["Rejected".to_string()]));
+    }
+}
@@ -0,0 +1,162 @@
+// MOTChallenge tracking export: one CSV line per tracked box per frame
+
+use crate::classes::ClassConfig;
+use crate::export::{class_exported, export_class_id, passes_state_filter};
+use crate::state::{frame_annotations, group_of, DatasetState};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Default file name of the MOT export
+pub const MOT_FILE_NAME: &str = "gt.txt";
+
+/// Tracked boxes of a dataset as MOT lines
+#[derive(Debug, Default)]
+pub struct MotTracks {
+    pub lines: Vec<String>,
+    /// Live boxes left out because they have no track id
+    pub untracked: usize,
+    /// Tracked rotated boxes left out because MOT boxes can't be rotated
+    pub rotated: usize,
+}
+
+impl MotTracks {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = self.lines.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        std::fs::write(path, text).map_err(|e| format!("Failed to write MOT file: {e}"))
+    }
+}
+
+/// One MOT line `frame,id,x,y,w,h,conf,class,visibility`: a 1-based frame
+/// number, the track id, the box's top-left corner and size in pixels, and the
+/// exported class id (visibility is always 1)
+pub fn mot_line(
+    frame: usize,
+    track_id: i32,
+    bbox: (f32, f32, f32, f32),
+    conf: f32,
+    class: i32,
+) -> String {
+    let (x, y, w, h) = bbox;
+    format!("{frame},{track_id},{x:.2},{y:.2},{w:.2},{h:.2},{conf},{class},1")
+}
+
+/// MOT lines for every tracked box, frame by frame (frame = entry index + 1) and
+/// by track id within a frame. Only bboxes are exported (tracked rbboxes are
+/// counted in `rotated`); `state_filter`, the classes' `export` flag and
+/// `class_map` apply as in the other exporters. Unvisited frames are read from disk.
+pub fn build_mot_tracks(
+    ds: &DatasetState,
+    classes: &ClassConfig,
+    class_map: &HashMap<i32, i32>,
+    state_filter: &[String],
+) -> MotTracks {
+    let mut tracks = MotTracks::default();
+    for idx in 0..ds.entries.len() {
+        let mut rows: Vec<(i32, (f32, f32, f32, f32), i32)> = Vec::new();
+        for ann in frame_annotations(ds, idx) {
+            let is_box = ann.r#type == "bbox" || ann.r#type == "rbbox";
+            if !is_box
+                || !passes_state_filter(&ann.state, state_filter)
+                || !class_exported(classes, ann.class)
+            {
+                continue;
+            }
+            match group_of(&ann) {
+                Some(_) if ann.r#type == "rbbox" => tracks.rotated += 1,
+                Some(id) => {
+                    let class = export_class_id(ann.class, class_map);
+                    rows.push((id, (ann.x, ann.y, ann.width, ann.height), class));
+                }
+                None => tracks.untracked += 1,
+            }
+        }
+        rows.sort_by_key(|(id, ..)| *id);
+        tracks.lines.extend(
+            rows.into_iter().map(|(id, bbox, class)| mot_line(idx + 1, id, bbox, 1.0, class)),
+        );
+    }
+    tracks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DatasetEntry;
+    use crate::Annotation;
+
+    fn tracked_box(group_id: i32, x: f32) -> Annotation {
+        Annotation {
+            r#type: "bbox".into(),
+            class: 1,
+            x,
+            y: 5.0,
+            width: 20.0,
+            height: 10.5,
+            state: "Manual".into(),
+            group_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mot_line_format() {
+        assert_eq!(
+            mot_line(3, 7, (10.0, 5.25, 20.0, 10.5), 1.0, 2),
+            "3,7,10.00,5.25,20.00,10.50,1,2,1"
+        );
+    }
+
+    #[test]
+    fn test_mot_tracks_skip_untracked_and_rejected_boxes() {
+        let entries = (0..2)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_{i}.png").into(),
+                labels_path: Some(format!("missing_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![tracked_box(4, 30.0), tracked_box(2, 0.0)]);
+        ds.stored_annotations[1] = Some(vec![
+            tracked_box(2, 10.0),
+            tracked_box(-1, 50.0),
+            Annotation { state: "Rejected".into(), ..tracked_box(4, 40.0) },
+        ]);
+
+        let tracks = build_mot_tracks(&ds, &ClassConfig::default(), &HashMap::new(), &[]);
+        assert_eq!(
+            tracks.lines,
+            vec![
+                "1,2,0.00,5.00,20.00,10.50,1,1,1",
+                "1,4,30.00,5.00,20.00,10.50,1,1,1",
+                "2,2,10.00,5.00,20.00,10.50,1,1,1",
+            ]
+        );
+        assert_eq!(tracks.untracked, 1);
+    }
+
+    #[test]
+    fn test_mot_tracks_map_classes_and_count_rotated_boxes() {
+        let entries = vec![DatasetEntry {
+            image_path: "missing.png".into(),
+            labels_path: Some("missing.txt".into()),
+        }];
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![
+            Annotation { class: 3, ..tracked_box(1, 0.0) },
+            Annotation { class: 2, ..tracked_box(2, 10.0) },
+            Annotation { r#type: "rbbox".into(), rotation: 30.0, ..tracked_box(3, 20.0) },
+        ]);
+        let mut classes = ClassConfig::default();
+        classes.classes.iter_mut().filter(|c| c.id == 2).for_each(|c| c.export = false);
+        let map = HashMap::from([(3, 5)]);
+
+        let tracks = build_mot_tracks(&ds, &classes, &map, &[]);
+        assert_eq!(tracks.lines, vec!["1,1,0.00,5.00,20.00,10.50,1,5,1"]);
+        assert_eq!((tracks.untracked, tracks.rotated), (0, 1));
+    }
+}
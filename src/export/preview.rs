@@ -0,0 +1,395 @@
+// Annotated thumbnail rendering, contact-sheet composition and density heatmaps for visual QA,
+// and per-object image chips for classifier training
+
+use super::{class_exported, passes_state_filter};
+use crate::classes::ClassConfig;
+use crate::state::{frame_annotations, load_yolo_annotations, DatasetState};
+use crate::transform::{normalize_box, rbbox_bounds, rbbox_corners};
+use crate::Annotation;
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_hollow_polygon_mut, draw_hollow_rect_mut};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+use std::path::Path;
+
+/// Longest side of a rendered thumbnail in pixels
+pub const TILE_SIZE: u32 = 256;
+
+/// Output file names for the contact sheet and its cell→filename index
+pub const CONTACT_SHEET_FILE_NAME: &str = "contact_sheet.png";
+pub const CONTACT_SHEET_INDEX_FILE_NAME: &str = "contact_sheet.txt";
+
+/// Grid resolution and rendered cell size of the annotation density heatmap
+pub const HEATMAP_BINS: usize = 32;
+pub const HEATMAP_CELL_SIZE: u32 = 16;
+
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+
+const PALETTE: [Rgba<u8>; 6] = [
+    Rgba([255, 80, 80, 255]),
+    Rgba([80, 220, 80, 255]),
+    Rgba([80, 140, 255, 255]),
+    Rgba([255, 200, 40, 255]),
+    Rgba([220, 80, 255, 255]),
+    Rgba([40, 220, 220, 255]),
+];
+
+fn class_color(class: i32) -> Rgba<u8> {
+    PALETTE[class.rem_euclid(PALETTE.len() as i32) as usize]
+}
+
+/// Downscale an image to fit `tile_size` and draw its live annotations on top
+pub fn render_thumbnail(img: &DynamicImage, anns: &[Annotation], tile_size: u32) -> RgbaImage {
+    let mut thumb = img.thumbnail(tile_size, tile_size).to_rgba8();
+    let scale = thumb.width() as f32 / img.width().max(1) as f32;
+
+    for ann in anns.iter().filter(|a| a.state != "Rejected") {
+        let color = class_color(ann.class);
+        let x = (ann.x * scale) as i32;
+        let y = (ann.y * scale) as i32;
+        if ann.r#type.as_str() == "point" {
+            draw_filled_circle_mut(&mut thumb, (x, y), 2, color);
+        } else if ann.r#type.as_str() == "rbbox" && ann.rotation != 0.0 {
+            let bbox = (ann.x * scale, ann.y * scale, ann.width * scale, ann.height * scale);
+            let corners: Vec<Point<f32>> = rbbox_corners(bbox, ann.rotation)
+                .iter()
+                .map(|&(cx, cy)| Point::new(cx, cy))
+                .collect();
+            draw_hollow_polygon_mut(&mut thumb, &corners, color);
+        } else {
+            let w = ((ann.width * scale) as u32).max(1);
+            let h = ((ann.height * scale) as u32).max(1);
+            draw_hollow_rect_mut(&mut thumb, Rect::at(x, y).of_size(w, h), color);
+        }
+    }
+    thumb
+}
+
+/// Tile thumbnails row-major into a grid with `columns` cells per row
+pub fn compose_contact_sheet(tiles: &[RgbaImage], columns: usize) -> DynamicImage {
+    let columns = columns.max(1);
+    let rows = tiles.len().div_ceil(columns);
+    let cell_w = tiles.iter().map(|t| t.width()).max().unwrap_or(0);
+    let cell_h = tiles.iter().map(|t| t.height()).max().unwrap_or(0);
+
+    let mut sheet = RgbaImage::from_pixel(
+        (cell_w * columns as u32).max(1),
+        (cell_h * rows as u32).max(1),
+        BACKGROUND,
+    );
+    for (i, tile) in tiles.iter().enumerate() {
+        let x = (i % columns) as u32 * cell_w;
+        let y = (i / columns) as u32 * cell_h;
+        imageops::overlay(&mut sheet, tile, x as i64, y as i64);
+    }
+    DynamicImage::ImageRgba8(sheet)
+}
+
+/// Count live box centers of exported classes per cell of a `bins`×`bins`
+/// grid over normalized image coordinates (`hist[row][col]`, row 0 at the top).
+/// Uncached frames are read from disk; frames whose image can't be read are skipped.
+pub fn build_density_heatmap(
+    ds: &DatasetState,
+    bins: usize,
+    classes: &ClassConfig,
+) -> Vec<Vec<u32>> {
+    let bins = bins.max(1);
+    let mut hist = vec![vec![0u32; bins]; bins];
+
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        let Ok((w, h)) = image::image_dimensions(&entry.image_path) else {
+            continue;
+        };
+        let img_size = (w as f32, h as f32);
+        let anns = match ds.stored_annotations.get(idx) {
+            Some(Some(anns)) => anns.clone(),
+            _ => load_yolo_annotations(entry, img_size, 0, &ds.label_extension),
+        };
+
+        let live = anns.iter().filter(|a| a.state != "Rejected");
+        for ann in live.filter(|a| class_exported(classes, a.class)) {
+            if ann.r#type.as_str() != "bbox" && ann.r#type.as_str() != "rbbox" {
+                continue;
+            }
+            let (cx, cy, _, _) = normalize_box((ann.x, ann.y, ann.width, ann.height), img_size);
+            if !(0.0..=1.0).contains(&cx) || !(0.0..=1.0).contains(&cy) {
+                continue;
+            }
+            let col = ((cx * bins as f32) as usize).min(bins - 1);
+            let row = ((cy * bins as f32) as usize).min(bins - 1);
+            hist[row][col] += 1;
+        }
+    }
+    hist
+}
+
+/// Render a density histogram as a black → red → yellow → white heatmap
+pub fn render_heatmap(hist: &[Vec<u32>], cell_size: u32) -> RgbaImage {
+    let rows = hist.len() as u32;
+    let cols = hist.iter().map(|r| r.len()).max().unwrap_or(0) as u32;
+    let max = hist.iter().flatten().copied().max().unwrap_or(0).max(1) as f32;
+
+    let mut img =
+        RgbaImage::from_pixel((cols * cell_size).max(1), (rows * cell_size).max(1), BACKGROUND);
+    for (row, counts) in hist.iter().enumerate() {
+        for (col, &count) in counts.iter().enumerate() {
+            let t = count as f32 / max;
+            let channel = |offset: f32| ((3.0 * t - offset).clamp(0.0, 1.0) * 255.0) as u8;
+            let color = Rgba([channel(0.0), channel(1.0), channel(2.0), 255]);
+            for y in 0..cell_size {
+                for x in 0..cell_size {
+                    img.put_pixel(col as u32 * cell_size + x, row as u32 * cell_size + y, color);
+                }
+            }
+        }
+    }
+    img
+}
+
+/// Crop the pixels of `bbox` (x, y, w, h in image px) out of `img`. The box is
+/// rounded outwards and clamped to the image, so a box hanging over an edge
+/// yields only its visible part (empty if it lies entirely outside).
+pub fn crop_chip(img: &DynamicImage, bbox: (f32, f32, f32, f32)) -> DynamicImage {
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let x0 = bbox.0.floor().clamp(0.0, w);
+    let y0 = bbox.1.floor().clamp(0.0, h);
+    let x1 = (bbox.0 + bbox.2).ceil().clamp(x0, w);
+    let y1 = (bbox.1 + bbox.3).ceil().clamp(y0, h);
+    img.crop_imm(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32)
+}
+
+/// Folder of a class's chips: its name made safe for paths, or `class_<id>`
+/// for classes missing from the config
+fn chip_folder_name(classes: &ClassConfig, class_id: i32) -> String {
+    match classes.classes.iter().find(|c| c.id == class_id) {
+        Some(c) if !c.name.trim().is_empty() => c
+            .name
+            .trim()
+            .chars()
+            .map(|ch| if ch.is_alphanumeric() || "-_. ".contains(ch) { ch } else { '_' })
+            .collect(),
+        _ => format!("class_{class_id}"),
+    }
+}
+
+/// Save every box passing `state_filter` whose class is exported as
+/// `<class name>/<image path below the dataset>_<id>.png` under `folder`, so
+/// images of the same name in different subfolders don't collide. Rotated
+/// boxes are cropped to the axis-aligned bounds of their corners. Each image
+/// is decoded once, and only if it has boxes; unreadable images and boxes
+/// outside the image are skipped. Returns the number of chips written.
+pub fn export_chips(
+    ds: &DatasetState,
+    folder: &Path,
+    classes: &ClassConfig,
+    state_filter: &[String],
+) -> Result<usize, String> {
+    let mut written = 0;
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        let boxes: Vec<Annotation> = frame_annotations(ds, idx)
+            .into_iter()
+            .filter(|a| matches!(a.r#type.as_str(), "bbox" | "rbbox"))
+            .filter(|a| passes_state_filter(&a.state, state_filter))
+            .filter(|a| class_exported(classes, a.class))
+            .collect();
+        if boxes.is_empty() {
+            continue;
+        }
+        let Ok(img) = image::open(&entry.image_path) else {
+            continue;
+        };
+        let rel_path = ds.relative_image_path(idx).unwrap_or_default();
+        let stem = Path::new(&rel_path).with_extension("");
+        for ann in &boxes {
+            let rect = (ann.x, ann.y, ann.width, ann.height);
+            let bounds = match ann.r#type.as_str() {
+                "rbbox" => rbbox_bounds(rect, ann.rotation),
+                _ => rect,
+            };
+            let chip = crop_chip(&img, bounds);
+            if chip.width() == 0 || chip.height() == 0 {
+                continue;
+            }
+            let path = folder
+                .join(chip_folder_name(classes, ann.class))
+                .join(format!("{}_{}.png", stem.display(), ann.id));
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+            }
+            chip.save(&path).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_compose_contact_sheet_places_tiles_row_major() {
+        let colors = [
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 255, 0, 255]),
+            Rgba([0, 0, 255, 255]),
+            Rgba([255, 255, 0, 255]),
+            Rgba([0, 255, 255, 255]),
+        ];
+        let tiles: Vec<RgbaImage> = colors
+            .iter()
+            .map(|c| RgbaImage::from_pixel(10, 8, *c))
+            .collect();
+
+        let sheet = compose_contact_sheet(&tiles, 2).to_rgba8();
+        // 5 tiles in 2 columns -> 3 rows
+        assert_eq!(sheet.dimensions(), (20, 24));
+        assert_eq!(*sheet.get_pixel(0, 0), colors[0]);
+        assert_eq!(*sheet.get_pixel(15, 4), colors[1]);
+        assert_eq!(*sheet.get_pixel(5, 12), colors[2]);
+        assert_eq!(*sheet.get_pixel(19, 15), colors[3]);
+        assert_eq!(*sheet.get_pixel(9, 23), colors[4]);
+        // Unused last cell keeps the background
+        assert_eq!(*sheet.get_pixel(15, 20), BACKGROUND);
+    }
+
+    #[test]
+    fn test_crop_chip_clamps_to_image() {
+        let mut base = RgbaImage::from_pixel(100, 80, BACKGROUND);
+        base.put_pixel(95, 75, Rgba([255, 0, 0, 255]));
+        let img = DynamicImage::ImageRgba8(base);
+        let size = |chip: DynamicImage| (chip.width(), chip.height());
+
+        assert_eq!(size(crop_chip(&img, (10.0, 20.0, 30.5, 15.0))), (31, 15));
+
+        // Hanging over the bottom-right corner keeps only the visible part
+        let corner = crop_chip(&img, (90.0, 70.0, 40.0, 40.0)).to_rgba8();
+        assert_eq!(corner.dimensions(), (10, 10));
+        assert_eq!(*corner.get_pixel(5, 5), Rgba([255, 0, 0, 255]));
+
+        // Over the top-left edge and entirely outside
+        assert_eq!(size(crop_chip(&img, (-5.0, -8.0, 20.0, 20.0))), (15, 12));
+        assert_eq!(size(crop_chip(&img, (150.0, 10.0, 20.0, 20.0))), (0, 20));
+    }
+
+    #[test]
+    fn test_render_thumbnail_fits_tile() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(512, 256, Rgba([0, 0, 0, 255])));
+        let thumb = render_thumbnail(&img, &[], 128);
+        assert_eq!(thumb.dimensions(), (128, 64));
+    }
+
+    #[test]
+    fn test_density_heatmap_concentrates_corner_centers() {
+        use crate::classes::{test_class, ClassDefinition};
+        use crate::state::DatasetEntry;
+
+        let dir = scratch_dir("heatmap");
+
+        let corner_box = |id: i32| Annotation {
+            id,
+            r#type: "bbox".into(),
+            x: 2.0,
+            y: 2.0,
+            width: 4.0,
+            height: 4.0,
+            state: "Pending".into(),
+            ..Default::default()
+        };
+        let mut entries = Vec::new();
+        let mut stored = Vec::new();
+        for i in 0..3 {
+            let image_path = dir.join(format!("img{i}.png"));
+            RgbaImage::from_pixel(100, 100, BACKGROUND).save(&image_path).unwrap();
+            entries.push(DatasetEntry { image_path, labels_path: None });
+            stored.push(Some(vec![corner_box(1), corner_box(2)]));
+        }
+        let mut rejected = corner_box(3);
+        rejected.x = 80.0;
+        rejected.state = "Rejected".into();
+        stored[0].as_mut().unwrap().push(rejected);
+        // A class left out of exports isn't counted either
+        let ignored = Annotation { class: 9, y: 80.0, ..corner_box(4) };
+        stored[1].as_mut().unwrap().push(ignored);
+
+        let mut ds = DatasetState::new(entries, None);
+        ds.stored_annotations = stored;
+
+        let classes = ClassConfig {
+            classes: vec![ClassDefinition { export: false, ..test_class(9, "ignore") }],
+            hierarchy: Vec::new(),
+        };
+        let hist = build_density_heatmap(&ds, 4, &classes);
+        assert_eq!(hist[0][0], 6);
+        assert_eq!(hist.iter().flatten().sum::<u32>(), 6);
+
+        let img = render_heatmap(&hist, 2);
+        assert_eq!(img.dimensions(), (8, 8));
+        assert_eq!(*img.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_chips_mirror_subfolders_and_apply_filters() {
+        use crate::classes::{test_class, ClassDefinition};
+        use crate::state::DatasetEntry;
+
+        let dir = scratch_dir("chips");
+        let chip = |id: i32, class: i32, state: &str| Annotation {
+            id,
+            r#type: "bbox".into(),
+            x: 10.0,
+            y: 10.0,
+            width: 20.0,
+            height: 20.0,
+            class,
+            state: state.into(),
+            ..Default::default()
+        };
+        let mut entries = Vec::new();
+        for cam in ["cam_a", "cam_b"] {
+            let image_path = dir.join(cam).join("0001.png");
+            std::fs::create_dir_all(image_path.parent().unwrap()).unwrap();
+            RgbaImage::from_pixel(64, 64, BACKGROUND).save(&image_path).unwrap();
+            entries.push(DatasetEntry { image_path, labels_path: None });
+        }
+        let mut ds = DatasetState::new(entries, None);
+        ds.manifest_path = Some(dir.join("manifest.json"));
+        // Standing upright, a 40 x 10 box covers a 10 x 40 column
+        let upright = Annotation {
+            r#type: "rbbox".into(),
+            y: 27.0,
+            width: 40.0,
+            height: 10.0,
+            rotation: 90.0,
+            ..chip(4, 1, "Accepted")
+        };
+        ds.stored_annotations = vec![
+            Some(vec![chip(1, 1, "Accepted"), chip(2, 1, "Pending"), chip(3, 2, "Accepted")]),
+            Some(vec![chip(1, 1, "Accepted"), upright]),
+        ];
+        let classes = ClassConfig {
+            classes: vec![
+                test_class(1, "car"),
+                ClassDefinition { export: false, ..test_class(2, "ignore") },
+            ],
+            hierarchy: Vec::new(),
+        };
+
+        let out = dir.join("chips");
+        let written = export_chips(&ds, &out, &classes, &["Accepted".to_string()]).unwrap();
+        assert_eq!(written, 3);
+        assert!(out.join("car/cam_a/0001_1.png").exists());
+        assert!(out.join("car/cam_b/0001_1.png").exists());
+        let upright_chip = image::open(out.join("car/cam_b/0001_4.png")).unwrap();
+        let (w, h) = (upright_chip.width(), upright_chip.height());
+        assert!((10..=12).contains(&w) && (40..=42).contains(&h), "{w} x {h}");
+        assert!(!out.join("car/cam_a/0001_2.png").exists());
+        assert!(!out.join("ignore").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
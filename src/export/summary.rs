@@ -0,0 +1,85 @@
+// Export summary written next to every export for reproducibility
+
+use super::{ExportFormat, ExportResult};
+use crate::classes::{self, ClassConfig};
+use crate::config::ExportConfig;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const SUMMARY_FILE_NAME: &str = "export_summary.json";
+
+#[derive(Debug, Serialize)]
+pub struct ExportSummary {
+    pub format: String,
+    pub timestamp: String,
+    pub image_count: usize,
+    pub annotation_count: usize,
+    pub per_class_counts: BTreeMap<String, usize>,
+    pub skipped_images: Vec<String>,
+    pub options: ExportConfig,
+}
+
+impl ExportSummary {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize export summary: {e}"))?;
+        fs::write(path, json)
+            .map_err(|e| format!("Failed to write export summary: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Build the summary record for a finished export
+pub fn build_export_summary(
+    format: ExportFormat,
+    result: &ExportResult,
+    classes: &ClassConfig,
+    options: &ExportConfig,
+) -> ExportSummary {
+    let per_class_counts = result
+        .class_counts
+        .iter()
+        .map(|(id, count)| (classes::get_class_name(classes, *id), *count))
+        .collect();
+
+    ExportSummary {
+        format: format.name().to_string(),
+        timestamp: chrono::Local::now().to_rfc3339(),
+        image_count: result.images_exported,
+        annotation_count: result.annotations_exported,
+        per_class_counts,
+        skipped_images: result.skipped_images.clone(),
+        options: options.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_reflects_counts_and_skipped() {
+        let mut result = ExportResult::default();
+        result.record_image();
+        result.record_annotation(1);
+        result.record_annotation(1);
+        result.record_annotation(2);
+        result.skipped_images.push("missing.png".to_string());
+
+        let summary = build_export_summary(
+            ExportFormat::CocoJson,
+            &result,
+            &ClassConfig::default(),
+            &ExportConfig::default(),
+        );
+
+        assert_eq!(summary.format, "COCO JSON");
+        assert_eq!(summary.image_count, 1);
+        assert_eq!(summary.annotation_count, 3);
+        assert_eq!(summary.per_class_counts.get("Class 1"), Some(&2));
+        assert_eq!(summary.per_class_counts.get("Class 2"), Some(&1));
+        assert_eq!(summary.skipped_images, vec!["missing.png".to_string()]);
+    }
+}
@@ -1,11 +1,19 @@
 // Pascal VOC XML format export
 
+use super::{class_exported, export_class_id, passes_state_filter};
+use crate::classes::{get_class_name, ClassConfig};
+use crate::transform::rbbox_bounds;
+use crate::Annotation;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 pub struct VocObject {
     pub name: String,
     pub bbox: (i32, i32, i32, i32), // xmin, ymin, xmax, ymax
+    /// Rotated box as roLabelImg writes it: center, size and angle in radians
+    /// in [0, π); `bbox` then encloses its corners
+    pub robndbox: Option<(f32, f32, f32, f32, f32)>, // cx, cy, w, h, angle
     pub difficult: i32,
     pub truncated: i32,
     pub pose: String,
@@ -38,12 +46,59 @@ impl VocAnnotation {
         self.objects.push(VocObject {
             name,
             bbox: (xmin, ymin, xmax, ymax),
+            robndbox: None,
             difficult: 0,
             truncated: 0,
             pose: "Unspecified".to_string(),
         });
     }
 
+    /// Add a rotated box as `<robndbox>`, with a `<bndbox>` enclosing its corners
+    /// for readers that only know axis-aligned boxes
+    pub fn add_rotated_object(&mut self, name: String, ann: &Annotation) {
+        let (x, y, w, h) = rbbox_bounds((ann.x, ann.y, ann.width, ann.height), ann.rotation);
+        let (xmin, ymin) = (x.floor() as i32, y.floor() as i32);
+        let (xmax, ymax) = ((x + w).ceil() as i32, (y + h).ceil() as i32);
+        self.add_object(name, xmin, ymin, xmax, ymax);
+        if let Some(obj) = self.objects.last_mut() {
+            let (cx, cy) = (ann.x + ann.width / 2.0, ann.y + ann.height / 2.0);
+            let angle = ann.rotation.rem_euclid(180.0).to_radians();
+            obj.robndbox = Some((cx, cy, ann.width, ann.height, angle));
+        }
+    }
+
+    /// Add the exported boxes of one image: bbox/rbbox annotations passing
+    /// the state filter whose class is exported, under their mapped class name.
+    /// Returns the exported class id of each object added.
+    pub fn add_annotations(
+        &mut self,
+        anns: &[Annotation],
+        classes: &ClassConfig,
+        class_map: &HashMap<i32, i32>,
+        state_filter: &[String],
+    ) -> Vec<i32> {
+        let mut added = Vec::new();
+        for ann in anns.iter().filter(|a| {
+            passes_state_filter(&a.state, state_filter) && class_exported(classes, a.class)
+        }) {
+            let class_id = export_class_id(ann.class, class_map);
+            let name = get_class_name(classes, class_id);
+            match ann.r#type.as_str() {
+                "bbox" => {
+                    let xmin = ann.x as i32;
+                    let ymin = ann.y as i32;
+                    let xmax = (ann.x + ann.width) as i32;
+                    let ymax = (ann.y + ann.height) as i32;
+                    self.add_object(name, xmin, ymin, xmax, ymax);
+                }
+                "rbbox" => self.add_rotated_object(name, ann),
+                _ => continue,
+            }
+            added.push(class_id);
+        }
+        added
+    }
+
     pub fn to_xml(&self) -> String {
         let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
         xml.push_str("<annotation>\n");
@@ -72,6 +127,15 @@ impl VocAnnotation {
             xml.push_str(&format!("      <xmax>{}</xmax>\n", obj.bbox.2));
             xml.push_str(&format!("      <ymax>{}</ymax>\n", obj.bbox.3));
             xml.push_str("    </bndbox>\n");
+            if let Some((cx, cy, w, h, angle)) = obj.robndbox {
+                xml.push_str("    <robndbox>\n");
+                xml.push_str(&format!("      <cx>{cx}</cx>\n"));
+                xml.push_str(&format!("      <cy>{cy}</cy>\n"));
+                xml.push_str(&format!("      <w>{w}</w>\n"));
+                xml.push_str(&format!("      <h>{h}</h>\n"));
+                xml.push_str(&format!("      <angle>{angle}</angle>\n"));
+                xml.push_str("    </robndbox>\n");
+            }
             xml.push_str("  </object>\n");
         }
 
@@ -85,4 +149,40 @@ impl VocAnnotation {
             .map_err(|e| format!("Failed to write VOC XML: {e}"))?;
         Ok(())
     }
+
+    /// Save unless the image has no objects and empty files are not wanted.
+    /// Returns whether a file was written.
+    pub fn save_unless_empty(&self, path: &Path, write_empty: bool) -> Result<bool, String> {
+        if self.objects.is_empty() && !write_empty {
+            return Ok(false);
+        }
+        self.save(path)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_empty_image_skipped_unless_configured() {
+        let dir = scratch_dir("voc");
+        let path = dir.join("empty.xml");
+        let _ = fs::remove_file(&path);
+
+        let empty = VocAnnotation::new("empty.png".to_string(), 64, 48);
+        assert!(!empty.save_unless_empty(&path, false).unwrap());
+        assert!(!path.exists());
+
+        assert!(empty.save_unless_empty(&path, true).unwrap());
+        assert!(path.exists());
+
+        let mut with_obj = VocAnnotation::new("obj.png".to_string(), 64, 48);
+        with_obj.add_object("car".to_string(), 1, 2, 3, 4);
+        assert!(with_obj.save_unless_empty(&dir.join("obj.xml"), false).unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,206 @@
+// YOLO dataset description (`data.yaml`) written alongside saved labels, and
+// the YOLO export of a whole dataset with exported class ids
+
+use super::{class_exported, export_class_id};
+use crate::classes::ClassConfig;
+use crate::config::ExportConfig;
+use crate::state::{frame_annotations, frame_image_size, yolo_label_lines, DatasetState};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const DATA_YAML_FILE_NAME: &str = "data.yaml";
+
+/// Folders of a YOLO export, below the export folder
+pub const IMAGES_DIR_NAME: &str = "images";
+pub const LABELS_DIR_NAME: &str = "labels";
+
+/// Build an Ultralytics-style `data.yaml` for labels holding the working class ids.
+/// YOLO class index `i` is class id `i + 1`; gaps in the ids get a `class_<id>` placeholder
+/// so `names` stays aligned with the label files.
+pub fn build_data_yaml(classes: &ClassConfig, train_dir: &str, val_dir: &str) -> String {
+    let names: HashMap<i32, String> =
+        classes.classes.iter().rev().map(|c| (c.id, c.name.clone())).collect();
+    data_yaml(&names, train_dir, val_dir)
+}
+
+/// Build `data.yaml` for exported labels: the exported classes under their
+/// ids in `class_map`, in exported-id order. A merged id takes the name of
+/// the class with that id, else of the first class mapped onto it.
+pub fn build_export_data_yaml(
+    classes: &ClassConfig,
+    class_map: &HashMap<i32, i32>,
+    train_dir: &str,
+    val_dir: &str,
+) -> String {
+    let mut names: HashMap<i32, String> = HashMap::new();
+    let exported = classes.classes.iter().filter(|c| c.export);
+    for c in exported.clone().filter(|c| export_class_id(c.id, class_map) == c.id) {
+        names.entry(c.id).or_insert_with(|| c.name.clone());
+    }
+    for c in exported {
+        names.entry(export_class_id(c.id, class_map)).or_insert_with(|| c.name.clone());
+    }
+    data_yaml(&names, train_dir, val_dir)
+}
+
+fn data_yaml(names: &HashMap<i32, String>, train_dir: &str, val_dir: &str) -> String {
+    let nc = names.keys().copied().max().unwrap_or(0).max(0);
+
+    let mut yaml = String::new();
+    yaml.push_str(&format!("train: {}\n", quote(train_dir)));
+    yaml.push_str(&format!("val: {}\n", quote(val_dir)));
+    yaml.push_str(&format!("nc: {nc}\n"));
+    yaml.push_str("names:\n");
+    for id in 1..=nc {
+        let name = names.get(&id).cloned().unwrap_or_else(|| format!("class_{id}"));
+        yaml.push_str(&format!("  - {}\n", quote(&name)));
+    }
+    yaml
+}
+
+/// Write `data.yaml` into `dir`, pointing train and val at `images_dir`.
+/// With `class_map`, names follow the exported class ids instead of the working ones.
+pub fn write_data_yaml(
+    dir: &Path,
+    images_dir: &Path,
+    classes: &ClassConfig,
+    class_map: Option<&HashMap<i32, i32>>,
+) -> Result<(), String> {
+    let images = images_dir.display().to_string();
+    let yaml = match class_map {
+        Some(map) => build_export_data_yaml(classes, map, &images, &images),
+        None => build_data_yaml(classes, &images, &images),
+    };
+    let path = dir.join(DATA_YAML_FILE_NAME);
+    fs::write(&path, yaml).map_err(|e| format!("Write {}: {e}", path.display()))
+}
+
+/// Export the dataset as a YOLO training folder: each image is copied to
+/// `images/<path below the dataset>` with its label file under `labels/`, and
+/// `data.yaml` sits next to them. Labels hold the exported annotations (state
+/// filter, exported classes) under their ids in `export.class_export_map`, so
+/// they match the `names` in `data.yaml`. Unreadable images are skipped.
+/// Returns the numbers of images and annotations exported.
+pub fn export_yolo_dataset(
+    ds: &DatasetState,
+    folder: &Path,
+    classes: &ClassConfig,
+    export: &ExportConfig,
+) -> Result<(usize, usize), String> {
+    let (mut images, mut annotations) = (0, 0);
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        if export.reviewed_only && !ds.is_reviewed(idx) {
+            continue;
+        }
+        let rel_path = ds.relative_image_path(idx).unwrap_or_default();
+        let image_path = folder.join(IMAGES_DIR_NAME).join(&rel_path);
+        let label_path = folder.join(LABELS_DIR_NAME).join(&rel_path).with_extension("txt");
+        for path in [&image_path, &label_path] {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+            }
+        }
+        if fs::copy(&entry.image_path, &image_path).is_err() {
+            continue;
+        }
+
+        let mut anns = frame_annotations(ds, idx);
+        anns.retain(|a| class_exported(classes, a.class));
+        for a in anns.iter_mut() {
+            a.class = export_class_id(a.class, &export.class_export_map);
+        }
+        let lines = yolo_label_lines(&anns, frame_image_size(ds, idx, entry), export);
+        fs::write(&label_path, lines.join("\n"))
+            .map_err(|e| format!("Write labels {}: {e}", label_path.display()))?;
+        images += 1;
+        annotations += lines.len();
+    }
+    let images_dir = folder.join(IMAGES_DIR_NAME);
+    write_data_yaml(folder, &images_dir, classes, Some(&export.class_export_map))?;
+    Ok((images, annotations))
+}
+
+/// JSON strings are valid YAML double-quoted scalars
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::test_class;
+    use crate::state::{create_dataset_from_folder, load_dataset, test_bbox};
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_data_yaml_names_follow_class_ids() {
+        let classes = ClassConfig {
+            classes: vec![test_class(3, "truck"), test_class(1, "car"), test_class(2, "person")],
+            hierarchy: Vec::new(),
+        };
+
+        let yaml = build_data_yaml(&classes, "images/train", "images/val");
+        assert_eq!(
+            yaml,
+            "train: \"images/train\"\nval: \"images/val\"\nnc: 3\nnames:\n  - \"car\"\n  - \"person\"\n  - \"truck\"\n"
+        );
+    }
+
+    #[test]
+    fn test_export_data_yaml_follows_exported_ids() {
+        let mut hidden = test_class(4, "hidden");
+        hidden.export = false;
+        let classes = ClassConfig {
+            classes: vec![
+                test_class(1, "car"),
+                test_class(2, "person"),
+                test_class(3, "van"),
+                hidden,
+            ],
+            hierarchy: Vec::new(),
+        };
+        // car and van merge into 2, person moves to 1; the hidden class is left out
+        let map = HashMap::from([(1, 2), (3, 2), (2, 1)]);
+
+        let yaml = build_export_data_yaml(&classes, &map, "images", "images");
+        assert_eq!(
+            yaml,
+            "train: \"images\"\nval: \"images\"\nnc: 2\nnames:\n  - \"person\"\n  - \"car\"\n"
+        );
+    }
+
+    #[test]
+    fn test_export_yolo_dataset_writes_mapped_labels() {
+        let dir = scratch_dir("yolo_export");
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        image::RgbImage::new(100, 50).save(src.join("sub").join("a.png")).unwrap();
+        let manifest_path = create_dataset_from_folder(&src, None, 1, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![
+            test_bbox(3, 0.0, 0.0, 50.0, 50.0),
+            test_bbox(2, 50.0, 0.0, 50.0, 50.0),
+        ]);
+        let mut hidden = test_class(2, "hidden");
+        hidden.export = false;
+        let classes = ClassConfig {
+            classes: vec![test_class(1, "car"), hidden, test_class(3, "truck")],
+            hierarchy: Vec::new(),
+        };
+        let mut export = ExportConfig::default();
+        export.class_export_map.insert(3, 1);
+
+        let out = dir.join("out");
+        assert_eq!(export_yolo_dataset(&ds, &out, &classes, &export).unwrap(), (1, 1));
+        assert!(out.join("images").join("sub").join("a.png").exists());
+        let labels = fs::read_to_string(out.join("labels").join("sub").join("a.txt")).unwrap();
+        assert_eq!(labels, "0 0.25 0.5 0.5 1");
+        let yaml = fs::read_to_string(out.join(DATA_YAML_FILE_NAME)).unwrap();
+        assert!(yaml.ends_with("nc: 1\nnames:\n  - \"car\"\n"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
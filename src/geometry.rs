@@ -0,0 +1,239 @@
+// Polygon and box geometry helpers
+//
+// Vertices are image-space `(x, y)` pairs; polygons are implicitly closed.
+// Boxes are `(x, y, width, height)` with the origin at the top-left corner.
+
+use crate::transform::rbbox_corners;
+
+/// Length of each edge, including the closing edge back to the first vertex
+fn edge_lengths(verts: &[(f32, f32)]) -> Vec<f32> {
+    (0..verts.len())
+        .map(|i| {
+            let a = verts[i];
+            let b = verts[(i + 1) % verts.len()];
+            ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+        })
+        .collect()
+}
+
+/// Resample a closed polygon outline to `n` points equally spaced along its
+/// perimeter, starting at the first vertex. Degenerate input (fewer than two
+/// vertices or zero perimeter) is returned unchanged.
+pub fn resample_polygon(verts: &[(f32, f32)], n: usize) -> Vec<(f32, f32)> {
+    let lengths = edge_lengths(verts);
+    let total: f32 = lengths.iter().sum();
+    if verts.len() < 2 || total <= 0.0 {
+        return verts.to_vec();
+    }
+
+    let step = total / n as f32;
+    let mut out = Vec::with_capacity(n);
+    let mut edge = 0;
+    let mut edge_start = 0.0;
+    for k in 0..n {
+        let target = k as f32 * step;
+        while edge < lengths.len() - 1 && edge_start + lengths[edge] < target {
+            edge_start += lengths[edge];
+            edge += 1;
+        }
+        let a = verts[edge];
+        let b = verts[(edge + 1) % verts.len()];
+        let t = if lengths[edge] > 0.0 {
+            ((target - edge_start) / lengths[edge]).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        out.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+    }
+    out
+}
+
+/// Shoelace area; positive when the vertices run clockwise on screen (y down)
+fn signed_area(verts: &[(f32, f32)]) -> f32 {
+    let twice: f32 = (0..verts.len())
+        .map(|i| {
+            let a = verts[i];
+            let b = verts[(i + 1) % verts.len()];
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    twice / 2.0
+}
+
+/// Area enclosed by a closed polygon (shoelace formula, either winding)
+pub fn polygon_area(verts: &[(f32, f32)]) -> f32 {
+    signed_area(verts).abs()
+}
+
+/// The polygon with its vertices running clockwise on screen (y down) when
+/// `clockwise`, else counter-clockwise. Reversal keeps the first vertex, so
+/// the shape and starting point are unchanged; degenerate input is returned as is.
+pub fn ensure_winding(verts: &[(f32, f32)], clockwise: bool) -> Vec<(f32, f32)> {
+    let area = signed_area(verts);
+    if area == 0.0 || (area > 0.0) == clockwise {
+        return verts.to_vec();
+    }
+    verts[..1].iter().chain(verts[1..].iter().rev()).copied().collect()
+}
+
+/// Whether `p` lies inside a closed polygon (even-odd ray casting; points
+/// exactly on an edge may go either way)
+pub fn point_in_polygon(p: (f32, f32), verts: &[(f32, f32)]) -> bool {
+    let mut inside = false;
+    let mut j = verts.len().wrapping_sub(1);
+    for (i, &a) in verts.iter().enumerate() {
+        let b = verts[j];
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Distance from `p` to the nearest edge of a closed polygon; 0 inside
+pub fn distance_to_polygon(p: (f32, f32), verts: &[(f32, f32)]) -> f32 {
+    if point_in_polygon(p, verts) {
+        return 0.0;
+    }
+    (0..verts.len())
+        .map(|i| {
+            let (a, b) = (verts[i], verts[(i + 1) % verts.len()]);
+            let (ex, ey) = (b.0 - a.0, b.1 - a.1);
+            let len_sq = ex * ex + ey * ey;
+            let t = if len_sq > 0.0 {
+                (((p.0 - a.0) * ex + (p.1 - a.1) * ey) / len_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            ((p.0 - a.0 - t * ex).powi(2) + (p.1 - a.1 - t * ey).powi(2)).sqrt()
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Whether `p` lies inside a box rotated by `rotation` degrees about its center
+pub fn point_in_rotated_rect(p: (f32, f32), bbox: (f32, f32, f32, f32), rotation: f32) -> bool {
+    point_in_polygon(p, &rbbox_corners(bbox, rotation))
+}
+
+/// Intersection over union of two axis-aligned boxes (0 when either is empty)
+pub fn iou(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> f32 {
+    let ix = ((a.0 + a.2).min(b.0 + b.2) - a.0.max(b.0)).max(0.0);
+    let iy = ((a.1 + a.3).min(b.1 + b.3) - a.1.max(b.1)).max(0.0);
+    let inter = ix * iy;
+    let union = a.2 * a.3 + b.2 * b.3 - inter;
+    if union > 0.0 { inter / union } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perimeter(verts: &[(f32, f32)]) -> f32 {
+        edge_lengths(verts).iter().sum()
+    }
+
+    #[test]
+    fn test_resample_square_hits_corners_and_midpoints() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let resampled = resample_polygon(&square, 8);
+        assert_eq!(
+            resampled,
+            vec![
+                (0.0, 0.0),
+                (5.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 5.0),
+                (10.0, 10.0),
+                (5.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 5.0),
+            ]
+        );
+        assert_eq!(perimeter(&resampled), 40.0);
+    }
+
+    #[test]
+    fn test_resample_preserves_length_and_perimeter() {
+        let triangle = [(0.0, 0.0), (40.0, 0.0), (0.0, 30.0)];
+        for n in [3, 7, 50, 128] {
+            let resampled = resample_polygon(&triangle, n);
+            assert_eq!(resampled.len(), n);
+        }
+        // Corners get cut slightly, so the perimeter shrinks a little
+        let resampled = resample_polygon(&triangle, 50);
+        let ratio = perimeter(&resampled) / perimeter(&triangle);
+        assert!(ratio > 0.95 && ratio <= 1.0, "ratio {ratio}");
+    }
+
+    #[test]
+    fn test_resample_degenerate_input_unchanged() {
+        assert!(resample_polygon(&[], 5).is_empty());
+        assert_eq!(resample_polygon(&[(1.0, 1.0)], 5), vec![(1.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_polygon_area() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert_eq!(polygon_area(&square), 100.0);
+        let reversed: Vec<(f32, f32)> = square.iter().rev().copied().collect();
+        assert_eq!(polygon_area(&reversed), 100.0);
+        assert_eq!(polygon_area(&square[..2]), 0.0);
+    }
+
+    #[test]
+    fn test_iou() {
+        let a = (0.0, 0.0, 10.0, 10.0);
+        assert_eq!(iou(a, a), 1.0);
+        assert_eq!(iou(a, (20.0, 20.0, 5.0, 5.0)), 0.0);
+        // Half-overlapping boxes share 50 of 150 px²
+        assert!((iou(a, (5.0, 0.0, 10.0, 10.0)) - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(iou((0.0, 0.0, 0.0, 0.0), (0.0, 0.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_point_in_rotated_rect() {
+        // 100x20 box centered on (50, 10), rotated 45° clockwise on screen
+        let bbox = (0.0, 0.0, 100.0, 20.0);
+        // Along the rotated long axis, below the unrotated box (y > 20)
+        assert!(point_in_rotated_rect((78.0, 38.0), bbox, 45.0));
+        // Inside the unrotated box near its corner, but off the rotated shape
+        assert!(!point_in_rotated_rect((95.0, 15.0), bbox, 45.0));
+        // No rotation behaves like the axis-aligned box
+        assert!(point_in_rotated_rect((95.0, 15.0), bbox, 0.0));
+        assert!(!point_in_rotated_rect((50.0, 25.0), bbox, 0.0));
+    }
+
+    #[test]
+    fn test_point_in_polygon() {
+        let triangle = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)];
+        assert!(point_in_polygon((2.0, 2.0), &triangle));
+        assert!(!point_in_polygon((8.0, 8.0), &triangle));
+        assert!(!point_in_polygon((1.0, 1.0), &[]));
+    }
+
+    #[test]
+    fn test_distance_to_polygon() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert_eq!(distance_to_polygon((5.0, 5.0), &square), 0.0);
+        assert_eq!(distance_to_polygon((15.0, 5.0), &square), 5.0);
+        // Off a corner the distance is to the corner itself
+        assert_eq!(distance_to_polygon((13.0, 14.0), &square), 5.0);
+    }
+
+    #[test]
+    fn test_ensure_winding() {
+        // Clockwise on screen: right along the top, then down
+        let cw = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert_eq!(ensure_winding(&cw, true), cw.to_vec());
+
+        let ccw = ensure_winding(&cw, false);
+        assert_eq!(ccw, vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]);
+        assert_eq!(ensure_winding(&ccw, false), ccw);
+        assert_eq!(ensure_winding(&ccw, true), cw.to_vec());
+        assert_eq!(polygon_area(&ccw), polygon_area(&cw));
+
+        assert!(ensure_winding(&[], true).is_empty());
+        assert_eq!(ensure_winding(&cw[..2], false), cw[..2].to_vec());
+    }
+}
@@ -9,20 +9,39 @@ pub struct HierarchyNavigator {
     hierarchy: Vec<HierarchicalClassNode>,
     /// Max depth of the hierarchy (1=flat, 2=two-tier, 3=three-tier)
     max_depth: usize,
+    /// Why the configured hierarchy was rejected, if we fell back to flat mode
+    fallback_reason: Option<String>,
 }
 
 impl HierarchyNavigator {
     /// Create a new navigator from a class config
+    /// Invalid hierarchies fall back to flat mode; see `fallback_reason`.
     pub fn new(config: &ClassConfig) -> Self {
+        if let Err(reason) = validate_hierarchy(&config.hierarchy) {
+            crate::logging::warn(|| format!("Invalid class hierarchy, using flat mode: {}", reason));
+            return Self {
+                path: Vec::new(),
+                hierarchy: Vec::new(),
+                max_depth: 0,
+                fallback_reason: Some(reason),
+            };
+        }
+
         let max_depth = validate_and_get_depth(&config.hierarchy);
 
         Self {
             path: Vec::new(),
             hierarchy: config.hierarchy.clone(),
             max_depth,
+            fallback_reason: None,
         }
     }
 
+    /// Reason the configured hierarchy was rejected, if any
+    pub fn fallback_reason(&self) -> Option<&str> {
+        self.fallback_reason.as_deref()
+    }
+
     /// Check if hierarchy mode is active (more than 5 classes)
     pub fn is_hierarchical(&self) -> bool {
         !self.hierarchy.is_empty()
@@ -147,7 +166,6 @@ fn validate_and_get_depth(nodes: &[HierarchicalClassNode]) -> usize {
 }
 
 /// Validate that hierarchy meets constraints
-#[allow(dead_code)]
 pub fn validate_hierarchy(nodes: &[HierarchicalClassNode]) -> Result<(), String> {
     // Check root level has at most 5 nodes
     if nodes.len() > 5 {
@@ -166,7 +184,6 @@ pub fn validate_hierarchy(nodes: &[HierarchicalClassNode]) -> Result<(), String>
     Ok(())
 }
 
-#[allow(dead_code)]
 fn validate_hierarchy_recursive(nodes: &[HierarchicalClassNode], level: usize) -> Result<(), String> {
     if nodes.len() > 5 {
         return Err(format!("Level {} has {} nodes, max 5 allowed", level, nodes.len()));
@@ -228,4 +245,49 @@ mod tests {
         assert_eq!(required_hierarchy_depth(125).unwrap(), 3);
         assert!(required_hierarchy_depth(126).is_err());
     }
+
+    fn leaf(key: u8, id: i32) -> HierarchicalClassNode {
+        HierarchicalClassNode {
+            key,
+            label: format!("Leaf {}", id),
+            children: Vec::new(),
+            id: Some(id),
+            name: Some(format!("class{}", id)),
+            color: None,
+        }
+    }
+
+    #[test]
+    fn test_over_wide_hierarchy_falls_back_to_flat() {
+        let hierarchy: Vec<_> = (1..=7).map(|i| leaf(i, i as i32)).collect();
+        let config = ClassConfig {
+            classes: crate::classes::flatten_hierarchy(&hierarchy),
+            hierarchy,
+        };
+        let nav = HierarchyNavigator::new(&config);
+        assert!(!nav.is_hierarchical());
+        assert!(nav.fallback_reason().unwrap().contains("Root level has 7 nodes"));
+    }
+
+    #[test]
+    fn test_invalid_key_falls_back_to_flat() {
+        let config = ClassConfig {
+            classes: Vec::new(),
+            hierarchy: vec![leaf(1, 1), leaf(6, 2)],
+        };
+        let nav = HierarchyNavigator::new(&config);
+        assert!(!nav.is_hierarchical());
+        assert!(nav.fallback_reason().unwrap().contains("Invalid key 6"));
+    }
+
+    #[test]
+    fn test_valid_hierarchy_has_no_fallback() {
+        let config = ClassConfig {
+            classes: Vec::new(),
+            hierarchy: vec![leaf(1, 1), leaf(2, 2)],
+        };
+        let nav = HierarchyNavigator::new(&config);
+        assert!(nav.is_hierarchical());
+        assert!(nav.fallback_reason().is_none());
+    }
 }
@@ -0,0 +1,164 @@
+//! Checking class shortcuts against the canvas's built-in keys.
+//!
+//! The canvas handles unmodified letter and digit keys itself (see the
+//! FocusScope in appwindow.slint); a class shortcut on one of them is shown in
+//! the sidebar but never selects the class, so such collisions are reported.
+
+use crate::classes::ClassConfig;
+use crate::{logging, AppWindow};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Unmodified keys the canvas reserves, with the action each one triggers
+pub const RESERVED_KEYS: &[(&str, &str)] = &[
+    ("1", "Select class 1"),
+    ("2", "Select class 2"),
+    ("3", "Select class 3"),
+    ("4", "Select class 4"),
+    ("5", "Select class 5"),
+    ("A", "Hold to select"),
+    ("B", "BBox tool"),
+    ("C", "Point tool"),
+    ("E", "Hold to edit"),
+    ("F", "Toggle frame complete"),
+    ("H", "Reset view"),
+    ("I", "Isolate selection"),
+    ("M", "Mirror selected"),
+    ("O", "Add polygon hole"),
+    ("Q", "Hold to cancel"),
+    ("R", "Rotated box tool"),
+    ("S", "Polygon tool"),
+    ("X", "Swap last class"),
+    ("SPACE", "Next image"),
+    ("[", "Rotate left"),
+    ("]", "Rotate right"),
+];
+
+/// Several bindings sharing one key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Normalized key, e.g. `H` or `3`
+    pub key: String,
+    /// Actions bound to the key, in name order
+    pub actions: Vec<String>,
+    /// Built-in action already using the key, if any
+    pub reserved_by: Option<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.actions.join(", "))?;
+        if let Some(action) = &self.reserved_by {
+            write!(f, " (key is reserved for {action})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Key name as compared: trimmed and upper-case, so `h` and `H` are one key
+fn normalize_key(key: &str) -> String {
+    key.trim().to_uppercase()
+}
+
+/// Class shortcuts as bindings, named like the built-in digit actions
+/// (`Select class <id>`) so class 3 on key 3 is not a conflict
+pub fn class_key_bindings(classes: &ClassConfig) -> BTreeMap<String, String> {
+    classes
+        .classes
+        .iter()
+        .filter_map(|c| {
+            let key = c.shortcut.as_deref().filter(|k| !k.trim().is_empty())?;
+            Some((format!("Select class {} ({})", c.id, c.name), key.to_string()))
+        })
+        .collect()
+}
+
+/// Conflicts among `bindings` (action name → key): keys bound to several
+/// actions, and keys the canvas reserves for a different action. An action
+/// counts as the reserved one when its name starts with the reserved name.
+pub fn validate_keybindings(bindings: &BTreeMap<String, String>) -> Vec<Conflict> {
+    let mut by_key: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (action, key) in bindings {
+        by_key.entry(normalize_key(key)).or_default().push(action.clone());
+    }
+    by_key
+        .into_iter()
+        .filter_map(|(key, actions)| {
+            let reserved_by = RESERVED_KEYS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, action)| action.to_string())
+                .filter(|reserved| actions.len() > 1 || !is_action(&actions[0], reserved));
+            (actions.len() > 1 || reserved_by.is_some())
+                .then_some(Conflict { key, actions, reserved_by })
+        })
+        .collect()
+}
+
+/// Whether binding `name` is the built-in `action` itself (`Select class 3
+/// (truck)` is `Select class 3`)
+fn is_action(name: &str, action: &str) -> bool {
+    name.strip_prefix(action).is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+/// Check the class shortcuts, log each conflict and show them in the
+/// keybindings dialog (empty when there are none)
+pub fn show_key_conflicts(ui: &AppWindow, classes: &ClassConfig) {
+    let conflicts = validate_keybindings(&class_key_bindings(classes));
+    for conflict in &conflicts {
+        logging::warn(|| format!("Shortcut conflict on {conflict}"));
+    }
+    let text = conflicts.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+    ui.set_key_conflicts(text.into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{test_class, ClassDefinition};
+
+    fn class(id: i32, name: &str, shortcut: &str) -> ClassDefinition {
+        ClassDefinition { shortcut: Some(shortcut.into()), ..test_class(id, name) }
+    }
+
+    #[test]
+    fn test_default_classes_have_no_conflicts() {
+        let bindings = class_key_bindings(&ClassConfig::default());
+        assert!(!bindings.is_empty());
+        assert_eq!(validate_keybindings(&bindings), Vec::new());
+    }
+
+    #[test]
+    fn test_duplicate_binding_is_reported() {
+        let classes = ClassConfig {
+            classes: vec![class(6, "bus", "k"), class(7, "tram", "K")],
+            hierarchy: Vec::new(),
+        };
+        let conflicts = validate_keybindings(&class_key_bindings(&classes));
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                key: "K".into(),
+                actions: vec!["Select class 6 (bus)".into(), "Select class 7 (tram)".into()],
+                reserved_by: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_class_shortcut_on_action_key_is_reported() {
+        let classes = ClassConfig {
+            classes: vec![class(3, "truck", "3"), class(4, "van", "h"), class(6, "bike", "2")],
+            hierarchy: Vec::new(),
+        };
+        let conflicts = validate_keybindings(&class_key_bindings(&classes));
+        let keys: Vec<&str> = conflicts.iter().map(|c| c.key.as_str()).collect();
+        // Class 3 on 3 matches the built-in digit; 2 and H belong to other actions
+        assert_eq!(keys, vec!["2", "H"]);
+        assert_eq!(conflicts[0].reserved_by.as_deref(), Some("Select class 2"));
+        assert_eq!(
+            conflicts[1].to_string(),
+            "H: Select class 4 (van) (key is reserved for Reset view)"
+        );
+    }
+}
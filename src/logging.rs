@@ -0,0 +1,111 @@
+//! Leveled diagnostics gated by `debug.log_level`.
+//!
+//! Warnings and errors go to stderr, info to stdout; debug and trace messages
+//! are appended to the same `debug_output.log` sink the UI's `log-debug` uses.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+pub const DEBUG_LOG_FILE_NAME: &str = "debug_output.log";
+
+/// Verbosity, from quietest to noisiest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    /// Per-mouse-move and per-vertex messages
+    Trace,
+}
+
+impl LogLevel {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Off,
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+
+/// Set the process-wide level (from config at startup)
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> LogLevel {
+    LogLevel::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Emit `message` if `level` is enabled under `max`. The message is only
+/// built when it will be written; returns whether it was.
+pub fn emit_at(max: LogLevel, level: LogLevel, message: impl FnOnce() -> String) -> bool {
+    if level == LogLevel::Off || level > max {
+        return false;
+    }
+    let text = message();
+    match level {
+        LogLevel::Error | LogLevel::Warn => eprintln!("{text}"),
+        LogLevel::Info => println!("{text}"),
+        LogLevel::Debug | LogLevel::Trace => append_debug_log(&text),
+        LogLevel::Off => {}
+    }
+    true
+}
+
+pub fn error(message: impl FnOnce() -> String) {
+    emit_at(level(), LogLevel::Error, message);
+}
+
+pub fn warn(message: impl FnOnce() -> String) {
+    emit_at(level(), LogLevel::Warn, message);
+}
+
+pub fn info(message: impl FnOnce() -> String) {
+    emit_at(level(), LogLevel::Info, message);
+}
+
+pub fn debug(message: impl FnOnce() -> String) {
+    emit_at(level(), LogLevel::Debug, message);
+}
+
+/// Hot-path logging (mouse moves, vertex adds); off unless `log_level = "trace"`
+pub fn trace(message: impl FnOnce() -> String) {
+    emit_at(level(), LogLevel::Trace, message);
+}
+
+/// Append a line to the debug log file
+pub fn append_debug_log(line: &str) {
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(DEBUG_LOG_FILE_NAME)
+    {
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_is_noop_at_default_level() {
+        let written = emit_at(LogLevel::default(), LogLevel::Trace, || {
+            panic!("trace message built at the default level")
+        });
+        assert!(!written);
+        assert!(!emit_at(LogLevel::default(), LogLevel::Debug, || unreachable!()));
+        assert!(!emit_at(LogLevel::Off, LogLevel::Error, || unreachable!()));
+    }
+}
@@ -2,15 +2,26 @@ slint::include_modules!();
 
 mod config;
 mod classes;
+mod debug_console;
 mod export;
+mod enhance;
+mod geometry;
 mod auto_resize;
 mod hierarchy;
+mod keybindings;
+mod logging;
+mod progress;
+mod project;
 mod state;
+mod transform;
 mod utils;
+mod validation;
+mod window_placement;
 mod callbacks;
 
 use state::{
-    DatasetState, DrawState, ResizeState, UndoHistory, ViewState,
+    BulkConfirm, ClassHistory, DatasetState, DrawState, ResizeState, UndoHistory, ViewState,
+    ZoomLimits, LOADED_ID_START,
     // Functions used in main.rs
     apply_view_state, get_view_state, load_dataset, load_image_from_entry,
     load_yolo_annotations, next_id_from_annotations, replace_annotations, sizes_close,
@@ -21,18 +32,22 @@ use slint::Model;
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 fn main() -> Result<(), slint::PlatformError> {
     let ui = AppWindow::new()?;
 
     // Load configuration
     let config = Rc::new(RefCell::new(config::load_config()));
+    logging::set_level(config.borrow().debug.log_level);
 
     // Load class definitions
     // Always prefer the bundled default classes.yaml in the repo root; users can
     // still override by replacing that file. This avoids stale paths in the
     // persisted config pointing elsewhere.
-    let classes = Rc::new(RefCell::new(classes::load_classes(None)));
+    let (class_config, class_trace) = classes::load_classes(None);
+    let classes = Rc::new(RefCell::new(class_config));
 
     // Apply initial theme from config
     let _theme_name = config.borrow().appearance.theme.clone();
@@ -57,6 +72,7 @@ fn main() -> Result<(), slint::PlatformError> {
         })
         .collect();
     ui.set_class_items(slint::ModelRc::new(slint::VecModel::from(class_items)));
+    keybindings::show_key_conflicts(&ui, &classes.borrow());
 
     // Initialize hierarchy navigation if hierarchy exists
     let hierarchy_navigator = Rc::new(RefCell::new(
@@ -65,10 +81,18 @@ fn main() -> Result<(), slint::PlatformError> {
 
     let is_hierarchical = hierarchy_navigator.borrow().is_hierarchical();
     ui.set_hierarchy_mode(is_hierarchical);
+    let hierarchy_error = hierarchy_navigator
+        .borrow()
+        .fallback_reason()
+        .map(|reason| format!("Class hierarchy invalid ({}); using flat mode", reason));
 
     if is_hierarchical {
-        println!("✓ Hierarchical class selection enabled ({} levels)",
-                 hierarchy_navigator.borrow().max_depth());
+        logging::info(|| {
+            format!(
+                "✓ Hierarchical class selection enabled ({} levels)",
+                hierarchy_navigator.borrow().max_depth()
+            )
+        });
 
         // Set initial hierarchy options
         let navigator = hierarchy_navigator.borrow();
@@ -85,68 +109,13 @@ fn main() -> Result<(), slint::PlatformError> {
         ui.set_hierarchy_breadcrumb("".into());
     }
 
-    // Debug: Terminal commands for adjusting sidebar
-    {
-        let ui_handle = ui.as_weak();
-        std::thread::spawn(move || {
-            use std::io::{self, BufRead};
-            let stdin = io::stdin();
-            println!("\n=== SIDEBAR DEBUG COMMANDS ===");
-            println!("Type commands to adjust sidebar:");
-            println!("  width <number>  - Set sidebar width in pixels (e.g., 'width 300')");
-            println!("  hide            - Hide sidebar");
-            println!("  show            - Show sidebar");
-            println!("  help            - Show this help");
-            println!("==============================\n");
-
-            for line in stdin.lock().lines() {
-                if let Ok(line) = line {
-                    let parts: Vec<&str> = line.trim().split_whitespace().collect();
-                    if parts.is_empty() {
-                        continue;
-                    }
-
-                    if let Some(ui) = ui_handle.upgrade() {
-                        match parts[0] {
-                            "width" | "w" => {
-                                if let Some(width_str) = parts.get(1) {
-                                    if let Ok(width) = width_str.parse::<f32>() {
-                                        ui.set_sidebar_width(width);
-                                        println!("✓ Sidebar width set to {}px", width);
-                                    } else {
-                                        println!("✗ Invalid number. Usage: width <number>");
-                                    }
-                                } else {
-                                    println!("✗ Usage: width <number>");
-                                }
-                            }
-                            "hide" | "h" => {
-                                ui.set_sidebar_visible(false);
-                                println!("✓ Sidebar hidden");
-                            }
-                            "show" | "s" => {
-                                ui.set_sidebar_visible(true);
-                                println!("✓ Sidebar shown");
-                            }
-                            "help" | "?" => {
-                                println!("\n=== SIDEBAR DEBUG COMMANDS ===");
-                                println!("  width <number>  - Set sidebar width in pixels");
-                                println!("  hide            - Hide sidebar");
-                                println!("  show            - Show sidebar");
-                                println!("  help            - Show this help");
-                                println!("==============================\n");
-                            }
-                            _ => {
-                                println!("✗ Unknown command '{}'. Type 'help' for commands.", parts[0]);
-                            }
-                        }
-                    } else {
-                        break; // UI closed
-                    }
-                }
-            }
-        });
-    }
+    // Debug: terminal commands for adjusting the sidebar (debug.stdin_console)
+    let console_running = Arc::new(AtomicBool::new(true));
+    debug_console::spawn_stdin_console(
+        config.borrow().debug.stdin_console,
+        ui.as_weak(),
+        console_running.clone(),
+    );
 
     // Add callback for getting class name
     {
@@ -162,21 +131,40 @@ fn main() -> Result<(), slint::PlatformError> {
     ui.set_enable_bboxes_setting(config.borrow().annotation_modes.enable_bboxes);
     ui.set_enable_polygons_setting(config.borrow().annotation_modes.enable_polygons);
     ui.set_randomize_dataset_setting(config.borrow().dataset.randomize_order);
+    ui.set_invert_zoom_scroll_setting(config.borrow().appearance.invert_zoom_scroll);
+    ui.set_polygon_finish_on_release(config.borrow().annotation_modes.polygon_finish.on_key_release());
+    apply_selection_style(&ui, &config.borrow().appearance);
+    let zoom_limits = ZoomLimits::from_config(&config.borrow().appearance);
+    ui.set_min_zoom(zoom_limits.min);
+    ui.set_max_zoom(zoom_limits.max);
 
     // Add callback for applying settings
     {
         let config_ref = config.clone();
-        ui.on_apply_settings(move |theme, enable_points, enable_bboxes, enable_polygons, randomize| {
+        let ui_handle = ui.as_weak();
+        ui.on_apply_settings(move |theme, enable_points, enable_bboxes, enable_polygons, randomize, selection_color, selection_thickness, invert_zoom_scroll| {
             let mut cfg = config_ref.borrow_mut();
             cfg.appearance.theme = theme.to_string();
             cfg.annotation_modes.enable_points = enable_points;
             cfg.annotation_modes.enable_bboxes = enable_bboxes;
             cfg.annotation_modes.enable_polygons = enable_polygons;
             cfg.dataset.randomize_order = randomize;
+            cfg.appearance.selection_thickness = selection_thickness.max(1.0);
+            cfg.appearance.invert_zoom_scroll = invert_zoom_scroll;
+
+            let selection_color = selection_color.trim().to_string();
+            if selection_color.is_empty() || parse_color(&selection_color).is_some() {
+                cfg.appearance.selection_color = selection_color;
+            } else if let Some(ui) = ui_handle.upgrade() {
+                ui.set_status_text(format!("Invalid selection color '{}' (expected #rrggbb)", selection_color).into());
+            }
+            if let Some(ui) = ui_handle.upgrade() {
+                apply_selection_style(&ui, &cfg.appearance);
+            }
 
             // Save to disk
             if let Err(e) = config::save_config(&cfg) {
-                eprintln!("Failed to save config: {}", e);
+                logging::error(|| format!("Failed to save config: {}", e));
             }
         });
     }
@@ -184,6 +172,26 @@ fn main() -> Result<(), slint::PlatformError> {
     let draw_state = Rc::new(RefCell::new(DrawState::new()));
     let resize_state = Rc::new(RefCell::new(ResizeState::new()));
     let undo_history = Rc::new(RefCell::new(UndoHistory::new(50))); // Max 50 undo steps
+    undo_history
+        .borrow_mut()
+        .set_exclude_rejected(config.borrow().annotation_modes.compact_undo);
+    // Pending confirmation shared by bulk edits (delete selected, reject class,
+    // revert, clear, purge, remap). Every undo change (an edit, undo/redo or
+    // switching frames) disarms it so a stale prompt can't be confirmed later.
+    let bulk_confirm = Rc::new(RefCell::new(BulkConfirm::default()));
+    {
+        let ui_weak = ui.as_weak();
+        let bulk_confirm = bulk_confirm.clone();
+        undo_history.borrow_mut().set_listener(move |can_undo, can_redo| {
+            if let Ok(mut confirm) = bulk_confirm.try_borrow_mut() {
+                confirm.clear();
+            }
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_can_undo(can_undo);
+                ui.set_can_redo(can_redo);
+            }
+        });
+    }
     let clipboard: Rc<RefCell<Vec<Annotation>>> = Rc::new(RefCell::new(Vec::new())); // Annotation clipboard for copy/paste (supports multiple)
     let annotations = std::rc::Rc::new(slint::VecModel::from(Vec::<Annotation>::new()));
     ui.set_annotations(annotations.clone().into());
@@ -216,7 +224,7 @@ fn main() -> Result<(), slint::PlatformError> {
                     for i in 0..count {
                         if let Some(mut ann) = annotations_ref.row_data(i) {
                             if ann.selected {
-                                ann.class = class_id;
+                                state::apply_class(&mut ann, class_id);
                                 annotations_ref.set_row_data(i, ann);
                                 changed = true;
                             }
@@ -264,16 +272,14 @@ fn main() -> Result<(), slint::PlatformError> {
     if let Some(ds_path) = args.get(1) {
         match load_dataset(Path::new(ds_path)) {
             Ok(state) => {
-                let len = state.entries.len();
                 let mut state = state;
-                state.stored_annotations = vec![None; len];
-                state.view_states = vec![None; len];
-                state.completed_frames = vec![false; len];
+                state.set_label_extension(&config.borrow().dataset.label_extension);
+                state.ensure_len();
 
                 // Use class configuration from dataset if available
                 if let Some(dataset_classes) = &state.class_config {
                     *classes.borrow_mut() = dataset_classes.clone();
-                    println!("Loaded class configuration from dataset manifest");
+                    logging::info(|| "Loaded class configuration from dataset manifest".to_string());
 
                     // Update class items in UI
                     let class_items: Vec<ClassItem> = dataset_classes
@@ -292,6 +298,7 @@ fn main() -> Result<(), slint::PlatformError> {
                         })
                         .collect();
                     ui.set_class_items(slint::ModelRc::new(slint::VecModel::from(class_items)));
+                    keybindings::show_key_conflicts(&ui, dataset_classes);
                 }
 
                 *dataset_state.borrow_mut() = Some(state);
@@ -312,29 +319,25 @@ fn main() -> Result<(), slint::PlatformError> {
         let placeholder = placeholder.clone();
         let dataset_state = dataset_state.clone();
         let draw_state = draw_state.clone();
+        let undo_history = undo_history.clone();
         Rc::new(move |index: usize| {
             let mut ds_opt = dataset_state.borrow_mut();
             let Some(ds) = ds_opt.as_mut() else { return; };
-            if ds.stored_annotations.len() != ds.entries.len() {
-                ds.stored_annotations.resize(ds.entries.len(), None);
-            }
-            if ds.view_states.len() != ds.entries.len() {
-                ds.view_states.resize(ds.entries.len(), None);
-            }
-            if ds.completed_frames.len() != ds.entries.len() {
-                ds.completed_frames.resize(ds.entries.len(), false);
-            }
+            ds.ensure_len();
             if index >= ds.entries.len() {
                 return;
             }
 
             ds.current_index = index;
+            // Undo and redo only ever apply to the frame on screen
+            ds.switch_undo_frame(&mut undo_history.borrow_mut(), index);
             let entry = ds.entries[index].clone();
 
             let img_result = load_image_from_entry(&entry);
             let (image, img_size, status_msg) = match img_result {
                 Ok(img) => {
                     let size = img.size();
+                    ds.image_sizes[index] = Some((size.width as f32, size.height as f32));
                     (
                         img,
                         (size.width as f32, size.height as f32),
@@ -350,7 +353,7 @@ fn main() -> Result<(), slint::PlatformError> {
                     )
                 }
             };
-            println!("{}", status_msg);
+            logging::debug(|| status_msg.clone());
 
             *image_dimensions.borrow_mut() = img_size;
 
@@ -358,14 +361,16 @@ fn main() -> Result<(), slint::PlatformError> {
             let mut annotations_for_image = if let Some(cached) = ds.stored_annotations.get(index).and_then(|v| v.clone()) {
                 cached
             } else {
-                let anns = load_yolo_annotations(&entry, img_size, 1000);
+                let anns = load_yolo_annotations(&entry, img_size, LOADED_ID_START, &ds.label_extension);
                 ds.stored_annotations[index] = Some(anns.clone());
+                ds.record_initial(index, &anns);
                 anns
             };
 
-            // Clear selection when (re)loading to avoid stale references.
+            // Clear selection and any isolate view when (re)loading to avoid stale references.
             for ann in annotations_for_image.iter_mut() {
                 ann.selected = false;
+                ann.hidden = false;
             }
 
             replace_annotations(&annotations, annotations_for_image.clone());
@@ -381,7 +386,8 @@ fn main() -> Result<(), slint::PlatformError> {
                     .and_then(|f| f.to_str())
                     .unwrap_or("?");
                 ui.set_current_image_name(fname.into());
-                ui.set_dataset_position(format!("{} / {}", index + 1, ds.entries.len()).into());
+                let position = ds.nav_position(index) + 1;
+                ui.set_dataset_position(format!("{} / {}", position, ds.entries.len()).into());
                 ui.set_status_text(status_msg.into());
 
                 // Set completion status for this frame
@@ -390,6 +396,11 @@ fn main() -> Result<(), slint::PlatformError> {
                 } else {
                     ui.set_frame_completed(false);
                 }
+                ui.set_frame_reviewed(ds.is_reviewed(index));
+                callbacks::file_ops::show_image_tags(&ui, ds.tags_for(index));
+                callbacks::view::show_onion_skin(&ui, ds);
+                // A reference diff belongs to the frame it was computed for
+                ui.set_diff_marks(slint::ModelRc::default());
 
                 // Apply view: prefer global (if same-ish size), else per-image cache, else reset.
                 if let (Some(gv), Some(last_size)) =
@@ -398,28 +409,34 @@ fn main() -> Result<(), slint::PlatformError> {
                     if sizes_close(last_size, img_size, 2.0) {
                         apply_view_state(&ui, &gv);
                         ds.view_states[index] = Some(gv.clone());
-                        println!(
-                            "Applied global view to index {}: pan=({}, {}), zoom={}",
-                            index, gv.pan_x, gv.pan_y, gv.zoom
-                        );
+                        logging::debug(|| {
+                            format!(
+                                "Applied global view to index {}: pan=({}, {}), zoom={}",
+                                index, gv.pan_x, gv.pan_y, gv.zoom
+                            )
+                        });
                         return;
                     }
                 }
 
                 if let Some(vs) = ds.view_states.get(index).and_then(|v| v.clone()) {
                     apply_view_state(&ui, &vs);
-                    println!(
-                        "Applied cached view to index {}: pan=({}, {}), zoom={}",
-                        index, vs.pan_x, vs.pan_y, vs.zoom
-                    );
+                    logging::debug(|| {
+                        format!(
+                            "Applied cached view to index {}: pan=({}, {}), zoom={}",
+                            index, vs.pan_x, vs.pan_y, vs.zoom
+                        )
+                    });
                 } else {
                     ui.invoke_reset_view();
                     let vs = get_view_state(&ui);
                     ds.view_states[index] = Some(vs.clone());
-                    println!(
-                        "Initial reset view for index {}: pan=({}, {}), zoom={}",
-                        index, vs.pan_x, vs.pan_y, vs.zoom
-                    );
+                    logging::debug(|| {
+                        format!(
+                            "Initial reset view for index {}: pan=({}, {}), zoom={}",
+                            index, vs.pan_x, vs.pan_y, vs.zoom
+                        )
+                    });
                 }
             }
         })
@@ -428,11 +445,22 @@ fn main() -> Result<(), slint::PlatformError> {
     // Load first image if dataset present
     (loader)(0);
 
+    // Surface hierarchy problems and a missing class file after the loader so
+    // its status doesn't hide them (a manifest with its own classes needs none)
+    let dataset_has_classes =
+        dataset_state.borrow().as_ref().is_some_and(|ds| ds.class_config.is_some());
+    let class_fallback = class_trace.fallback_message().filter(|_| !dataset_has_classes);
+    if let Some(msg) = hierarchy_error.or(class_fallback) {
+        ui.set_status_text(msg.into());
+    }
+
     // Selection callbacks (extracted to callbacks/selection.rs)
     callbacks::selection::setup_selection_callbacks(
         &ui,
         annotations.clone(),
         undo_history.clone(),
+        config.clone(),
+        bulk_confirm.clone(),
     );
     // Dataset navigation callbacks (extracted to callbacks/navigation.rs)
     callbacks::navigation::setup_navigation_callbacks(
@@ -441,6 +469,24 @@ fn main() -> Result<(), slint::PlatformError> {
         dataset_state.clone(),
         annotations.clone(),
         image_dimensions.clone(),
+        config.clone(),
+    );
+
+    // Class selection callbacks (extracted to callbacks/class_selection.rs)
+    let class_history = Rc::new(RefCell::new(ClassHistory::new(ui.get_current_class())));
+    callbacks::class_selection::setup_class_callbacks(
+        &ui,
+        class_history.clone(),
+        classes.clone(),
+        annotations.clone(),
+    );
+
+    // View callbacks (extracted to callbacks/view.rs)
+    callbacks::view::setup_view_callbacks(
+        &ui,
+        config.clone(),
+        dataset_state.clone(),
+        annotations.clone(),
     );
 
     // Track global view changes (pan/zoom) to reuse across images
@@ -457,22 +503,15 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
-    ui.on_log_debug(move |msg| {
-        use std::io::Write;
-        if let Ok(mut file) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("debug_output.log")
-        {
-            let _ = writeln!(file, "{}", msg);
-        }
-    });
+    ui.on_log_debug(move |msg| logging::append_debug_log(&msg));
     // Drawing callbacks (extracted to callbacks/drawing.rs)
     callbacks::drawing::setup_drawing_callbacks(
         &ui,
         draw_state.clone(),
         annotations.clone(),
         undo_history.clone(),
+        config.clone(),
+        classes.clone(),
     );
 
     // Annotation manipulation callbacks (extracted to callbacks/annotation.rs)
@@ -481,6 +520,11 @@ fn main() -> Result<(), slint::PlatformError> {
         annotations.clone(),
         undo_history.clone(),
         clipboard.clone(),
+        dataset_state.clone(),
+        image_dimensions.clone(),
+        config.clone(),
+        bulk_confirm,
+        class_history,
     );
 
     // Auto-resize callback (extracted to callbacks/auto_resize.rs)
@@ -489,6 +533,7 @@ fn main() -> Result<(), slint::PlatformError> {
         annotations.clone(),
         dataset_state.clone(),
         image_dimensions.clone(),
+        undo_history.clone(),
     );
 
     // Polygon callbacks (extracted to callbacks/polygon.rs)
@@ -496,6 +541,9 @@ fn main() -> Result<(), slint::PlatformError> {
         &ui,
         draw_state.clone(),
         annotations.clone(),
+        undo_history.clone(),
+        config.clone(),
+        classes.clone(),
     );
 
     // Resize callbacks (extracted to callbacks/resize.rs)
@@ -503,6 +551,7 @@ fn main() -> Result<(), slint::PlatformError> {
         &ui,
         resize_state.clone(),
         annotations.clone(),
+        config.clone(),
     );
 
     // File operation callbacks (extracted to callbacks/file_ops.rs)
@@ -511,9 +560,29 @@ fn main() -> Result<(), slint::PlatformError> {
         loader.clone(),
         dataset_state.clone(),
         annotations.clone(),
+        undo_history.clone(),
         image_dimensions.clone(),
         classes.clone(),
+        config.clone(),
     );
 
-    ui.run()
+    window_placement::setup_window_placement(&ui, config.clone());
+
+    let result = ui.run();
+    // Let the console thread stop at its next line instead of touching a closed UI
+    console_running.store(false, Ordering::Relaxed);
+    result
+}
+
+/// Push the configured selection outline style into the UI
+fn apply_selection_style(ui: &AppWindow, appearance: &config::AppearanceConfig) {
+    match parse_color(&appearance.selection_color) {
+        Some(color) => {
+            ui.set_selection_color_value(color.into());
+            ui.set_custom_selection_color(true);
+        }
+        None => ui.set_custom_selection_color(false),
+    }
+    ui.set_selection_color_setting(appearance.selection_color.clone().into());
+    ui.set_selection_thickness(appearance.selection_thickness.max(1.0));
 }
@@ -0,0 +1,114 @@
+//! Progress and time-remaining reporting for long batch operations such as
+//! exports.
+
+use std::time::{Duration, Instant};
+
+/// Minimum time between two progress reports (~10 Hz)
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Estimated time left after `processed` of `total` items took `elapsed`,
+/// assuming the remaining items take as long on average. `None` until at
+/// least one item is done; zero once everything is.
+pub fn eta(processed: usize, total: usize, elapsed: Duration) -> Option<Duration> {
+    if processed == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(processed);
+    Some(elapsed.mul_f64(remaining as f64 / processed as f64))
+}
+
+/// Short `m:ss` (or `h:mm:ss`) form of a duration for the status bar
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Counts processed items of a batch and decides when a report is due
+#[derive(Debug)]
+pub struct Progress {
+    total: usize,
+    processed: usize,
+    started: Instant,
+    last_report: Option<Instant>,
+}
+
+/// Snapshot handed to the UI: done share 0–1 and the remaining-time text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressReport {
+    pub fraction: f32,
+    pub eta: String,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self { total, processed: 0, started: Instant::now(), last_report: None }
+    }
+
+    /// Mark one more item done. Returns a report at most every
+    /// `REPORT_INTERVAL`, and always for the last item.
+    pub fn advance(&mut self) -> Option<ProgressReport> {
+        self.processed = (self.processed + 1).min(self.total);
+        let now = Instant::now();
+        let due = self.processed == self.total
+            || self.last_report.is_none_or(|last| now - last >= REPORT_INTERVAL);
+        if !due {
+            return None;
+        }
+        self.last_report = Some(now);
+        Some(self.report(now - self.started))
+    }
+
+    /// Time since the batch started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn report(&self, elapsed: Duration) -> ProgressReport {
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            self.processed as f32 / self.total as f32
+        };
+        let eta = match eta(self.processed, self.total, elapsed) {
+            Some(left) => format!("{} left", format_duration(left)),
+            None => String::new(),
+        };
+        ProgressReport { fraction, eta }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta_math() {
+        let ten_secs = Duration::from_secs(10);
+        // Nothing done yet: no basis for an estimate
+        assert_eq!(eta(0, 100, ten_secs), None);
+        assert_eq!(eta(0, 0, Duration::ZERO), None);
+        // A quarter done in 10 s leaves three quarters at the same rate
+        assert_eq!(eta(25, 100, ten_secs), Some(Duration::from_secs(30)));
+        assert_eq!(eta(100, 100, ten_secs), Some(Duration::ZERO));
+        // Overshooting the total never goes negative
+        assert_eq!(eta(120, 100, ten_secs), Some(Duration::ZERO));
+
+        assert_eq!(format_duration(Duration::from_secs(65)), "1:05");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+    }
+
+    #[test]
+    fn test_progress_reports_first_and_last_item() {
+        let mut progress = Progress::new(3);
+        assert!(progress.advance().is_some());
+        // Within the throttle window the middle item stays quiet
+        assert_eq!(progress.advance(), None);
+        let last = progress.advance().unwrap();
+        assert_eq!(last.fraction, 1.0);
+        assert_eq!(last.eta, "0:00 left");
+    }
+}
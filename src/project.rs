@@ -0,0 +1,124 @@
+//! Project files (`.annproj`): several datasets plus shared class and export settings.
+
+use crate::classes::ClassConfig;
+use crate::config::ExportConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_EXTENSION: &str = "annproj";
+
+/// A saved multi-dataset session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Project {
+    /// Dataset manifests; stored relative to the project file when possible
+    pub datasets: Vec<PathBuf>,
+    /// Index into `datasets` of the dataset to reopen
+    #[serde(default)]
+    pub active_dataset: usize,
+    /// Frame to show in the active dataset
+    #[serde(default)]
+    pub active_index: usize,
+    /// Class configuration shared by all member datasets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classes: Option<ClassConfig>,
+    #[serde(default)]
+    pub export: ExportConfig,
+}
+
+impl Project {
+    /// Manifest of the dataset to reopen
+    pub fn active_manifest(&self) -> Option<&Path> {
+        self.datasets.get(self.active_dataset).map(|p| p.as_path())
+    }
+
+    /// Add a manifest if it isn't a member yet and make it the active one
+    pub fn set_active(&mut self, manifest: &Path, index: usize) {
+        let pos = match self.datasets.iter().position(|p| p == manifest) {
+            Some(pos) => pos,
+            None => {
+                self.datasets.push(manifest.to_path_buf());
+                self.datasets.len() - 1
+            }
+        };
+        self.active_dataset = pos;
+        self.active_index = index;
+    }
+}
+
+/// Load a project, resolving dataset paths against the project file's folder
+pub fn load_project(path: &Path) -> Result<Project, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read project: {e}"))?;
+    let mut project: Project =
+        serde_json::from_str(&text).map_err(|e| format!("Failed to parse project: {e}"))?;
+
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    for dataset in project.datasets.iter_mut() {
+        if dataset.is_relative() {
+            *dataset = base_dir.join(&dataset);
+        }
+    }
+    if project.datasets.is_empty() {
+        return Err("Project has no datasets".into());
+    }
+    Ok(project)
+}
+
+/// Save a project; datasets below the project folder are written as relative paths
+pub fn save_project(path: &Path, project: &Project) -> Result<(), String> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let mut stored = project.clone();
+    for dataset in stored.datasets.iter_mut() {
+        if let Ok(rel) = dataset.strip_prefix(base_dir) {
+            *dataset = rel.to_path_buf();
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize project: {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write project: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{test_class, ClassDefinition};
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_project_round_trip() {
+        let dir = scratch_dir("project");
+
+        let class = |id: i32, name: &str| ClassDefinition {
+            color: Some("#ff0000".to_string()),
+            ..test_class(id, name)
+        };
+        let mut project = Project {
+            datasets: vec![dir.join("day/manifest.json")],
+            classes: Some(ClassConfig {
+                classes: vec![class(1, "car"), class(2, "person")],
+                hierarchy: Vec::new(),
+            }),
+            ..Default::default()
+        };
+        project.export.coco_category_start_id = 0;
+        project.set_active(&dir.join("night.json"), 7);
+
+        let path = dir.join(format!("session.{PROJECT_EXTENSION}"));
+        save_project(&path, &project).unwrap();
+        // Members are stored relative to the project file
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\"night.json\""));
+
+        let loaded = load_project(&path).unwrap();
+        assert_eq!(loaded.datasets, vec![dir.join("day/manifest.json"), dir.join("night.json")]);
+        assert_eq!(loaded.active_manifest(), Some(dir.join("night.json").as_path()));
+        assert_eq!(loaded.active_index, 7);
+        let names: Vec<String> =
+            loaded.classes.unwrap().classes.into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["car", "person"]);
+        assert_eq!(loaded.export.coco_category_start_id, 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,97 @@
+//! Review overlays: per-image lists of annotation ids to double-check in a
+//! second pass, flagged with `needs_review` on top of the base labels.
+
+use super::dataset::cached_frame_mut;
+use super::types::DatasetState;
+use serde::Deserialize;
+use std::path::Path;
+
+/// One image of a corrections file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CorrectionEntry {
+    /// Image file name, or its path below the manifest folder
+    pub image: String,
+    /// Ids of the annotations on that image to review
+    pub ids: Vec<i32>,
+}
+
+/// Read a corrections file: a JSON array of `{"image": ..., "ids": [...]}`
+pub fn load_corrections(path: &Path) -> Result<Vec<CorrectionEntry>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&text).map_err(|e| format!("Failed to parse corrections: {e}"))
+}
+
+/// What applying a corrections file changed
+#[derive(Debug, Default, PartialEq)]
+pub struct CorrectionsOutcome {
+    /// Annotations newly flagged for review
+    pub flagged: usize,
+    /// Images of the file that aren't in the dataset
+    pub unknown_images: Vec<String>,
+}
+
+/// Flag the listed annotations with `needs_review`. Frames not visited yet are
+/// read from disk first; ids missing from a frame are ignored and annotations
+/// not listed keep their flag.
+pub fn apply_corrections(
+    ds: &mut DatasetState,
+    corrections: &[CorrectionEntry],
+) -> CorrectionsOutcome {
+    let mut outcome = CorrectionsOutcome::default();
+    for correction in corrections {
+        let image = Path::new(&correction.image);
+        let Some(idx) = ds.entries.iter().position(|e| e.image_path.ends_with(image)) else {
+            outcome.unknown_images.push(correction.image.clone());
+            continue;
+        };
+        for ann in cached_frame_mut(ds, idx).iter_mut() {
+            if correction.ids.contains(&ann.id) && !ann.needs_review {
+                ann.needs_review = true;
+                outcome.flagged += 1;
+            }
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DatasetEntry;
+    use crate::Annotation;
+
+    #[test]
+    fn test_corrections_flag_only_listed_annotations() {
+        let entries = ["images/a.jpg", "images/b.jpg"]
+            .iter()
+            .map(|p| DatasetEntry { image_path: p.into(), labels_path: None })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        let ann = |id: i32| Annotation { id, r#type: "bbox".into(), ..Default::default() };
+        ds.stored_annotations[0] = Some(vec![ann(1), ann(2), ann(3)]);
+        ds.stored_annotations[1] = Some(vec![ann(1), ann(2)]);
+
+        let corrections: Vec<CorrectionEntry> = serde_json::from_str(
+            r#"[
+                { "image": "a.jpg", "ids": [2, 3, 99] },
+                { "image": "images/b.jpg", "ids": [1] },
+                { "image": "c.jpg", "ids": [1] }
+            ]"#,
+        )
+        .unwrap();
+        let outcome = apply_corrections(&mut ds, &corrections);
+        assert_eq!(outcome.flagged, 3);
+        assert_eq!(outcome.unknown_images, vec!["c.jpg".to_string()]);
+
+        let flags = |idx: usize| -> Vec<bool> {
+            ds.stored_annotations[idx].as_ref().unwrap().iter().map(|a| a.needs_review).collect()
+        };
+        assert_eq!(flags(0), vec![false, true, true]);
+        assert_eq!(flags(1), vec![true, false]);
+
+        // Applying the same file again flags nothing new
+        assert_eq!(apply_corrections(&mut ds, &corrections).flagged, 0);
+    }
+}
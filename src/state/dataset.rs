@@ -1,7 +1,18 @@
 //! Dataset loading, saving, and management functions.
 
+use crate::config::{AppearanceConfig, ExportConfig};
+use crate::export::passes_state_filter;
+use crate::state::edits::{
+    accept_pending, format_millis, group_of, max_group_id, parse_millis, polygon_points,
+    set_polygon_points, MIN_RING_VERTICES,
+};
+use crate::state::video::{explode_gif, frame_file_name, frames_dir_for};
+use crate::transform::{
+    denormalize_box, image_to_screen, normalize_box, obb_to_rbbox, screen_to_image,
+};
 use crate::state::types::{
-    DatasetEntry, DatasetFile, DatasetFileEntry, DatasetState, StoredAnnotation, ViewState,
+    DatasetEntry, DatasetFile, DatasetFileEntry, DatasetState, ProgressFile, StoredAnnotation,
+    ViewState, BOOKMARK_SLOTS, NO_GROUP,
 };
 use crate::{Annotation, AppWindow, PolygonVertex};
 use slint::Model;
@@ -42,53 +53,97 @@ pub fn load_dataset(path: &Path) -> Result<DatasetState, String> {
         None
     };
 
-    Ok(DatasetState {
-        entries,
-        current_index: 0,
-        stored_annotations: Vec::new(),
-        view_states: Vec::new(),
-        global_view: None,
-        last_view_image_size: None,
-        completed_frames: Vec::new(),
-        class_config,
-    })
+    let mut state = DatasetState::new(entries, class_config);
+    state.manifest_path = Some(path.to_path_buf());
+    state.labels_dir = parsed.labels_dir.map(|dir| base_dir.join(dir));
+    load_progress(&mut state);
+    Ok(state)
+}
+
+/// Progress sidecar for a manifest (`manifest.json` → `manifest.progress.json`)
+pub fn progress_path_for(manifest: &Path) -> PathBuf {
+    manifest.with_extension("progress.json")
+}
+
+/// Restore bookmarks, completion/review flags and image tags from the progress sidecar;
+/// a missing or invalid file is ignored
+fn load_progress(ds: &mut DatasetState) {
+    let Some(manifest) = ds.manifest_path.as_ref() else { return };
+    let Ok(text) = fs::read_to_string(progress_path_for(manifest)) else { return };
+    let Ok(progress) = serde_json::from_str::<ProgressFile>(&text) else { return };
+
+    for (slot, view) in progress.bookmarks.into_iter().enumerate().take(BOOKMARK_SLOTS) {
+        if let Some(view) = view {
+            ds.set_bookmark(slot, view);
+        }
+    }
+    ds.completed_frames = progress.completed_frames;
+    ds.reviewed_frames = progress.reviewed_frames;
+    ds.image_tags = progress.image_tags;
+    ds.max_track_id = progress.max_track_id;
+    ds.ensure_len();
+    ds.backed_up.1 = ds.progress();
+}
+
+/// Write the progress sidecar next to the manifest (no-op without a manifest)
+pub fn save_progress(ds: &DatasetState) -> Result<(), String> {
+    let Some(manifest) = ds.manifest_path.as_ref() else { return Ok(()) };
+    write_progress(&ds.progress(), &progress_path_for(manifest))
+}
+
+fn write_progress(progress: &ProgressFile, path: &Path) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(progress).map_err(|e| format!("Serialize progress: {e}"))?;
+    fs::write(path, json).map_err(|e| format!("Write progress {}: {e}", path.display()))
+}
+
+/// Dataset manifests (`*.json`) next to `path`, sorted by file name.
+/// Includes `path` itself; sidecars (`*.state.json`, `*.progress.json`) are skipped.
+pub fn sibling_manifests(path: &Path) -> Vec<PathBuf> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut manifests: Vec<PathBuf> = read_dir
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+            name.ends_with(".json")
+                && !name.ends_with(".state.json")
+                && !name.ends_with(".progress.json")
+        })
+        .collect();
+    manifests.sort();
+    manifests
 }
 
 /// Create a new dataset manifest from a folder of images
+///
+/// Subfolders are scanned up to `max_depth` levels below `folder` (0 = only
+/// the folder itself); manifest paths are relative to `folder`.
 pub fn create_dataset_from_folder(
     folder: &Path,
     class_config: Option<&crate::classes::ClassConfig>,
+    max_depth: usize,
+    label_extension: &str,
 ) -> Result<PathBuf, String> {
-    // Scan folder for image files
-    let extensions = ["png", "jpg", "jpeg", "bmp", "gif"];
-    let mut image_files = Vec::new();
-
-    let entries =
-        fs::read_dir(folder).map_err(|e| format!("Failed to read folder: {e}"))?;
-
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        if extensions.contains(&ext_str.to_lowercase().as_str()) {
-                            if let Some(filename) = path.file_name() {
-                                image_files.push(filename.to_string_lossy().to_string());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let image_files = scan_image_files(folder, max_depth)?;
 
     if image_files.is_empty() {
         return Err("No image files found in folder".into());
     }
 
+    let mut image_files = expand_animated_gifs(folder, image_files);
+
     // Sort for consistent ordering
     image_files.sort();
+    image_files.dedup();
 
     // Create manifest entries
     let manifest_entries: Vec<DatasetFileEntry> = image_files
@@ -97,7 +152,7 @@ pub fn create_dataset_from_folder(
             image: img.clone(),
             labels: Some(
                 Path::new(&img)
-                    .with_extension("txt")
+                    .with_extension(label_extension.trim_start_matches('.'))
                     .to_string_lossy()
                     .to_string(),
             ),
@@ -106,6 +161,7 @@ pub fn create_dataset_from_folder(
 
     let manifest = DatasetFile {
         images: manifest_entries,
+        labels_dir: None,
         classes: class_config.map(|cfg| cfg.classes.clone()),
         hierarchy: class_config.map(|cfg| cfg.hierarchy.clone()).unwrap_or_default(),
     };
@@ -119,20 +175,336 @@ pub fn create_dataset_from_folder(
     Ok(manifest_path)
 }
 
+/// Replace each multi-frame GIF in `files` with the frames split out next to it,
+/// so every frame gets its own entry and label file. GIFs that fail to decode
+/// stay a single entry (their first frame).
+fn expand_animated_gifs(folder: &Path, files: Vec<String>) -> Vec<String> {
+    let mut expanded = Vec::with_capacity(files.len());
+    for rel in files {
+        let is_gif = Path::new(&rel)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+        if !is_gif {
+            expanded.push(rel);
+            continue;
+        }
+        match explode_gif(&folder.join(&rel)) {
+            Ok(count) if count > 1 => {
+                let frames_dir = frames_dir_for(Path::new(&rel));
+                expanded.extend((0..count).map(|i| {
+                    frames_dir.join(frame_file_name(i)).to_string_lossy().replace('\\', "/")
+                }));
+            }
+            Ok(_) => expanded.push(rel),
+            Err(e) => {
+                crate::logging::warn(|| e);
+                expanded.push(rel);
+            }
+        }
+    }
+    expanded
+}
+
+/// Recursively collect image files below `root` as root-relative paths
+pub fn scan_image_files(root: &Path, max_depth: usize) -> Result<Vec<String>, String> {
+    let extensions = ["png", "jpg", "jpeg", "bmp", "gif"];
+    let mut image_files = Vec::new();
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = stack.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Only the root folder is required to be readable
+            Err(e) if depth == 0 => return Err(format!("Failed to read folder: {e}")),
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if depth < max_depth {
+                    stack.push((path, depth + 1));
+                }
+                continue;
+            }
+            let is_image = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false);
+            if is_image {
+                if let Ok(rel) = path.strip_prefix(root) {
+                    image_files.push(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    Ok(image_files)
+}
+
 /// Load an image from a dataset entry
 pub fn load_image_from_entry(entry: &DatasetEntry) -> Result<slint::Image, String> {
     slint::Image::load_from_path(&entry.image_path)
         .map_err(|_| format!("Image not found: {}", entry.image_path.display()))
 }
 
+/// First id given to annotations read from label files, as the frame loader does
+pub const LOADED_ID_START: i32 = 1000;
+
+/// Annotations of frame `idx`: the cached edit if the frame was visited this
+/// session, otherwise its files on disk scaled to the image size
+pub fn frame_annotations(ds: &DatasetState, idx: usize) -> Vec<Annotation> {
+    if let Some(Some(anns)) = ds.stored_annotations.get(idx) {
+        return anns.clone();
+    }
+    let Some(entry) = ds.entries.get(idx) else { return Vec::new() };
+    let img_size = frame_image_size(ds, idx, entry);
+    load_yolo_annotations(entry, img_size, LOADED_ID_START, &ds.label_extension)
+}
+
+/// Highest track id used anywhere in the dataset. The first call scans every
+/// frame (unvisited ones from their files); after that the high-water mark
+/// kept in `max_track_id` (and the progress sidecar) answers.
+pub fn dataset_max_track_id(ds: &mut DatasetState) -> i32 {
+    if let Some(max) = ds.max_track_id {
+        return max;
+    }
+    let max = (0..ds.entries.len())
+        .map(|idx| max_group_id(&frame_annotations(ds, idx)))
+        .max()
+        .unwrap_or(0);
+    ds.max_track_id = Some(max);
+    max
+}
+
+/// Copies of the live annotations on the frame before (`prev`) and/or after
+/// (`next`) frame `idx`, for the read-only onion-skin overlay. Neighbors not
+/// visited yet are read from disk but not cached; the copies are never selected.
+pub fn onion_skin_annotations(
+    ds: &DatasetState,
+    idx: usize,
+    prev: bool,
+    next: bool,
+) -> Vec<Annotation> {
+    let mut neighbors = Vec::new();
+    if prev && idx > 0 {
+        neighbors.push(idx - 1);
+    }
+    if next && idx + 1 < ds.entries.len() {
+        neighbors.push(idx + 1);
+    }
+    neighbors
+        .into_iter()
+        .flat_map(|n| frame_annotations(ds, n))
+        .filter(|a| a.state != "Rejected")
+        .map(|a| Annotation { selected: false, ..a })
+        .collect()
+}
+
+/// Cached annotations of frame `idx` for editing, read from disk first if the
+/// frame wasn't visited yet (and remembered for revert)
+pub fn cached_frame_mut(ds: &mut DatasetState, idx: usize) -> &mut Vec<Annotation> {
+    ds.ensure_len();
+    if ds.stored_annotations[idx].is_none() {
+        let existing = frame_annotations(ds, idx);
+        ds.record_initial(idx, &existing);
+        ds.stored_annotations[idx] = Some(existing);
+    }
+    ds.stored_annotations[idx].get_or_insert_with(Vec::new)
+}
+
+/// One box of a YOLO label line: 0-based class and normalized center and size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YoloLine {
+    pub class: i32,
+    pub cx: f32,
+    pub cy: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Optional sixth column written by detectors
+    pub confidence: Option<f32>,
+}
+
+/// Parse one line of a YOLO label file. Blank lines and `#` comments are
+/// `Ok(None)`; surrounding whitespace (including a CRLF `\r`) is ignored. A line
+/// that isn't 5 or 6 numbers is an error saying what is wrong with it.
+pub fn parse_yolo_line(line: &str) -> Result<Option<YoloLine>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if !(5..=6).contains(&parts.len()) {
+        return Err(format!("expected 5 or 6 columns, found {}", parts.len()));
+    }
+    let class = parts[0].parse().map_err(|_| format!("invalid class id '{}'", parts[0]))?;
+    let mut values = [0.0f32; 5];
+    for (value, part) in values.iter_mut().zip(&parts[1..]) {
+        *value = part.parse().map_err(|_| format!("invalid number '{part}'"))?;
+    }
+    let [cx, cy, width, height, confidence] = values;
+    Ok(Some(YoloLine {
+        class,
+        cx,
+        cy,
+        width,
+        height,
+        confidence: (parts.len() == 6).then_some(confidence),
+    }))
+}
+
+/// One oriented box of a YOLO-OBB or DOTA label line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObbLine {
+    /// 0-based class id
+    pub class: i32,
+    /// Corners in drawing order
+    pub corners: [(f32, f32); 4],
+    /// YOLO-OBB corners are normalized to 0..1, DOTA corners are pixels
+    pub normalized: bool,
+}
+
+/// Parse an oriented-box line: YOLO-OBB `class x1 y1 ... x4 y4` (normalized)
+/// or DOTA `x1 y1 ... x4 y4 class difficult` (pixels, numeric class). Blank
+/// lines, `#` comments and DOTA `imagesource:`/`gsd:` headers are `Ok(None)`.
+pub fn parse_obb_line(line: &str) -> Result<Option<ObbLine>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || is_dota_header(line) {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let (class_part, coord_parts, normalized) = match parts.len() {
+        9 => (parts[0], &parts[1..], true),
+        10 => (parts[8], &parts[..8], false),
+        n => return Err(format!("expected 9 or 10 columns, found {n}")),
+    };
+    let class = class_part.parse().map_err(|_| format!("invalid class id '{class_part}'"))?;
+    let mut values = [0.0f32; 8];
+    for (value, part) in values.iter_mut().zip(coord_parts) {
+        *value = part.parse().map_err(|_| format!("invalid number '{part}'"))?;
+    }
+    let corner = |i: usize| (values[2 * i], values[2 * i + 1]);
+    Ok(Some(ObbLine { class, corners: [corner(0), corner(1), corner(2), corner(3)], normalized }))
+}
+
+/// Corners of an oriented box, and the vertex count that a saved polygon
+/// line repeats its first vertex for
+const OBB_CORNERS: usize = 4;
+
+/// One polygon outline of a YOLO-seg label line
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegLine {
+    /// 0-based class id
+    pub class: i32,
+    /// Normalized vertices, without a repeated closing vertex
+    pub points: Vec<(f32, f32)>,
+}
+
+/// Whether a label line with `columns` columns is YOLO-seg: a class and at
+/// least three `x y` pairs. Nine columns are always YOLO-OBB, so four-vertex
+/// polygons are saved closed (first vertex repeated, 11 columns).
+fn is_seg_columns(columns: usize) -> bool {
+    columns >= 1 + 2 * MIN_RING_VERTICES && columns % 2 == 1 && columns != 1 + 2 * OBB_CORNERS
+}
+
+/// Parse a YOLO-seg line `class x1 y1 x2 y2 ...`; a last vertex equal to the
+/// first (a closed ring) is dropped. Blank lines and `#` comments are `Ok(None)`.
+pub fn parse_seg_line(line: &str) -> Result<Option<SegLine>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if !is_seg_columns(parts.len()) {
+        let found = parts.len();
+        return Err(format!("expected a class and 3 or more x y pairs, found {found} columns"));
+    }
+    let class = parts[0].parse().map_err(|_| format!("invalid class id '{}'", parts[0]))?;
+    let mut values = Vec::with_capacity(parts.len() - 1);
+    for part in &parts[1..] {
+        values.push(part.parse::<f32>().map_err(|_| format!("invalid number '{part}'"))?);
+    }
+    let mut points: Vec<(f32, f32)> = values.chunks(2).map(|p| (p[0], p[1])).collect();
+    if points.len() > MIN_RING_VERTICES && points.first() == points.last() {
+        points.pop();
+    }
+    Ok(Some(SegLine { class, points }))
+}
+
+/// DOTA files may start with `imagesource:...` and `gsd:...` lines
+fn is_dota_header(line: &str) -> bool {
+    line.starts_with("imagesource:") || line.starts_with("gsd:")
+}
+
+/// Annotation for one label line: YOLO boxes (5 or 6 columns) become bboxes,
+/// YOLO-OBB and DOTA lines (9 or 10 columns) rbboxes, and YOLO-seg lines (any
+/// other odd count from 7) polygons. Classes shift to 1-based.
+fn label_line_annotation(
+    line: &str,
+    img_size: (f32, f32),
+    id: i32,
+) -> Result<Option<Annotation>, String> {
+    let columns = line.split_whitespace().count();
+    if is_seg_columns(columns) {
+        let Some(seg) = parse_seg_line(line)? else { return Ok(None) };
+        let points: Vec<(f32, f32)> =
+            seg.points.iter().map(|&(x, y)| (x * img_size.0, y * img_size.1)).collect();
+        let mut ann = Annotation {
+            id,
+            r#type: "polygon".into(),
+            class: seg.class + 1,
+            state: "Pending".into(),
+            ..Default::default()
+        };
+        set_polygon_points(&mut ann, &points);
+        return Ok(Some(ann));
+    }
+    if columns == 9 || columns == 10 || is_dota_header(line.trim()) {
+        let Some(obb) = parse_obb_line(line)? else { return Ok(None) };
+        let scale = if obb.normalized { img_size } else { (1.0, 1.0) };
+        let corners = obb.corners.map(|(x, y)| (x * scale.0, y * scale.1));
+        let (x, y, width, height, rotation) = obb_to_rbbox(corners);
+        return Ok(Some(Annotation {
+            id,
+            r#type: "rbbox".into(),
+            x,
+            y,
+            width,
+            height,
+            rotation,
+            class: obb.class + 1,
+            state: "Pending".into(),
+            ..Default::default()
+        }));
+    }
+
+    let Some(yolo) = parse_yolo_line(line)? else { return Ok(None) };
+    let (x, y, width, height) =
+        denormalize_box((yolo.cx, yolo.cy, yolo.width, yolo.height), img_size);
+    Ok(Some(Annotation {
+        id,
+        r#type: "bbox".into(),
+        x,
+        y,
+        width,
+        height,
+        class: yolo.class + 1, // shift to 1-based class IDs
+        state: "Pending".into(),
+        ..Default::default()
+    }))
+}
+
 /// Load YOLO-format annotations for a dataset entry
 pub fn load_yolo_annotations(
     entry: &DatasetEntry,
     img_size: (f32, f32),
     next_id_start: i32,
+    label_extension: &str,
 ) -> Vec<Annotation> {
     // Prefer persisted state file if present
-    let state_path = state_path_for(entry);
+    let state_path = state_path_for(entry, label_extension);
     if let Ok(text) = fs::read_to_string(&state_path) {
         if let Ok(stored) = serde_json::from_str::<Vec<StoredAnnotation>>(&text) {
             return stored
@@ -148,71 +520,57 @@ pub fn load_yolo_annotations(
                     selected: s.selected,
                     class: s.class,
                     state: s.state.into(),
+                    polygon_vertices: std::rc::Rc::new(slint::VecModel::from(parse_vertices(
+                        &s.vertices,
+                    )))
+                    .into(),
+                    polygon_path_commands: generate_rings_path_commands(&parse_rings(
+                        &s.vertices,
+                    ))
+                    .into(),
                     vertices: s.vertices.into(),
-                    polygon_vertices: Default::default(),
-                    polygon_path_commands: "".into(),
+                    created_at: format_millis(s.created_at).into(),
+                    modified_at: format_millis(s.modified_at).into(),
+                    author: s.author.unwrap_or_default().into(),
+                    locked: s.locked,
+                    needs_review: s.needs_review,
+                    group_id: s.group_id,
+                    hidden: false,
                 })
                 .collect();
         }
     }
 
     let mut anns = Vec::new();
-    let Some(label_path) = entry.labels_path.as_ref() else {
-        return anns;
-    };
-    let Ok(text) = fs::read_to_string(label_path) else {
+    let label_path = label_path_for(entry, label_extension);
+    let Ok(text) = fs::read_to_string(&label_path) else {
         return anns;
     };
 
     for (idx, line) in text.lines().enumerate() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() != 5 {
-            continue;
+        match label_line_annotation(line, img_size, next_id_start + idx as i32) {
+            Ok(Some(ann)) => anns.push(ann),
+            Ok(None) => {}
+            Err(e) => crate::logging::warn(|| {
+                format!("{}:{}: skipping line: {e}", label_path.display(), idx + 1)
+            }),
         }
-        let cls: i32 = parts[0].parse().unwrap_or(0) + 1; // shift to 1-based class IDs
-        let cx: f32 = parts[1].parse().unwrap_or(0.5);
-        let cy: f32 = parts[2].parse().unwrap_or(0.5);
-        let w: f32 = parts[3].parse().unwrap_or(0.0);
-        let h: f32 = parts[4].parse().unwrap_or(0.0);
-
-        let img_w = img_size.0;
-        let img_h = img_size.1;
-
-        let abs_w = w * img_w;
-        let abs_h = h * img_h;
-        let x = cx * img_w - abs_w / 2.0;
-        let y = cy * img_h - abs_h / 2.0;
-
-        anns.push(Annotation {
-            id: next_id_start + idx as i32,
-            r#type: "bbox".into(),
-            x,
-            y,
-            width: abs_w,
-            height: abs_h,
-            rotation: 0.0,
-            selected: false,
-            class: cls,
-            state: "Pending".into(),
-            vertices: "".into(),
-            polygon_vertices: Default::default(),
-            polygon_path_commands: "".into(),
-        });
     }
     anns
 }
 
-/// Get the label file path for a dataset entry
-pub fn label_path_for(entry: &DatasetEntry) -> PathBuf {
+/// Get the label file path for a dataset entry.
+/// Entries without an explicit labels path use the image path with `label_extension`.
+pub fn label_path_for(entry: &DatasetEntry, label_extension: &str) -> PathBuf {
     entry
         .labels_path
         .clone()
-        .unwrap_or_else(|| entry.image_path.with_extension("txt"))
+        .unwrap_or_else(|| entry.image_path.with_extension(label_extension.trim_start_matches('.')))
 }
 
 /// Get the state file path for a dataset entry
-pub fn state_path_for(entry: &DatasetEntry) -> PathBuf {
-    label_path_for(entry).with_extension("state.json")
+pub fn state_path_for(entry: &DatasetEntry, label_extension: &str) -> PathBuf {
+    label_path_for(entry, label_extension).with_extension("state.json")
 }
 
 /// Save current state to the dataset
@@ -222,14 +580,73 @@ pub fn save_current_state(
     ui: &AppWindow,
     img_size: (f32, f32),
 ) {
+    store_frame_state(ds, snapshot_annotations(annotations), get_view_state(ui), img_size);
+}
+
+/// Cache the current frame before navigating away; with `flush`, also write
+/// its label and state files right away. Only that frame is written, so this
+/// stays cheap on large datasets.
+pub fn leave_frame(
+    ds: &mut DatasetState,
+    anns: Vec<Annotation>,
+    view: ViewState,
+    img_size: (f32, f32),
+    flush: Option<&ExportConfig>,
+) -> Result<(), String> {
+    store_frame_state(ds, anns, view, img_size);
+    match flush {
+        Some(export) => save_frame(ds, ds.current_index, export),
+        None => Ok(()),
+    }
+}
+
+/// Cache annotations and view for the current frame
+fn store_frame_state(
+    ds: &mut DatasetState,
+    anns: Vec<Annotation>,
+    view: ViewState,
+    img_size: (f32, f32),
+) {
+    ds.ensure_len();
     let idx = ds.current_index;
     if idx >= ds.entries.len() {
         return;
     }
-    ds.stored_annotations[idx] = Some(snapshot_annotations(annotations));
-    ds.view_states[idx] = Some(get_view_state(ui));
+    ds.stored_annotations[idx] = Some(anns);
+    ds.view_states[idx] = Some(view);
     ds.global_view = ds.view_states[idx].clone();
     ds.last_view_image_size = Some(img_size);
+    ds.image_sizes[idx] = Some(img_size);
+}
+
+/// Pixel size read from an image file's header, if it can be read
+fn decode_image_size(entry: &DatasetEntry) -> Option<(f32, f32)> {
+    image::image_dimensions(&entry.image_path).ok().map(|(w, h)| (w as f32, h as f32))
+}
+
+/// Size of frame `idx`, from the cache if known, else read from its image
+/// (1×1 if unreadable) without caching it
+pub fn frame_image_size(ds: &DatasetState, idx: usize, entry: &DatasetEntry) -> (f32, f32) {
+    ds.image_sizes
+        .get(idx)
+        .copied()
+        .flatten()
+        .or_else(|| decode_image_size(entry))
+        .unwrap_or((1.0, 1.0))
+}
+
+/// Size of frame `idx` from `sizes`, decoding (and caching) it only if unknown.
+/// An unreadable image normalizes by 1 and is tried again next time.
+fn cached_image_size(
+    sizes: &mut [Option<(f32, f32)>],
+    idx: usize,
+    entry: &DatasetEntry,
+) -> (f32, f32) {
+    if let Some(size) = sizes[idx] {
+        return size;
+    }
+    sizes[idx] = decode_image_size(entry);
+    sizes[idx].unwrap_or((1.0, 1.0))
 }
 
 /// Convert an Annotation to StoredAnnotation format
@@ -246,57 +663,251 @@ pub fn ann_to_stored(a: &Annotation) -> StoredAnnotation {
         class: a.class,
         state: a.state.to_string(),
         vertices: a.vertices.to_string(),
+        created_at: parse_millis(&a.created_at),
+        modified_at: parse_millis(&a.modified_at),
+        author: if a.author.is_empty() {
+            None
+        } else {
+            Some(a.author.to_string())
+        },
+        locked: a.locked,
+        needs_review: a.needs_review,
+        group_id: group_of(a).unwrap_or(NO_GROUP),
     }
 }
 
-/// Save all dataset entries to disk
-pub fn save_all(ds: &DatasetState) -> Result<(), String> {
-    for (idx, entry) in ds.entries.iter().enumerate() {
-        let anns = ds
-            .stored_annotations
-            .get(idx)
-            .and_then(|v| v.clone())
-            .unwrap_or_default();
-
-        // Write YOLO labels (bbox/rbbox only, non-rejected)
-        let label_path = label_path_for(entry);
-        if let Some(parent) = label_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| format!("Label dir create: {e}"))?;
+/// Save all loaded dataset entries to disk: YOLO labels plus state sidecars,
+/// then the dataset's progress sidecar.
+/// `export.state_filter` limits which annotation states go into the YOLO labels;
+/// both files keep the working class ids (`export.class_export_map` is applied
+/// by the exports only), and the state sidecar keeps every annotation.
+/// With `export.accept_pending_on_save`, the cached `Pending` annotations are
+/// accepted first; the caller handles the frame on screen so it can be undone.
+pub fn save_all(ds: &mut DatasetState, export: &ExportConfig) -> Result<(), String> {
+    ds.ensure_len();
+    if export.accept_pending_on_save {
+        for anns in ds.stored_annotations.iter_mut().flatten() {
+            accept_pending(anns);
         }
+    }
+    let create_dirs = export.create_missing_dirs;
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        // Frames never loaded this session keep their files as they are on disk
+        let Some(anns) = ds.stored_annotations[idx].as_ref() else { continue };
+        let label_path = label_path_for(entry, &ds.label_extension);
+        let state_path = state_path_for(entry, &ds.label_extension);
+        let img_size = cached_image_size(&mut ds.image_sizes, idx, entry);
+        write_frame_files(anns, img_size, export, &label_path, &state_path, create_dirs)?;
+    }
+    save_progress(ds)
+}
 
-        let mut yolo_lines = Vec::new();
-        // Load image size to normalize
-        let img_size = slint::Image::load_from_path(&entry.image_path)
-            .map(|img| img.size())
-            .map(|s| (s.width as f32, s.height as f32))
-            .unwrap_or((1.0, 1.0));
+/// Write the label and state files of frame `idx` alone (nothing for a frame
+/// not loaded this session)
+pub fn save_frame(ds: &mut DatasetState, idx: usize, export: &ExportConfig) -> Result<(), String> {
+    ds.ensure_len();
+    let (Some(entry), Some(Some(anns))) = (ds.entries.get(idx), ds.stored_annotations.get(idx))
+    else {
+        return Ok(());
+    };
+    let label_path = label_path_for(entry, &ds.label_extension);
+    let state_path = state_path_for(entry, &ds.label_extension);
+    let img_size = cached_image_size(&mut ds.image_sizes, idx, entry);
+    let create_dirs = export.create_missing_dirs;
+    write_frame_files(anns, img_size, export, &label_path, &state_path, create_dirs)
+}
+
+/// One YOLO label line with the class and normalized `(cx, cy, w, h)` box it
+/// is sorted by (a polygon's box is the one around its vertices)
+type YoloRow = (i32, (f32, f32, f32, f32), String);
+
+/// Order YOLO rows by class, then center x, then center y (width, height and
+/// the line itself break any remaining tie), independent of drawing order
+fn sort_yolo_rows(rows: &mut [YoloRow]) {
+    rows.sort_by(|(ca, a, la), (cb, b, lb)| {
+        ca.cmp(cb)
+            .then(a.0.total_cmp(&b.0))
+            .then(a.1.total_cmp(&b.1))
+            .then(a.2.total_cmp(&b.2))
+            .then(a.3.total_cmp(&b.3))
+            .then(la.cmp(lb))
+    });
+}
+
+/// Write one frame's YOLO labels (see [`yolo_label_lines`]), normalized by
+/// `img_size`, and its state file with all annotations. Missing parent folders
+/// are created only with `create_dirs`; otherwise they are an error.
+fn write_frame_files(
+    anns: &[Annotation],
+    img_size: (f32, f32),
+    export: &ExportConfig,
+    label_path: &Path,
+    state_path: &Path,
+    create_dirs: bool,
+) -> Result<(), String> {
+    ensure_parent_dir(label_path, create_dirs, "Label")?;
+    let yolo_lines = yolo_label_lines(anns, img_size, export);
+    std::fs::write(label_path, yolo_lines.join("\n"))
+        .map_err(|e| format!("Write labels {}: {e}", label_path.display()))?;
 
-        for a in anns.iter() {
-            if a.state == "Rejected" {
+    let stored: Vec<StoredAnnotation> = anns.iter().map(ann_to_stored).collect();
+    let json =
+        serde_json::to_string_pretty(&stored).map_err(|e| format!("Serialize state: {e}"))?;
+    ensure_parent_dir(state_path, create_dirs, "State")?;
+    std::fs::write(state_path, json)
+        .map_err(|e| format!("Write state {}: {e}", state_path.display()))
+}
+
+/// YOLO label lines of one frame: boxes as detection lines and polygon
+/// outlines as YOLO-seg lines (holes are dropped), normalized by `img_size`,
+/// with the 0-based working class and filtered by `export.state_filter`
+pub fn yolo_label_lines(
+    anns: &[Annotation],
+    img_size: (f32, f32),
+    export: &ExportConfig,
+) -> Vec<String> {
+    let mut yolo_rows = Vec::new();
+    for a in anns.iter() {
+        if !passes_state_filter(&a.state, &export.state_filter) {
+            continue;
+        }
+        let cls = (a.class - 1).max(0);
+        if a.r#type == "bbox" || a.r#type == "rbbox" {
+            let (cx, cy, w, h) = normalize_box((a.x, a.y, a.width, a.height), img_size);
+            let w = w.clamp(0.0, 1.0);
+            let h = h.clamp(0.0, 1.0);
+            let line = format_yolo_line(cls, (cx, cy, w, h), export.yolo_precision);
+            yolo_rows.push((cls, (cx, cy, w, h), line));
+        } else if a.r#type == "polygon" {
+            let norm = |v: f32, size: f32| (v / size).clamp(0.0, 1.0);
+            let points: Vec<(f32, f32)> = polygon_points(a)
+                .into_iter()
+                .map(|(x, y)| (norm(x, img_size.0), norm(y, img_size.1)))
+                .collect();
+            if points.len() < MIN_RING_VERTICES {
                 continue;
             }
-            if a.r#type == "bbox" || a.r#type == "rbbox" {
-                let cx = (a.x + a.width / 2.0) / img_size.0;
-                let cy = (a.y + a.height / 2.0) / img_size.1;
-                let w = (a.width / img_size.0).clamp(0.0, 1.0);
-                let h = (a.height / img_size.1).clamp(0.0, 1.0);
-                let cls = (a.class - 1).max(0);
-                yolo_lines.push(format!("{cls} {cx} {cy} {w} {h}"));
+            let mut points = points;
+            if points.len() == OBB_CORNERS {
+                // A closed quad has 11 columns, so it can't be read back as an OBB line
+                points.push(points[0]);
             }
+            let (x0, y0, x1, y1) = points.iter().fold((1.0f32, 1.0f32, 0.0f32, 0.0f32), |b, p| {
+                (b.0.min(p.0), b.1.min(p.1), b.2.max(p.0), b.3.max(p.1))
+            });
+            let key = ((x0 + x1) / 2.0, (y0 + y1) / 2.0, x1 - x0, y1 - y0);
+            let line = format_yolo_seg_line(cls, &points, export.yolo_precision);
+            yolo_rows.push((cls, key, line));
         }
-        std::fs::write(&label_path, yolo_lines.join("\n"))
-            .map_err(|e| format!("Write labels {}: {e}", label_path.display()))?;
-
-        // Write state file with all annotations
-        let state_path = state_path_for(entry);
-        let stored: Vec<StoredAnnotation> = anns.iter().map(ann_to_stored).collect();
-        let json =
-            serde_json::to_string_pretty(&stored).map_err(|e| format!("Serialize state: {e}"))?;
-        if let Some(parent) = state_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| format!("State dir create: {e}"))?;
+    }
+    if export.sort_yolo_lines {
+        sort_yolo_rows(&mut yolo_rows);
+    }
+    yolo_rows.into_iter().map(|(.., line)| line).collect()
+}
+
+/// One YOLO label line `class cx cy w h`, with `precision` decimal places or
+/// the shortest exact form of each number when `None`
+pub fn format_yolo_line(
+    class: i32,
+    yolo: (f32, f32, f32, f32),
+    precision: Option<usize>,
+) -> String {
+    let (cx, cy, w, h) = yolo;
+    match precision {
+        Some(p) => format!("{class} {cx:.p$} {cy:.p$} {w:.p$} {h:.p$}"),
+        None => format!("{class} {cx} {cy} {w} {h}"),
+    }
+}
+
+/// One YOLO-seg label line: 0-based `class` then each normalized outline
+/// vertex as `x y`, with `precision` as in [`format_yolo_line`]
+pub fn format_yolo_seg_line(class: i32, points: &[(f32, f32)], precision: Option<usize>) -> String {
+    let mut line = class.to_string();
+    for (x, y) in points {
+        match precision {
+            Some(p) => line.push_str(&format!(" {x:.p$} {y:.p$}")),
+            None => line.push_str(&format!(" {x} {y}")),
         }
-        std::fs::write(&state_path, json)
-            .map_err(|e| format!("Write state {}: {e}", state_path.display()))?;
+    }
+    line
+}
+
+/// Make sure the folder of `path` exists, creating it only if `create` allows
+fn ensure_parent_dir(path: &Path, create: bool, what: &str) -> Result<(), String> {
+    let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+    if create {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{what} dir create: {e}"))
+    } else if parent.is_dir() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{what} folder {} does not exist (export.create_missing_dirs is off)",
+            parent.display()
+        ))
+    }
+}
+
+/// Prefix of the timestamped folders written by `save_backup`
+pub const BACKUP_DIR_PREFIX: &str = "autosave-";
+
+/// Write the labels and state files of every loaded frame, and the progress
+/// sidecar, into a new timestamped folder under `backup_root`, leaving the
+/// working files alone. Paths keep their layout relative to the manifest's
+/// folder. Only the newest `keep` backup folders are kept. Returns the folder
+/// written, or `None` when nothing changed since the last backup.
+pub fn save_backup(
+    ds: &mut DatasetState,
+    export: &ExportConfig,
+    backup_root: &Path,
+    keep: usize,
+) -> Result<Option<PathBuf>, String> {
+    if !ds.changed_since_backup() {
+        return Ok(None);
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+    let folder = backup_root.join(format!("{BACKUP_DIR_PREFIX}{stamp}"));
+    let base = ds.manifest_path.as_ref().and_then(|p| p.parent());
+    let in_backup = |path: PathBuf| match base.and_then(|b| path.strip_prefix(b).ok()) {
+        Some(relative) => folder.join(relative),
+        None => folder.join(path.file_name().unwrap_or_default()),
+    };
+
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        let Some(Some(anns)) = ds.stored_annotations.get(idx) else { continue };
+        let label_path = in_backup(label_path_for(entry, &ds.label_extension));
+        let state_path = in_backup(state_path_for(entry, &ds.label_extension));
+        let img_size = frame_image_size(ds, idx, entry);
+        // The backup folder is new, so its subfolders are always created
+        write_frame_files(anns, img_size, export, &label_path, &state_path, true)?;
+    }
+    fs::create_dir_all(&folder).map_err(|e| format!("Backup dir create: {e}"))?;
+    if let Some(manifest) = &ds.manifest_path {
+        write_progress(&ds.progress(), &in_backup(progress_path_for(manifest)))?;
+    }
+    prune_backups(backup_root, keep.max(1))?;
+    ds.mark_backed_up();
+    Ok(Some(folder))
+}
+
+/// Delete all but the newest `keep` backup folders (their names sort by time)
+fn prune_backups(backup_root: &Path, keep: usize) -> Result<(), String> {
+    let mut folders: Vec<PathBuf> = fs::read_dir(backup_root)
+        .map_err(|e| format!("Read backup dir: {e}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(BACKUP_DIR_PREFIX))
+        })
+        .collect();
+    folders.sort();
+    let excess = folders.len().saturating_sub(keep);
+    for old in &folders[..excess] {
+        fs::remove_dir_all(old).map_err(|e| format!("Remove old backup {}: {e}", old.display()))?;
     }
     Ok(())
 }
@@ -310,23 +921,141 @@ pub fn get_view_state(ui: &AppWindow) -> ViewState {
     ViewState {
         pan_x: ui.get_view_pan_x(),
         pan_y: ui.get_view_pan_y(),
-        zoom: ui.get_view_zoom().max(0.1),
+        zoom: zoom_limits(ui).clamp(ui.get_view_zoom()),
     }
 }
 
-/// Apply a view state to the UI
+/// Apply a view state to the UI, clamped to the zoom limits and canvas
 pub fn apply_view_state(ui: &AppWindow, vs: &ViewState) {
     let safe_zoom = if vs.zoom <= 0.0 || !vs.zoom.is_finite() {
         1.0
     } else {
         vs.zoom
     };
+    let img = ui.get_image_source().size();
+    let vs = clamp_view(
+        &ViewState { zoom: safe_zoom, ..vs.clone() },
+        (img.width as f32, img.height as f32),
+        canvas_size(ui),
+        zoom_limits(ui),
+    );
     ui.set_view_pan_x(vs.pan_x);
     ui.set_view_pan_y(vs.pan_y);
-    ui.set_view_zoom(safe_zoom);
+    ui.set_view_zoom(vs.zoom);
 
     // Also push into global view change callback to keep downstream state coherent
-    ui.invoke_view_changed(vs.pan_x, vs.pan_y, safe_zoom);
+    ui.invoke_view_changed(vs.pan_x, vs.pan_y, vs.zoom);
+}
+
+/// Default zoom limits (`appearance.min_zoom` / `max_zoom`)
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 10.0;
+
+/// Screen px of the image that panning must leave inside the canvas
+pub const MIN_VISIBLE_PX: f32 = 40.0;
+
+/// Allowed zoom range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomLimits {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for ZoomLimits {
+    fn default() -> Self {
+        Self { min: MIN_ZOOM, max: MAX_ZOOM }
+    }
+}
+
+impl ZoomLimits {
+    /// Limits from config; invalid values fall back to the defaults
+    pub fn from_config(cfg: &AppearanceConfig) -> Self {
+        let min = if cfg.min_zoom > 0.0 && cfg.min_zoom.is_finite() { cfg.min_zoom } else { MIN_ZOOM };
+        let max = if cfg.max_zoom.is_finite() { cfg.max_zoom.max(min) } else { MAX_ZOOM.max(min) };
+        Self { min, max }
+    }
+
+    pub fn clamp(&self, zoom: f32) -> f32 {
+        zoom.clamp(self.min, self.max)
+    }
+}
+
+/// Zoom limits pushed into the UI at startup
+pub fn zoom_limits(ui: &AppWindow) -> ZoomLimits {
+    let min = ui.get_min_zoom();
+    let max = ui.get_max_zoom();
+    if min > 0.0 && max >= min { ZoomLimits { min, max } } else { ZoomLimits::default() }
+}
+
+/// Size of the canvas area in logical px
+pub fn canvas_size(ui: &AppWindow) -> (f32, f32) {
+    (ui.get_canvas_width(), ui.get_canvas_height())
+}
+
+/// Keep `pan` so that at least `MIN_VISIBLE_PX` of an image `extent` px long
+/// overlaps a canvas `canvas` px long (less if the image or canvas is smaller)
+fn clamp_pan(pan: f32, extent: f32, canvas: f32) -> f32 {
+    if extent <= 0.0 || canvas <= 0.0 {
+        return pan;
+    }
+    let margin = MIN_VISIBLE_PX.min(extent).min(canvas);
+    pan.clamp(margin - extent, canvas - margin)
+}
+
+/// Clamp zoom to `limits` and pull the pan back so part of the image stays visible.
+/// An unknown (zero) image or canvas size leaves the pan untouched.
+pub fn clamp_view(
+    view: &ViewState,
+    img_size: (f32, f32),
+    canvas: (f32, f32),
+    limits: ZoomLimits,
+) -> ViewState {
+    let zoom = limits.clamp(view.zoom);
+    ViewState {
+        pan_x: clamp_pan(view.pan_x, img_size.0 * zoom, canvas.0),
+        pan_y: clamp_pan(view.pan_y, img_size.1 * zoom, canvas.1),
+        zoom,
+    }
+}
+
+/// Zoom by `factor` while keeping the image point under `cursor` (screen px) fixed
+pub fn zoom_at(view: &ViewState, cursor: (f32, f32), factor: f32, limits: ZoomLimits) -> ViewState {
+    let old_zoom = view.zoom.max(limits.min);
+    let new_zoom = limits.clamp(old_zoom * factor);
+    let anchor = screen_to_image(&ViewState { zoom: old_zoom, ..view.clone() }, cursor);
+    // Where the anchor lands unpanned is how far the pan must shift it back
+    let unpanned = image_to_screen(&ViewState { pan_x: 0.0, pan_y: 0.0, zoom: new_zoom }, anchor);
+    ViewState { pan_x: cursor.0 - unpanned.0, pan_y: cursor.1 - unpanned.1, zoom: new_zoom }
+}
+
+/// View that centres the image region `(x0, y0, x1, y1)` on the canvas with at
+/// least `margin` px to spare on every side. The zoom stays within `limits`, so
+/// tiny regions such as a single point stop at the maximum zoom.
+pub fn frame_view(
+    region: (f32, f32, f32, f32),
+    canvas: (f32, f32),
+    margin: f32,
+    limits: ZoomLimits,
+) -> ViewState {
+    let (x0, y0, x1, y1) = region;
+    let room_w = (canvas.0 - 2.0 * margin).max(1.0);
+    let room_h = (canvas.1 - 2.0 * margin).max(1.0);
+    let fit_w = room_w / (x1 - x0).max(f32::EPSILON);
+    let fit_h = room_h / (y1 - y0).max(f32::EPSILON);
+    let zoom = limits.clamp(fit_w.min(fit_h));
+    let center = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+    let unpanned = image_to_screen(&ViewState { pan_x: 0.0, pan_y: 0.0, zoom }, center);
+    ViewState { pan_x: canvas.0 / 2.0 - unpanned.0, pan_y: canvas.1 / 2.0 - unpanned.1, zoom }
+}
+
+/// Limits of a point marker's radius in image pixels
+pub const MIN_POINT_RADIUS: f32 = 1.0;
+pub const MAX_POINT_RADIUS: f32 = 40.0;
+
+/// Image-space radius for point markers: `base` screen px divided by `zoom`,
+/// so markers keep their on-screen size until the clamp takes over
+pub fn point_display_radius(base: f32, zoom: f32) -> f32 {
+    (base / zoom.max(MIN_ZOOM)).clamp(MIN_POINT_RADIUS, MAX_POINT_RADIUS)
 }
 
 /// Check if two sizes are close within a tolerance
@@ -334,6 +1063,23 @@ pub fn sizes_close(a: (f32, f32), b: (f32, f32), tolerance: f32) -> bool {
     (a.0 - b.0).abs() <= tolerance && (a.1 - b.1).abs() <= tolerance
 }
 
+/// Append a newly drawn annotation. With `select`, it becomes the only
+/// selected annotation so it can be resized or classified straight away.
+pub fn push_created(model: &slint::VecModel<Annotation>, mut ann: Annotation, select: bool) {
+    if select {
+        for i in 0..model.row_count() {
+            if let Some(mut other) = model.row_data(i) {
+                if other.selected {
+                    other.selected = false;
+                    model.set_row_data(i, other);
+                }
+            }
+        }
+    }
+    ann.selected = select;
+    model.push(ann);
+}
+
 /// Replace all annotations in a VecModel
 pub fn replace_annotations(model: &slint::VecModel<Annotation>, anns: Vec<Annotation>) {
     for _ in (0..model.row_count()).rev() {
@@ -364,26 +1110,56 @@ pub fn next_id_from_annotations(anns: &[Annotation], default_start: i32) -> i32
         .unwrap_or(default_start)
 }
 
-/// Parse a vertices string into PolygonVertex array
-pub fn parse_vertices(vertices_str: &str) -> Vec<PolygonVertex> {
+/// Separates a polygon's outline from its holes in the vertices string
+/// (`"x,y;x,y;...|x,y;..."`); strings without it are a single ring
+pub const RING_SEPARATOR: char = '|';
+
+/// Parse a vertices string into rings of `(x, y)`: the outline first, then any holes
+pub fn parse_rings(vertices_str: &str) -> Vec<Vec<(f32, f32)>> {
     vertices_str
-        .split(';')
-        .filter(|s| !s.is_empty())
-        .filter_map(|pair| {
+        .split(RING_SEPARATOR)
+        .map(|ring| parse_vertices(ring).iter().map(|v| (v.x, v.y)).collect::<Vec<_>>())
+        .filter(|ring| !ring.is_empty())
+        .collect()
+}
+
+/// Build a vertices string from rings (inverse of `parse_rings`)
+pub fn format_rings(rings: &[Vec<(f32, f32)>]) -> String {
+    rings
+        .iter()
+        .map(|ring| {
+            ring.iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(";")
+        })
+        .collect::<Vec<_>>()
+        .join(&RING_SEPARATOR.to_string())
+}
+
+/// Parse a vertices string into PolygonVertex array (vertices of every ring,
+/// each knowing where its ring starts in the array)
+pub fn parse_vertices(vertices_str: &str) -> Vec<PolygonVertex> {
+    let mut vertices = Vec::new();
+    for ring in vertices_str.split(RING_SEPARATOR) {
+        let ring_start = vertices.len() as i32;
+        vertices.extend(ring.split(';').filter(|s| !s.is_empty()).filter_map(|pair| {
             let parts: Vec<&str> = pair.split(',').collect();
             if parts.len() == 2 {
                 let x = parts[0].parse::<f32>().ok()?;
                 let y = parts[1].parse::<f32>().ok()?;
-                Some(PolygonVertex { x, y })
+                Some(PolygonVertex { x, y, ring_start })
             } else {
                 None
             }
-        })
-        .collect()
+        }));
+    }
+    vertices
 }
 
-/// Generate SVG path commands from vertices
-pub fn generate_path_commands(vertices: &[(f32, f32)]) -> String {
+/// Generate SVG path commands from vertices. `closed` adds a `Z` back to the
+/// first vertex; the preview of a polygon still being drawn stays open.
+pub fn generate_path_commands(vertices: &[(f32, f32)], closed: bool) -> String {
     if vertices.is_empty() {
         return String::new();
     }
@@ -394,6 +1170,882 @@ pub fn generate_path_commands(vertices: &[(f32, f32)]) -> String {
         commands.push_str(&format!(" L {} {}", vertex.0, vertex.1));
     }
 
-    commands.push_str(" Z"); // Close path
+    if closed {
+        commands.push_str(" Z");
+    }
     commands
 }
+
+/// Path commands for a polygon with holes: one closed subpath per ring, so an
+/// even-odd fill leaves the holes empty
+pub fn generate_rings_path_commands(rings: &[Vec<(f32, f32)>]) -> String {
+    rings
+        .iter()
+        .filter(|ring| !ring.is_empty())
+        .map(|ring| generate_path_commands(ring, true))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::edits::annotations_extent;
+    use crate::utils::scratch_dir;
+
+    #[test]
+    fn test_create_dataset_recurses_into_subfolders() {
+        let root = scratch_dir("nested_dataset");
+        fs::create_dir_all(root.join("day/cam1")).unwrap();
+        fs::create_dir_all(root.join("night")).unwrap();
+        for rel in ["a.png", "day/b.jpg", "day/cam1/c.png", "night/d.JPG", "night/notes.txt"] {
+            fs::write(root.join(rel), b"").unwrap();
+        }
+
+        let manifest_path = create_dataset_from_folder(&root, None, 8, "txt").unwrap();
+        let manifest: DatasetFile =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        let images: Vec<&str> = manifest.images.iter().map(|e| e.image.as_str()).collect();
+        assert_eq!(images, vec!["a.png", "day/b.jpg", "day/cam1/c.png", "night/d.JPG"]);
+        assert_eq!(manifest.images[2].labels.as_deref(), Some("day/cam1/c.txt"));
+
+        // Paths resolve relative to the manifest when loaded
+        let ds = load_dataset(&manifest_path).unwrap();
+        assert_eq!(ds.entries[2].image_path, root.join("day/cam1/c.png"));
+
+        // Depth limit stops the walk
+        let shallow = create_dataset_from_folder(&root, None, 0, "txt").unwrap();
+        let manifest: DatasetFile =
+            serde_json::from_str(&fs::read_to_string(&shallow).unwrap()).unwrap();
+        assert_eq!(manifest.images.len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    fn test_state(n: usize) -> DatasetState {
+        let entries = (0..n)
+            .map(|i| DatasetEntry {
+                image_path: PathBuf::from(format!("img{i}.png")),
+                labels_path: None,
+            })
+            .collect();
+        DatasetState::new(entries, None)
+    }
+
+    fn test_box(id: i32, class: i32) -> Annotation {
+        Annotation {
+            id,
+            r#type: "bbox".into(),
+            x: 10.0 * id as f32,
+            y: 5.0,
+            width: 20.0,
+            height: 20.0,
+            class,
+            state: "Pending".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_custom_label_extension_round_trips() {
+        let dir = scratch_dir("label_ext");
+        fs::write(dir.join("a.png"), b"").unwrap();
+
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, ".label").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        assert_eq!(ds.entries[0].labels_path, Some(dir.join("a.label")));
+
+        // Entries without an explicit labels path fall back to the configured extension
+        ds.entries[0].labels_path = None;
+        ds.label_extension = "label".into();
+        assert_eq!(label_path_for(&ds.entries[0], "label"), dir.join("a.label"));
+        assert_eq!(state_path_for(&ds.entries[0], "label"), dir.join("a.state.json"));
+
+        ds.stored_annotations = vec![Some(vec![test_box(1, 2)])];
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+        assert!(dir.join("a.label").exists());
+        assert!(dir.join("a.state.json").exists());
+        assert!(!dir.join("a.txt").exists());
+
+        // Reads prefer the sidecar, then the label file with the same extension
+        assert_eq!(load_yolo_annotations(&ds.entries[0], (1.0, 1.0), 0, "label").len(), 1);
+        fs::remove_file(dir.join("a.state.json")).unwrap();
+        let from_labels = load_yolo_annotations(&ds.entries[0], (1.0, 1.0), 0, "label");
+        assert_eq!(from_labels.len(), 1);
+        assert_eq!(from_labels[0].class, 2);
+        assert!(load_yolo_annotations(&ds.entries[0], (1.0, 1.0), 0, "txt").is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_startup_load_uses_configured_label_extension() {
+        let dir = scratch_dir("label_ext_startup");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("a.label"), "1 0.5 0.5 0.2 0.2\n").unwrap();
+        let manifest = r#"{"images":[{"image":"a.png"}]}"#;
+        fs::write(dir.join("manifest.json"), manifest).unwrap();
+
+        // A manifest reopened at startup picks up the configured extension
+        let mut ds = load_dataset(&dir.join("manifest.json")).unwrap();
+        ds.set_label_extension(".label");
+        assert_eq!(ds.label_extension, "label");
+        let anns = load_yolo_annotations(&ds.entries[0], (1.0, 1.0), 0, &ds.label_extension);
+        assert_eq!(anns.len(), 1);
+        assert_eq!(anns[0].class, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_writes_to_custom_label_paths() {
+        let dir = scratch_dir("custom_labels");
+        fs::create_dir_all(dir.join("images/cam1")).unwrap();
+        fs::write(dir.join("images/a.png"), b"").unwrap();
+        fs::write(dir.join("images/cam1/b.png"), b"").unwrap();
+        let manifest_path = dir.join("manifest.json");
+        fs::write(
+            &manifest_path,
+            r#"{
+                "labels_dir": "yolo",
+                "images": [
+                    { "image": "images/a.png", "labels": "elsewhere/first.txt" },
+                    { "image": "images/cam1/b.png" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.set_label_extension("txt");
+        assert_eq!(ds.entries[0].labels_path, Some(dir.join("elsewhere/first.txt")));
+        assert_eq!(ds.entries[1].labels_path, Some(dir.join("yolo/images/cam1/b.txt")));
+
+        ds.ensure_len();
+        ds.stored_annotations = vec![Some(vec![test_box(1, 1)]), Some(vec![test_box(2, 2)])];
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+        assert!(dir.join("elsewhere/first.txt").exists());
+        assert!(dir.join("elsewhere/first.state.json").exists());
+        assert!(dir.join("yolo/images/cam1/b.txt").exists());
+        assert!(!dir.join("images/a.txt").exists());
+        assert!(!dir.join("images/cam1/b.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_yolo_line_precision() {
+        let yolo = (0.5, 1.0 / 3.0, 0.125, 0.2);
+        assert_eq!(format_yolo_line(2, yolo, None), "2 0.5 0.33333334 0.125 0.2");
+        assert_eq!(format_yolo_line(2, yolo, Some(4)), "2 0.5000 0.3333 0.1250 0.2000");
+        // A move below the precision leaves the line unchanged
+        let nudged = (0.5 + 1e-7, 1.0 / 3.0, 0.125, 0.2);
+        assert_eq!(format_yolo_line(2, nudged, Some(6)), format_yolo_line(2, yolo, Some(6)));
+    }
+
+    #[test]
+    fn test_polygon_saves_as_normalized_yolo_seg_line() {
+        let mut triangle = Annotation {
+            r#type: "polygon".into(),
+            class: 3,
+            state: "Manual".into(),
+            ..Default::default()
+        };
+        set_polygon_points(&mut triangle, &[(20.0, 10.0), (200.0, 50.0), (100.0, 100.0)]);
+        let rejected = Annotation { state: "Rejected".into(), ..triangle.clone() };
+        let export = ExportConfig::default();
+
+        let lines = yolo_label_lines(&[triangle, rejected], (200.0, 100.0), &export);
+        // Class 3 is written as 2; x by the width, y by the height
+        assert_eq!(lines, vec!["2 0.1 0.1 1 0.5 0.5 1"]);
+    }
+
+    #[test]
+    fn test_polygons_reload_from_label_file_without_sidecar() {
+        let dir = scratch_dir("seg_round_trip");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.image_sizes[0] = Some((200.0, 100.0));
+        let polygon = |class: i32, points: &[(f32, f32)]| {
+            let mut ann = Annotation { r#type: "polygon".into(), class, ..Default::default() };
+            set_polygon_points(&mut ann, points);
+            ann
+        };
+        let triangle = polygon(3, &[(20.0, 10.0), (200.0, 50.0), (100.0, 100.0)]);
+        let quad = polygon(2, &[(20.0, 10.0), (180.0, 20.0), (160.0, 90.0), (40.0, 80.0)]);
+        ds.stored_annotations[0] = Some(vec![triangle, quad, test_box(1, 4)]);
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+        let text = fs::read_to_string(dir.join("a.txt")).unwrap();
+        // The quad is written closed so it isn't mistaken for an OBB line
+        assert_eq!(text.lines().nth(1).unwrap().split_whitespace().count(), 11);
+
+        fs::remove_file(dir.join("a.state.json")).unwrap();
+        let reloaded = load_yolo_annotations(&ds.entries[0], (200.0, 100.0), 0, "txt");
+        let kinds: Vec<(&str, i32, usize)> = reloaded
+            .iter()
+            .map(|a| (a.r#type.as_str(), a.class, polygon_points(a).len()))
+            .collect();
+        assert_eq!(kinds, vec![("polygon", 3, 3), ("polygon", 2, 4), ("bbox", 4, 0)]);
+        let quad_points = polygon_points(&reloaded[1]);
+        assert!((quad_points[2].0 - 160.0).abs() < 1e-3 && (quad_points[2].1 - 90.0).abs() < 1e-3);
+
+        // A nine-column line is still an oriented box
+        fs::write(dir.join("a.txt"), "0 0.1 0.1 0.5 0.1 0.5 0.3 0.1 0.3").unwrap();
+        let obb = load_yolo_annotations(&ds.entries[0], (200.0, 100.0), 0, "txt");
+        assert_eq!(obb[0].r#type, "rbbox");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_without_create_missing_dirs_fails() {
+        let dir = scratch_dir("strict_dirs");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let mut ds = DatasetState::new(
+            vec![DatasetEntry {
+                image_path: dir.join("a.png"),
+                labels_path: Some(dir.join("typo/a.txt")),
+            }],
+            None,
+        );
+        ds.stored_annotations = vec![Some(vec![test_box(1, 1)])];
+
+        let strict = ExportConfig { create_missing_dirs: false, ..Default::default() };
+        let err = save_all(&mut ds, &strict).unwrap_err();
+        assert!(err.contains("typo"), "{err}");
+        assert!(!dir.join("typo").exists());
+
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+        assert!(dir.join("typo/a.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_backup_leaves_working_files_alone() {
+        let dir = scratch_dir("backup");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        fs::write(dir.join("a.txt"), "0 0.5 0.5 0.2 0.2").unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![test_box(1, 3)]);
+        let backups = dir.join("backups");
+        let export = ExportConfig::default();
+
+        let folder = save_backup(&mut ds, &export, &backups, 2).unwrap().unwrap();
+        assert!(folder.starts_with(&backups));
+        assert!(fs::read_to_string(folder.join("a.txt")).unwrap().starts_with("2 "));
+        assert!(folder.join("a.state.json").exists());
+        let progress = progress_path_for(&manifest_path);
+        assert!(folder.join(progress.file_name().unwrap()).exists());
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "0 0.5 0.5 0.2 0.2");
+        assert!(!dir.join("a.state.json").exists());
+
+        // Nothing changed, so no new backup
+        assert_eq!(save_backup(&mut ds, &export, &backups, 2).unwrap(), None);
+
+        // A progress-only change is backed up; only the newest `keep` backups remain
+        ds.completed_frames[0] = true;
+        assert!(save_backup(&mut ds, &export, &backups, 2).unwrap().is_some());
+        for class in 4..6 {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            ds.stored_annotations[0] = Some(vec![test_box(1, class)]);
+            assert!(save_backup(&mut ds, &export, &backups, 2).unwrap().is_some());
+        }
+        assert_eq!(fs::read_dir(&backups).unwrap().count(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_all_leaves_unvisited_labels_untouched() {
+        let dir = scratch_dir("unvisited");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("b.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        fs::write(dir.join("b.txt"), "0 0.5 0.5 0.2 0.2").unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![test_box(1, 1)]);
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+
+        assert!(dir.join("a.txt").exists());
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "0 0.5 0.5 0.2 0.2");
+        assert!(!dir.join("b.state.json").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_saved_labels_keep_working_classes_under_export_map() {
+        let dir = scratch_dir("export_map_save");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.image_sizes[0] = Some((100.0, 100.0));
+        ds.stored_annotations[0] = Some(vec![test_box(1, 3), test_box(2, 1)]);
+
+        let mut export = ExportConfig::default();
+        export.class_export_map.insert(3, 2);
+        export.class_export_map.insert(1, 5);
+        save_all(&mut ds, &export).unwrap();
+
+        // Reloading from the label file alone gives back the working classes
+        fs::remove_file(dir.join("a.state.json")).unwrap();
+        let reloaded = load_yolo_annotations(&ds.entries[0], (100.0, 100.0), 0, "txt");
+        let classes: Vec<i32> = reloaded.iter().map(|a| a.class).collect();
+        assert_eq!(classes, vec![3, 1]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_max_track_id_covers_unvisited_frames_and_persists() {
+        let dir = scratch_dir("track_ids");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("b.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[1] = Some(vec![Annotation { group_id: 7, ..test_box(1, 1) }]);
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+
+        // A new session has only frame a cached; b's track comes from its files
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![Annotation { group_id: 2, ..test_box(1, 1) }]);
+        assert_eq!(dataset_max_track_id(&mut ds), 7);
+
+        ds.max_track_id = Some(9);
+        save_progress(&ds).unwrap();
+        let mut reloaded = load_dataset(&manifest_path).unwrap();
+        assert_eq!(dataset_max_track_id(&mut reloaded), 9);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_leave_frame_with_flush_writes_only_that_frame() {
+        let dir = scratch_dir("save_on_navigate");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("b.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.stored_annotations[1] = Some(vec![test_box(1, 1)]);
+        let export = ExportConfig::default();
+
+        // Without the flag only the cache changes
+        leave_frame(&mut ds, vec![test_box(2, 1)], ViewState::default(), (1.0, 1.0), None)
+            .unwrap();
+        assert!(!dir.join("a.txt").exists());
+
+        let anns = vec![test_box(2, 1), test_box(3, 2)];
+        leave_frame(&mut ds, anns, ViewState::default(), (1.0, 1.0), Some(&export)).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap().lines().count(), 2);
+        assert!(dir.join("a.state.json").exists());
+        // The other cached frame waits for the next full save
+        assert!(!dir.join("b.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_all_accepting_pending_leaves_none_in_state_file() {
+        let dir = scratch_dir("accept_pending");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        let rejected = Annotation { state: "Rejected".into(), ..test_box(3, 1) };
+        ds.stored_annotations[0] = Some(vec![test_box(1, 1), test_box(2, 2), rejected]);
+
+        let export = ExportConfig { accept_pending_on_save: true, ..Default::default() };
+        save_all(&mut ds, &export).unwrap();
+
+        let stored: Vec<StoredAnnotation> =
+            serde_json::from_str(&fs::read_to_string(dir.join("a.state.json")).unwrap()).unwrap();
+        let states: Vec<&str> = stored.iter().map(|s| s.state.as_str()).collect();
+        assert_eq!(states, vec!["Accepted", "Accepted", "Rejected"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_all_normalizes_by_cached_image_size_without_reading_image() {
+        let dir = scratch_dir("image_size_cache");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("b.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        // Neither image can be decoded, so only the cache can give a size
+        fs::remove_file(dir.join("a.png")).unwrap();
+        ds.image_sizes[0] = Some((200.0, 100.0));
+        ds.stored_annotations[0] = Some(vec![test_box(2, 1)]);
+        ds.stored_annotations[1] = Some(Vec::new());
+
+        save_all(&mut ds, &ExportConfig::default()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "0 0.15 0.15 0.1 0.2");
+        assert_eq!(ds.image_sizes, vec![Some((200.0, 100.0)), None]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sorted_yolo_lines_ignore_insertion_order() {
+        let dir = scratch_dir("sort_yolo");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        let export = ExportConfig { sort_yolo_lines: true, ..Default::default() };
+        let anns = vec![test_box(3, 2), test_box(1, 2), test_box(2, 1), test_box(4, 1)];
+
+        let mut saved = Vec::new();
+        for order in [anns.clone(), anns.into_iter().rev().collect()] {
+            ds.stored_annotations[0] = Some(order);
+            save_all(&mut ds, &export).unwrap();
+            saved.push(fs::read_to_string(dir.join("a.txt")).unwrap());
+        }
+        assert_eq!(saved[0], saved[1]);
+        // Class 1 (YOLO 0) first, each class left to right
+        let first_columns: Vec<(&str, &str)> = saved[0]
+            .lines()
+            .map(|l| {
+                let mut cols = l.split(' ');
+                (cols.next().unwrap(), cols.next().unwrap())
+            })
+            .collect();
+        assert_eq!(first_columns, vec![("0", "30"), ("0", "50"), ("1", "20"), ("1", "40")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_store_frame_state_fills_empty_caches() {
+        let mut ds = test_state(3);
+        ds.current_index = 2;
+        assert!(ds.stored_annotations.is_empty());
+
+        let view = ViewState { pan_x: 1.0, pan_y: 2.0, zoom: 1.5 };
+        store_frame_state(&mut ds, vec![test_box(1, 1)], view, (64.0, 48.0));
+
+        assert_eq!(ds.stored_annotations.len(), 3);
+        assert_eq!(ds.view_states.len(), 3);
+        assert_eq!(ds.completed_frames, vec![false; 3]);
+        assert_eq!(ds.stored_annotations[2].as_ref().map(|a| a.len()), Some(1));
+        assert_eq!(ds.last_view_image_size, Some((64.0, 48.0)));
+    }
+
+    #[test]
+    fn test_view_bookmarks_persist_in_progress_sidecar() {
+        let dir = scratch_dir("bookmarks");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        let view = ViewState { pan_x: -12.25, pan_y: 40.5, zoom: 2.75 };
+        assert!(ds.set_bookmark(1, view.clone()));
+        assert!(!ds.set_bookmark(BOOKMARK_SLOTS, view.clone()));
+        save_progress(&ds).unwrap();
+        assert!(progress_path_for(&manifest_path).exists());
+
+        let reopened = load_dataset(&manifest_path).unwrap();
+        assert_eq!(reopened.bookmark(1), Some(&view));
+        // Empty and out-of-range slots recall nothing
+        assert_eq!(reopened.bookmark(0), None);
+        assert_eq!(reopened.bookmark(BOOKMARK_SLOTS), None);
+        // The sidecar is not mistaken for another manifest
+        assert_eq!(sibling_manifests(&manifest_path), vec![manifest_path.clone()]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reviewed_and_completed_flags_are_independent_and_persist() {
+        let dir = scratch_dir("reviewed");
+        for name in ["a.png", "b.png", "c.png", "d.png"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        ds.ensure_len();
+        ds.completed_frames[0] = true;
+        ds.reviewed_frames[1] = true;
+        ds.completed_frames[2] = true;
+        ds.reviewed_frames[2] = true;
+        assert_eq!(ds.reviewed_percent(), 50.0);
+        save_progress(&ds).unwrap();
+
+        let reopened = load_dataset(&manifest_path).unwrap();
+        assert_eq!(reopened.completed_frames, vec![true, false, true, false]);
+        assert_eq!(reopened.reviewed_frames, vec![false, true, true, false]);
+        assert!(!reopened.is_reviewed(0));
+        assert!(reopened.is_reviewed(1));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_image_tags_persist_per_image() {
+        let dir = scratch_dir("image_tags");
+        fs::write(dir.join("a.png"), b"").unwrap();
+        fs::write(dir.join("b.png"), b"").unwrap();
+        let manifest_path = create_dataset_from_folder(&dir, None, 0, "txt").unwrap();
+
+        let mut ds = load_dataset(&manifest_path).unwrap();
+        assert!(ds.add_image_tag(1, " rainy "));
+        assert!(ds.add_image_tag(1, "night"));
+        assert!(!ds.add_image_tag(1, "rainy"));
+        assert!(!ds.add_image_tag(0, "  "));
+        assert!(ds.add_image_tag(0, "daytime"));
+        assert!(ds.remove_image_tag(0, "daytime"));
+        assert!(!ds.remove_image_tag(0, "daytime"));
+        save_progress(&ds).unwrap();
+
+        let reopened = load_dataset(&manifest_path).unwrap();
+        assert!(reopened.tags_for(0).is_empty());
+        assert_eq!(reopened.tags_for(1), ["rainy", "night"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_initial_snapshot_survives_edits() {
+        let mut ds = test_state(2);
+        let initial = vec![test_box(1, 1), test_box(2, 2)];
+        ds.record_initial(1, &initial);
+
+        // Edits to the working copy, and later loads, don't touch the snapshot
+        let mut edited = initial.clone();
+        edited[0].class = 4;
+        edited.push(test_box(3, 1));
+        crate::state::reject(&mut edited[1]);
+        ds.record_initial(1, &edited);
+
+        assert_eq!(ds.initial_for(1), Some(initial));
+        assert_eq!(ds.initial_for(0), None);
+    }
+
+    #[test]
+    fn test_sibling_manifests_sorted_json_only() {
+        let dir = scratch_dir("siblings");
+        for name in ["b.json", "a.json", "c.JSON", "a.state.json", "a.progress.json", "notes.txt"] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        fs::create_dir_all(dir.join("sub.json")).unwrap();
+
+        let names: Vec<String> = sibling_manifests(&dir.join("b.json"))
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.json", "b.json", "c.JSON"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_zoom_at_keeps_cursor_point_fixed() {
+        let view = ViewState { pan_x: 30.0, pan_y: -12.0, zoom: 1.5 };
+        let cursor = (200.0, 150.0);
+        let img_pt = screen_to_image(&view, cursor);
+
+        for factor in [1.1, 1.0 / 1.1, 3.0] {
+            let zoomed = zoom_at(&view, cursor, factor, ZoomLimits::default());
+            let after = image_to_screen(&zoomed, img_pt);
+            assert!((after.0 - cursor.0).abs() < 1e-3);
+            assert!((after.1 - cursor.1).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_zoom_at_clamps_zoom() {
+        let view = ViewState { pan_x: 0.0, pan_y: 0.0, zoom: 9.0 };
+        let zoomed = zoom_at(&view, (10.0, 10.0), 2.0, ZoomLimits::default());
+        assert_eq!(zoomed.zoom, MAX_ZOOM);
+        let img_pt = screen_to_image(&view, (10.0, 10.0));
+        let after = image_to_screen(&zoomed, img_pt);
+        assert!((after.0 - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parse_yolo_line_tolerates_whitespace_comments_and_confidence() {
+        let expected = YoloLine {
+            class: 2,
+            cx: 0.5,
+            cy: 0.25,
+            width: 0.1,
+            height: 0.2,
+            confidence: None,
+        };
+        assert_eq!(parse_yolo_line("2 0.5 0.25 0.1 0.2"), Ok(Some(expected)));
+        assert_eq!(parse_yolo_line("  2\t0.5 0.25  0.1 0.2 \r"), Ok(Some(expected)));
+        assert_eq!(
+            parse_yolo_line("2 0.5 0.25 0.1 0.2 0.87"),
+            Ok(Some(YoloLine { confidence: Some(0.87), ..expected }))
+        );
+        assert_eq!(parse_yolo_line(""), Ok(None));
+        assert_eq!(parse_yolo_line(" \r"), Ok(None));
+        assert_eq!(parse_yolo_line("# class cx cy w h"), Ok(None));
+
+        // Truly malformed lines are still reported
+        assert!(parse_yolo_line("2 0.5 0.25 0.1").is_err());
+        assert!(parse_yolo_line("2 0.5 0.25 0.1 0.2 0.9 7").is_err());
+        assert!(parse_yolo_line("car 0.5 0.25 0.1 0.2").is_err());
+        assert!(parse_yolo_line("2 0.5 abc 0.1 0.2").is_err());
+    }
+
+    #[test]
+    fn test_load_yolo_reads_crlf_blank_and_commented_files() {
+        let dir = scratch_dir("yolo_crlf");
+        let entry = DatasetEntry { image_path: dir.join("a.png"), labels_path: None };
+        let text = "# exported by detector\r\n0 0.5 0.5 0.2 0.2\r\n\r\n\
+                    1 0.25 0.25 0.1 0.1 0.9\r\nbroken line\r\n2 0.75 0.75 0.1 0.1";
+        fs::write(dir.join("a.txt"), text).unwrap();
+
+        let anns = load_yolo_annotations(&entry, (100.0, 100.0), LOADED_ID_START, "txt");
+        let classes: Vec<i32> = anns.iter().map(|a| a.class).collect();
+        assert_eq!(classes, vec![1, 2, 3]);
+        assert_eq!((anns[0].x, anns[0].y, anns[0].width), (40.0, 40.0, 20.0));
+        assert_eq!((anns[1].x, anns[1].width), (20.0, 10.0));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_dota_and_yolo_obb_lines_as_rotated_boxes() {
+        let dir = scratch_dir("dota_obb");
+        let entry = DatasetEntry { image_path: dir.join("a.png"), labels_path: None };
+        // A 40x10 box at (10, 20) turned 30° about its center, in DOTA pixels and
+        // YOLO-OBB normalized to a 100x100 image
+        let corners = crate::transform::rbbox_corners((10.0, 20.0, 40.0, 10.0), 30.0);
+        let pixels: Vec<String> = corners.iter().map(|(x, y)| format!("{x} {y}")).collect();
+        let normalized: Vec<String> =
+            corners.iter().map(|(x, y)| format!("{} {}", x / 100.0, y / 100.0)).collect();
+        let text = format!(
+            "imagesource:GoogleEarth\ngsd:0.5\n{} 2 0\n1 {}\n0 0.5 0.5 0.2 0.2\n",
+            pixels.join(" "),
+            normalized.join(" ")
+        );
+        fs::write(dir.join("a.txt"), text).unwrap();
+
+        let anns = load_yolo_annotations(&entry, (100.0, 100.0), 0, "txt");
+        let kinds: Vec<(&str, i32)> = anns.iter().map(|a| (a.r#type.as_str(), a.class)).collect();
+        assert_eq!(kinds, vec![("rbbox", 3), ("rbbox", 2), ("bbox", 1)]);
+        for rbbox in &anns[..2] {
+            assert!((rbbox.x - 10.0).abs() < 1e-3 && (rbbox.y - 20.0).abs() < 1e-3);
+            assert!((rbbox.width - 40.0).abs() < 1e-3 && (rbbox.height - 10.0).abs() < 1e-3);
+            assert!((rbbox.rotation - 30.0).abs() < 1e-3);
+        }
+        assert_eq!((anns[2].x, anns[2].width, anns[2].rotation), (40.0, 20.0, 0.0));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_onion_skin_shows_neighbors_without_touching_current() {
+        let entries = (0..3)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_{i}.png").into(),
+                labels_path: Some(format!("missing_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        let ann = |id: i32, x: f32, state: &str| Annotation {
+            id,
+            r#type: "bbox".into(),
+            x,
+            width: 10.0,
+            height: 10.0,
+            state: state.into(),
+            selected: true,
+            ..Default::default()
+        };
+        ds.stored_annotations[0] = Some(vec![ann(1, 5.0, "Manual"), ann(2, 50.0, "Rejected")]);
+        ds.stored_annotations[1] = Some(vec![ann(3, 8.0, "Pending")]);
+        ds.stored_annotations[2] = Some(vec![ann(4, 12.0, "Accepted")]);
+        let current = ds.stored_annotations[1].clone();
+
+        let ghosts = onion_skin_annotations(&ds, 1, true, false);
+        let ids: Vec<i32> = ghosts.iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![1]);
+        assert!(!ghosts[0].selected);
+        let both = onion_skin_annotations(&ds, 1, true, true);
+        assert_eq!(both.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1, 4]);
+        assert!(onion_skin_annotations(&ds, 1, false, false).is_empty());
+        // No neighbor before the first frame or after the last
+        assert!(onion_skin_annotations(&ds, 0, true, false).is_empty());
+        assert!(onion_skin_annotations(&ds, 2, false, true).is_empty());
+
+        // The active frame and the neighbors' cache are left as they were
+        assert_eq!(ds.stored_annotations[1], current);
+        assert!(ds.stored_annotations[0].as_ref().unwrap()[0].selected);
+    }
+
+    #[test]
+    fn test_frame_view_encloses_scattered_boxes() {
+        let bbox = |x: f32, y: f32, w: f32, h: f32, state: &str| Annotation {
+            r#type: "bbox".into(),
+            x,
+            y,
+            width: w,
+            height: h,
+            state: state.into(),
+            ..Default::default()
+        };
+        let anns = [
+            bbox(1000.0, 800.0, 50.0, 50.0, "Manual"),
+            bbox(1200.0, 900.0, 100.0, 40.0, "Pending"),
+            bbox(1100.0, 1000.0, 20.0, 100.0, "Accepted"),
+            // Rejected boxes don't widen the view
+            bbox(0.0, 0.0, 10.0, 10.0, "Rejected"),
+        ];
+        let region = annotations_extent(&anns).unwrap();
+        assert_eq!(region, (1000.0, 800.0, 1300.0, 1100.0));
+
+        // 300x300 region on an 800x600 canvas: height limits the zoom to 2
+        let view = frame_view(region, (800.0, 600.0), 0.0, ZoomLimits::default());
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(image_to_screen(&view, (1000.0, 800.0)), (100.0, 0.0));
+        assert_eq!(image_to_screen(&view, (1300.0, 1100.0)), (700.0, 600.0));
+
+        // A margin shrinks the zoom so every box stays clear of the edges
+        let view = frame_view(region, (800.0, 600.0), 30.0, ZoomLimits::default());
+        assert!((view.zoom - 1.8).abs() < 1e-5);
+        let (left, top) = image_to_screen(&view, (1000.0, 800.0));
+        let (right, bottom) = image_to_screen(&view, (1300.0, 1100.0));
+        assert!(left >= 30.0 && top >= 29.99 && right <= 770.0 && bottom <= 570.01);
+
+        // Nothing live to frame
+        assert_eq!(annotations_extent(&anns[3..]), None);
+        // A single point zooms in as far as allowed
+        let point = frame_view((5.0, 5.0, 5.0, 5.0), (800.0, 600.0), 30.0, ZoomLimits::default());
+        assert_eq!(point.zoom, MAX_ZOOM);
+        assert_eq!(image_to_screen(&point, (5.0, 5.0)), (400.0, 300.0));
+    }
+
+    #[test]
+    fn test_clamp_view_limits_zoom() {
+        let limits = ZoomLimits { min: 0.5, max: 4.0 };
+        let img = (100.0, 100.0);
+        let canvas = (800.0, 600.0);
+
+        let over = ViewState { pan_x: 10.0, pan_y: 10.0, zoom: 20.0 };
+        assert_eq!(clamp_view(&over, img, canvas, limits).zoom, 4.0);
+
+        let under = ViewState { pan_x: 10.0, pan_y: 10.0, zoom: 0.01 };
+        let clamped = clamp_view(&under, img, canvas, limits);
+        assert_eq!(clamped.zoom, 0.5);
+        assert_eq!((clamped.pan_x, clamped.pan_y), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_clamp_view_pulls_back_offscreen_pan() {
+        let limits = ZoomLimits::default();
+        let img = (200.0, 100.0);
+        let canvas = (800.0, 600.0);
+
+        // Panned past the right/bottom edge: the image's top-left stays MIN_VISIBLE_PX inside
+        let far = ViewState { pan_x: 5000.0, pan_y: 900.0, zoom: 1.0 };
+        let clamped = clamp_view(&far, img, canvas, limits);
+        assert_eq!((clamped.pan_x, clamped.pan_y), (800.0 - MIN_VISIBLE_PX, 600.0 - MIN_VISIBLE_PX));
+
+        // Panned past the left/top edge at 2x: the far edge stays MIN_VISIBLE_PX inside
+        let gone = ViewState { pan_x: -5000.0, pan_y: -5000.0, zoom: 2.0 };
+        let clamped = clamp_view(&gone, img, canvas, limits);
+        assert_eq!((clamped.pan_x, clamped.pan_y), (MIN_VISIBLE_PX - 400.0, MIN_VISIBLE_PX - 200.0));
+
+        // In-bounds views and unknown sizes are left alone
+        let ok = ViewState { pan_x: -50.0, pan_y: 20.0, zoom: 1.0 };
+        assert_eq!(clamp_view(&ok, img, canvas, limits), ok);
+        assert_eq!(clamp_view(&far, (0.0, 0.0), canvas, limits).pan_x, 5000.0);
+    }
+
+    #[test]
+    fn test_point_display_radius_scales_with_zoom() {
+        assert_eq!(point_display_radius(5.0, 1.0), 5.0);
+        assert_eq!(point_display_radius(5.0, 2.0), 2.5);
+        assert_eq!(point_display_radius(5.0, 0.5), 10.0);
+        // Clamped at extreme zoom levels
+        assert_eq!(point_display_radius(5.0, MAX_ZOOM), MIN_POINT_RADIUS);
+        assert_eq!(point_display_radius(5.0, MIN_ZOOM), MAX_POINT_RADIUS);
+        assert_eq!(point_display_radius(5.0, 0.0), MAX_POINT_RADIUS);
+    }
+
+    #[test]
+    fn test_rings_round_trip() {
+        // Single-ring strings parse exactly as before
+        assert_eq!(parse_rings("0,0;10,0;10,10"), vec![vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]]);
+        assert!(parse_rings("").is_empty());
+
+        let rings = vec![
+            vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)],
+            vec![(40.0, 40.0), (60.0, 40.0), (60.0, 60.0)],
+        ];
+        let text = format_rings(&rings);
+        assert_eq!(text, "0,0;100,0;100,100;0,100|40,40;60,40;60,60");
+        assert_eq!(parse_rings(&text), rings);
+        assert_eq!(parse_vertices(&text).len(), 7);
+        assert_eq!(format_rings(&rings[..1]), "0,0;100,0;100,100;0,100");
+    }
+
+    #[test]
+    fn test_path_commands_open_closed_and_rings() {
+        let triangle = [(0.0, 0.0), (10.0, 0.0), (5.0, 8.0)];
+        assert_eq!(generate_path_commands(&triangle, true), "M 0 0 L 10 0 L 5 8 Z");
+        assert_eq!(generate_path_commands(&triangle, false), "M 0 0 L 10 0 L 5 8");
+        assert_eq!(generate_path_commands(&triangle[..1], false), "M 0 0");
+        assert_eq!(generate_path_commands(&[], true), "");
+
+        let rings = parse_rings("0,0;100,0;100,100;0,100|40,40;60,40;60,60");
+        assert_eq!(
+            generate_rings_path_commands(&rings),
+            "M 0 0 L 100 0 L 100 100 L 0 100 Z M 40 40 L 60 40 L 60 60 Z"
+        );
+        assert_eq!(generate_rings_path_commands(&[]), "");
+    }
+
+    #[test]
+    fn test_polygons_from_state_file_get_display_path() {
+        let dir = scratch_dir("polygon_state");
+        let entry = DatasetEntry {
+            image_path: dir.join("a.png"),
+            labels_path: None,
+        };
+        let polygon = Annotation {
+            r#type: "polygon".into(),
+            vertices: "0,0;10,0;10,10|2,2;4,2;4,4".into(),
+            ..test_box(1, 1)
+        };
+        let json = serde_json::to_string(&vec![ann_to_stored(&polygon)]).unwrap();
+        fs::write(state_path_for(&entry, "txt"), json).unwrap();
+
+        let loaded = load_yolo_annotations(&entry, (100.0, 100.0), 0, "txt");
+        assert_eq!(loaded[0].polygon_path_commands, "M 0 0 L 10 0 L 10 10 Z M 2 2 L 4 2 L 4 4 Z");
+        assert_eq!(loaded[0].polygon_vertices.row_count(), 6);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_push_created_selects_only_new_annotation() {
+        let model = slint::VecModel::from(vec![
+            Annotation { selected: true, ..test_box(1, 1) },
+            test_box(2, 1),
+        ]);
+        push_created(&model, test_box(3, 2), true);
+        let selected: Vec<i32> = model.iter().filter(|a| a.selected).map(|a| a.id).collect();
+        assert_eq!(selected, vec![3]);
+
+        push_created(&model, test_box(4, 2), false);
+        let selected: Vec<i32> = model.iter().filter(|a| a.selected).map(|a| a.id).collect();
+        assert_eq!(selected, vec![3]);
+    }
+}
@@ -0,0 +1,1412 @@
+//! Pure annotation edit helpers shared by the callbacks.
+//!
+//! These operate on plain `Annotation` values (no UI access) so the
+//! mutation rules live in one place and can be unit tested.
+
+use crate::classes::{is_lock_on_create, ClassConfig};
+use crate::geometry::{distance_to_polygon, iou, point_in_rotated_rect};
+use crate::state::{
+    format_rings, generate_rings_path_commands, next_id_from_annotations, parse_rings,
+    parse_vertices, NO_GROUP,
+};
+use crate::transform::{normalize_rotation, rbbox_corners};
+use crate::utils::now_millis;
+use crate::Annotation;
+
+/// Stamp a freshly created annotation with creation time and author
+pub fn stamp_created(ann: &mut Annotation, author: &str) {
+    let now = now_millis().to_string();
+    ann.created_at = now.clone().into();
+    ann.modified_at = now.into();
+    ann.author = author.into();
+}
+
+/// Lock a freshly created annotation if its class is lock-on-create
+pub fn apply_lock_on_create(ann: &mut Annotation, classes: &ClassConfig) {
+    if is_lock_on_create(classes, ann.class) {
+        ann.locked = true;
+    }
+}
+
+/// Whether an annotation's geometry may be changed or deleted
+pub fn can_edit(ann: &Annotation) -> bool {
+    !ann.locked && ann.state != "Rejected"
+}
+
+/// Record that an annotation was modified just now
+pub fn stamp_modified(ann: &mut Annotation) {
+    ann.modified_at = now_millis().to_string().into();
+}
+
+/// Assign a class to an annotation, promoting Pending to Accepted
+pub fn apply_class(ann: &mut Annotation, class: i32) {
+    ann.class = class;
+    if ann.state == "Pending" {
+        ann.state = "Accepted".into();
+    }
+    stamp_modified(ann);
+}
+
+/// Promote every `Pending` annotation to `Accepted`; returns how many changed
+pub fn accept_pending(anns: &mut [Annotation]) -> usize {
+    let mut changed = 0;
+    for ann in anns.iter_mut().filter(|a| a.state == "Pending") {
+        ann.state = "Accepted".into();
+        stamp_modified(ann);
+        changed += 1;
+    }
+    changed
+}
+
+/// Outline vertices of a polygon annotation as `(x, y)` pairs (holes excluded)
+pub fn polygon_points(ann: &Annotation) -> Vec<(f32, f32)> {
+    parse_rings(&ann.vertices).into_iter().next().unwrap_or_default()
+}
+
+/// All rings of a polygon annotation: the outline first, then its holes
+pub fn polygon_rings(ann: &Annotation) -> Vec<Vec<(f32, f32)>> {
+    parse_rings(&ann.vertices)
+}
+
+/// Replace a polygon's outline, keeping its holes, vertex string, vertex model,
+/// path commands and bounding box in sync
+pub fn set_polygon_points(ann: &mut Annotation, points: &[(f32, f32)]) {
+    let mut rings = polygon_rings(ann);
+    match rings.first_mut() {
+        Some(outline) => *outline = points.to_vec(),
+        None => rings.push(points.to_vec()),
+    }
+    set_polygon_rings(ann, &rings);
+}
+
+/// Replace all rings of a polygon (outline first); the bounding box follows the outline
+pub fn set_polygon_rings(ann: &mut Annotation, rings: &[Vec<(f32, f32)>]) {
+    let vertices = format_rings(rings);
+    let commands = generate_rings_path_commands(rings);
+
+    ann.polygon_vertices =
+        std::rc::Rc::new(slint::VecModel::from(parse_vertices(&vertices))).into();
+    ann.polygon_path_commands = commands.into();
+    ann.vertices = vertices.into();
+    if let Some(outline) = rings.first().filter(|r| !r.is_empty()) {
+        let min_x = outline.iter().map(|p| p.0).fold(f32::INFINITY, f32::min);
+        let max_x = outline.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = outline.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let max_y = outline.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        ann.x = min_x;
+        ann.y = min_y;
+        ann.width = max_x - min_x;
+        ann.height = max_y - min_y;
+    }
+}
+
+/// Fewest vertices a polygon ring may have
+pub const MIN_RING_VERTICES: usize = 3;
+
+/// Cut a hole into a polygon; false if `ann` isn't a polygon or the hole is too small
+pub fn add_polygon_hole(ann: &mut Annotation, hole: &[(f32, f32)]) -> bool {
+    if ann.r#type != "polygon" || hole.len() < MIN_RING_VERTICES {
+        return false;
+    }
+    let mut rings = polygon_rings(ann);
+    if rings.is_empty() {
+        return false;
+    }
+    rings.push(hole.to_vec());
+    set_polygon_rings(ann, &rings);
+    true
+}
+
+/// Rings of an editable polygon and the ring and position in it of vertex
+/// `vertex`, counted across all rings in order (as in `polygon_vertices`)
+fn locate_polygon_vertex(
+    ann: &Annotation,
+    vertex: usize,
+) -> Result<(Vec<Vec<(f32, f32)>>, usize, usize), String> {
+    if ann.r#type != "polygon" {
+        return Err("Only polygon vertices can be edited".to_string());
+    }
+    if !can_edit(ann) {
+        return Err("Polygon is locked or rejected".to_string());
+    }
+    let rings = polygon_rings(ann);
+    let mut start = 0;
+    for (ring, points) in rings.iter().enumerate() {
+        if vertex < start + points.len() {
+            return Ok((rings, ring, vertex - start));
+        }
+        start += points.len();
+    }
+    Err(format!("Polygon has no vertex {vertex}"))
+}
+
+/// Move vertex `vertex` of a polygon (numbered across its rings) to `to`
+pub fn move_polygon_vertex(
+    ann: &mut Annotation,
+    vertex: usize,
+    to: (f32, f32),
+) -> Result<(), String> {
+    let (mut rings, ring, pos) = locate_polygon_vertex(ann, vertex)?;
+    rings[ring][pos] = to;
+    set_polygon_rings(ann, &rings);
+    stamp_reshaped(ann);
+    Ok(())
+}
+
+/// Split edge `edge` of a polygon, the one leaving vertex `edge` towards the
+/// next vertex of its ring, with a new vertex at `at`
+pub fn insert_polygon_vertex(
+    ann: &mut Annotation,
+    edge: usize,
+    at: (f32, f32),
+) -> Result<(), String> {
+    let (mut rings, ring, pos) = locate_polygon_vertex(ann, edge)?;
+    rings[ring].insert(pos + 1, at);
+    set_polygon_rings(ann, &rings);
+    stamp_reshaped(ann);
+    Ok(())
+}
+
+/// Remove vertex `vertex` of a polygon; refused if its ring would drop below
+/// [`MIN_RING_VERTICES`]
+pub fn delete_polygon_vertex(ann: &mut Annotation, vertex: usize) -> Result<(), String> {
+    let (mut rings, ring, pos) = locate_polygon_vertex(ann, vertex)?;
+    if rings[ring].len() <= MIN_RING_VERTICES {
+        return Err(format!("A polygon needs at least {MIN_RING_VERTICES} vertices"));
+    }
+    rings[ring].remove(pos);
+    set_polygon_rings(ann, &rings);
+    stamp_reshaped(ann);
+    Ok(())
+}
+
+/// A hand-edited shape counts as reviewed, as with a resize
+fn stamp_reshaped(ann: &mut Annotation) {
+    if ann.state == "Pending" {
+        ann.state = "Accepted".into();
+    }
+    stamp_modified(ann);
+}
+
+/// Line an annotation is reflected across
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Reflect across the vertical center line (left <-> right)
+    Vertical,
+    /// Reflect across the horizontal center line (top <-> bottom)
+    Horizontal,
+}
+
+impl MirrorAxis {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vertical" => Some(MirrorAxis::Vertical),
+            "horizontal" => Some(MirrorAxis::Horizontal),
+            _ => None,
+        }
+    }
+}
+
+/// Reflect an annotation across the image center line, clamped to the image
+pub fn mirror_annotation(ann: &mut Annotation, img_size: (f32, f32), axis: MirrorAxis) {
+    let (img_w, img_h) = img_size;
+    let flip = |(x, y): (f32, f32)| match axis {
+        MirrorAxis::Vertical => ((img_w - x).clamp(0.0, img_w), y),
+        MirrorAxis::Horizontal => (x, (img_h - y).clamp(0.0, img_h)),
+    };
+
+    match ann.r#type.as_str() {
+        "point" => (ann.x, ann.y) = flip((ann.x, ann.y)),
+        "polygon" => {
+            let rings: Vec<Vec<(f32, f32)>> = polygon_rings(ann)
+                .into_iter()
+                .map(|ring| ring.into_iter().map(flip).collect())
+                .collect();
+            set_polygon_rings(ann, &rings);
+        }
+        _ => {
+            match axis {
+                MirrorAxis::Vertical => {
+                    ann.x = (img_w - ann.x - ann.width).clamp(0.0, (img_w - ann.width).max(0.0))
+                }
+                MirrorAxis::Horizontal => {
+                    ann.y = (img_h - ann.y - ann.height).clamp(0.0, (img_h - ann.height).max(0.0))
+                }
+            }
+            // A reflection reverses the sense of rotation
+            ann.rotation = -ann.rotation;
+        }
+    }
+    stamp_modified(ann);
+}
+
+/// Paired class for a mirrored annotation (e.g. left hand <-> right hand)
+pub fn mirrored_class(class: i32, pairs: &[[i32; 2]]) -> i32 {
+    pairs
+        .iter()
+        .find_map(|&[a, b]| {
+            if class == a {
+                Some(b)
+            } else if class == b {
+                Some(a)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(class)
+}
+
+/// Whether an image-space point hits an annotation (points use a 10px radius,
+/// rotated boxes their rotated outline)
+pub fn hit_test(ann: &Annotation, x: f32, y: f32) -> bool {
+    match ann.r#type.as_str() {
+        "point" => {
+            let dx = x - ann.x;
+            let dy = y - ann.y;
+            (dx * dx + dy * dy).sqrt() < 10.0
+        }
+        "rbbox" => {
+            point_in_rotated_rect((x, y), (ann.x, ann.y, ann.width, ann.height), ann.rotation)
+        }
+        _ => x >= ann.x && x <= ann.x + ann.width && y >= ann.y && y <= ann.y + ann.height,
+    }
+}
+
+/// Whether an annotation is drawn: not rejected and not hidden by isolation
+fn is_shown(ann: &Annotation) -> bool {
+    ann.state != "Rejected" && !ann.hidden
+}
+
+/// Index of the topmost (last drawn) shown annotation under a point
+pub fn topmost_hit(anns: &[Annotation], x: f32, y: f32) -> Option<usize> {
+    anns.iter().rposition(|a| is_shown(a) && hit_test(a, x, y))
+}
+
+/// Distance from a point to an annotation: to the center of points, to the
+/// nearest edge of boxes (rotated ones by their rotated outline) and polygon
+/// bounds (0 inside)
+fn distance_to(ann: &Annotation, x: f32, y: f32) -> f32 {
+    match ann.r#type.as_str() {
+        "point" => return ((x - ann.x).powi(2) + (y - ann.y).powi(2)).sqrt(),
+        "rbbox" => {
+            let corners = rbbox_corners((ann.x, ann.y, ann.width, ann.height), ann.rotation);
+            return distance_to_polygon((x, y), &corners);
+        }
+        _ => {}
+    }
+    let dx = (ann.x - x).max(x - (ann.x + ann.width)).max(0.0);
+    let dy = (ann.y - y).max(y - (ann.y + ann.height)).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Index of the live annotation closest to a point; ties go to the lowest index
+pub fn nearest_annotation(anns: &[Annotation], x: f32, y: f32) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (i, ann) in anns.iter().enumerate().filter(|(_, a)| is_shown(a)) {
+        let d = distance_to(ann, x, y);
+        if best.is_none_or(|(_, best_d)| d < best_d) {
+            best = Some((i, d));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+/// Selection flags after a plain (unmodified) click on `target`: only the
+/// target, unless `keep_group` is on and the click dragged a member of a
+/// multi-selection, which leaves the group as it was
+pub fn plain_click_selection(
+    selected: &[bool],
+    target: usize,
+    keep_group: bool,
+    dragged: bool,
+) -> Vec<bool> {
+    let in_group = selected.get(target).copied().unwrap_or(false)
+        && selected.iter().filter(|&&s| s).count() > 1;
+    if keep_group && dragged && in_group {
+        return selected.to_vec();
+    }
+    (0..selected.len()).map(|i| i == target).collect()
+}
+
+/// Hide every annotation that isn't selected, to inspect the selection on its
+/// own. Hiding is display-only: hidden annotations are still saved. Does
+/// nothing without a selection; returns how many annotations were hidden.
+pub fn isolate_selected(anns: &mut [Annotation]) -> usize {
+    if !anns.iter().any(|a| a.selected) {
+        return 0;
+    }
+    let mut hidden = 0;
+    for ann in anns.iter_mut().filter(|a| !a.selected && !a.hidden) {
+        ann.hidden = true;
+        hidden += 1;
+    }
+    hidden
+}
+
+/// Show every annotation hidden by `isolate_selected`; returns how many
+pub fn clear_isolation(anns: &mut [Annotation]) -> usize {
+    let mut shown = 0;
+    for ann in anns.iter_mut().filter(|a| a.hidden) {
+        ann.hidden = false;
+        shown += 1;
+    }
+    shown
+}
+
+/// Class of the topmost annotation under a point (eyedropper)
+pub fn class_at(anns: &[Annotation], x: f32, y: f32) -> Option<i32> {
+    topmost_hit(anns, x, y).map(|i| anns[i].class)
+}
+
+/// Indices of live boxes overlapping another live box with IoU >= `threshold`
+pub fn overlapping_indices(anns: &[Annotation], threshold: f32) -> Vec<usize> {
+    let boxes: Vec<(usize, (f32, f32, f32, f32))> = anns
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.state != "Rejected")
+        .filter(|(_, a)| a.r#type.as_str() == "bbox" || a.r#type.as_str() == "rbbox")
+        .map(|(i, a)| (i, (a.x, a.y, a.width, a.height)))
+        .collect();
+
+    boxes
+        .iter()
+        .filter(|(i, a)| boxes.iter().any(|(j, b)| i != j && iou(*a, *b) >= threshold))
+        .map(|(i, _)| *i)
+        .collect()
+}
+
+/// Mark an annotation as rejected (soft delete); locked ones are left alone
+pub fn reject(ann: &mut Annotation) -> bool {
+    if !can_edit(ann) {
+        return false;
+    }
+    ann.state = "Rejected".into();
+    ann.selected = false;
+    stamp_modified(ann);
+    true
+}
+
+/// Reject every live, unlocked annotation of `class`; returns how many were rejected
+pub fn reject_by_class(anns: &mut [Annotation], class: i32) -> usize {
+    anns.iter_mut()
+        .filter(|a| a.class == class)
+        .map(reject)
+        .filter(|&rejected| rejected)
+        .count()
+}
+
+/// Reject every live, unlocked annotation on the frame; returns how many were rejected
+pub fn clear_frame(anns: &mut [Annotation]) -> usize {
+    anns.iter_mut().map(reject).filter(|&rejected| rejected).count()
+}
+
+/// Drop rejected annotations for good; returns how many were removed
+pub fn purge_rejected(anns: &mut Vec<Annotation>) -> usize {
+    let before = anns.len();
+    anns.retain(|a| a.state != "Rejected");
+    before - anns.len()
+}
+
+/// Ids of the annotations in `before` that `after` changed or removed
+pub fn changed_ids(before: &[Annotation], after: &[Annotation]) -> Vec<i32> {
+    before
+        .iter()
+        .filter(|old| !after.iter().any(|new| new == *old))
+        .map(|old| old.id)
+        .collect()
+}
+
+/// Move every live, unlocked annotation of class `from` to class `to`;
+/// returns how many changed
+pub fn remap_class(anns: &mut [Annotation], from: i32, to: i32) -> usize {
+    if from == to {
+        return 0;
+    }
+    let mut count = 0;
+    for ann in anns.iter_mut().filter(|a| a.class == from && can_edit(a)) {
+        apply_class(ann, to);
+        count += 1;
+    }
+    count
+}
+
+/// `(x0, y0, x1, y1)` extent of an annotation; points are their center
+fn extent(ann: &Annotation) -> (f32, f32, f32, f32) {
+    if ann.r#type.as_str() == "point" {
+        (ann.x, ann.y, ann.x, ann.y)
+    } else {
+        (ann.x, ann.y, ann.x + ann.width, ann.y + ann.height)
+    }
+}
+
+/// `(x0, y0, x1, y1)` extent enclosing every live (not Rejected) annotation;
+/// `None` when there is none
+pub fn annotations_extent(anns: &[Annotation]) -> Option<(f32, f32, f32, f32)> {
+    anns.iter()
+        .filter(|a| a.state != "Rejected")
+        .map(extent)
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+}
+
+/// Whether an annotation lies entirely inside the region spanned by two corners
+fn contained_in(ann: &Annotation, a: (f32, f32), b: (f32, f32)) -> bool {
+    let (left, right) = (a.0.min(b.0), a.0.max(b.0));
+    let (top, bottom) = (a.1.min(b.1), a.1.max(b.1));
+    let (x0, y0, x1, y1) = extent(ann);
+    x0 >= left && x1 <= right && y0 >= top && y1 <= bottom
+}
+
+/// Set `class` on every editable annotation fully inside the region spanned
+/// by corners `a` and `b`; boxes only partly inside are left alone. Returns
+/// how many annotations changed class.
+pub fn reclassify_region(
+    anns: &mut [Annotation],
+    a: (f32, f32),
+    b: (f32, f32),
+    class: i32,
+) -> usize {
+    let mut count = 0;
+    for ann in anns
+        .iter_mut()
+        .filter(|ann| can_edit(ann) && ann.class != class && contained_in(ann, a, b))
+    {
+        apply_class(ann, class);
+        count += 1;
+    }
+    count
+}
+
+/// Offset in image pixels between an annotation and its duplicate
+pub const DUPLICATE_OFFSET: f32 = 10.0;
+
+/// Offset between a copied annotation and its pasted copy, as a fraction of
+/// the image's width and height
+pub const PASTE_OFFSET_FRACTION: f32 = 0.05;
+
+/// Move an annotation by `(dx, dy)`, including polygon vertices
+fn translate(ann: &mut Annotation, dx: f32, dy: f32) {
+    if ann.r#type == "polygon" {
+        let rings: Vec<Vec<(f32, f32)>> = polygon_rings(ann)
+            .iter()
+            .map(|ring| ring.iter().map(|(x, y)| (x + dx, y + dy)).collect())
+            .collect();
+        set_polygon_rings(ann, &rings);
+    } else {
+        ann.x += dx;
+        ann.y += dy;
+    }
+}
+
+/// Move every live, unlocked annotation by `(dx, dy)` to correct a frame-wide
+/// offset. Each annotation keeps its shape and stops at the image edge, so
+/// ones near an edge may move less than the rest. Returns how many moved.
+pub fn shift_annotations(
+    anns: &mut [Annotation],
+    dx: f32,
+    dy: f32,
+    img_size: (f32, f32),
+) -> usize {
+    let mut moved = 0;
+    for ann in anns.iter_mut().filter(|a| can_edit(a)) {
+        let (x0, y0, x1, y1) = extent(ann);
+        let ann_dx = dx.min(img_size.0 - x1).max(-x0);
+        let ann_dy = dy.min(img_size.1 - y1).max(-y0);
+        if ann_dx == 0.0 && ann_dy == 0.0 {
+            continue;
+        }
+        translate(ann, ann_dx, ann_dy);
+        stamp_modified(ann);
+        moved += 1;
+    }
+    moved
+}
+
+/// Degrees one `[`/`]` press turns a rotated box (`ROTATE_FINE_STEP` with Shift);
+/// the keys send a step count and fine flag, so these are the only step sizes
+pub const ROTATE_STEP: f32 = 5.0;
+pub const ROTATE_FINE_STEP: f32 = 1.0;
+
+/// Turn the selected editable rotated boxes by `degrees` (positive is
+/// clockwise on screen) about their centers. Returns how many turned.
+pub fn rotate_selected(anns: &mut [Annotation], degrees: f32) -> usize {
+    let mut turned = 0;
+    for ann in anns.iter_mut().filter(|a| a.selected && a.r#type == "rbbox" && can_edit(a)) {
+        ann.rotation = normalize_rotation(ann.rotation + degrees);
+        stamp_modified(ann);
+        turned += 1;
+    }
+    turned
+}
+
+/// Move the selected live, unlocked annotations by `(dx, dy)` as one group:
+/// the delta is clamped so the whole group stays inside the image, so every
+/// member moves by the same amount. Returns how many moved.
+pub fn move_selected(
+    anns: &mut [Annotation],
+    dx: f32,
+    dy: f32,
+    img_size: (f32, f32),
+) -> usize {
+    let group = anns
+        .iter()
+        .filter(|a| a.selected && can_edit(a))
+        .map(extent)
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)));
+    let Some((x0, y0, x1, y1)) = group else { return 0 };
+    let dx = dx.min(img_size.0 - x1).max(-x0);
+    let dy = dy.min(img_size.1 - y1).max(-y0);
+    if dx == 0.0 && dy == 0.0 {
+        return 0;
+    }
+    let mut moved = 0;
+    for ann in anns.iter_mut().filter(|a| a.selected && can_edit(a)) {
+        translate(ann, dx, dy);
+        stamp_modified(ann);
+        moved += 1;
+    }
+    moved
+}
+
+/// Edge or size `align_boxes` copies from the reference box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignMode {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    SameWidth,
+    SameHeight,
+}
+
+impl AlignMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "left" => Some(AlignMode::Left),
+            "right" => Some(AlignMode::Right),
+            "top" => Some(AlignMode::Top),
+            "bottom" => Some(AlignMode::Bottom),
+            "same_width" => Some(AlignMode::SameWidth),
+            "same_height" => Some(AlignMode::SameHeight),
+            _ => None,
+        }
+    }
+}
+
+fn is_box(ann: &Annotation) -> bool {
+    ann.r#type == "bbox" || ann.r#type == "rbbox"
+}
+
+/// Align the boxes at `selected` to the first box among them (the reference):
+/// edge modes move a box so that edge matches the reference's, size modes
+/// resize it keeping its top-left corner. Other annotation types and locked or
+/// rejected boxes are left alone. Returns how many boxes changed.
+pub fn align_boxes(anns: &mut [Annotation], selected: &[usize], mode: AlignMode) -> usize {
+    let Some(&ref_idx) = selected.iter().find(|&&i| anns.get(i).is_some_and(is_box)) else {
+        return 0;
+    };
+    let reference = anns[ref_idx].clone();
+    let mut changed = 0;
+    for &idx in selected.iter().filter(|&&i| i != ref_idx) {
+        let Some(ann) = anns.get_mut(idx) else { continue };
+        if !is_box(ann) || !can_edit(ann) {
+            continue;
+        }
+        let before = (ann.x, ann.y, ann.width, ann.height);
+        match mode {
+            AlignMode::Left => ann.x = reference.x,
+            AlignMode::Right => ann.x = reference.x + reference.width - ann.width,
+            AlignMode::Top => ann.y = reference.y,
+            AlignMode::Bottom => ann.y = reference.y + reference.height - ann.height,
+            AlignMode::SameWidth => ann.width = reference.width,
+            AlignMode::SameHeight => ann.height = reference.height,
+        }
+        if (ann.x, ann.y, ann.width, ann.height) != before {
+            stamp_modified(ann);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// `(x, y, w, h)` of the smallest rectangle enclosing every annotation's box;
+/// all zero for an empty slice
+pub fn union_bbox(anns: &[Annotation]) -> (f32, f32, f32, f32) {
+    anns.iter()
+        .map(extent)
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .map_or((0.0, 0.0, 0.0, 0.0), |(x0, y0, x1, y1)| (x0, y0, x1 - x0, y1 - y0))
+}
+
+/// Replace the selected editable bboxes with one box covering their union. The
+/// first of them (in list order) survives with its id and class; the rest are
+/// removed. Other selected types, and rotated, locked or rejected boxes, are
+/// left alone. Returns how many boxes were merged away (0 with fewer than two).
+pub fn merge_selected_boxes(anns: &mut Vec<Annotation>) -> usize {
+    let mergeable = |a: &Annotation| a.selected && a.r#type == "bbox" && can_edit(a);
+    let boxes: Vec<Annotation> = anns.iter().filter(|a| mergeable(a)).cloned().collect();
+    if boxes.len() < 2 {
+        return 0;
+    }
+    let (x, y, width, height) = union_bbox(&boxes);
+    let keep = boxes[0].id;
+    anns.retain(|a| !mergeable(a) || a.id == keep);
+    if let Some(ann) = anns.iter_mut().find(|a| a.id == keep) {
+        (ann.x, ann.y, ann.width, ann.height) = (x, y, width, height);
+        stamp_modified(ann);
+    }
+    boxes.len() - 1
+}
+
+/// Side in px of the boxes made from points when no size is given
+pub const DEFAULT_POINT_BOX_SIZE: f32 = 20.0;
+
+/// Square bbox of side `size` centered on a point; id, class, state and
+/// audit fields carry over
+pub fn point_to_box(point: &Annotation, size: f32) -> Annotation {
+    let mut bbox = Annotation {
+        r#type: "bbox".into(),
+        x: point.x - size / 2.0,
+        y: point.y - size / 2.0,
+        width: size,
+        height: size,
+        rotation: 0.0,
+        ..point.clone()
+    };
+    stamp_modified(&mut bbox);
+    bbox
+}
+
+/// Point at the center of a box; id, class, state and audit fields carry over
+pub fn box_to_point(bbox: &Annotation) -> Annotation {
+    let mut point = Annotation {
+        r#type: "point".into(),
+        x: bbox.x + bbox.width / 2.0,
+        y: bbox.y + bbox.height / 2.0,
+        width: 0.0,
+        height: 0.0,
+        rotation: 0.0,
+        ..bbox.clone()
+    };
+    stamp_modified(&mut point);
+    point
+}
+
+/// Turn every editable point into a `size` px box; returns how many changed
+pub fn points_to_boxes(anns: &mut [Annotation], size: f32) -> usize {
+    let mut changed = 0;
+    for ann in anns.iter_mut().filter(|a| a.r#type == "point" && can_edit(a)) {
+        *ann = point_to_box(ann, size);
+        changed += 1;
+    }
+    changed
+}
+
+/// Turn every editable bbox or rbbox into a point at its center; returns how
+/// many changed
+pub fn boxes_to_points(anns: &mut [Annotation]) -> usize {
+    let mut changed = 0;
+    for ann in anns.iter_mut().filter(|a| is_box(a) && can_edit(a)) {
+        *ann = box_to_point(ann);
+        changed += 1;
+    }
+    changed
+}
+
+/// Clone `ann` as new annotation `id`, shifted by up to `offset` but clamped
+/// so its extent stays inside the image (one larger than the image rests at
+/// the top-left edge). A copy is a different object: unlocked, ungrouped and
+/// stamped as created by `author`.
+fn placed_copy(
+    ann: &Annotation,
+    offset: (f32, f32),
+    img_size: (f32, f32),
+    id: i32,
+    author: &str,
+) -> Annotation {
+    let (x0, y0, x1, y1) = extent(ann);
+    let dx = offset.0.min(img_size.0 - x1).max(-x0);
+    let dy = offset.1.min(img_size.1 - y1).max(-y0);
+    let mut copy = ann.clone();
+    translate(&mut copy, dx, dy);
+    copy.id = id;
+    copy.locked = false;
+    copy.group_id = NO_GROUP;
+    stamp_created(&mut copy, author);
+    copy
+}
+
+/// Append a copy of every selected live annotation, shifted by `offset` px
+/// (less near the right/bottom edge so copies stay inside the image).
+/// The copies get fresh ids and become the selection; returns how many were made.
+pub fn duplicate_selected(
+    anns: &mut Vec<Annotation>,
+    offset: f32,
+    img_size: (f32, f32),
+    author: &str,
+) -> usize {
+    let mut next_id = next_id_from_annotations(anns, 1);
+    let mut copies = Vec::new();
+    for ann in anns.iter_mut().filter(|a| a.selected && a.state != "Rejected") {
+        ann.selected = false;
+        let mut copy = placed_copy(ann, (offset, offset), img_size, next_id, author);
+        copy.selected = true;
+        next_id += 1;
+        copies.push(copy);
+    }
+    let count = copies.len();
+    anns.extend(copies);
+    count
+}
+
+/// Append `copied` with fresh ids, each shifted by `PASTE_OFFSET_FRACTION` of
+/// `img_size` and kept inside the image, like duplicates. Returns how many
+/// were pasted.
+pub fn paste_annotations(
+    anns: &mut Vec<Annotation>,
+    copied: &[Annotation],
+    img_size: (f32, f32),
+    author: &str,
+) -> usize {
+    let offset = (img_size.0 * PASTE_OFFSET_FRACTION, img_size.1 * PASTE_OFFSET_FRACTION);
+    let mut next_id = next_id_from_annotations(anns, 1);
+    for ann in copied {
+        let mut copy = placed_copy(ann, offset, img_size, next_id, author);
+        copy.selected = false;
+        next_id += 1;
+        anns.push(copy);
+    }
+    copied.len()
+}
+
+/// Track id of `ann`; ids start at 1, so 0 (a new annotation) and
+/// [`NO_GROUP`] both mean none
+pub fn group_of(ann: &Annotation) -> Option<i32> {
+    (ann.group_id > 0).then_some(ann.group_id)
+}
+
+/// Largest track id in use among `anns` (0 if none)
+pub fn max_group_id<'a>(anns: impl IntoIterator<Item = &'a Annotation>) -> i32 {
+    anns.into_iter().filter_map(group_of).max().unwrap_or(0)
+}
+
+/// Put the selected editable annotations on track `group_id`, or take them off
+/// their track with `None`. Returns how many changed.
+pub fn set_group_id(anns: &mut [Annotation], group_id: Option<i32>) -> usize {
+    let value = group_id.unwrap_or(NO_GROUP);
+    let mut changed = 0;
+    for ann in anns.iter_mut().filter(|a| a.selected && can_edit(a)) {
+        if group_of(ann) != group_id {
+            ann.group_id = value;
+            stamp_modified(ann);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Parse a millisecond timestamp stored on an annotation (0 if unset)
+pub fn parse_millis(value: &str) -> i64 {
+    value.parse().unwrap_or(0)
+}
+
+/// Format a millisecond timestamp for an annotation ("" if unset)
+pub fn format_millis(value: i64) -> String {
+    if value > 0 {
+        value.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Manual bbox of `class` at `(x, y)` sized `w`×`h`, for tests
+#[cfg(test)]
+pub fn test_bbox(class: i32, x: f32, y: f32, w: f32, h: f32) -> Annotation {
+    Annotation {
+        r#type: "bbox".into(),
+        x,
+        y,
+        width: w,
+        height: h,
+        class,
+        state: "Manual".into(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_bbox as bbox;
+    use slint::Model;
+
+    #[test]
+    fn test_stamp_created_sets_timestamps_and_author() {
+        let mut ann = Annotation::default();
+        stamp_created(&mut ann, "alice");
+        assert!(parse_millis(&ann.created_at) > 0);
+        assert_eq!(ann.created_at, ann.modified_at);
+        assert_eq!(ann.author, "alice");
+    }
+
+    #[test]
+    fn test_apply_class_updates_modified_at() {
+        let mut ann = Annotation {
+            state: "Pending".into(),
+            created_at: "1000".into(),
+            modified_at: "1000".into(),
+            ..Default::default()
+        };
+        apply_class(&mut ann, 3);
+        assert_eq!(ann.class, 3);
+        assert_eq!(ann.state, "Accepted");
+        assert_eq!(parse_millis(&ann.created_at), 1000);
+        assert!(parse_millis(&ann.modified_at) > 1000);
+    }
+
+    #[test]
+    fn test_class_at_picks_topmost_box() {
+        let anns = vec![bbox(1, 0.0, 0.0, 100.0, 100.0), bbox(3, 40.0, 40.0, 20.0, 20.0)];
+        assert_eq!(class_at(&anns, 50.0, 50.0), Some(3));
+        assert_eq!(class_at(&anns, 10.0, 10.0), Some(1));
+        assert_eq!(class_at(&anns, 150.0, 150.0), None);
+    }
+
+    #[test]
+    fn test_reject_by_class_only_touches_matching_class() {
+        let mut anns = vec![
+            bbox(2, 0.0, 0.0, 10.0, 10.0),
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+            bbox(2, 5.0, 5.0, 10.0, 10.0),
+        ];
+        anns[2].selected = true;
+        assert_eq!(reject_by_class(&mut anns, 2), 2);
+        assert_eq!(anns[0].state, "Rejected");
+        assert_eq!(anns[1].state, "Manual");
+        assert_eq!(anns[2].state, "Rejected");
+        assert!(!anns[2].selected);
+        // Already rejected rows are not counted twice
+        assert_eq!(reject_by_class(&mut anns, 2), 0);
+    }
+
+    #[test]
+    fn test_clear_purge_and_remap_skip_locked() {
+        let mut anns = vec![
+            bbox(2, 0.0, 0.0, 10.0, 10.0),
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+            bbox(2, 5.0, 5.0, 10.0, 10.0),
+        ];
+        anns[2].locked = true;
+
+        assert_eq!(remap_class(&mut anns, 2, 4), 1);
+        assert_eq!((anns[0].class, anns[2].class), (4, 2));
+        assert_eq!(remap_class(&mut anns, 4, 4), 0);
+
+        assert_eq!(clear_frame(&mut anns), 2);
+        assert_eq!(anns[2].state, "Manual");
+        assert_eq!(purge_rejected(&mut anns), 2);
+        assert_eq!(anns.len(), 1);
+        assert!(anns[0].locked);
+    }
+
+    #[test]
+    fn test_hit_test_follows_rbbox_rotation() {
+        let mut rbox = bbox(1, 0.0, 0.0, 100.0, 20.0);
+        rbox.r#type = "rbbox".into();
+        rbox.rotation = 45.0;
+        // Inside the rotated shape, outside the unrotated envelope
+        assert!(hit_test(&rbox, 78.0, 38.0));
+        assert!(!hit_test(&rbox, 95.0, 15.0));
+        assert_eq!(topmost_hit(&[rbox.clone()], 78.0, 38.0), Some(0));
+
+        rbox.rotation = 0.0;
+        assert!(hit_test(&rbox, 95.0, 15.0));
+    }
+
+    #[test]
+    fn test_reclassify_region_only_touches_contained() {
+        let mut anns = vec![
+            bbox(1, 10.0, 10.0, 20.0, 20.0),
+            bbox(1, 90.0, 90.0, 20.0, 20.0), // sticks out of the region
+            bbox(1, 40.0, 40.0, 5.0, 5.0),
+            bbox(2, 50.0, 50.0, 5.0, 5.0),
+        ];
+        anns[2].state = "Rejected".into();
+        anns.push(Annotation {
+            r#type: "point".into(),
+            ..bbox(1, 60.0, 60.0, 0.0, 0.0)
+        });
+
+        // Corners may be given in any order
+        assert_eq!(reclassify_region(&mut anns, (100.0, 100.0), (0.0, 0.0), 4), 3);
+        let classes: Vec<i32> = anns.iter().map(|a| a.class).collect();
+        assert_eq!(classes, vec![4, 1, 1, 4, 4]);
+    }
+
+    #[test]
+    fn test_changed_ids_names_edited_and_removed_rows() {
+        let before: Vec<Annotation> = (1..=4)
+            .map(|id| Annotation { id, ..bbox(1, 0.0, 0.0, 10.0, 10.0) })
+            .collect();
+        let mut after = before.clone();
+        after[1].class = 2;
+        after.remove(3);
+        assert_eq!(changed_ids(&before, &after), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_reclassify_region_skips_locked() {
+        let mut anns = vec![bbox(1, 10.0, 10.0, 20.0, 20.0), bbox(1, 40.0, 40.0, 5.0, 5.0)];
+        anns[1].locked = true;
+
+        assert_eq!(reclassify_region(&mut anns, (0.0, 0.0), (100.0, 100.0), 4), 1);
+        assert_eq!(anns[0].class, 4);
+        assert_eq!(anns[1].class, 1);
+    }
+
+    #[test]
+    fn test_shift_annotations_keeps_shape_and_clamps_at_edges() {
+        let mut anns = vec![
+            bbox(1, 10.0, 10.0, 20.0, 20.0),
+            bbox(1, 85.0, 40.0, 10.0, 10.0), // only 5 px from the right edge
+            bbox(1, 50.0, 50.0, 5.0, 5.0),
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+        ];
+        anns[2].state = "Rejected".into();
+        anns[3].locked = true;
+        let mut polygon = bbox(1, 0.0, 0.0, 0.0, 0.0);
+        polygon.r#type = "polygon".into();
+        set_polygon_points(&mut polygon, &[(20.0, 60.0), (40.0, 60.0), (30.0, 80.0)]);
+        anns.push(polygon);
+
+        assert_eq!(shift_annotations(&mut anns, 8.0, -3.0, (100.0, 100.0)), 3);
+        let rect = |a: &Annotation| (a.x, a.y, a.width, a.height);
+        assert_eq!(rect(&anns[0]), (18.0, 7.0, 20.0, 20.0));
+        assert_eq!(rect(&anns[1]), (90.0, 37.0, 10.0, 10.0));
+        assert_eq!(rect(&anns[2]), (50.0, 50.0, 5.0, 5.0));
+        assert_eq!(rect(&anns[3]), (0.0, 0.0, 10.0, 10.0));
+        assert_eq!(polygon_points(&anns[4]), vec![(28.0, 57.0), (48.0, 57.0), (38.0, 77.0)]);
+    }
+
+    #[test]
+    fn test_move_selected_moves_group_by_same_delta() {
+        let mut anns = vec![
+            bbox(1, 10.0, 10.0, 20.0, 20.0),
+            bbox(2, 60.0, 30.0, 30.0, 10.0),
+            bbox(1, 40.0, 40.0, 5.0, 5.0),
+        ];
+        anns[0].selected = true;
+        anns[1].selected = true;
+        let rect = |a: &Annotation| (a.x, a.y, a.width, a.height);
+
+        assert_eq!(move_selected(&mut anns, 5.0, -4.0, (100.0, 100.0)), 2);
+        assert_eq!(rect(&anns[0]), (15.0, 6.0, 20.0, 20.0));
+        assert_eq!(rect(&anns[1]), (65.0, 26.0, 30.0, 10.0));
+        assert_eq!(rect(&anns[2]), (40.0, 40.0, 5.0, 5.0));
+
+        // The right box stops at the edge and holds the left one back with it
+        assert_eq!(move_selected(&mut anns, 20.0, 0.0, (100.0, 100.0)), 2);
+        assert_eq!((anns[0].x, anns[1].x), (20.0, 70.0));
+        assert_eq!(move_selected(&mut anns, 3.0, 0.0, (100.0, 100.0)), 0);
+    }
+
+    #[test]
+    fn test_align_boxes_each_mode() {
+        let boxes = vec![
+            bbox(1, 10.0, 20.0, 40.0, 30.0), // reference
+            bbox(1, 70.0, 60.0, 10.0, 10.0),
+            bbox(1, 5.0, 5.0, 20.0, 50.0),
+        ];
+        let rect = |a: &Annotation| (a.x, a.y, a.width, a.height);
+        let aligned = |mode| {
+            let mut anns = boxes.clone();
+            let changed = align_boxes(&mut anns, &[0, 1, 2], mode);
+            assert_eq!(rect(&anns[0]), (10.0, 20.0, 40.0, 30.0));
+            (changed, rect(&anns[1]), rect(&anns[2]))
+        };
+
+        let left = aligned(AlignMode::Left);
+        assert_eq!(left, (2, (10.0, 60.0, 10.0, 10.0), (10.0, 5.0, 20.0, 50.0)));
+        let right = aligned(AlignMode::Right);
+        assert_eq!(right, (2, (40.0, 60.0, 10.0, 10.0), (30.0, 5.0, 20.0, 50.0)));
+        let top = aligned(AlignMode::Top);
+        assert_eq!(top, (2, (70.0, 20.0, 10.0, 10.0), (5.0, 20.0, 20.0, 50.0)));
+        let bottom = aligned(AlignMode::Bottom);
+        assert_eq!(bottom, (2, (70.0, 40.0, 10.0, 10.0), (5.0, 0.0, 20.0, 50.0)));
+        let width = aligned(AlignMode::SameWidth);
+        assert_eq!(width, (2, (70.0, 60.0, 40.0, 10.0), (5.0, 5.0, 40.0, 50.0)));
+        let height = aligned(AlignMode::SameHeight);
+        assert_eq!(height, (2, (70.0, 60.0, 10.0, 30.0), (5.0, 5.0, 20.0, 30.0)));
+
+        // The first selected box is the reference; locked boxes stay put
+        let mut anns = boxes.clone();
+        anns[1].locked = true;
+        assert_eq!(align_boxes(&mut anns, &[2, 1, 0], AlignMode::Left), 1);
+        assert_eq!(rect(&anns[0]), (5.0, 20.0, 40.0, 30.0));
+        assert_eq!(rect(&anns[1]), (70.0, 60.0, 10.0, 10.0));
+        assert_eq!(AlignMode::parse("same_height"), Some(AlignMode::SameHeight));
+    }
+
+    #[test]
+    fn test_point_box_conversion_keeps_center_class_and_state() {
+        let point = Annotation {
+            id: 7,
+            r#type: "point".into(),
+            x: 40.0,
+            y: 60.0,
+            class: 3,
+            state: "Accepted".into(),
+            ..Default::default()
+        };
+        let boxed = point_to_box(&point, 20.0);
+        assert_eq!(boxed.r#type, "bbox");
+        assert_eq!((boxed.x, boxed.y, boxed.width, boxed.height), (30.0, 50.0, 20.0, 20.0));
+        assert_eq!((boxed.id, boxed.class, boxed.state.as_str()), (7, 3, "Accepted"));
+
+        let back = box_to_point(&boxed);
+        assert_eq!(back.r#type, "point");
+        assert_eq!((back.x, back.y, back.width, back.height), (40.0, 60.0, 0.0, 0.0));
+        assert_eq!((back.id, back.class, back.state.as_str()), (7, 3, "Accepted"));
+
+        // Bulk conversion skips locked, rejected and other types
+        let mut locked = point.clone();
+        locked.locked = true;
+        let mut anns = vec![point.clone(), locked, bbox(2, 0.0, 0.0, 10.0, 30.0)];
+        assert_eq!(points_to_boxes(&mut anns, 8.0), 1);
+        let types: Vec<&str> = anns.iter().map(|a| a.r#type.as_str()).collect();
+        assert_eq!(types, vec!["bbox", "point", "bbox"]);
+        assert_eq!(boxes_to_points(&mut anns), 2);
+        assert_eq!((anns[0].x, anns[0].y), (40.0, 60.0));
+        assert_eq!((anns[2].x, anns[2].y, anns[2].class), (5.0, 15.0, 2));
+    }
+
+    #[test]
+    fn test_plain_click_on_group_member_keeps_or_collapses() {
+        let group = [true, false, true, true];
+        let only = |i: usize| -> Vec<bool> { (0..4).map(|j| j == i).collect() };
+
+        // Default: any plain click collapses to the clicked annotation
+        assert_eq!(plain_click_selection(&group, 2, false, false), only(2));
+        assert_eq!(plain_click_selection(&group, 2, false, true), only(2));
+
+        // keep_group: dragging a member keeps the group, a plain release collapses
+        assert_eq!(plain_click_selection(&group, 2, true, true), group.to_vec());
+        assert_eq!(plain_click_selection(&group, 2, true, false), only(2));
+        // Dragging an unselected annotation, or a lone selection, selects just it
+        assert_eq!(plain_click_selection(&group, 1, true, true), only(1));
+        assert_eq!(plain_click_selection(&only(0), 0, true, true), only(0));
+    }
+
+    #[test]
+    fn test_isolate_hides_unselected_until_cleared() {
+        let mut anns = vec![
+            bbox(1, 0.0, 0.0, 50.0, 50.0),
+            bbox(2, 10.0, 10.0, 20.0, 20.0),
+            bbox(3, 100.0, 100.0, 10.0, 10.0),
+        ];
+        // Nothing selected: nothing to isolate
+        assert_eq!(isolate_selected(&mut anns), 0);
+
+        anns[0].selected = true;
+        assert_eq!(isolate_selected(&mut anns), 2);
+        let hidden: Vec<bool> = anns.iter().map(|a| a.hidden).collect();
+        assert_eq!(hidden, vec![false, true, true]);
+        // Hidden boxes no longer catch clicks, even when drawn on top
+        assert_eq!(topmost_hit(&anns, 15.0, 15.0), Some(0));
+        assert_eq!(nearest_annotation(&anns, 104.0, 104.0), Some(0));
+
+        assert_eq!(clear_isolation(&mut anns), 2);
+        assert!(anns.iter().all(|a| !a.hidden));
+        assert_eq!(topmost_hit(&anns, 15.0, 15.0), Some(1));
+    }
+
+    #[test]
+    fn test_merge_selected_boxes_takes_union() {
+        let one = [bbox(2, 10.0, 20.0, 30.0, 40.0)];
+        assert_eq!(union_bbox(&one), (10.0, 20.0, 30.0, 40.0));
+        assert_eq!(union_bbox(&[]), (0.0, 0.0, 0.0, 0.0));
+
+        let selected = |id: i32, mut ann: Annotation| {
+            ann.id = id;
+            ann.selected = true;
+            ann
+        };
+        let point = Annotation { r#type: "point".into(), x: 500.0, y: 500.0, ..one[0].clone() };
+        let mut anns = vec![
+            selected(1, bbox(2, 10.0, 20.0, 30.0, 40.0)),
+            bbox(4, 0.0, 0.0, 5.0, 5.0), // not selected
+            selected(3, point),
+            selected(4, bbox(5, 35.0, 50.0, 20.0, 30.0)),
+        ];
+        assert_eq!(union_bbox(&[anns[0].clone(), anns[3].clone()]), (10.0, 20.0, 45.0, 60.0));
+
+        // A lone selected box is a no-op
+        let mut single = anns[..3].to_vec();
+        assert_eq!(merge_selected_boxes(&mut single), 0);
+        assert_eq!(single, anns[..3].to_vec());
+
+        // Only the boxes merge; the first keeps its id and class, the point stays
+        assert_eq!(merge_selected_boxes(&mut anns), 1);
+        assert_eq!(anns.len(), 3);
+        assert_eq!((anns[0].id, anns[0].class), (1, 2));
+        let merged = &anns[0];
+        assert_eq!((merged.x, merged.y, merged.width, merged.height), (10.0, 20.0, 45.0, 60.0));
+        assert_eq!(anns[2].r#type, "point");
+        assert_eq!(anns[1].width, 5.0);
+    }
+
+    #[test]
+    fn test_lock_on_create_class_resists_delete() {
+        let mut classes = ClassConfig::default();
+        classes.classes[1].lock_on_create = true;
+
+        let mut marker = bbox(2, 0.0, 0.0, 10.0, 10.0);
+        apply_lock_on_create(&mut marker, &classes);
+        assert!(marker.locked);
+        assert!(!can_edit(&marker));
+        assert!(!reject(&mut marker));
+        assert_eq!(marker.state, "Manual");
+
+        let mut plain = bbox(1, 0.0, 0.0, 10.0, 10.0);
+        apply_lock_on_create(&mut plain, &classes);
+        assert!(!plain.locked);
+
+        let mut anns = vec![marker, plain, bbox(2, 5.0, 5.0, 10.0, 10.0)];
+        assert_eq!(reject_by_class(&mut anns, 2), 1);
+        assert_eq!(anns[0].state, "Manual");
+        assert_eq!(anns[2].state, "Rejected");
+    }
+
+    #[test]
+    fn test_mirror_box_and_point() {
+        let mut b = bbox(1, 10.0, 20.0, 30.0, 40.0);
+        b.r#type = "rbbox".into();
+        b.rotation = 15.0;
+        mirror_annotation(&mut b, (100.0, 200.0), MirrorAxis::Vertical);
+        assert_eq!((b.x, b.y, b.width, b.height), (60.0, 20.0, 30.0, 40.0));
+        assert_eq!(b.rotation, -15.0);
+
+        mirror_annotation(&mut b, (100.0, 200.0), MirrorAxis::Horizontal);
+        assert_eq!((b.x, b.y), (60.0, 140.0));
+
+        let mut p = Annotation {
+            r#type: "point".into(),
+            x: 25.0,
+            y: 5.0,
+            ..Default::default()
+        };
+        mirror_annotation(&mut p, (100.0, 50.0), MirrorAxis::Vertical);
+        assert_eq!((p.x, p.y), (75.0, 5.0));
+        // Out-of-image points are clamped back inside
+        p.y = -10.0;
+        mirror_annotation(&mut p, (100.0, 50.0), MirrorAxis::Horizontal);
+        assert_eq!(p.y, 50.0);
+    }
+
+    #[test]
+    fn test_mirror_polygon() {
+        let mut poly = Annotation {
+            r#type: "polygon".into(),
+            ..Default::default()
+        };
+        set_polygon_points(&mut poly, &[(10.0, 10.0), (30.0, 10.0), (30.0, 40.0)]);
+        mirror_annotation(&mut poly, (100.0, 100.0), MirrorAxis::Vertical);
+        assert_eq!(polygon_points(&poly), vec![(90.0, 10.0), (70.0, 10.0), (70.0, 40.0)]);
+        assert_eq!((poly.x, poly.y, poly.width, poly.height), (70.0, 10.0, 20.0, 30.0));
+    }
+
+    #[test]
+    fn test_polygon_hole_kept_through_edits() {
+        let mut poly = Annotation {
+            r#type: "polygon".into(),
+            ..Default::default()
+        };
+        set_polygon_points(&mut poly, &[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        assert!(!add_polygon_hole(&mut poly, &[(40.0, 40.0), (60.0, 40.0)]));
+        assert!(add_polygon_hole(&mut poly, &[(40.0, 40.0), (60.0, 40.0), (60.0, 60.0)]));
+        assert_eq!(poly.vertices, "0,0;100,0;100,100;0,100|40,40;60,40;60,60");
+        assert_eq!(poly.polygon_path_commands.matches('M').count(), 2);
+
+        // The outline alone drives the bbox and outline edits; the hole stays
+        assert_eq!(polygon_points(&poly).len(), 4);
+        assert_eq!((poly.width, poly.height), (100.0, 100.0));
+        set_polygon_points(&mut poly, &[(0.0, 0.0), (100.0, 0.0), (50.0, 100.0)]);
+        assert_eq!(polygon_rings(&poly).len(), 2);
+
+        translate(&mut poly, 5.0, 0.0);
+        assert_eq!(polygon_rings(&poly)[1][0], (45.0, 40.0));
+    }
+
+    #[test]
+    fn test_polygon_vertex_edits_follow_rings() {
+        let mut poly = Annotation {
+            r#type: "polygon".into(),
+            state: "Pending".into(),
+            ..Default::default()
+        };
+        set_polygon_points(&mut poly, &[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        assert!(add_polygon_hole(&mut poly, &[(40.0, 40.0), (60.0, 40.0), (60.0, 60.0)]));
+
+        // Vertex 4 is the hole's first vertex
+        move_polygon_vertex(&mut poly, 4, (45.0, 45.0)).unwrap();
+        assert_eq!(poly.vertices, "0,0;100,0;100,100;0,100|45,45;60,40;60,60");
+        assert_eq!(poly.state, "Accepted");
+
+        // The last outline vertex's edge closes back to the first
+        insert_polygon_vertex(&mut poly, 3, (-20.0, 50.0)).unwrap();
+        assert_eq!(poly.vertices, "0,0;100,0;100,100;0,100;-20,50|45,45;60,40;60,60");
+        assert_eq!(poly.polygon_vertices.row_count(), 8);
+        assert_eq!((poly.x, poly.width), (-20.0, 120.0));
+
+        delete_polygon_vertex(&mut poly, 4).unwrap();
+        assert_eq!(poly.vertices, "0,0;100,0;100,100;0,100|45,45;60,40;60,60");
+        // The hole is down to three vertices
+        assert!(delete_polygon_vertex(&mut poly, 6).is_err());
+        assert!(move_polygon_vertex(&mut poly, 7, (0.0, 0.0)).is_err());
+
+        poly.locked = true;
+        assert!(delete_polygon_vertex(&mut poly, 0).is_err());
+        assert_eq!(polygon_points(&poly).len(), 4);
+    }
+
+    #[test]
+    fn test_mirrored_class_swaps_pairs() {
+        let pairs = [[3, 4]];
+        assert_eq!(mirrored_class(3, &pairs), 4);
+        assert_eq!(mirrored_class(4, &pairs), 3);
+        assert_eq!(mirrored_class(1, &pairs), 1);
+    }
+
+    #[test]
+    fn test_overlapping_indices_finds_only_the_pair() {
+        let anns = vec![
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+            bbox(1, 100.0, 100.0, 10.0, 10.0),
+            bbox(2, 1.0, 1.0, 10.0, 10.0),
+        ];
+        assert_eq!(overlapping_indices(&anns, 0.5), vec![0, 2]);
+        assert!(overlapping_indices(&anns, 0.9).is_empty());
+
+        // Rejected rows don't count as overlaps
+        let mut with_rejected = anns.clone();
+        reject(&mut with_rejected[2]);
+        assert!(overlapping_indices(&with_rejected, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_nearest_annotation() {
+        let point = Annotation {
+            r#type: "point".into(),
+            x: 50.0,
+            y: 50.0,
+            state: "Manual".into(),
+            ..Default::default()
+        };
+        let anns = vec![
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+            bbox(2, 20.0, 0.0, 10.0, 10.0),
+            point,
+        ];
+
+        // Inside a box counts as distance 0
+        assert_eq!(nearest_annotation(&anns, 25.0, 5.0), Some(1));
+        assert_eq!(nearest_annotation(&anns, 48.0, 49.0), Some(2));
+        // Equidistant from both box edges: the lower index wins
+        assert_eq!(nearest_annotation(&anns, 15.0, 5.0), Some(0));
+
+        let mut rejected = anns.clone();
+        reject(&mut rejected[0]);
+        assert_eq!(nearest_annotation(&rejected, 15.0, 5.0), Some(1));
+        assert_eq!(nearest_annotation(&[], 0.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_nearest_annotation_uses_rotated_outline() {
+        // Standing upright, the long box covers x 45–55, y 10–90
+        let upright = Annotation {
+            r#type: "rbbox".into(),
+            rotation: 90.0,
+            ..bbox(1, 10.0, 45.0, 80.0, 10.0)
+        };
+        let anns = vec![upright, bbox(2, 70.0, 45.0, 10.0, 10.0)];
+
+        // Inside the unrotated extent but 11 px from the rotated outline;
+        // the other box is 4 px away
+        assert_eq!(nearest_annotation(&anns, 66.0, 50.0), Some(1));
+        // Above the unrotated extent yet inside the rotated box
+        assert_eq!(nearest_annotation(&anns, 50.0, 20.0), Some(0));
+    }
+
+    #[test]
+    fn test_duplicate_selects_copies_with_new_ids() {
+        let mut anns = vec![
+            Annotation { id: 4, selected: true, ..bbox(1, 10.0, 10.0, 20.0, 20.0) },
+            Annotation { id: 7, ..bbox(2, 50.0, 50.0, 10.0, 10.0) },
+            Annotation { id: 9, selected: true, ..bbox(3, 90.0, 0.0, 10.0, 10.0) },
+        ];
+        let count = duplicate_selected(&mut anns, DUPLICATE_OFFSET, (100.0, 100.0), "bob");
+        assert_eq!(count, 2);
+        assert_eq!(anns.len(), 5);
+
+        let selected: Vec<i32> = anns.iter().filter(|a| a.selected).map(|a| a.id).collect();
+        assert_eq!(selected, vec![10, 11]);
+        let mut ids: Vec<i32> = anns.iter().map(|a| a.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+
+        assert_eq!((anns[3].x, anns[3].y, anns[3].class), (20.0, 20.0, 1));
+        assert_eq!(anns[3].author, "bob");
+        // Already touching the right edge: only moved down
+        assert_eq!((anns[4].x, anns[4].y), (90.0, 10.0));
+    }
+
+    #[test]
+    fn test_rotate_selected_turns_only_editable_rotated_boxes() {
+        let rbox = |rotation: f32| Annotation {
+            r#type: "rbbox".into(),
+            rotation,
+            selected: true,
+            ..bbox(1, 10.0, 10.0, 20.0, 10.0)
+        };
+        let mut anns = vec![
+            rbox(0.0),
+            rbox(88.0),
+            Annotation { selected: true, ..bbox(1, 0.0, 0.0, 5.0, 5.0) },
+            Annotation { locked: true, ..rbox(10.0) },
+            Annotation { selected: false, ..rbox(20.0) },
+        ];
+        assert_eq!(rotate_selected(&mut anns, ROTATE_STEP), 2);
+        let rotations: Vec<f32> = anns.iter().map(|a| a.rotation).collect();
+        // 93° wraps to the same box at -87°
+        assert_eq!(rotations, vec![5.0, -87.0, 0.0, 10.0, 20.0]);
+        assert!(parse_millis(&anns[0].modified_at) > 0);
+    }
+
+    #[test]
+    fn test_paste_offsets_by_image_fraction_and_stays_inside() {
+        let copied = vec![
+            Annotation { id: 3, selected: true, ..bbox(1, 100.0, 50.0, 40.0, 20.0) },
+            Annotation { id: 5, ..bbox(2, 370.0, 185.0, 20.0, 10.0) },
+        ];
+        let mut anns = copied.clone();
+        anns[0].locked = true;
+        anns[0].group_id = 4;
+        let copied = anns.clone();
+        assert_eq!(paste_annotations(&mut anns, &copied, (400.0, 200.0), "ana"), 2);
+
+        // 5% of 400 x 200 is 20 px right and 10 px down
+        assert_eq!((anns[2].id, anns[2].x, anns[2].y), (6, 120.0, 60.0));
+        assert!(!anns[2].selected);
+        // A pasted copy is a new object, like a duplicate
+        assert!(!anns[2].locked);
+        assert_eq!(anns[2].group_id, NO_GROUP);
+        assert_eq!(anns[2].author, "ana");
+        // Near the bottom-right corner the copy stops at the edge
+        assert_eq!((anns[3].id, anns[3].x, anns[3].y), (7, 380.0, 190.0));
+
+        // A point is kept inside by its position alone
+        let point = Annotation { r#type: "point".into(), ..bbox(1, 395.0, 195.0, 0.0, 0.0) };
+        paste_annotations(&mut anns, &[point], (400.0, 200.0), "ana");
+        assert_eq!((anns[4].x, anns[4].y), (400.0, 200.0));
+    }
+}
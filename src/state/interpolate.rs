@@ -0,0 +1,277 @@
+//! Keyframe interpolation of boxes across the frames between two keyframes.
+
+use super::dataset::{cached_frame_mut, frame_annotations, next_id_from_annotations};
+use super::edits::{group_of, stamp_created};
+use super::types::{DatasetState, UndoHistory, NO_GROUP};
+use crate::geometry::iou;
+use crate::Annotation;
+
+/// Earlier frames searched for a start keyframe. Unvisited frames are read
+/// from disk, so a box without a match mustn't walk the whole dataset.
+pub const KEYFRAME_SEARCH_FRAMES: usize = 250;
+
+/// How the interpolation parameter advances between two keyframes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant velocity
+    #[default]
+    Linear,
+    /// Slow start and stop (smoothstep)
+    EaseInOut,
+}
+
+impl Easing {
+    /// Parse a UI name (`linear`, `ease_in_out`); unknown names are `None`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "linear" => Some(Easing::Linear),
+            "ease_in_out" => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+}
+
+/// Map `t` in 0–1 through the easing curve; 0 stays 0 and 1 stays 1
+pub fn ease(t: f32, kind: Easing) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match kind {
+        Easing::Linear => t,
+        Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+}
+
+/// Box (x, y, w, h) at `t` between `start` (t = 0) and `end` (t = 1)
+pub fn interpolate_box(
+    start: (f32, f32, f32, f32),
+    end: (f32, f32, f32, f32),
+    t: f32,
+    easing: Easing,
+) -> (f32, f32, f32, f32) {
+    let t = ease(t, easing);
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    (lerp(start.0, end.0), lerp(start.1, end.1), lerp(start.2, end.2), lerp(start.3, end.3))
+}
+
+/// Fill the frames before `end` with boxes moving towards `end_boxes`. Each
+/// bbox is matched to the nearest box of its class on the closest earlier frame
+/// that has one, at most `KEYFRAME_SEARCH_FRAMES` back (the start keyframe);
+/// frames in between get an interpolated Pending box unless a box of that class
+/// already overlaps it. Frames not visited yet are read from disk first. A box
+/// on a track is matched to the same track when it can be, and the filled boxes
+/// carry the track id forward. Each frame that gains a box gets one undo step
+/// in its own history, so Ctrl+Z on that frame takes the fill back out;
+/// `history` is left holding frame `end`'s steps. Those steps are parked like
+/// any other frame's, so a fill over more than `MAX_PARKED_UNDO_FRAMES` frames
+/// keeps undo only for the frames nearest `end`. Returns the number of boxes
+/// added.
+pub fn interpolate_from_keyframe(
+    ds: &mut DatasetState,
+    history: &mut UndoHistory,
+    end: usize,
+    end_boxes: &[Annotation],
+    easing: Easing,
+    author: &str,
+) -> usize {
+    ds.ensure_len();
+    let mut added = 0;
+    // Each touched frame as it was before this fill, in the order touched
+    let mut before: Vec<(usize, Vec<Annotation>)> = Vec::new();
+    for target in end_boxes.iter().filter(|a| a.r#type == "bbox") {
+        let Some((start, key)) = find_keyframe(ds, end, target) else { continue };
+        for idx in start + 1..end {
+            let t = (idx - start) as f32 / (end - start) as f32;
+            let bbox = interpolate_box(rect(&key), rect(target), t, easing);
+            let anns = cached_frame_mut(ds, idx);
+            let covered = anns.iter().any(|a| {
+                a.class == target.class && a.state != "Rejected" && iou(rect(a), bbox) >= 0.5
+            });
+            if covered {
+                continue;
+            }
+            if !before.iter().any(|(frame, _)| *frame == idx) {
+                before.push((idx, anns.clone()));
+            }
+            let group_id = group_of(target).or(group_of(&key)).unwrap_or(NO_GROUP);
+            let mut ann = Annotation {
+                id: next_id_from_annotations(anns, 1),
+                r#type: "bbox".into(),
+                x: bbox.0,
+                y: bbox.1,
+                width: bbox.2,
+                height: bbox.3,
+                class: target.class,
+                state: "Pending".into(),
+                group_id,
+                ..Default::default()
+            };
+            stamp_created(&mut ann, author);
+            anns.push(ann);
+            added += 1;
+        }
+    }
+    for (idx, snapshot) in before {
+        ds.switch_undo_frame(history, idx);
+        history.push(snapshot);
+    }
+    ds.switch_undo_frame(history, end);
+    added
+}
+
+fn rect(a: &Annotation) -> (f32, f32, f32, f32) {
+    (a.x, a.y, a.width, a.height)
+}
+
+/// Closest earlier frame (within `KEYFRAME_SEARCH_FRAMES`) with a live bbox of
+/// `target`'s class, and the box on it on `target`'s track, or else the one
+/// whose centre is nearest to `target`'s
+fn find_keyframe(
+    ds: &DatasetState,
+    end: usize,
+    target: &Annotation,
+) -> Option<(usize, Annotation)> {
+    let centre = |a: &Annotation| (a.x + a.width / 2.0, a.y + a.height / 2.0);
+    let (tx, ty) = centre(target);
+    let end = end.min(ds.entries.len());
+    (end.saturating_sub(KEYFRAME_SEARCH_FRAMES)..end).rev().find_map(|idx| {
+        frame_annotations(ds, idx)
+            .into_iter()
+            .filter(|a| a.r#type == "bbox" && a.class == target.class && a.state != "Rejected")
+            .min_by(|a, b| {
+                let dist = |a: &Annotation| {
+                    let (x, y) = centre(a);
+                    (x - tx).powi(2) + (y - ty).powi(2)
+                };
+                let off_track =
+                    |a: &Annotation| group_of(target).is_some() && group_of(a) != group_of(target);
+                off_track(a).cmp(&off_track(b)).then(dist(a).total_cmp(&dist(b)))
+            })
+            .map(|key| (idx, key))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{test_bbox, DatasetEntry};
+
+    /// 20×20 box with `id` at `(x, 10)`
+    fn bbox(id: i32, class: i32, x: f32) -> Annotation {
+        Annotation { id, ..test_bbox(class, x, 10.0, 20.0, 20.0) }
+    }
+
+    #[test]
+    fn test_ease_endpoints_and_midpoint() {
+        for kind in [Easing::Linear, Easing::EaseInOut] {
+            assert_eq!(ease(0.0, kind), 0.0);
+            assert_eq!(ease(1.0, kind), 1.0);
+            assert_eq!(ease(0.5, kind), 0.5);
+            // Out-of-range parameters clamp to the endpoints
+            assert_eq!(ease(-1.0, kind), 0.0);
+            assert_eq!(ease(2.0, kind), 1.0);
+        }
+        assert_eq!(ease(0.25, Easing::Linear), 0.25);
+        // Ease-in-out lags at the start and leads near the end
+        assert!(ease(0.25, Easing::EaseInOut) < 0.25);
+        assert!(ease(0.75, Easing::EaseInOut) > 0.75);
+
+        assert_eq!(Easing::from_name("ease-in-out"), Some(Easing::EaseInOut));
+        assert_eq!(Easing::from_name("bounce"), None);
+    }
+
+    #[test]
+    fn test_interpolate_fills_frames_between_keyframes() {
+        let entries = (0..5)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_{i}.png").into(),
+                labels_path: Some(format!("missing_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        let mut history = UndoHistory::new(10);
+        ds.ensure_len();
+        ds.stored_annotations[0] = Some(vec![bbox(1, 2, 0.0), bbox(2, 3, 500.0)]);
+        for slot in &mut ds.stored_annotations[1..4] {
+            *slot = Some(Vec::new());
+        }
+        // Other classes on the way neither stop the search nor block the fill
+        ds.stored_annotations[2] = Some(vec![bbox(7, 3, 50.0)]);
+
+        let end = [bbox(1, 2, 100.0)];
+        let added = interpolate_from_keyframe(&mut ds, &mut history, 4, &end, Easing::Linear, "");
+        assert_eq!(added, 3);
+        let frame1 = ds.stored_annotations[1].as_ref().unwrap();
+        assert_eq!(frame1.len(), 1);
+        assert_eq!((frame1[0].x, frame1[0].class), (25.0, 2));
+        assert_eq!(frame1[0].state, "Pending");
+        let frame2 = ds.stored_annotations[2].as_ref().unwrap();
+        assert_eq!((frame2.len(), frame2[1].x, frame2[1].id), (2, 50.0, 8));
+        assert_eq!(ds.stored_annotations[3].as_ref().unwrap()[0].x, 75.0);
+
+        // Frame 3 is now the nearest keyframe, so a second run adds nothing
+        let added =
+            interpolate_from_keyframe(&mut ds, &mut history, 4, &end, Easing::EaseInOut, "");
+        assert_eq!(added, 0);
+        assert_eq!(ds.undo_frame, Some(4));
+        assert!(!history.can_undo());
+
+        // Each filled frame got one undo step back to its boxes before the fill
+        ds.switch_undo_frame(&mut history, 1);
+        assert_eq!(history.undo(Vec::new()), Some(Vec::new()));
+        assert!(!history.can_undo());
+        ds.switch_undo_frame(&mut history, 2);
+        assert_eq!(history.undo(Vec::new()), Some(vec![bbox(7, 3, 50.0)]));
+    }
+
+    #[test]
+    fn test_interpolate_carries_group_id_forward() {
+        let entries = (0..3)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_{i}.png").into(),
+                labels_path: Some(format!("missing_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        let mut history = UndoHistory::new(10);
+        ds.ensure_len();
+        // The nearer box is on another track, so track 5 is followed instead
+        ds.stored_annotations[0] = Some(vec![
+            Annotation { group_id: 9, ..bbox(1, 2, 90.0) },
+            Annotation { group_id: 5, ..bbox(2, 2, 0.0) },
+        ]);
+        ds.stored_annotations[1] = Some(Vec::new());
+
+        let end = [Annotation { group_id: 5, ..bbox(1, 2, 100.0) }];
+        let added = interpolate_from_keyframe(&mut ds, &mut history, 2, &end, Easing::Linear, "");
+        assert_eq!(added, 1);
+        let filled = &ds.stored_annotations[1].as_ref().unwrap()[0];
+        assert_eq!((filled.x, filled.group_id), (50.0, 5));
+
+        // A target without a track picks up the keyframe's
+        ds.stored_annotations[1] = Some(Vec::new());
+        let untracked = [bbox(1, 2, 100.0)];
+        interpolate_from_keyframe(&mut ds, &mut history, 2, &untracked, Easing::Linear, "");
+        assert_eq!(ds.stored_annotations[1].as_ref().unwrap()[0].group_id, 9);
+    }
+
+    #[test]
+    fn test_keyframe_search_stops_at_window() {
+        let len = KEYFRAME_SEARCH_FRAMES + 2;
+        let entries = (0..len)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_{i}.png").into(),
+                labels_path: Some(format!("missing_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        for slot in ds.stored_annotations.iter_mut() {
+            *slot = Some(Vec::new());
+        }
+        ds.stored_annotations[0] = Some(vec![bbox(1, 2, 0.0)]);
+        let target = bbox(1, 2, 100.0);
+
+        // Frame 0 is one frame beyond the window from the last frame
+        assert!(find_keyframe(&ds, len - 1, &target).is_none());
+        assert_eq!(find_keyframe(&ds, len - 2, &target).map(|(idx, _)| idx), Some(0));
+    }
+}
@@ -6,9 +6,27 @@
 //! - Drawing and resize states
 //! - Undo/redo history
 //! - Stored annotation format
+//! - Pure annotation edit helpers
+//! - Datasets extracted from video files
+//! - Batch renaming of images with their label files
+//! - Per-class usage across the dataset
+//! - Keyframe interpolation of boxes with easing
+//! - Review overlays flagging annotations to double-check
 
 mod types;
 mod dataset;
+mod edits;
+mod video;
+mod rename;
+mod usage;
+mod interpolate;
+mod corrections;
 
 pub use types::*;
 pub use dataset::*;
+pub use edits::*;
+pub use video::*;
+pub use rename::*;
+pub use usage::*;
+pub use interpolate::*;
+pub use corrections::*;
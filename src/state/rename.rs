@@ -0,0 +1,276 @@
+//! Batch renaming of dataset images together with their label and state files.
+
+use super::dataset::{label_path_for, state_path_for};
+use super::types::{DatasetEntry, DatasetFile, DatasetState};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Expand a rename pattern for the image at `index` (0-based).
+/// `{index}` is the 1-based position, `{index:05}` zero-pads it to 5 digits,
+/// and `{stem}` is the current file name without extension.
+fn format_name(pattern: &str, index: usize, stem: &str) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|e| start + e)
+            .ok_or_else(|| format!("Unclosed '{{' in rename pattern '{pattern}'"))?;
+        let placeholder = &rest[start + 1..end];
+        match placeholder.split_once(':') {
+            None if placeholder == "index" => name.push_str(&(index + 1).to_string()),
+            None if placeholder == "stem" => name.push_str(stem),
+            Some(("index", width)) => {
+                let width: usize = width
+                    .trim_start_matches('0')
+                    .parse()
+                    .map_err(|_| format!("Invalid width in '{{{placeholder}}}'"))?;
+                name.push_str(&format!("{:0width$}", index + 1));
+            }
+            _ => return Err(format!("Unknown placeholder '{{{placeholder}}}' in rename pattern")),
+        }
+        rest = &rest[end + 1..];
+    }
+    name.push_str(rest);
+
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("Rename pattern '{pattern}' gives an invalid file name '{name}'"));
+    }
+    Ok(name)
+}
+
+/// Replace a path's file stem, keeping its folder and extension
+fn with_stem(path: &Path, stem: &str) -> PathBuf {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => path.with_file_name(format!("{stem}.{ext}")),
+        None => path.with_file_name(stem),
+    }
+}
+
+/// Entries as they will be after renaming every image with `pattern`
+pub fn renamed_entries(entries: &[DatasetEntry], pattern: &str) -> Result<Vec<DatasetEntry>, String> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let stem = entry.image_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let new_stem = format_name(pattern, idx, stem)?;
+            Ok(DatasetEntry {
+                image_path: with_stem(&entry.image_path, &new_stem),
+                labels_path: entry.labels_path.as_ref().map(|p| with_stem(p, &new_stem)),
+            })
+        })
+        .collect()
+}
+
+/// Plan the `(from, to)` moves for renaming every image with `pattern`,
+/// including each image's label file and state sidecar. Unchanged paths are
+/// left out; two files mapping to the same destination is an error.
+pub fn plan_renames(
+    entries: &[DatasetEntry],
+    pattern: &str,
+    label_extension: &str,
+) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let renamed = renamed_entries(entries, pattern)?;
+    let mut plan = Vec::new();
+    let mut destinations = HashSet::new();
+
+    for (old, new) in entries.iter().zip(&renamed) {
+        let moves = [
+            (old.image_path.clone(), new.image_path.clone()),
+            (label_path_for(old, label_extension), label_path_for(new, label_extension)),
+            (state_path_for(old, label_extension), state_path_for(new, label_extension)),
+        ];
+        for (from, to) in moves {
+            if !destinations.insert(to.clone()) {
+                return Err(format!("Rename collision: more than one file would become {}", to.display()));
+            }
+            if from != to {
+                plan.push((from, to));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Perform planned moves as one unit: files are staged under temporary names
+/// (so swaps and chains work) and every move is undone if any step fails.
+/// Sources that don't exist (e.g. frames without labels yet) are skipped.
+pub fn apply_renames(plan: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    let moves: Vec<&(PathBuf, PathBuf)> = plan.iter().filter(|(from, _)| from.exists()).collect();
+    let sources: HashSet<&Path> = moves.iter().map(|(from, _)| from.as_path()).collect();
+    for (_, to) in &moves {
+        if to.exists() && !sources.contains(to.as_path()) {
+            return Err(format!("{} already exists", to.display()));
+        }
+    }
+
+    let staged: Vec<(&Path, PathBuf, &Path)> = moves
+        .iter()
+        .enumerate()
+        .map(|(i, (from, to))| {
+            let name = from.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+            (from.as_path(), from.with_file_name(format!(".renaming-{i}-{name}")), to.as_path())
+        })
+        .collect();
+
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut result = Ok(());
+    let steps = staged
+        .iter()
+        .map(|(from, tmp, _)| (from.to_path_buf(), tmp.clone()))
+        .chain(staged.iter().map(|(_, tmp, to)| (tmp.clone(), to.to_path_buf())));
+    for (from, to) in steps {
+        if let Err(e) = fs::rename(&from, &to) {
+            result = Err(format!("Rename {} failed: {e}", from.display()));
+            break;
+        }
+        done.push((from, to));
+    }
+
+    if result.is_err() {
+        for (from, to) in done.iter().rev() {
+            let _ = fs::rename(to, from);
+        }
+    }
+    result
+}
+
+/// Rename every image of `ds` with `pattern`, move its label and state files,
+/// and rewrite the manifest. Nothing changes on disk if any step fails.
+/// Returns the number of images whose name changed.
+pub fn rename_dataset_images(ds: &mut DatasetState, pattern: &str) -> Result<usize, String> {
+    let renamed = renamed_entries(&ds.entries, pattern)?;
+    let plan = plan_renames(&ds.entries, pattern, &ds.label_extension)?;
+
+    // Prepare the new manifest before touching any file
+    let manifest = match ds.manifest_path.as_ref() {
+        Some(path) => {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("Failed to read manifest: {e}"))?;
+            let mut file: DatasetFile = serde_json::from_str(&text)
+                .map_err(|e| format!("Failed to parse manifest: {e}"))?;
+            let base_dir = path.parent().unwrap_or(Path::new("."));
+            let new_by_old: HashMap<&Path, &DatasetEntry> = ds
+                .entries
+                .iter()
+                .map(|e| e.image_path.as_path())
+                .zip(&renamed)
+                .collect();
+            for item in file.images.iter_mut() {
+                let Some(new) = new_by_old.get(base_dir.join(&item.image).as_path()) else {
+                    continue;
+                };
+                if let Some(name) = new.image_path.file_name() {
+                    item.image = file_name_swapped(&item.image, name);
+                }
+                if let (Some(labels), Some(name)) = (
+                    item.labels.as_mut(),
+                    new.labels_path.as_ref().and_then(|p| p.file_name()),
+                ) {
+                    *labels = file_name_swapped(labels, name);
+                }
+            }
+            let json = serde_json::to_string_pretty(&file)
+                .map_err(|e| format!("Failed to serialize manifest: {e}"))?;
+            Some((path.clone(), json))
+        }
+        None => None,
+    };
+
+    apply_renames(&plan)?;
+    if let Some((path, json)) = manifest {
+        if let Err(e) = fs::write(&path, json) {
+            let undo: Vec<(PathBuf, PathBuf)> =
+                plan.iter().map(|(from, to)| (to.clone(), from.clone())).collect();
+            let _ = apply_renames(&undo);
+            return Err(format!("Failed to write manifest: {e}"));
+        }
+    }
+
+    let count = ds
+        .entries
+        .iter()
+        .zip(&renamed)
+        .filter(|(old, new)| old.image_path != new.image_path)
+        .count();
+    ds.entries = renamed;
+    Ok(count)
+}
+
+/// Replace the file name of a manifest path string, keeping its folder part
+fn file_name_swapped(path: &str, name: &std::ffi::OsStr) -> String {
+    Path::new(path).with_file_name(name).to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scratch_dir;
+
+    fn entry(image: &str, labels: Option<&str>) -> DatasetEntry {
+        DatasetEntry {
+            image_path: PathBuf::from(image),
+            labels_path: labels.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn test_plan_renames_moves_labels_and_state() {
+        let entries = vec![
+            entry("data/img_a.png", Some("labels/img_a.txt")),
+            entry("data/img_b.jpg", None),
+        ];
+        let plan = plan_renames(&entries, "frame_{index:03}", "txt").unwrap();
+        let p = PathBuf::from;
+        assert_eq!(
+            plan,
+            vec![
+                (p("data/img_a.png"), p("data/frame_001.png")),
+                (p("labels/img_a.txt"), p("labels/frame_001.txt")),
+                (p("labels/img_a.state.json"), p("labels/frame_001.state.json")),
+                (p("data/img_b.jpg"), p("data/frame_002.jpg")),
+                (p("data/img_b.txt"), p("data/frame_002.txt")),
+                (p("data/img_b.state.json"), p("data/frame_002.state.json")),
+            ]
+        );
+
+        // Keeping the stem leaves the files where they are
+        assert!(plan_renames(&entries, "{stem}", "txt").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plan_renames_detects_collisions() {
+        let entries = vec![entry("a.png", None), entry("b.png", None)];
+        let err = plan_renames(&entries, "frame", "txt").unwrap_err();
+        assert!(err.contains("collision"), "{err}");
+
+        // Same stem with different image extensions still shares a label file
+        let entries = vec![entry("x/a.png", None), entry("x/a.jpg", None)];
+        assert!(plan_renames(&entries, "{stem}_v2", "txt").is_err());
+
+        assert!(plan_renames(&entries, "{frame}", "txt").is_err());
+        assert!(plan_renames(&entries, "sub/{index}", "txt").is_err());
+    }
+
+    #[test]
+    fn test_apply_renames_swaps_and_rolls_back() {
+        let dir = scratch_dir("rename");
+        fs::write(dir.join("a.png"), "a").unwrap();
+        fs::write(dir.join("b.png"), "b").unwrap();
+
+        // A swap goes through the temporary names
+        apply_renames(&[(dir.join("a.png"), dir.join("b.png")), (dir.join("b.png"), dir.join("a.png"))])
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.png")).unwrap(), "b");
+
+        // An existing file outside the plan blocks the rename before anything moves
+        fs::write(dir.join("c.png"), "c").unwrap();
+        assert!(apply_renames(&[(dir.join("a.png"), dir.join("c.png"))]).is_err());
+        assert_eq!(fs::read_to_string(dir.join("a.png")).unwrap(), "b");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,7 +1,7 @@
 //! Core state types for the annotator.
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // Re-export Annotation from Slint-generated code (will be imported in main.rs)
 // The Annotation type is generated by Slint and accessed via slint::include_modules!()
@@ -17,6 +17,9 @@ pub struct DatasetFileEntry {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatasetFile {
     pub images: Vec<DatasetFileEntry>,
+    /// Folder holding the labels of entries without `labels`, relative to the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels_dir: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub classes: Option<Vec<crate::classes::ClassDefinition>>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -30,8 +33,34 @@ pub struct DatasetEntry {
     pub labels_path: Option<PathBuf>,
 }
 
+/// Per-dataset progress sidecar stored next to the manifest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProgressFile {
+    /// View bookmarks by slot
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<Option<ViewState>>,
+    /// Frames marked complete by the annotator, by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed_frames: Vec<bool>,
+    /// Frames approved in review, by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reviewed_frames: Vec<bool>,
+    /// Whole-image tags, by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub image_tags: Vec<Vec<String>>,
+    /// Highest track id handed out so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_track_id: Option<i32>,
+}
+
+/// Number of view bookmark slots per dataset
+pub const BOOKMARK_SLOTS: usize = 5;
+
+/// Frames besides the current one that keep their undo history
+pub const MAX_PARKED_UNDO_FRAMES: usize = 20;
+
 /// View state for pan and zoom
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ViewState {
     pub pan_x: f32,
     pub pan_y: f32,
@@ -53,6 +82,33 @@ pub struct StoredAnnotation {
     pub class: i32,
     pub state: String,
     pub vertices: String,
+    /// Creation time in unix millis (0 for annotations predating this field)
+    #[serde(default)]
+    pub created_at: i64,
+    /// Last modification time in unix millis
+    #[serde(default)]
+    pub modified_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub locked: bool,
+    /// Flagged by a corrections overlay (omitted when false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub needs_review: bool,
+    /// Track id shared by the same object across frames ([`NO_GROUP`] = none)
+    #[serde(default = "no_group", skip_serializing_if = "is_no_group")]
+    pub group_id: i32,
+}
+
+/// Stored `group_id` of annotations that belong to no track
+pub const NO_GROUP: i32 = -1;
+
+fn no_group() -> i32 {
+    NO_GROUP
+}
+
+fn is_no_group(id: &i32) -> bool {
+    *id == NO_GROUP
 }
 
 /// Full dataset state including all entries and cached data
@@ -65,7 +121,343 @@ pub struct DatasetState {
     pub global_view: Option<ViewState>,
     pub last_view_image_size: Option<(f32, f32)>,
     pub completed_frames: Vec<bool>,
+    /// Frames approved in review; independent of completion
+    pub reviewed_frames: Vec<bool>,
+    /// Whole-image tags such as "daytime" or "rainy"
+    pub image_tags: Vec<Vec<String>>,
     pub class_config: Option<crate::classes::ClassConfig>,
+    /// Annotations as first loaded from disk this session (for revert)
+    pub initial_annotations: Vec<Option<Vec<crate::Annotation>>>,
+    /// Manifest this dataset was loaded from
+    pub manifest_path: Option<PathBuf>,
+    /// Extension of label files without an explicit path (`dataset.label_extension`)
+    pub label_extension: String,
+    /// Resolved manifest `labels_dir`, if the manifest set one
+    pub labels_dir: Option<PathBuf>,
+    /// Saved views by slot (persisted in the progress sidecar)
+    pub bookmarks: Vec<Option<ViewState>>,
+    /// Frame indices in the order next/previous step through them; `None`
+    /// follows the manifest. Indices (and so label paths) are never changed.
+    pub nav_order: Option<Vec<usize>>,
+    /// Pixel size of each frame's image once known, so saving needn't decode it
+    pub image_sizes: Vec<Option<(f32, f32)>>,
+    /// Undo stacks of frames other than the one whose history is live, least
+    /// recently left first; at most `MAX_PARKED_UNDO_FRAMES` are kept
+    pub undo_stacks: Vec<(usize, Vec<Vec<crate::Annotation>>)>,
+    /// Frame the live undo history belongs to; `None` until the first frame loads
+    pub undo_frame: Option<usize>,
+    /// Highest track id in the dataset (persisted); `None` until every frame
+    /// has been scanned once
+    pub max_track_id: Option<i32>,
+    /// Frames (by index) and progress as the last autosave backup wrote them,
+    /// or as loaded; frames missing here compare against `initial_annotations`
+    pub backed_up: (Vec<Option<Vec<crate::Annotation>>>, ProgressFile),
+}
+
+impl DatasetState {
+    /// Create a state for resolved entries with empty per-frame caches
+    pub fn new(
+        entries: Vec<DatasetEntry>,
+        class_config: Option<crate::classes::ClassConfig>,
+    ) -> Self {
+        let mut state = Self {
+            entries,
+            current_index: 0,
+            stored_annotations: Vec::new(),
+            view_states: Vec::new(),
+            global_view: None,
+            last_view_image_size: None,
+            completed_frames: Vec::new(),
+            reviewed_frames: Vec::new(),
+            image_tags: Vec::new(),
+            class_config,
+            initial_annotations: Vec::new(),
+            manifest_path: None,
+            label_extension: crate::config::DEFAULT_LABEL_EXTENSION.to_string(),
+            labels_dir: None,
+            bookmarks: vec![None; BOOKMARK_SLOTS],
+            nav_order: None,
+            image_sizes: Vec::new(),
+            undo_stacks: Vec::new(),
+            undo_frame: None,
+            max_track_id: None,
+            backed_up: Default::default(),
+        };
+        // Nothing to back up until something changes from this fresh state
+        let len = state.entries.len();
+        state.backed_up.1 = ProgressFile {
+            completed_frames: vec![false; len],
+            reviewed_frames: vec![false; len],
+            image_tags: vec![Vec::new(); len],
+            ..state.progress()
+        };
+        state
+    }
+
+    /// The progress sidecar contents for the current state
+    pub fn progress(&self) -> ProgressFile {
+        ProgressFile {
+            bookmarks: self.bookmarks.clone(),
+            completed_frames: self.completed_frames.clone(),
+            reviewed_frames: self.reviewed_frames.clone(),
+            image_tags: self.image_tags.clone(),
+            max_track_id: self.max_track_id,
+        }
+    }
+
+    /// Whether any frame or the progress differ from the last backup
+    pub fn changed_since_backup(&self) -> bool {
+        let (frames, progress) = &self.backed_up;
+        *progress != self.progress()
+            || self.stored_annotations.iter().enumerate().any(|(idx, stored)| {
+                let Some(stored) = stored else { return false };
+                let baseline = match frames.get(idx) {
+                    Some(Some(frame)) => Some(frame),
+                    _ => self.initial_annotations.get(idx).and_then(|f| f.as_ref()),
+                };
+                baseline != Some(stored)
+            })
+    }
+
+    /// Record the current frames and progress as backed up
+    pub fn mark_backed_up(&mut self) {
+        self.backed_up = (self.stored_annotations.clone(), self.progress());
+    }
+
+    /// Keep every per-frame cache exactly as long as `entries`
+    pub(crate) fn ensure_len(&mut self) {
+        let len = self.entries.len();
+        self.stored_annotations.resize(len, None);
+        self.view_states.resize(len, None);
+        self.completed_frames.resize(len, false);
+        self.reviewed_frames.resize(len, false);
+        self.image_tags.resize(len, Vec::new());
+        self.initial_annotations.resize(len, None);
+        self.image_sizes.resize(len, None);
+    }
+
+    /// Set the label extension and point entries without an explicit labels
+    /// path into `labels_dir`, mirroring the image's path below the manifest
+    pub fn set_label_extension(&mut self, extension: &str) {
+        self.label_extension = extension.trim_start_matches('.').to_string();
+        let Some(labels_dir) = &self.labels_dir else { return };
+        let base = self.manifest_path.as_ref().and_then(|p| p.parent());
+        for entry in self.entries.iter_mut().filter(|e| e.labels_path.is_none()) {
+            let rel = relative_to(base, &entry.image_path);
+            entry.labels_path = Some(labels_dir.join(rel).with_extension(&self.label_extension));
+        }
+    }
+
+    /// Image path below the manifest folder with `/` separators (just the
+    /// file name for images outside it); unique within the dataset
+    pub fn relative_image_path(&self, index: usize) -> Option<String> {
+        let base = self.manifest_path.as_ref().and_then(|p| p.parent());
+        let entry = self.entries.get(index)?;
+        let rel = relative_to(base, &entry.image_path);
+        let parts: Vec<_> = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+        Some(parts.join("/"))
+    }
+
+    /// Remember the on-disk annotations for a frame the first time it is loaded
+    pub fn record_initial(&mut self, index: usize, anns: &[crate::Annotation]) {
+        self.ensure_len();
+        if let Some(slot) = self.initial_annotations.get_mut(index) {
+            if slot.is_none() {
+                *slot = Some(anns.to_vec());
+            }
+        }
+    }
+
+    /// Make `history` hold frame `index`'s undo steps: the live ones are put
+    /// aside for the frame they belong to and the redo steps are dropped. A
+    /// fresh dataset discards whatever the previous one left in `history`.
+    pub fn switch_undo_frame(&mut self, history: &mut UndoHistory, index: usize) {
+        if self.undo_frame == Some(index) {
+            return;
+        }
+        let stack = match self.undo_stacks.iter().position(|(frame, _)| *frame == index) {
+            Some(pos) => self.undo_stacks.remove(pos).1,
+            None => Vec::new(),
+        };
+        let previous = history.swap_undo_stack(stack);
+        if let Some(frame) = self.undo_frame {
+            if !previous.is_empty() {
+                self.undo_stacks.push((frame, previous));
+            }
+        }
+        // The frame left longest ago loses its history first
+        if self.undo_stacks.len() > MAX_PARKED_UNDO_FRAMES {
+            let excess = self.undo_stacks.len() - MAX_PARKED_UNDO_FRAMES;
+            self.undo_stacks.drain(..excess);
+        }
+        self.undo_frame = Some(index);
+    }
+
+    /// Store a view in a bookmark slot; false if the slot doesn't exist
+    pub fn set_bookmark(&mut self, slot: usize, view: ViewState) -> bool {
+        match self.bookmarks.get_mut(slot) {
+            Some(entry) => {
+                *entry = Some(view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// View saved in a bookmark slot, if any
+    pub fn bookmark(&self, slot: usize) -> Option<&ViewState> {
+        self.bookmarks.get(slot).and_then(|v| v.as_ref())
+    }
+
+    /// Whether frame `index` has been approved in review
+    pub fn is_reviewed(&self, index: usize) -> bool {
+        self.reviewed_frames.get(index).copied().unwrap_or(false)
+    }
+
+    /// Share of frames approved in review, 0–100
+    pub fn reviewed_percent(&self) -> f32 {
+        if self.entries.is_empty() {
+            return 0.0;
+        }
+        let reviewed = self.reviewed_frames.iter().filter(|r| **r).count();
+        reviewed as f32 * 100.0 / self.entries.len() as f32
+    }
+
+    /// Tags of frame `index`
+    pub fn tags_for(&self, index: usize) -> &[String] {
+        self.image_tags.get(index).map(|t| t.as_slice()).unwrap_or(&[])
+    }
+
+    /// Add a tag to a frame; false if it is blank or already present
+    pub fn add_image_tag(&mut self, index: usize, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.ensure_len();
+        match self.image_tags.get_mut(index) {
+            Some(tags) if !tag.is_empty() && !tags.iter().any(|t| t == tag) => {
+                tags.push(tag.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove a tag from a frame; false if it wasn't there
+    pub fn remove_image_tag(&mut self, index: usize, tag: &str) -> bool {
+        let Some(tags) = self.image_tags.get_mut(index) else { return false };
+        let before = tags.len();
+        tags.retain(|t| t != tag);
+        tags.len() != before
+    }
+
+    /// Position of frame `index` in the navigation order (0-based)
+    pub fn nav_position(&self, index: usize) -> usize {
+        match &self.nav_order {
+            Some(order) => order.iter().position(|&i| i == index).unwrap_or(index),
+            None => index,
+        }
+    }
+
+    /// Frame at navigation position `position`, clamped to the last one
+    pub fn nav_index(&self, position: usize) -> usize {
+        let last = self.entries.len().saturating_sub(1);
+        match &self.nav_order {
+            Some(order) => order.get(position.min(last)).copied().unwrap_or(0),
+            None => position.min(last),
+        }
+    }
+
+    /// Frame after `index` in the navigation order (itself at the end)
+    pub fn next_index(&self, index: usize) -> usize {
+        self.nav_index(self.nav_position(index) + 1)
+    }
+
+    /// Frame before `index` in the navigation order (itself at the start)
+    pub fn prev_index(&self, index: usize) -> usize {
+        self.nav_index(self.nav_position(index).saturating_sub(1))
+    }
+
+    /// Annotations a frame had when first loaded this session
+    pub fn initial_for(&self, index: usize) -> Option<Vec<crate::Annotation>> {
+        self.initial_annotations.get(index).and_then(|v| v.clone())
+    }
+}
+
+/// `path` below `base`, falling back to its file name
+fn relative_to<'a>(base: Option<&Path>, path: &'a Path) -> &'a Path {
+    base.and_then(|b| path.strip_prefix(b).ok())
+        .or_else(|| path.file_name().map(Path::new))
+        .unwrap_or(path)
+}
+
+/// Two-step confirmation for bulk edits that touch many annotations
+#[derive(Debug, Clone, Default)]
+pub struct BulkConfirm {
+    /// Armed action and the sorted ids of the annotations it would touch
+    pending: Option<(String, Vec<i32>)>,
+}
+
+impl BulkConfirm {
+    /// Whether `action` affecting the annotations `affected` (by id) may run
+    /// now. Above `limit` (0 = never ask) the first call only arms a
+    /// confirmation and repeating the same action on the same annotations
+    /// confirms it; a different action or a changed set (say, the selection
+    /// grew in between) re-arms.
+    pub fn check(&mut self, action: &str, affected: &[i32], limit: usize) -> bool {
+        let mut ids = affected.to_vec();
+        ids.sort_unstable();
+        let armed = self
+            .pending
+            .as_ref()
+            .is_some_and(|(pending, pending_ids)| pending == action && *pending_ids == ids);
+        if limit == 0 || ids.len() <= limit || armed {
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some((action.to_string(), ids));
+        false
+    }
+
+    /// Drop an armed confirmation (navigation or any other edit happened)
+    pub fn clear(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// Tracks the current and previously used class for quick swapping
+#[derive(Debug, Clone)]
+pub struct ClassHistory {
+    current: i32,
+    previous: Option<i32>,
+}
+
+impl ClassHistory {
+    pub fn new(initial: i32) -> Self {
+        Self {
+            current: initial,
+            previous: None,
+        }
+    }
+
+    /// Record that `class` became the current class
+    pub fn select(&mut self, class: i32) {
+        if class != self.current {
+            self.previous = Some(self.current);
+            self.current = class;
+        }
+    }
+
+    /// Class used before the current one, if any
+    pub fn previous(&self) -> Option<i32> {
+        self.previous
+    }
+
+    /// Swap current and previous class; returns the new current class
+    pub fn swap(&mut self) -> Option<i32> {
+        let previous = self.previous?;
+        self.previous = Some(self.current);
+        self.current = previous;
+        Some(previous)
+    }
 }
 
 /// State for drawing operations (bbox, point creation)
@@ -74,6 +466,28 @@ pub struct DrawState {
     pub start_y: f32,
     pub next_id: i32,
     pub polygon_vertices: Vec<(f32, f32)>,
+    /// Polygon the ring being drawn is cut out of (hole-drawing mode)
+    pub hole_target: Option<usize>,
+    /// Vertices closer than this (image px) to the previous one are skipped,
+    /// thinning freehand strokes
+    pub min_vertex_distance: f32,
+    /// Most vertices a polygon being drawn may have
+    pub max_polygon_vertices: usize,
+}
+
+/// Default `DrawState::min_vertex_distance` in image pixels
+pub const MIN_VERTEX_DISTANCE: f32 = 2.0;
+/// Default `DrawState::max_polygon_vertices`
+pub const MAX_POLYGON_VERTICES: usize = 1000;
+
+/// What `DrawState::push_polygon_vertex` did with a point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexPush {
+    Added,
+    /// Within `min_vertex_distance` of the previous vertex
+    TooClose,
+    /// The polygon already has `max_polygon_vertices`
+    AtCap,
 }
 
 impl DrawState {
@@ -83,7 +497,25 @@ impl DrawState {
             start_y: 0.0,
             next_id: 100, // Start from 100 to avoid conflicts with test data
             polygon_vertices: Vec::new(),
+            hole_target: None,
+            min_vertex_distance: MIN_VERTEX_DISTANCE,
+            max_polygon_vertices: MAX_POLYGON_VERTICES,
+        }
+    }
+
+    /// Add a vertex to the polygon being drawn unless it is too close to the
+    /// previous one or the vertex cap is reached
+    pub fn push_polygon_vertex(&mut self, p: (f32, f32)) -> VertexPush {
+        if let Some(&(lx, ly)) = self.polygon_vertices.last() {
+            if (p.0 - lx).hypot(p.1 - ly) < self.min_vertex_distance {
+                return VertexPush::TooClose;
+            }
+        }
+        if self.polygon_vertices.len() >= self.max_polygon_vertices {
+            return VertexPush::AtCap;
         }
+        self.polygon_vertices.push(p);
+        VertexPush::Added
     }
 }
 
@@ -122,12 +554,26 @@ impl Default for ResizeState {
     }
 }
 
+/// Called with `(can_undo, can_redo)` whenever the history changes
+#[derive(Clone)]
+pub struct UndoListener(std::rc::Rc<dyn Fn(bool, bool)>);
+
+impl std::fmt::Debug for UndoListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("UndoListener")
+    }
+}
+
 /// Undo/Redo history manager
 #[derive(Debug, Clone)]
 pub struct UndoHistory {
     undo_stack: Vec<Vec<crate::Annotation>>,
     redo_stack: Vec<Vec<crate::Annotation>>,
     max_history: usize,
+    /// Drop rejected rows from stored snapshots (see `annotation_modes.compact_undo`)
+    exclude_rejected: bool,
+    /// Keeps the UI's undo/redo availability in sync
+    listener: Option<UndoListener>,
 }
 
 impl UndoHistory {
@@ -136,11 +582,54 @@ impl UndoHistory {
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
             max_history,
+            exclude_rejected: false,
+            listener: None,
+        }
+    }
+
+    /// Report availability changes to `listener` (called once right away)
+    pub fn set_listener(&mut self, listener: impl Fn(bool, bool) + 'static) {
+        self.listener = Some(UndoListener(std::rc::Rc::new(listener)));
+        self.notify();
+    }
+
+    fn notify(&self) {
+        if let Some(UndoListener(listener)) = &self.listener {
+            listener(self.can_undo(), self.can_redo());
+        }
+    }
+
+    pub fn set_exclude_rejected(&mut self, exclude: bool) {
+        self.exclude_rejected = exclude;
+    }
+
+    pub fn excludes_rejected(&self) -> bool {
+        self.exclude_rejected
+    }
+
+    /// Rejected rows of `current` that restoring `restored` drops: with
+    /// compact undo, rows already rejected when `restored` was taken
+    pub fn dropped_rejected(
+        current: &[crate::Annotation],
+        restored: &[crate::Annotation],
+    ) -> usize {
+        current
+            .iter()
+            .filter(|a| a.state.as_str() == "Rejected")
+            .filter(|a| !restored.iter().any(|r| r.id == a.id))
+            .count()
+    }
+
+    fn compact(&self, mut snapshot: Vec<crate::Annotation>) -> Vec<crate::Annotation> {
+        if self.exclude_rejected {
+            snapshot.retain(|ann| ann.state.as_str() != "Rejected");
         }
+        snapshot
     }
 
     /// Push current state onto undo stack (call BEFORE making a change)
     pub fn push(&mut self, snapshot: Vec<crate::Annotation>) {
+        let snapshot = self.compact(snapshot);
         self.undo_stack.push(snapshot);
 
         // Limit history size
@@ -150,12 +639,15 @@ impl UndoHistory {
 
         // Clear redo stack when new action is performed
         self.redo_stack.clear();
+        self.notify();
     }
 
     /// Undo: pop from undo stack, push current to redo stack, return previous state
     pub fn undo(&mut self, current: Vec<crate::Annotation>) -> Option<Vec<crate::Annotation>> {
         if let Some(previous) = self.undo_stack.pop() {
+            let current = self.compact(current);
             self.redo_stack.push(current);
+            self.notify();
             Some(previous)
         } else {
             None
@@ -165,26 +657,247 @@ impl UndoHistory {
     /// Redo: pop from redo stack, push current to undo stack, return next state
     pub fn redo(&mut self, current: Vec<crate::Annotation>) -> Option<Vec<crate::Annotation>> {
         if let Some(next) = self.redo_stack.pop() {
+            let current = self.compact(current);
             self.undo_stack.push(current);
+            self.notify();
             Some(next)
         } else {
             None
         }
     }
 
-    #[allow(dead_code)]
     pub fn can_undo(&self) -> bool {
         !self.undo_stack.is_empty()
     }
 
-    #[allow(dead_code)]
     pub fn can_redo(&self) -> bool {
         !self.redo_stack.is_empty()
     }
 
+    /// Replace the undo steps with `stack` and drop the redo steps; returns the
+    /// undo steps replaced
+    pub fn swap_undo_stack(
+        &mut self,
+        stack: Vec<Vec<crate::Annotation>>,
+    ) -> Vec<Vec<crate::Annotation>> {
+        self.redo_stack.clear();
+        let previous = std::mem::replace(&mut self.undo_stack, stack);
+        self.notify();
+        previous
+    }
+
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.undo_stack.clear();
         self.redo_stack.clear();
+        self.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_polygon_vertex_thins_and_caps() {
+        let mut state = DrawState::new();
+        // A slow freehand stroke: a point every 0.5 px along x
+        for i in 0..100 {
+            state.push_polygon_vertex((i as f32 * 0.5, 10.0));
+        }
+        // Only every 4th point is 2 px from the last kept one
+        assert_eq!(state.polygon_vertices.len(), 25);
+        assert!(state.polygon_vertices.windows(2).all(|w| w[1].0 - w[0].0 >= 2.0));
+        assert_eq!(state.push_polygon_vertex((48.5, 10.0)), VertexPush::TooClose);
+
+        state.max_polygon_vertices = 26;
+        assert_eq!(state.push_polygon_vertex((60.0, 10.0)), VertexPush::Added);
+        assert_eq!(state.push_polygon_vertex((70.0, 10.0)), VertexPush::AtCap);
+        assert_eq!(state.polygon_vertices.len(), 26);
+    }
+
+    #[test]
+    fn test_class_history_swaps_between_last_two() {
+        let mut history = ClassHistory::new(1);
+        assert_eq!(history.swap(), None);
+
+        history.select(2); // A
+        history.select(5); // B
+        assert_eq!(history.swap(), Some(2));
+        assert_eq!(history.swap(), Some(5));
+
+        // Re-selecting the current class doesn't lose the previous one
+        history.select(5);
+        assert_eq!(history.swap(), Some(2));
+    }
+
+    fn ann_with_state(state: &str) -> crate::Annotation {
+        crate::Annotation {
+            state: state.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_undo_availability_follows_history() {
+        let seen = std::rc::Rc::new(std::cell::Cell::new((true, true)));
+        let mut history = UndoHistory::new(10);
+        let sink = seen.clone();
+        history.set_listener(move |undo, redo| sink.set((undo, redo)));
+        assert!(!history.can_undo() && !history.can_redo());
+        assert_eq!(seen.get(), (false, false));
+
+        history.push(Vec::new());
+        assert!(history.can_undo() && !history.can_redo());
+        assert_eq!(seen.get(), (true, false));
+
+        history.undo(Vec::new());
+        assert!(!history.can_undo() && history.can_redo());
+        assert_eq!(seen.get(), (false, true));
+
+        history.redo(Vec::new());
+        assert_eq!(seen.get(), (true, false));
+
+        // A new action drops the redo branch
+        history.undo(Vec::new());
+        history.push(Vec::new());
+        assert_eq!(seen.get(), (true, false));
+    }
+
+    #[test]
+    fn test_undo_after_navigation_stays_with_its_frame() {
+        let mut ds = DatasetState::new(Vec::new(), None);
+        let mut history = UndoHistory::new(10);
+        let on_a = vec![ann_with_state("Manual")];
+        let on_b = vec![ann_with_state("Accepted")];
+
+        ds.switch_undo_frame(&mut history, 0);
+        history.push(Vec::new()); // draw a box on A
+        history.push(on_a.clone()); // and another
+        history.undo(Vec::new());
+        assert!(history.can_redo());
+
+        // On B, A's steps are neither undone nor redone
+        ds.switch_undo_frame(&mut history, 1);
+        assert!(!history.can_undo() && !history.can_redo());
+        assert_eq!(history.undo(on_b.clone()), None);
+        history.push(on_b.clone());
+
+        // Back on A its own steps return, without the redo branch
+        ds.switch_undo_frame(&mut history, 0);
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(on_a.clone()).map(|s| s.len()), Some(0));
+        assert!(!history.can_undo());
+
+        ds.switch_undo_frame(&mut history, 1);
+        let undone = history.undo(Vec::new()).unwrap();
+        assert_eq!(undone[0].state, "Accepted");
+
+        // A new dataset starts with an empty history
+        let mut other = DatasetState::new(Vec::new(), None);
+        history.push(on_b);
+        other.switch_undo_frame(&mut history, 1);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_least_recently_left_undo_history_is_evicted() {
+        let mut ds = DatasetState::new(Vec::new(), None);
+        let mut history = UndoHistory::new(10);
+        for frame in 0..=MAX_PARKED_UNDO_FRAMES + 1 {
+            ds.switch_undo_frame(&mut history, frame);
+            history.push(vec![ann_with_state("Manual")]);
+        }
+        assert_eq!(ds.undo_stacks.len(), MAX_PARKED_UNDO_FRAMES);
+        let parked = |ds: &DatasetState, f: usize| ds.undo_stacks.iter().any(|(i, _)| *i == f);
+        // Frame 0 was left first and lost its steps; frame 1 kept them
+        assert!(!parked(&ds, 0) && parked(&ds, 1));
+
+        ds.switch_undo_frame(&mut history, 1);
+        assert!(history.can_undo());
+        // The frame just left is now the most recent
+        assert_eq!(ds.undo_stacks.last().map(|(i, _)| *i), Some(MAX_PARKED_UNDO_FRAMES + 1));
+        ds.switch_undo_frame(&mut history, 0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_compact_undo_drops_rejected_rows_from_snapshots() {
+        let snapshot = vec![
+            ann_with_state("Manual"),
+            ann_with_state("Rejected"),
+            ann_with_state("Rejected"),
+            ann_with_state("Accepted"),
+        ];
+
+        let mut full = UndoHistory::new(10);
+        full.push(snapshot.clone());
+        let mut compact = UndoHistory::new(10);
+        compact.set_exclude_rejected(true);
+        compact.push(snapshot.clone());
+
+        assert_eq!(full.undo_stack[0].len(), 4);
+        assert_eq!(compact.undo_stack[0].len(), 2);
+
+        // The current state pushed to redo is compacted too
+        compact.undo(snapshot);
+        assert_eq!(compact.redo_stack[0].len(), 2);
+    }
+
+    #[test]
+    fn test_dropped_rejected_counts_rows_missing_from_restored() {
+        let with_id = |id: i32, state: &str| crate::Annotation { id, ..ann_with_state(state) };
+        // Row 2 was rejected before the snapshot, row 3 after it
+        let restored = vec![with_id(1, "Manual"), with_id(3, "Manual")];
+        let current = vec![with_id(1, "Manual"), with_id(2, "Rejected"), with_id(3, "Rejected")];
+        assert_eq!(UndoHistory::dropped_rejected(&current, &restored), 1);
+        assert_eq!(UndoHistory::dropped_rejected(&restored, &current), 0);
+    }
+
+    #[test]
+    fn test_bulk_confirm_requires_second_call_above_limit() {
+        let mut confirm = BulkConfirm::default();
+        let many: Vec<i32> = (0..50).collect();
+        // Small operations run immediately
+        assert!(confirm.check("delete-selected", &[1, 2, 3], 10));
+
+        // A large one is held back once, then runs on the repeat
+        assert!(!confirm.check("delete-selected", &many, 10));
+        assert!(confirm.check("delete-selected", &many, 10));
+        // ...and the confirmation is consumed
+        assert!(!confirm.check("delete-selected", &many, 10));
+
+        // A different action in between re-arms instead of confirming
+        assert!(!confirm.check("reject-class:2", &many, 10));
+        assert!(!confirm.check("delete-selected", &many, 10));
+
+        // A limit of 0 disables the prompt
+        assert!(confirm.check("revert-frame", &many, 0));
+
+        // Anything happening in between disarms the confirmation
+        assert!(!confirm.check("clear-frame", &many, 10));
+        confirm.clear();
+        assert!(!confirm.check("clear-frame", &many, 10));
+    }
+
+    #[test]
+    fn test_bulk_confirm_rearms_when_selection_changes() {
+        let mut confirm = BulkConfirm::default();
+        let armed: Vec<i32> = (0..20).collect();
+        let everything: Vec<i32> = (0..200).collect();
+
+        assert!(!confirm.check("delete-selected", &armed, 10));
+        // Ctrl+A while armed: the wider delete must ask again
+        assert!(!confirm.check("delete-selected", &everything, 10));
+        assert!(confirm.check("delete-selected", &everything, 10));
+
+        // Same size but different rows is a different selection too
+        let shifted: Vec<i32> = (100..120).collect();
+        assert!(!confirm.check("delete-selected", &armed, 10));
+        assert!(!confirm.check("delete-selected", &shifted, 10));
+
+        // Order doesn't matter, only which annotations
+        let reversed: Vec<i32> = shifted.iter().rev().copied().collect();
+        assert!(confirm.check("delete-selected", &reversed, 10));
     }
 }
@@ -0,0 +1,111 @@
+//! Per-class usage across a whole dataset, for jumping to frames of a class.
+
+use super::dataset::load_yolo_annotations;
+use super::types::DatasetState;
+use std::collections::HashMap;
+
+/// How often a class is used in the dataset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassUsage {
+    /// Live annotations of the class
+    pub count: usize,
+    /// Indices of frames with at least one such annotation, ascending
+    pub frames: Vec<usize>,
+}
+
+/// Count live annotations per class over every frame. Cached frames are used
+/// as edited; frames not visited yet are read from their label files.
+pub fn class_usage(ds: &DatasetState) -> HashMap<i32, ClassUsage> {
+    let mut usage: HashMap<i32, ClassUsage> = HashMap::new();
+    for (idx, entry) in ds.entries.iter().enumerate() {
+        // Only classes are needed, so the label geometry scale doesn't matter
+        let loaded;
+        let anns = match ds.stored_annotations.get(idx) {
+            Some(Some(anns)) => anns,
+            _ => {
+                loaded = load_yolo_annotations(entry, (1.0, 1.0), 0, &ds.label_extension);
+                &loaded
+            }
+        };
+        for ann in anns.iter().filter(|a| a.state != "Rejected") {
+            let class = usage.entry(ann.class).or_default();
+            class.count += 1;
+            if class.frames.last() != Some(&idx) {
+                class.frames.push(idx);
+            }
+        }
+    }
+    usage
+}
+
+/// Frame indices ordered for reviewing rare classes first: by how often the
+/// least used class in each frame occurs across the dataset, ascending. Ties
+/// keep dataset order; frames without live annotations come last.
+pub fn rarity_order(ds: &DatasetState) -> Vec<usize> {
+    let mut rarity = vec![usize::MAX; ds.entries.len()];
+    for usage in class_usage(ds).values() {
+        for &frame in &usage.frames {
+            rarity[frame] = rarity[frame].min(usage.count);
+        }
+    }
+    let mut order: Vec<usize> = (0..ds.entries.len()).collect();
+    order.sort_by_key(|&frame| rarity[frame]);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{test_bbox as bbox, DatasetEntry};
+    use crate::utils::scratch_dir;
+    use crate::Annotation;
+    use std::fs;
+
+    #[test]
+    fn test_class_usage_includes_unvisited_frames() {
+        let dir = scratch_dir("usage");
+        let entries = (0..3)
+            .map(|i| DatasetEntry {
+                image_path: dir.join(format!("img{i}.png")),
+                labels_path: None,
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+
+        // Frame 0 was edited this session; frame 2 only exists on disk
+        ds.stored_annotations[0] = Some(vec![
+            bbox(1, 0.0, 0.0, 0.0, 0.0),
+            Annotation { state: "Accepted".into(), ..bbox(1, 0.0, 0.0, 0.0, 0.0) },
+            Annotation { state: "Rejected".into(), ..bbox(2, 0.0, 0.0, 0.0, 0.0) },
+        ]);
+        fs::write(dir.join("img2.txt"), "0 0.5 0.5 0.1 0.1\n1 0.2 0.2 0.1 0.1\n").unwrap();
+
+        let usage = class_usage(&ds);
+        assert_eq!(usage[&1], ClassUsage { count: 3, frames: vec![0, 2] });
+        assert_eq!(usage[&2], ClassUsage { count: 1, frames: vec![2] });
+        assert_eq!(usage.len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rarity_order_puts_singleton_class_first() {
+        let entries = (0..4)
+            .map(|i| DatasetEntry {
+                image_path: format!("missing_rarity_{i}.png").into(),
+                labels_path: Some(format!("missing_rarity_{i}.txt").into()),
+            })
+            .collect();
+        let mut ds = DatasetState::new(entries, None);
+        ds.ensure_len();
+        let common = bbox(1, 0.0, 0.0, 0.0, 0.0);
+        ds.stored_annotations[0] = Some(vec![common.clone(), common.clone()]);
+        ds.stored_annotations[1] = Some(Vec::new());
+        ds.stored_annotations[2] = Some(vec![common.clone()]);
+        // The only class 3 annotation, next to a common one
+        ds.stored_annotations[3] = Some(vec![common, bbox(3, 0.0, 0.0, 0.0, 0.0)]);
+
+        assert_eq!(rarity_order(&ds), vec![3, 0, 2, 1]);
+    }
+}
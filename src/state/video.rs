@@ -0,0 +1,273 @@
+//! Datasets built from video files.
+//!
+//! Frames are sampled into a sibling `<stem>_frames/` folder with
+//! deterministic zero-padded names (`frame_000000.png`, ...) so label files
+//! keep mapping to the same frames, then indexed like any image folder.
+//! Animated GIFs found in an image folder are split the same way.
+
+use crate::state::dataset::create_dataset_from_folder;
+use image::{AnimationDecoder, Frame};
+use image::codecs::gif::GifDecoder;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Video extensions offered in the "Open Video" dialog
+pub const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mov", "avi", "mkv", "webm"];
+
+/// Extracts frames from a video into a folder using [`frame_file_name`] names
+pub trait FrameExtractor {
+    fn extract(&self, video: &Path, fps: f32, out_dir: &Path) -> Result<(), String>;
+}
+
+/// Shells out to the `ffmpeg` binary on PATH
+pub struct FfmpegExtractor;
+
+impl FrameExtractor for FfmpegExtractor {
+    fn extract(&self, video: &Path, fps: f32, out_dir: &Path) -> Result<(), String> {
+        let output = Command::new("ffmpeg")
+            .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(video)
+            .args(["-vf", &format!("fps={fps}"), "-start_number", "0"])
+            .arg(out_dir.join("frame_%06d.png"))
+            .output()
+            .map_err(|e| format!("Failed to run ffmpeg: {e}"))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "ffmpeg failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+/// File name of the `index`-th extracted frame
+pub fn frame_file_name(index: usize) -> String {
+    format!("frame_{index:06}.png")
+}
+
+/// Folder that holds the frames extracted from `video`
+pub fn frames_dir_for(video: &Path) -> PathBuf {
+    let stem = video
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "video".to_string());
+    video.with_file_name(format!("{stem}_frames"))
+}
+
+/// Written into a GIF's frames folder once all its frames are saved; holds the
+/// frame count
+const GIF_FRAMES_STAMP: &str = "frames.count";
+
+/// Split a multi-frame GIF into [`frames_dir_for`] and return its frame count.
+/// Single-frame GIFs are left alone (the result is 1) and open as plain images.
+/// Frames are decoded and saved one at a time. If the folder already holds a
+/// complete split at least as new as the GIF, nothing is decoded; otherwise
+/// its old frame images are removed first so frames of an earlier, longer
+/// GIF aren't listed.
+pub fn explode_gif(gif: &Path) -> Result<usize, String> {
+    let frames_dir = frames_dir_for(gif);
+    if let Some(count) = expanded_frame_count(gif, &frames_dir) {
+        return Ok(count);
+    }
+    remove_frame_images(&frames_dir)?;
+
+    let file =
+        fs::File::open(gif).map_err(|e| format!("Failed to open {}: {e}", gif.display()))?;
+    let decoder = GifDecoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {e}", gif.display()))?;
+    let mut frames = decoder.into_frames();
+    let mut next_frame = || {
+        frames
+            .next()
+            .transpose()
+            .map_err(|e| format!("Failed to decode {}: {e}", gif.display()))
+    };
+    let Some(first) = next_frame()? else { return Ok(0) };
+    let Some(second) = next_frame()? else { return Ok(1) };
+
+    fs::create_dir_all(&frames_dir).map_err(|e| format!("Failed to create frames folder: {e}"))?;
+    let save = |index: usize, frame: &Frame| {
+        frame
+            .buffer()
+            .save(frames_dir.join(frame_file_name(index)))
+            .map_err(|e| format!("Failed to write GIF frame {index}: {e}"))
+    };
+    save(0, &first)?;
+    save(1, &second)?;
+    let mut count = 2;
+    while let Some(frame) = next_frame()? {
+        save(count, &frame)?;
+        count += 1;
+    }
+    fs::write(frames_dir.join(GIF_FRAMES_STAMP), count.to_string())
+        .map_err(|e| format!("Failed to write {GIF_FRAMES_STAMP}: {e}"))?;
+    Ok(count)
+}
+
+/// Frame count of a complete earlier split of `gif` into `frames_dir`, if its
+/// stamp is at least as new as the GIF and every frame is still there
+fn expanded_frame_count(gif: &Path, frames_dir: &Path) -> Option<usize> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let stamp = frames_dir.join(GIF_FRAMES_STAMP);
+    if modified(&stamp)? < modified(gif)? {
+        return None;
+    }
+    let count: usize = fs::read_to_string(&stamp).ok()?.trim().parse().ok()?;
+    (0..count)
+        .all(|i| frames_dir.join(frame_file_name(i)).is_file())
+        .then_some(count)
+}
+
+/// Remove the frame images and stamp of an earlier split; label files and
+/// anything else in the folder stay
+fn remove_frame_images(frames_dir: &Path) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(frames_dir) else { return Ok(()) };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let index = name.strip_prefix("frame_").and_then(|n| n.strip_suffix(".png"));
+        let is_frame = index.is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()));
+        if is_frame || name == GIF_FRAMES_STAMP {
+            fs::remove_file(entry.path())
+                .map_err(|e| format!("Failed to remove {}: {e}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Extract frames from `video` with ffmpeg and build a manifest for them
+pub fn create_dataset_from_video(
+    video: &Path,
+    fps: f32,
+    class_config: Option<&crate::classes::ClassConfig>,
+    label_extension: &str,
+) -> Result<PathBuf, String> {
+    create_dataset_from_video_with(video, fps, class_config, label_extension, &FfmpegExtractor)
+}
+
+/// Like [`create_dataset_from_video`] with a custom frame extractor.
+/// An existing manifest in the frames folder is reused without re-extracting.
+pub fn create_dataset_from_video_with(
+    video: &Path,
+    fps: f32,
+    class_config: Option<&crate::classes::ClassConfig>,
+    label_extension: &str,
+    extractor: &dyn FrameExtractor,
+) -> Result<PathBuf, String> {
+    if !(fps > 0.0 && fps.is_finite()) {
+        return Err(format!("Invalid frame rate: {fps}"));
+    }
+
+    let frames_dir = frames_dir_for(video);
+    let manifest_path = frames_dir.join("manifest.json");
+    if manifest_path.exists() {
+        return Ok(manifest_path);
+    }
+
+    fs::create_dir_all(&frames_dir).map_err(|e| format!("Failed to create frames folder: {e}"))?;
+    extractor.extract(video, fps, &frames_dir)?;
+    create_dataset_from_folder(&frames_dir, class_config, 0, label_extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DatasetFile;
+    use crate::utils::scratch_dir;
+
+    /// Writes `count` placeholder frames instead of decoding a video
+    struct FakeExtractor {
+        count: usize,
+    }
+
+    impl FrameExtractor for FakeExtractor {
+        fn extract(&self, _video: &Path, _fps: f32, out_dir: &Path) -> Result<(), String> {
+            for i in 0..self.count {
+                fs::write(out_dir.join(frame_file_name(i)), b"png").map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_video_manifest_lists_extracted_frames() {
+        let dir = scratch_dir("video");
+        let video = dir.join("clip.mp4");
+
+        let extract = |count| {
+            create_dataset_from_video_with(&video, 2.0, None, "txt", &FakeExtractor { count })
+        };
+        let manifest_path = extract(3).unwrap();
+        assert_eq!(manifest_path, dir.join("clip_frames").join("manifest.json"));
+
+        let manifest: DatasetFile =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        let images: Vec<&str> = manifest.images.iter().map(|e| e.image.as_str()).collect();
+        assert_eq!(images, vec!["frame_000000.png", "frame_000001.png", "frame_000002.png"]);
+        assert_eq!(manifest.images[1].labels.as_deref(), Some("frame_000001.txt"));
+
+        // Reopening reuses the manifest instead of re-extracting
+        let again = extract(5).unwrap();
+        assert_eq!(again, manifest_path);
+
+        let invalid_fps =
+            create_dataset_from_video_with(&video, 0.0, None, "txt", &FakeExtractor { count: 1 });
+        assert!(invalid_fps.is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_three_frame_gif_becomes_three_manifest_entries() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Frame, Rgba, RgbaImage};
+
+        let dir = scratch_dir("gif");
+        let write_gif = |count: u8| {
+            let file = fs::File::create(dir.join("anim.gif")).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            let frames = (0..count)
+                .map(|i| Frame::new(RgbaImage::from_pixel(4, 4, Rgba([i * 80, 0, 0, 255]))));
+            encoder.encode_frames(frames).unwrap();
+        };
+        write_gif(3);
+        RgbaImage::new(4, 4).save(dir.join("still.png")).unwrap();
+
+        let read_images = |max_depth| {
+            let path = create_dataset_from_folder(&dir, None, max_depth, "txt").unwrap();
+            let manifest: DatasetFile =
+                serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            manifest.images.into_iter().map(|e| e.image).collect::<Vec<_>>()
+        };
+        let expected = vec![
+            "anim_frames/frame_000000.png",
+            "anim_frames/frame_000001.png",
+            "anim_frames/frame_000002.png",
+            "still.png",
+        ];
+        assert_eq!(read_images(0), expected);
+        // Scanning into the frames folder doesn't list the frames twice
+        assert_eq!(read_images(1), expected);
+
+        // An up-to-date split is reused without decoding the GIF again
+        let first_frame = dir.join("anim_frames").join(frame_file_name(0));
+        fs::remove_file(&first_frame).unwrap();
+        fs::write(&first_frame, b"kept").unwrap();
+        assert_eq!(read_images(0), expected);
+        assert_eq!(fs::read(&first_frame).unwrap(), b"kept");
+
+        // A newer, shorter GIF replaces the old frames
+        write_gif(2);
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        let gif = fs::File::options().write(true).open(dir.join("anim.gif")).unwrap();
+        gif.set_modified(later).unwrap();
+        assert_eq!(read_images(0), vec![expected[0], expected[1], "still.png"]);
+        assert!(!dir.join("anim_frames").join(frame_file_name(2)).exists());
+        assert_ne!(fs::read(&first_frame).unwrap(), b"kept");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
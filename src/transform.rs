@@ -0,0 +1,275 @@
+// Coordinate transforms shared by the canvas, editing callbacks and exporters
+//
+// Screen space is the canvas (after pan/zoom), image space is pixels in the
+// source image, normalized space is YOLO-style center/size in 0..1.
+
+use crate::state::ViewState;
+
+/// Convert a canvas point to image pixels under the given pan/zoom
+pub fn screen_to_image(view: &ViewState, screen: (f32, f32)) -> (f32, f32) {
+    (
+        (screen.0 - view.pan_x) / view.zoom,
+        (screen.1 - view.pan_y) / view.zoom,
+    )
+}
+
+/// Convert an image pixel to its canvas position under the given pan/zoom
+pub fn image_to_screen(view: &ViewState, image: (f32, f32)) -> (f32, f32) {
+    (
+        view.pan_x + image.0 * view.zoom,
+        view.pan_y + image.1 * view.zoom,
+    )
+}
+
+/// Axis-aligned `(x, y, width, height)` spanned by two opposite corners
+pub fn box_from_corners(a: (f32, f32), b: (f32, f32)) -> (f32, f32, f32, f32) {
+    (a.0.min(b.0), a.1.min(b.1), (b.0 - a.0).abs(), (b.1 - a.1).abs())
+}
+
+/// Pixel `(x, y, width, height)` to normalized YOLO `(cx, cy, w, h)`
+pub fn normalize_box(bbox: (f32, f32, f32, f32), img_size: (f32, f32)) -> (f32, f32, f32, f32) {
+    let (x, y, w, h) = bbox;
+    (
+        (x + w / 2.0) / img_size.0,
+        (y + h / 2.0) / img_size.1,
+        w / img_size.0,
+        h / img_size.1,
+    )
+}
+
+/// Normalized YOLO `(cx, cy, w, h)` to pixel `(x, y, width, height)`
+pub fn denormalize_box(yolo: (f32, f32, f32, f32), img_size: (f32, f32)) -> (f32, f32, f32, f32) {
+    let (cx, cy, w, h) = yolo;
+    let abs_w = w * img_size.0;
+    let abs_h = h * img_size.1;
+    (cx * img_size.0 - abs_w / 2.0, cy * img_size.1 - abs_h / 2.0, abs_w, abs_h)
+}
+
+/// Round `v` to the nearest multiple of `grid` (unchanged for non-positive grids)
+pub fn snap_to_grid(v: f32, grid: f32) -> f32 {
+    if grid > 0.0 && grid.is_finite() {
+        (v / grid).round() * grid
+    } else {
+        v
+    }
+}
+
+/// Snap an image-space point when a grid is configured
+pub fn snap_point(p: (f32, f32), grid: Option<f32>) -> (f32, f32) {
+    match grid {
+        Some(g) => (snap_to_grid(p.0, g), snap_to_grid(p.1, g)),
+        None => p,
+    }
+}
+
+/// Corners of a box rotated by `rotation` degrees about its center,
+/// clockwise from the (unrotated) top-left corner
+pub fn rbbox_corners(bbox: (f32, f32, f32, f32), rotation: f32) -> [(f32, f32); 4] {
+    let (x, y, w, h) = bbox;
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let rotate = |dx: f32, dy: f32| (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos);
+    [
+        rotate(-w / 2.0, -h / 2.0),
+        rotate(w / 2.0, -h / 2.0),
+        rotate(w / 2.0, h / 2.0),
+        rotate(-w / 2.0, h / 2.0),
+    ]
+}
+
+/// Axis-aligned `(x, y, width, height)` enclosing a box rotated by
+/// `rotation` degrees about its center
+pub fn rbbox_bounds(bbox: (f32, f32, f32, f32), rotation: f32) -> (f32, f32, f32, f32) {
+    let (x0, y0, x1, y1) = rbbox_corners(bbox, rotation).iter().fold(
+        (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+    );
+    (x0, y0, x1 - x0, y1 - y0)
+}
+
+/// Rotated box `(x, y, width, height, rotation)` from four corners in
+/// drawing order (YOLO-OBB / DOTA), the inverse of [`rbbox_corners`]. The
+/// first edge gives the width and angle, the second the height; uneven
+/// quadrilaterals get their mean edge lengths. Rotation lands in (-90°, 90°].
+pub fn obb_to_rbbox(coords: [(f32, f32); 4]) -> (f32, f32, f32, f32, f32) {
+    let [p0, p1, p2, p3] = coords;
+    let dist = |a: (f32, f32), b: (f32, f32)| (b.0 - a.0).hypot(b.1 - a.1);
+    let cx = (p0.0 + p1.0 + p2.0 + p3.0) / 4.0;
+    let cy = (p0.1 + p1.1 + p2.1 + p3.1) / 4.0;
+    let w = (dist(p0, p1) + dist(p3, p2)) / 2.0;
+    let h = (dist(p1, p2) + dist(p0, p3)) / 2.0;
+    let rotation = normalize_rotation((p1.1 - p0.1).atan2(p1.0 - p0.0).to_degrees());
+    (cx - w / 2.0, cy - h / 2.0, w, h, rotation)
+}
+
+/// Box rotation in degrees brought into (-90°, 90°]; a box turned by 180° is
+/// the same box
+pub fn normalize_rotation(degrees: f32) -> f32 {
+    let rotation = degrees.rem_euclid(180.0);
+    if rotation > 90.0 { rotation - 180.0 } else { rotation }
+}
+
+/// Resize `bbox` by dragging `handle` (`corner-tl`, `edge-r`, ...) to `mouse`;
+/// the opposite corner or edge stays put. `None` for an unknown handle.
+pub fn resize_box(
+    bbox: (f32, f32, f32, f32),
+    handle: &str,
+    mouse: (f32, f32),
+) -> Option<(f32, f32, f32, f32)> {
+    let (x, y, w, h) = bbox;
+    let resized = match handle {
+        "corner-tl" => box_from_corners(mouse, (x + w, y + h)),
+        "corner-tr" => box_from_corners(mouse, (x, y + h)),
+        "corner-bl" => box_from_corners(mouse, (x + w, y)),
+        "corner-br" => box_from_corners(mouse, (x, y)),
+        "edge-t" => {
+            let top = mouse.1.min(y + h);
+            (x, top, w, y + h - top)
+        }
+        "edge-r" => (x, y, (mouse.0 - x).max(1.0), h),
+        "edge-b" => (x, y, w, (mouse.1 - y).max(1.0)),
+        "edge-l" => {
+            let left = mouse.0.min(x + w);
+            (left, y, x + w - left, h)
+        }
+        _ => return None,
+    };
+    Some(resized)
+}
+
+/// [`resize_box`] for a box rotated by `rotation` degrees about its center:
+/// the drag is measured along the box's own sides, and the corner or edge
+/// opposite the handle stays where it is on the image
+pub fn resize_rotated_box(
+    bbox: (f32, f32, f32, f32),
+    rotation: f32,
+    handle: &str,
+    mouse: (f32, f32),
+) -> Option<(f32, f32, f32, f32)> {
+    let (x, y, w, h) = bbox;
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    // Mouse in the box's unrotated frame, relative to its center
+    let (dx, dy) = (mouse.0 - cx, mouse.1 - cy);
+    let local = (dx * cos + dy * sin, -dx * sin + dy * cos);
+    let (lx, ly, lw, lh) = resize_box((-w / 2.0, -h / 2.0, w, h), handle, local)?;
+    // The new center, turned back onto the image
+    let (lcx, lcy) = (lx + lw / 2.0, ly + lh / 2.0);
+    let center = (cx + lcx * cos - lcy * sin, cy + lcx * sin + lcy * cos);
+    Some((center.0 - lw / 2.0, center.1 - lh / 2.0, lw, lh))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_screen_image_round_trip() {
+        let view = ViewState { pan_x: 30.0, pan_y: -12.0, zoom: 1.5 };
+        assert_eq!(image_to_screen(&view, (10.0, 20.0)), (45.0, 18.0));
+        assert_eq!(screen_to_image(&view, (45.0, 18.0)), (10.0, 20.0));
+
+        let img = (123.4, 56.7);
+        assert!(close(screen_to_image(&view, image_to_screen(&view, img)), img));
+    }
+
+    #[test]
+    fn test_box_from_corners_any_order() {
+        assert_eq!(box_from_corners((10.0, 40.0), (30.0, 5.0)), (10.0, 5.0, 20.0, 35.0));
+        assert_eq!(box_from_corners((30.0, 5.0), (10.0, 40.0)), (10.0, 5.0, 20.0, 35.0));
+    }
+
+    #[test]
+    fn test_normalize_denormalize_round_trip() {
+        let img_size = (640.0, 480.0);
+        let bbox = (64.0, 48.0, 128.0, 96.0);
+        let yolo = normalize_box(bbox, img_size);
+        assert_eq!(yolo, (0.2, 0.2, 0.2, 0.2));
+        assert_eq!(denormalize_box(yolo, img_size), bbox);
+    }
+
+    #[test]
+    fn test_snap_to_grid() {
+        assert_eq!(snap_to_grid(12.4, 5.0), 10.0);
+        assert_eq!(snap_to_grid(12.6, 5.0), 15.0);
+        assert_eq!(snap_to_grid(-7.6, 5.0), -10.0);
+        assert_eq!(snap_to_grid(3.3, 0.5), 3.5);
+        // Disabled / invalid grids leave values untouched
+        assert_eq!(snap_to_grid(12.4, 0.0), 12.4);
+        assert_eq!(snap_point((12.4, 7.7), None), (12.4, 7.7));
+        assert_eq!(snap_point((12.4, 7.7), Some(4.0)), (12.0, 8.0));
+    }
+
+    #[test]
+    fn test_rbbox_corners() {
+        let bbox = (0.0, 0.0, 4.0, 2.0);
+        let corners = rbbox_corners(bbox, 0.0);
+        assert_eq!(corners, [(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (0.0, 2.0)]);
+
+        // 90° swaps the extents around the center (2, 1)
+        let rotated = rbbox_corners(bbox, 90.0);
+        assert!(close(rotated[0], (3.0, -1.0)));
+        assert!(close(rotated[1], (3.0, 3.0)));
+        assert!(close(rotated[2], (1.0, 3.0)));
+        assert!(close(rotated[3], (1.0, -1.0)));
+    }
+
+    #[test]
+    fn test_rbbox_bounds_encloses_corners() {
+        let bbox = (0.0, 0.0, 4.0, 2.0);
+        assert_eq!(rbbox_bounds(bbox, 0.0), bbox);
+
+        let (x, y, w, h) = rbbox_bounds(bbox, 90.0);
+        assert!(close((x, y), (1.0, -1.0)));
+        assert!(close((w, h), (2.0, 4.0)));
+    }
+
+    #[test]
+    fn test_resize_rotated_box_keeps_opposite_corner() {
+        let bbox = (0.0, 0.0, 20.0, 10.0);
+        // Unrotated, it matches the axis-aligned resize
+        assert_eq!(
+            resize_rotated_box(bbox, 0.0, "corner-br", (30.0, 15.0)),
+            resize_box(bbox, "corner-br", (30.0, 15.0))
+        );
+
+        let before = rbbox_corners(bbox, 90.0);
+        let mouse = (0.0, 25.0);
+        let resized = resize_rotated_box(bbox, 90.0, "corner-br", mouse).unwrap();
+        let after = rbbox_corners(resized, 90.0);
+        // The size grows along the box's own sides
+        assert!(close((resized.2, resized.3), (30.0, 15.0)));
+        assert!(close(after[0], before[0]));
+        assert!(close(after[2], mouse));
+
+        assert_eq!(resize_rotated_box(bbox, 90.0, "rotate", mouse), None);
+    }
+
+    #[test]
+    fn test_normalize_rotation() {
+        assert_eq!(normalize_rotation(93.0), -87.0);
+        assert_eq!(normalize_rotation(-95.0), 85.0);
+        assert_eq!(normalize_rotation(-90.0), 90.0);
+        assert_eq!(normalize_rotation(30.0), 30.0);
+    }
+
+    #[test]
+    fn test_obb_to_rbbox_inverts_rbbox_corners() {
+        let bbox = (10.0, 20.0, 40.0, 10.0);
+        for rotation in [0.0, 30.0, -45.0, 75.0] {
+            let (x, y, w, h, r) = obb_to_rbbox(rbbox_corners(bbox, rotation));
+            assert!(close((x, y), (bbox.0, bbox.1)), "{rotation}: {x}, {y}");
+            assert!(close((w, h), (bbox.2, bbox.3)), "{rotation}: {w}, {h}");
+            assert!((r - rotation).abs() < 1e-3, "{rotation}: {r}");
+        }
+        // Corners listed from the opposite side describe the same box
+        let [a, b, c, d] = rbbox_corners(bbox, 30.0);
+        let (x, y, w, h, r) = obb_to_rbbox([c, d, a, b]);
+        assert!(close((x, y), (10.0, 20.0)) && close((w, h), (40.0, 10.0)));
+        assert!((r - 30.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,250 @@
+// Annotation validation rules
+//
+// Pure checks over a frame's annotations, used before marking a frame complete,
+// and matching against a reference export for QA.
+
+use crate::config::CountRule;
+use crate::geometry::iou;
+use crate::state::DEFAULT_POINT_BOX_SIZE;
+use crate::transform::rbbox_bounds;
+use crate::Annotation;
+
+/// A class whose per-image count falls outside its configured limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountViolation {
+    pub class_id: i32,
+    pub count: usize,
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl CountViolation {
+    /// Human readable description, e.g. `plate: 2 (max 1)`
+    pub fn describe(&self, class_name: &str) -> String {
+        let limit = match (self.min, self.max) {
+            (Some(min), _) if self.count < min => format!("min {min}"),
+            (_, Some(max)) => format!("max {max}"),
+            _ => String::new(),
+        };
+        format!("{}: {} ({})", class_name, self.count, limit)
+    }
+}
+
+/// Check per-class annotation counts against `rules` (rejected rows are ignored)
+pub fn validate_counts(anns: &[Annotation], rules: &[CountRule]) -> Vec<CountViolation> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let count = anns
+                .iter()
+                .filter(|a| a.class == rule.class_id && a.state != "Rejected")
+                .count();
+            let too_few = rule.min.is_some_and(|min| count < min);
+            let too_many = rule.max.is_some_and(|max| count > max);
+            (too_few || too_many).then_some(CountViolation {
+                class_id: rule.class_id,
+                count,
+                min: rule.min,
+                max: rule.max,
+            })
+        })
+        .collect()
+}
+
+/// Result of matching a frame's annotations against reference (ground truth) ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameMatch {
+    /// `(prediction index, reference index, IoU)` for each matched pair
+    pub matches: Vec<(usize, usize, f32)>,
+    /// Predictions that match no reference (false positives)
+    pub unmatched_pred: Vec<usize>,
+    /// References that no prediction matches (false negatives)
+    pub unmatched_gt: Vec<usize>,
+}
+
+impl FrameMatch {
+    /// Share of predictions that matched; 1 when there are none
+    pub fn precision(&self) -> f32 {
+        ratio(self.matches.len(), self.matches.len() + self.unmatched_pred.len())
+    }
+
+    /// Share of references that were found; 1 when there are none
+    pub fn recall(&self) -> f32 {
+        ratio(self.matches.len(), self.matches.len() + self.unmatched_gt.len())
+    }
+}
+
+fn ratio(part: usize, total: usize) -> f32 {
+    if total == 0 { 1.0 } else { part as f32 / total as f32 }
+}
+
+/// Box compared by [`greedy_match`]: polygons and rotated boxes by their
+/// axis-aligned bounds (so two rotated boxes at different angles can overlap
+/// more than their outlines do), points as `DEFAULT_POINT_BOX_SIZE` squares
+/// centred on them
+fn match_box(ann: &Annotation) -> (f32, f32, f32, f32) {
+    match ann.r#type.as_str() {
+        "point" => {
+            let half = DEFAULT_POINT_BOX_SIZE / 2.0;
+            (ann.x - half, ann.y - half, DEFAULT_POINT_BOX_SIZE, DEFAULT_POINT_BOX_SIZE)
+        }
+        "rbbox" if ann.rotation != 0.0 => {
+            rbbox_bounds((ann.x, ann.y, ann.width, ann.height), ann.rotation)
+        }
+        _ => (ann.x, ann.y, ann.width, ann.height),
+    }
+}
+
+/// Match `pred` to `gt` one-to-one: pairs of the same class with IoU of at
+/// least `iou_thresh` are taken best first. Rejected annotations on either
+/// side are left out entirely.
+pub fn greedy_match(pred: &[Annotation], gt: &[Annotation], iou_thresh: f32) -> FrameMatch {
+    let live = |anns: &[Annotation]| -> Vec<usize> {
+        (0..anns.len()).filter(|&i| anns[i].state != "Rejected").collect()
+    };
+    let (pred_live, gt_live) = (live(pred), live(gt));
+
+    let mut candidates = Vec::new();
+    for &p in &pred_live {
+        for &g in gt_live.iter().filter(|&&g| gt[g].class == pred[p].class) {
+            let overlap = iou(match_box(&pred[p]), match_box(&gt[g]));
+            if overlap > 0.0 && overlap >= iou_thresh {
+                candidates.push((p, g, overlap));
+            }
+        }
+    }
+    // Best overlap first; ties keep index order so the result is deterministic
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+
+    let mut pred_used = vec![false; pred.len()];
+    let mut gt_used = vec![false; gt.len()];
+    let mut matches = Vec::new();
+    for (p, g, overlap) in candidates {
+        if !pred_used[p] && !gt_used[g] {
+            pred_used[p] = true;
+            gt_used[g] = true;
+            matches.push((p, g, overlap));
+        }
+    }
+    FrameMatch {
+        matches,
+        unmatched_pred: pred_live.into_iter().filter(|&p| !pred_used[p]).collect(),
+        unmatched_gt: gt_live.into_iter().filter(|&g| !gt_used[g]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_bbox as bbox;
+
+    fn exactly_one(class_id: i32) -> CountRule {
+        CountRule {
+            class_id,
+            min: Some(1),
+            max: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_within_limits_has_no_violations() {
+        let anns = vec![
+            bbox(1, 0.0, 0.0, 0.0, 0.0),
+            bbox(2, 0.0, 0.0, 0.0, 0.0),
+            Annotation { state: "Accepted".into(), ..bbox(2, 0.0, 0.0, 0.0, 0.0) },
+        ];
+        assert!(validate_counts(&anns, &[exactly_one(1)]).is_empty());
+    }
+
+    #[test]
+    fn test_under_limit_is_reported() {
+        // Rejected annotations don't count towards the minimum
+        let anns = vec![
+            Annotation { state: "Rejected".into(), ..bbox(1, 0.0, 0.0, 0.0, 0.0) },
+            bbox(2, 0.0, 0.0, 0.0, 0.0),
+        ];
+        let violations = validate_counts(&anns, &[exactly_one(1)]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].count, 0);
+        assert_eq!(violations[0].describe("plate"), "plate: 0 (min 1)");
+    }
+
+    #[test]
+    fn test_over_limit_is_reported() {
+        let anns = vec![
+            bbox(1, 0.0, 0.0, 0.0, 0.0),
+            Annotation { state: "Pending".into(), ..bbox(1, 0.0, 0.0, 0.0, 0.0) },
+        ];
+        let rules = [
+            exactly_one(1),
+            CountRule {
+                class_id: 2,
+                min: None,
+                max: Some(3),
+            },
+        ];
+        let violations = validate_counts(&anns, &rules);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].class_id, 1);
+        assert_eq!(violations[0].describe("plate"), "plate: 2 (max 1)");
+    }
+    #[test]
+    fn test_greedy_match_counts_hits_and_misses() {
+        let gt = vec![
+            bbox(1, 0.0, 0.0, 10.0, 10.0),
+            bbox(1, 20.0, 0.0, 10.0, 10.0),
+            bbox(2, 40.0, 0.0, 10.0, 10.0),
+        ];
+        let pred = vec![
+            // Overlaps gt 0 by 50 of 150 (IoU 1/3): below the threshold
+            bbox(1, 5.0, 0.0, 10.0, 10.0),
+            // Overlaps gt 1 by 80 of 120 (IoU 2/3)
+            bbox(1, 22.0, 0.0, 10.0, 10.0),
+            // Exactly on gt 2, but the wrong class
+            bbox(1, 40.0, 0.0, 10.0, 10.0),
+            Annotation { state: "Rejected".into(), ..bbox(2, 0.0, 0.0, 0.0, 0.0) },
+        ];
+        let result = greedy_match(&pred, &gt, 0.5);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!((result.matches[0].0, result.matches[0].1), (1, 1));
+        assert!((result.matches[0].2 - 2.0 / 3.0).abs() < 1e-5);
+        assert_eq!(result.unmatched_pred, vec![0, 2]);
+        assert_eq!(result.unmatched_gt, vec![0, 2]);
+        // 1 of 3 live predictions and 1 of 3 references
+        assert!((result.precision() - 1.0 / 3.0).abs() < 1e-6);
+        assert!((result.recall() - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_greedy_match_takes_best_overlap_first() {
+        // Both predictions overlap the single reference; the closer one wins
+        let gt = vec![bbox(1, 0.0, 0.0, 10.0, 10.0)];
+        let pred = vec![bbox(1, 2.0, 0.0, 10.0, 10.0), bbox(1, 1.0, 0.0, 10.0, 10.0)];
+        let result = greedy_match(&pred, &gt, 0.5);
+        assert_eq!(result.matches.iter().map(|m| (m.0, m.1)).collect::<Vec<_>>(), vec![(1, 0)]);
+        assert_eq!(result.unmatched_pred, vec![0]);
+        assert!(result.unmatched_gt.is_empty());
+        assert_eq!(result.precision(), 0.5);
+        assert_eq!(result.recall(), 1.0);
+        // An empty frame against an empty reference is perfect
+        assert_eq!(greedy_match(&[], &[], 0.5).precision(), 1.0);
+    }
+
+    #[test]
+    fn test_greedy_match_handles_points_and_rotation() {
+        let point = |x: f32| Annotation { r#type: "point".into(), ..bbox(1, x, 50.0, 0.0, 0.0) };
+        // Standing upright, the long box fills a 10 x 100 column
+        let upright = Annotation {
+            r#type: "rbbox".into(),
+            rotation: 90.0,
+            ..bbox(2, 0.0, 0.0, 100.0, 10.0)
+        };
+        let gt = vec![point(50.0), upright];
+        let pred = vec![point(52.0), point(90.0), bbox(2, 45.0, -45.0, 10.0, 100.0)];
+
+        let result = greedy_match(&pred, &gt, 0.5);
+        let pairs: Vec<(usize, usize)> = result.matches.iter().map(|m| (m.0, m.1)).collect();
+        assert_eq!(pairs, vec![(2, 1), (0, 0)]);
+        assert_eq!(result.unmatched_pred, vec![1]);
+    }
+}
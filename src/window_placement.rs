@@ -0,0 +1,146 @@
+//! Restoring the window geometry saved in `appearance.window`.
+//!
+//! Monitors may have been unplugged or rearranged since the geometry was
+//! saved, so with the `monitor-clamp` feature a restored rect that no longer
+//! shows on any monitor is moved back to the center of the primary one.
+//! Without it the saved rect is restored as is.
+
+use crate::config::{save_config, AppConfig, WindowRect};
+use crate::{logging, AppWindow};
+#[cfg(feature = "monitor-clamp")]
+use slint::winit_030::WinitWindowAccessor;
+use slint::ComponentHandle;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// How much of the window (per axis, in px) must overlap a monitor to count
+/// as reachable
+const MIN_VISIBLE_PX: i64 = 64;
+
+/// Overlap of two rects along one axis
+fn overlap(a_start: i32, a_len: u32, b_start: i32, b_len: u32) -> i64 {
+    let start = (a_start as i64).max(b_start as i64);
+    let end = (a_start as i64 + a_len as i64).min(b_start as i64 + b_len as i64);
+    end - start
+}
+
+/// Keep `saved` if enough of it lies on one of `monitors` (primary first);
+/// otherwise center it on the primary monitor, shrunk to fit. With no
+/// monitor information the rect is returned unchanged.
+pub fn clamp_window_rect(saved: WindowRect, monitors: &[WindowRect]) -> WindowRect {
+    let Some(primary) = monitors.first() else {
+        return saved;
+    };
+    let visible = |m: &WindowRect| {
+        overlap(saved.x, saved.width, m.x, m.width) >= MIN_VISIBLE_PX.min(saved.width as i64)
+            && overlap(saved.y, saved.height, m.y, m.height)
+                >= MIN_VISIBLE_PX.min(saved.height as i64)
+    };
+    if monitors.iter().any(visible) {
+        return saved;
+    }
+
+    let width = saved.width.min(primary.width);
+    let height = saved.height.min(primary.height);
+    WindowRect {
+        x: primary.x + ((primary.width - width) / 2) as i32,
+        y: primary.y + ((primary.height - height) / 2) as i32,
+        width,
+        height,
+    }
+}
+
+/// Current geometry of the window
+fn window_rect(ui: &AppWindow) -> WindowRect {
+    let position = ui.window().position();
+    let size = ui.window().size();
+    WindowRect {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Monitor rects, primary first; empty if the backend can't tell
+#[cfg(feature = "monitor-clamp")]
+fn monitor_rects(ui: &AppWindow) -> Vec<WindowRect> {
+    ui.window()
+        .with_winit_window(|window| {
+            let primary = window.primary_monitor();
+            let mut monitors: Vec<_> = window.available_monitors().collect();
+            monitors.sort_by_key(|m| Some(m) != primary.as_ref());
+            monitors
+                .iter()
+                .map(|m| WindowRect {
+                    x: m.position().x,
+                    y: m.position().y,
+                    width: m.size().width,
+                    height: m.size().height,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Monitors are unknown without the `monitor-clamp` feature
+#[cfg(not(feature = "monitor-clamp"))]
+fn monitor_rects(_ui: &AppWindow) -> Vec<WindowRect> {
+    Vec::new()
+}
+
+/// Restore the saved geometry once the window exists, and save the geometry
+/// again when the window is closed
+pub fn setup_window_placement(ui: &AppWindow, config: Rc<RefCell<AppConfig>>) {
+    if let Some(saved) = config.borrow().appearance.window {
+        ui.window().set_size(slint::PhysicalSize::new(saved.width, saved.height));
+        // Monitors are only known after the native window is created
+        let ui_weak = ui.as_weak();
+        slint::Timer::single_shot(std::time::Duration::ZERO, move || {
+            let Some(ui) = ui_weak.upgrade() else { return };
+            let rect = clamp_window_rect(saved, &monitor_rects(&ui));
+            ui.window().set_size(slint::PhysicalSize::new(rect.width, rect.height));
+            ui.window().set_position(slint::PhysicalPosition::new(rect.x, rect.y));
+        });
+    }
+
+    let ui_weak = ui.as_weak();
+    ui.window().on_close_requested(move || {
+        if let Some(ui) = ui_weak.upgrade() {
+            config.borrow_mut().appearance.window = Some(window_rect(&ui));
+            if let Err(e) = save_config(&config.borrow()) {
+                logging::warn(|| format!("Failed to save window geometry: {e}"));
+            }
+        }
+        slint::CloseRequestResponse::HideWindow
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> WindowRect {
+        WindowRect { x, y, width, height }
+    }
+
+    #[test]
+    fn test_clamp_window_rect_recenters_off_screen_window() {
+        let monitors = [rect(0, 0, 1920, 1080), rect(1920, 0, 1280, 1024)];
+
+        // On the second monitor: kept
+        let saved = rect(2000, 100, 1000, 800);
+        assert_eq!(clamp_window_rect(saved, &monitors), saved);
+
+        // Saved on a third monitor that is gone: centered on the primary
+        let gone = rect(3500, 200, 1000, 800);
+        assert_eq!(clamp_window_rect(gone, &monitors), rect(460, 140, 1000, 800));
+
+        // Only a sliver left on screen counts as off-screen; oversize windows shrink
+        let sliver = rect(-2990, -100, 3000, 2000);
+        assert_eq!(clamp_window_rect(sliver, &monitors), rect(0, 0, 1920, 1080));
+
+        // Without monitor information nothing changes
+        assert_eq!(clamp_window_rect(gone, &[]), gone);
+    }
+}